@@ -0,0 +1,341 @@
+//! A1 notation: parsing, validation and formatting of ranges such as
+//! `Data!A1:C10`, `'Q1 '' Results'!B:B` or `2:5`, and conversion to and from
+//! the API's GridRange. Sheet names are quoted whenever Sheets needs them to
+//! be, so ranges built from any sheet name are valid.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+
+/// Largest column Sheets allows, `ZZZ`.
+const MAX_COLUMN: usize = 18277;
+/// Largest row number Sheets allows.
+const MAX_ROW: usize = 10_000_000;
+
+/// A rectangular range of a sheet. Indices are 0-based and inclusive; a
+/// missing bound leaves that side open, so `A:C` has no rows, `2:5` no
+/// columns and `A1:ZZ` no end row.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Range {
+    pub sheet: Option<String>,
+    pub start_column: Option<usize>,
+    pub start_row: Option<usize>,
+    pub end_column: Option<usize>,
+    pub end_row: Option<usize>,
+}
+
+impl Range {
+    /// Parses an A1 range, optionally prefixed by its sheet: `A1:C10`,
+    /// `Data!A:C`, `'Q1 Results'!2:5`.
+    pub fn parse(text: &str) -> Result<Range> {
+        let text = text.trim();
+        let (sheet, cells) = split_sheet(text)?;
+        let mut range = Range::parse_cells(cells)?;
+        range.sheet = sheet;
+        Ok(range)
+    }
+
+    /// Parses an A1 range that must not name a sheet.
+    pub fn parse_cells(cells: &str) -> Result<Range> {
+        let cells = cells.trim();
+        if split_sheet(cells)?.0.is_some() {
+            bail!(
+                "range `{}` names a sheet; give only the cells, e.g. `A1:C10`",
+                cells
+            );
+        }
+        let invalid = || {
+            format!(
+                "invalid range `{}`: expected A1 notation such as `A1:C10`, `A:C`, `2:5` or `A2:F`",
+                cells
+            )
+        };
+        let (start, end) = match cells.split_once(':') {
+            Some((start, end)) => (start, Some(end)),
+            None => (cells, None),
+        };
+        let (start_column, start_row) = parse_cell(start).with_context(invalid)?;
+        let (end_column, end_row) = match end {
+            Some(end) => parse_cell(end).with_context(invalid)?,
+            // A lone cell must name both its column and row
+            None if start_column.is_some() && start_row.is_some() => (start_column, start_row),
+            None => bail!(invalid()),
+        };
+
+        // Either both ends name a column or neither does, and an open start
+        // row only goes with an open end row (`A:C`, not `A:C5`)
+        if start_column.is_some() != end_column.is_some()
+            || (start_row.is_none() && end_row.is_some())
+            || (start_column.is_none() && (start_row.is_none() || end_row.is_none()))
+        {
+            bail!(invalid());
+        }
+
+        let ordered = |a: Option<usize>, b: Option<usize>| match (a, b) {
+            (Some(a), Some(b)) if a > b => (Some(b), Some(a)),
+            other => other,
+        };
+        let (start_column, end_column) = ordered(start_column, end_column);
+        let (start_row, end_row) = ordered(start_row, end_row);
+        Ok(Range {
+            sheet: None,
+            start_column,
+            start_row,
+            end_column,
+            end_row,
+        })
+    }
+
+    /// Parses `cells`, which must not name a sheet, as a range of `sheet`.
+    pub fn on_sheet(sheet: &str, cells: &str) -> Result<Range> {
+        Ok(Range::parse_cells(cells)?.with_sheet(sheet))
+    }
+
+    /// Every cell of `sheet`.
+    pub fn whole_sheet(sheet: &str) -> Range {
+        Range::default().with_sheet(sheet)
+    }
+
+    /// The single cell at a 0-based column and row.
+    pub fn cell(column: usize, row: usize) -> Range {
+        Range::block(column, row, 1, 1)
+    }
+
+    /// The block `rows` by `columns` cells in size whose top-left cell is at
+    /// a 0-based column and row.
+    pub fn block(column: usize, row: usize, rows: usize, columns: usize) -> Range {
+        Range {
+            sheet: None,
+            start_column: Some(column),
+            start_row: Some(row),
+            end_column: Some(column + columns.max(1) - 1),
+            end_row: Some(row + rows.max(1) - 1),
+        }
+    }
+
+    pub fn with_sheet(mut self, sheet: &str) -> Range {
+        self.sheet = Some(sheet.to_string());
+        self
+    }
+
+    /// The 0-based first column, 0 for ranges of whole rows.
+    pub fn first_column(&self) -> usize {
+        self.start_column.unwrap_or(0)
+    }
+
+    /// The 0-based first row, 0 for ranges of whole columns.
+    pub fn first_row(&self) -> usize {
+        self.start_row.unwrap_or(0)
+    }
+
+    /// The range without its sheet, e.g. `A1:C10`; empty for a whole sheet.
+    pub fn cells(&self) -> String {
+        let corner = |column: Option<usize>, row: Option<usize>| {
+            format!(
+                "{}{}",
+                column.map(column_letters).unwrap_or_default(),
+                row.map(|row| (row + 1).to_string()).unwrap_or_default()
+            )
+        };
+        let start = corner(self.start_column, self.start_row);
+        let end = corner(self.end_column, self.end_row);
+        if start == end && self.start_column.is_some() && self.start_row.is_some() {
+            start
+        } else if start.is_empty() && end.is_empty() {
+            String::new()
+        } else {
+            format!("{}:{}", start, end)
+        }
+    }
+
+    /// The range as a GridRange on the sheet with ID `sheet_id`; open sides
+    /// are left out, which the API reads as unbounded.
+    pub fn to_grid_range(&self, sheet_id: i32) -> Value {
+        let mut grid = json!({ "sheetId": sheet_id });
+        for (field, index) in [
+            ("startRowIndex", self.start_row),
+            ("endRowIndex", self.end_row.map(|row| row + 1)),
+            ("startColumnIndex", self.start_column),
+            ("endColumnIndex", self.end_column.map(|column| column + 1)),
+        ] {
+            if let Some(index) = index {
+                grid[field] = json!(index);
+            }
+        }
+        grid
+    }
+
+    /// The range a GridRange covers, on `sheet` if given. Sides A1 notation
+    /// cannot leave open end at the last column or row Sheets allows.
+    pub fn from_grid_range(grid: &Value, sheet: Option<&str>) -> Range {
+        let index = |field: &str| grid[field].as_u64().map(|index| index as usize);
+        let span = |start: &str, end: &str| {
+            if grid.get(start).is_none() && grid.get(end).is_none() {
+                return (None, None);
+            }
+            let last = index(end).and_then(|end| end.checked_sub(1));
+            (Some(index(start).unwrap_or(0)), last)
+        };
+        let (start_column, end_column) = span("startColumnIndex", "endColumnIndex");
+        let (start_row, mut end_row) = span("startRowIndex", "endRowIndex");
+        let end_column = end_column.or(start_column.map(|_| MAX_COLUMN));
+        if start_column.is_none() && start_row.is_some() {
+            end_row = end_row.or(Some(MAX_ROW - 1));
+        }
+        Range {
+            sheet: sheet.map(str::to_string),
+            start_column,
+            start_row,
+            end_column,
+            end_row,
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cells = self.cells();
+        match &self.sheet {
+            Some(sheet) if cells.is_empty() => write!(f, "{}", quote_sheet(sheet)),
+            Some(sheet) => write!(f, "{}!{}", quote_sheet(sheet), cells),
+            None => write!(f, "{}", cells),
+        }
+    }
+}
+
+impl FromStr for Range {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Range::parse(s)
+    }
+}
+
+/// A sheet name as A1 notation needs it: quoted, with quotes doubled, unless
+/// it is a plain word that cannot be read as a cell.
+pub fn quote_sheet(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && parse_cell(name).is_err()
+        && !is_r1c1(name);
+    if plain {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\'', "''"))
+    }
+}
+
+/// One end of an A1 range: column letters, a row number or both (`B3`, `B`,
+/// `3`), as 0-based indices.
+pub fn parse_cell(text: &str) -> Result<(Option<usize>, Option<usize>)> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(text.len());
+    let (letters, digits) = text.split_at(split);
+    if letters.is_empty() && digits.is_empty() {
+        bail!("empty cell reference");
+    }
+    let column = match letters {
+        "" => None,
+        letters => Some(
+            column_index(&letters.to_ascii_uppercase())
+                .filter(|column| *column <= MAX_COLUMN)
+                .with_context(|| format!("invalid column `{}` in `{}`", letters, text))?,
+        ),
+    };
+    let row = match digits {
+        "" => None,
+        digits => Some(
+            digits
+                .parse::<usize>()
+                .ok()
+                .filter(|row| {
+                    (1..=MAX_ROW).contains(row) && digits.bytes().all(|b| b.is_ascii_digit())
+                })
+                .with_context(|| format!("invalid row `{}` in `{}`", digits, text))?
+                - 1,
+        ),
+    };
+    Ok((column, row))
+}
+
+/// A single cell such as `B3`, as its 0-based column and row.
+pub fn parse_cell_position(text: &str) -> Result<(usize, usize)> {
+    match parse_cell(text)? {
+        (Some(column), Some(row)) => Ok((column, row)),
+        _ => bail!(
+            "invalid cell `{}`: expected a cell such as `B3`",
+            text.trim()
+        ),
+    }
+}
+
+/// Letters naming the 0-based sheet column `index`: 0 is `A`, 26 is `AA`.
+pub fn column_letters(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.iter().rev().map(|b| *b as char).collect()
+}
+
+/// The 0-based sheet column named by upper-case `letters`, such as `C` or `AB`.
+pub fn column_index(letters: &str) -> Option<usize> {
+    if letters.is_empty() || letters.len() > 3 || !letters.bytes().all(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(
+        letters
+            .bytes()
+            .fold(0, |acc, b| acc * 26 + (b - b'A') as usize + 1)
+            - 1,
+    )
+}
+
+/// Splits a leading sheet name, quoted or not, from the cells after `!`.
+fn split_sheet(text: &str) -> Result<(Option<String>, &str)> {
+    if let Some(quoted) = text.strip_prefix('\'') {
+        let mut name = String::new();
+        let mut chars = quoted.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != '\'' {
+                name.push(c);
+            } else if chars.peek().is_some_and(|(_, next)| *next == '\'') {
+                name.push('\'');
+                chars.next();
+            } else {
+                let rest = &quoted[i + 1..];
+                let cells = rest
+                    .strip_prefix('!')
+                    .with_context(|| format!("expected `!` after the sheet name in `{}`", text))?;
+                return Ok((Some(name), cells));
+            }
+        }
+        bail!("unterminated sheet name quote in `{}`", text);
+    }
+    Ok(match text.rsplit_once('!') {
+        Some((sheet, cells)) if !sheet.is_empty() => (Some(sheet.to_string()), cells),
+        Some(_) => bail!("missing sheet name before `!` in `{}`", text),
+        None => (None, text),
+    })
+}
+
+/// Whether `name` reads as an R1C1 reference such as `R1C1` or `C5`.
+fn is_r1c1(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    let rest = upper.strip_prefix('R').map_or(upper.as_str(), |rest| {
+        rest.trim_start_matches(|c: char| c.is_ascii_digit())
+    });
+    rest.strip_prefix('C')
+        .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+        || (upper.starts_with('R') && rest.is_empty())
+}
@@ -0,0 +1,119 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// One line of the audit log, describing a single mutating tool call.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub tool: String,
+    /// Spreadsheet or file the call targeted, when known
+    pub target: Option<String>,
+    /// SHA-256 of the JSON-encoded arguments, so values are not stored verbatim
+    pub arguments_sha256: String,
+    pub account: Option<String>,
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+/// Arguments naming what a call modifies, most specific first.
+const TARGET_KEYS: &[&str] = &[
+    "spreadsheet_id",
+    "file_id",
+    "file_ids",
+    "document_id",
+    "presentation_id",
+    "destination_spreadsheet_id",
+];
+
+impl AuditRecord {
+    /// A pending record of a call to `tool`. The target comes from the
+    /// arguments, then the request meta, then `default_spreadsheet_id`; the
+    /// account is the server's configured one, or else what the client
+    /// reported in the meta.
+    pub fn new(
+        tool: &str,
+        arguments: &Value,
+        meta: &Value,
+        account: Option<&str>,
+        default_spreadsheet_id: Option<&str>,
+    ) -> Self {
+        let target = TARGET_KEYS
+            .iter()
+            .find_map(|key| target_id(arguments.get(key)?))
+            .or_else(|| TARGET_KEYS.iter().find_map(|key| target_id(meta.get(key)?)))
+            .or_else(|| default_spreadsheet_id.map(String::from));
+        let account = account.map(String::from).or_else(|| {
+            meta.get("account")
+                .or_else(|| meta.get("user_email"))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+        });
+
+        Self {
+            timestamp: Utc::now(),
+            tool: tool.to_string(),
+            target,
+            arguments_sha256: format!("{:x}", Sha256::digest(arguments.to_string())),
+            account,
+            outcome: "pending".to_string(),
+            error: None,
+        }
+    }
+}
+
+/// An ID, or a comma-separated list of IDs, from a target argument.
+fn target_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) if !id.is_empty() => Some(id.clone()),
+        Value::Array(ids) => {
+            let ids = ids.iter().filter_map(Value::as_str).collect::<Vec<_>>();
+            (!ids.is_empty()).then(|| ids.join(","))
+        }
+        _ => None,
+    }
+}
+
+/// Append-only JSONL file recording every mutating tool invocation.
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening audit log {}", path.display()))?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn record(&self, record: &AuditRecord) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to serialize audit record: {}", e);
+                return;
+            }
+        };
+
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            warn!("Failed to write audit record: {}", e);
+        }
+    }
+}
@@ -1,9 +1,39 @@
+use std::{
+    fmt,
+    process::{Command, Stdio},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{distributions::Alphanumeric, Rng};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tracing::debug;
+use sha2::{Digest, Sha256};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::Mutex,
+};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    client::get_http_client,
+    redact::redact,
+    token_store::{StoredCredentials, StoredToken, TokenStore},
+    InvokeError,
+};
 
-use crate::InvokeError;
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKENINFO_URL: &str = "https://oauth2.googleapis.com/tokeninfo";
+/// How long `login` waits for the browser to come back
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+/// Cached access tokens are refreshed this long before Google expires them
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+const CALLBACK_PAGE: &str = "<html><body><h3>Authorization complete.</h3><p>You can close this tab and return to the terminal.</p></body></html>";
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TokenResponse {
@@ -35,12 +65,109 @@ impl Default for GoogleAuthService {
 impl GoogleAuthService {
     pub fn new(client_id: String, client_secret: String) -> Result<Self, InvokeError> {
         Ok(Self {
-            client: Client::new(),
+            client: get_http_client()
+                .map_err(|e| InvokeError::Transport(format!("{:#}", e)))?
+                .clone(),
             google_client_id: client_id,
             google_client_secret: client_secret,
         })
     }
 
+    /// The consent screen URL for the authorization code flow with PKCE.
+    /// `access_type=offline` and `prompt=consent` make Google return a
+    /// refresh token even if the user authorized this client before, and
+    /// `include_granted_scopes` adds `scopes` to those granted earlier.
+    pub fn authorization_url(
+        &self,
+        redirect_uri: &str,
+        scopes: &[&str],
+        state: &str,
+        code_challenge: &str,
+    ) -> String {
+        let mut url = Url::parse(AUTH_URL).expect("valid auth URL");
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.google_client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("response_type", "code")
+            .append_pair("scope", &scopes.join(" "))
+            .append_pair("access_type", "offline")
+            .append_pair("prompt", "consent")
+            .append_pair("include_granted_scopes", "true")
+            .append_pair("state", state)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        url.into()
+    }
+
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: &str,
+    ) -> Result<TokenResponse, InvokeError> {
+        let payload = json!({
+            "client_id": self.google_client_id,
+            "client_secret": self.google_client_secret,
+            "code": code,
+            "code_verifier": code_verifier,
+            "redirect_uri": redirect_uri,
+            "grant_type": "authorization_code"
+        });
+
+        self.exchange_token(&payload).await
+    }
+
+    /// Runs the authorization code flow: serves the redirect on a loopback
+    /// port, sends the user to the consent screen and exchanges the returned
+    /// code. `port` 0 picks a free port.
+    pub async fn login(
+        &self,
+        scopes: &[&str],
+        port: u16,
+        open_browser: bool,
+    ) -> anyhow::Result<TokenResponse> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .with_context(|| format!("Failed to listen on 127.0.0.1:{}", port))?;
+        let redirect_uri = format!("http://127.0.0.1:{}/", listener.local_addr()?.port());
+
+        let state = random_string(32);
+        let code_verifier = random_string(64);
+        let url = self.authorization_url(
+            &redirect_uri,
+            scopes,
+            &state,
+            &pkce_challenge(&code_verifier),
+        );
+
+        eprintln!("Open this URL to authorize access:\n\n  {}\n", url);
+        if open_browser && !launch_browser(&url) {
+            eprintln!("Could not open a browser; open the URL manually.");
+        }
+
+        let code = tokio::time::timeout(LOGIN_TIMEOUT, wait_for_code(&listener, &state))
+            .await
+            .context("Timed out waiting for the authorization redirect")??;
+
+        Ok(self
+            .exchange_code(&code, &redirect_uri, &code_verifier)
+            .await?)
+    }
+
+    /// Email of the account an access token belongs to, when the token was
+    /// granted the `email` scope.
+    pub async fn account_email(&self, access_token: &str) -> Option<String> {
+        let response = self
+            .client
+            .get(TOKENINFO_URL)
+            .query(&[("access_token", access_token)])
+            .send()
+            .await
+            .ok()?;
+        let info = response.json::<serde_json::Value>().await.ok()?;
+        info["email"].as_str().map(str::to_string)
+    }
+
     pub async fn refresh_token(&self, refresh_token: &str) -> Result<TokenResponse, InvokeError> {
         let payload = json!({
             "client_id": self.google_client_id,
@@ -56,7 +183,7 @@ impl GoogleAuthService {
         &self,
         payload: &serde_json::Value,
     ) -> Result<TokenResponse, InvokeError> {
-        debug!("Token exchange payload: {:?}", payload);
+        debug!("Token exchange payload: {}", redact(&payload.to_string()));
 
         let response = self
             .client
@@ -64,14 +191,10 @@ impl GoogleAuthService {
             .json(payload)
             .send()
             .await
-            .map_err(|e| InvokeError::GoogleApi(e.to_string()))?;
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
 
         if !response.status().is_success() {
-            let error = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(InvokeError::GoogleApi(error));
+            return Err(InvokeError::from_response(response).await);
         }
 
         response
@@ -80,3 +203,229 @@ impl GoogleAuthService {
             .map_err(|e| InvokeError::TokenParse(e.to_string()))
     }
 }
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// The S256 PKCE challenge for `verifier`.
+pub(crate) fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Extracts the authorization code from the redirect's request line, e.g.
+/// `GET /?state=..&code=.. HTTP/1.1`. Returns `None` for unrelated requests
+/// such as `/favicon.ico`.
+pub(crate) fn parse_callback(
+    request_line: &str,
+    expected_state: &str,
+) -> anyhow::Result<Option<String>> {
+    let target = request_line
+        .split_whitespace()
+        .nth(1)
+        .context("malformed redirect request")?;
+    let url = Url::parse("http://127.0.0.1")?.join(target)?;
+    if url.path() != "/" {
+        return Ok(None);
+    }
+
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    if let Some(error) = param("error") {
+        anyhow::bail!("Authorization was denied: {}", error);
+    }
+    let Some(code) = param("code") else {
+        return Ok(None);
+    };
+    if param("state").as_deref() != Some(expected_state) {
+        anyhow::bail!("Authorization redirect has a mismatched state; try again");
+    }
+    Ok(Some(code))
+}
+
+async fn wait_for_code(listener: &TcpListener, state: &str) -> anyhow::Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buf = vec![0; 8192];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let request_line = request.lines().next().unwrap_or_default();
+
+        let result = parse_callback(request_line, state);
+        let (status, body) = match &result {
+            Ok(Some(_)) => ("200 OK", CALLBACK_PAGE.to_string()),
+            Ok(None) => ("404 Not Found", String::new()),
+            Err(e) => (
+                "400 Bad Request",
+                format!("<html><body>{}</body></html>", e),
+            ),
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        // The browser showing the page is best effort
+        let _ = stream.write_all(response.as_bytes()).await;
+
+        if let Some(code) = result? {
+            return Ok(code);
+        }
+    }
+}
+
+/// Opens `url` in the user's default browser.
+fn launch_browser(url: &str) -> bool {
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = Command::new("xdg-open");
+
+    command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .is_ok()
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+struct TokenState {
+    refresh_token: String,
+    cached: Option<CachedToken>,
+}
+
+/// Supplies access tokens minted from a refresh token, caching each one until
+/// shortly before it expires so long-running servers never hand out a stale
+/// token.
+pub struct TokenProvider {
+    auth: GoogleAuthService,
+    state: Mutex<TokenState>,
+    /// Where refreshed tokens are written back, keyed by account
+    store: Option<(Arc<dyn TokenStore>, String)>,
+}
+
+impl fmt::Debug for TokenProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenProvider")
+            .field("client_id", &self.auth.google_client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenProvider {
+    pub fn new(
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+    ) -> Result<Self, InvokeError> {
+        Ok(Self {
+            auth: GoogleAuthService::new(client_id, client_secret)?,
+            state: Mutex::new(TokenState {
+                refresh_token,
+                cached: None,
+            }),
+            store: None,
+        })
+    }
+
+    /// A provider for tokens loaded from a token store. A stored access token
+    /// that is still fresh is used before the first refresh, and every
+    /// refreshed token is saved back to the store.
+    pub fn from_store(
+        client_id: String,
+        client_secret: String,
+        stored: StoredCredentials,
+    ) -> Result<Self, InvokeError> {
+        let cached =
+            stored
+                .token
+                .fresh_access_token(EXPIRY_MARGIN)
+                .map(|(access_token, remaining)| CachedToken {
+                    access_token: access_token.to_string(),
+                    expires_at: Instant::now() + remaining,
+                });
+        Ok(Self {
+            auth: GoogleAuthService::new(client_id, client_secret)?,
+            state: Mutex::new(TokenState {
+                refresh_token: stored.token.refresh_token,
+                cached,
+            }),
+            store: Some((stored.store, stored.account)),
+        })
+    }
+
+    /// Returns the cached access token, refreshing it first when it is missing
+    /// or about to expire. Concurrent callers wait on a single refresh, and a
+    /// refresh token Google rotates is kept for the next one.
+    pub async fn access_token(&self) -> Result<String, InvokeError> {
+        let mut state = self.state.lock().await;
+        if let Some(token) = state
+            .cached
+            .as_ref()
+            .filter(|token| token.expires_at > Instant::now() + EXPIRY_MARGIN)
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        debug!("Refreshing access token");
+        let response = self.auth.refresh_token(&state.refresh_token).await?;
+        if let Some(rotated) = &response.refresh_token {
+            state.refresh_token = rotated.clone();
+        }
+        let lifetime = Duration::from_secs(response.expires_in.max(0) as u64);
+        state.cached = Some(CachedToken {
+            access_token: response.access_token.clone(),
+            expires_at: Instant::now() + lifetime,
+        });
+
+        if let Some((store, account)) = &self.store {
+            let token = StoredToken {
+                refresh_token: state.refresh_token.clone(),
+                access_token: Some(response.access_token.clone()),
+                expires_at: chrono::Duration::from_std(lifetime)
+                    .ok()
+                    .map(|lifetime| chrono::Utc::now() + lifetime),
+            };
+            // The refreshed token is still usable when it cannot be persisted
+            if let Err(e) = store.save(account, &token) {
+                warn!(
+                    "Could not save the refreshed token for {}: {:#}",
+                    account, e
+                );
+            }
+        }
+        Ok(response.access_token)
+    }
+
+    /// Forgets `rejected` so the next `access_token` call refreshes. A token
+    /// that another call has already replaced is left alone.
+    pub async fn invalidate(&self, rejected: &str) {
+        let mut state = self.state.lock().await;
+        if state
+            .cached
+            .as_ref()
+            .is_some_and(|token| token.access_token == rejected)
+        {
+            state.cached = None;
+        }
+    }
+}
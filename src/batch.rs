@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use reqwest::header;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    circuit::{guarded, Service},
+    client::{drive_root_url, get_http_client},
+    payload, InvokeError,
+};
+/// Google rejects batches with more than 100 calls.
+const MAX_BATCH_SIZE: usize = 100;
+
+/// A single call inside a batch, e.g. `PATCH /drive/v3/files/{id}`.
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    pub method: &'static str,
+    pub path: String,
+    pub body: Option<Value>,
+}
+
+/// Outcome of one batched call. Failures are reported per item rather than
+/// failing the whole batch.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl BatchResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Sends `requests` to the Drive batch endpoint in chunks, returning one
+/// response per request in the original order.
+pub async fn execute_drive_batch(
+    access_token: &str,
+    requests: &[BatchRequest],
+) -> Result<Vec<BatchResponse>> {
+    let batch_url = format!("{}batch/drive/v3", drive_root_url());
+    let mut responses = Vec::with_capacity(requests.len());
+    let client = get_http_client()?;
+
+    for chunk in requests.chunks(MAX_BATCH_SIZE) {
+        let boundary = format!("batch_{:016x}", rand::random::<u64>());
+        for request in chunk {
+            payload::log_request(request.method, &request.path, request.body.as_ref());
+        }
+        let response = guarded(Service::Drive, async {
+            let response = client
+                .post(&batch_url)
+                .bearer_auth(access_token)
+                .header(
+                    header::CONTENT_TYPE,
+                    format!("multipart/mixed; boundary={}", boundary),
+                )
+                .body(encode_batch(&boundary, chunk))
+                .send()
+                .await
+                .map_err(|e| InvokeError::Transport(e.to_string()))?;
+
+            if !response.status().is_success() {
+                return Err(InvokeError::from_response(response).await);
+            }
+            Ok(response)
+        })
+        .await?;
+
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+
+        let mut parsed = parse_batch_response(&content_type, &body)?;
+        parsed.sort_by_key(|(index, _)| *index);
+        if parsed.len() != chunk.len() {
+            anyhow::bail!(
+                "batch response contained {} parts for {} requests",
+                parsed.len(),
+                chunk.len()
+            );
+        }
+        for ((_, response), request) in parsed.iter().zip(chunk) {
+            payload::log_response(&request.path, response.status, &response.body);
+        }
+        responses.extend(parsed.into_iter().map(|(_, response)| response));
+    }
+
+    Ok(responses)
+}
+
+pub(crate) fn encode_batch(boundary: &str, requests: &[BatchRequest]) -> String {
+    let mut body = String::new();
+    for (index, request) in requests.iter().enumerate() {
+        body.push_str(&format!(
+            "--{}\r\nContent-Type: application/http\r\nContent-ID: <item{}>\r\n\r\n",
+            boundary, index
+        ));
+        body.push_str(&format!("{} {}\r\n", request.method, request.path));
+        match &request.body {
+            Some(json) => body.push_str(&format!(
+                "Content-Type: application/json; charset=UTF-8\r\n\r\n{}\r\n",
+                json
+            )),
+            None => body.push_str("\r\n"),
+        }
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    body
+}
+
+/// Parses a `multipart/mixed` batch response into `(request index, response)` pairs.
+pub(crate) fn parse_batch_response(
+    content_type: &str,
+    body: &str,
+) -> Result<Vec<(usize, BatchResponse)>> {
+    let boundary = content_type
+        .split(';')
+        .find_map(|p| p.trim().strip_prefix("boundary="))
+        .map(|b| b.trim_matches('"'))
+        .context("batch response is missing a multipart boundary")?;
+    let delimiter = format!("--{}", boundary);
+
+    let mut responses = Vec::new();
+    for part in body.split(delimiter.as_str()).skip(1) {
+        if part.starts_with("--") {
+            break;
+        }
+        let part = part.replace("\r\n", "\n");
+        let (part_headers, http) = part
+            .trim_start_matches('\n')
+            .split_once("\n\n")
+            .context("malformed batch part")?;
+
+        let index = part_headers
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case("content-id")
+                    .then(|| value.trim())
+            })
+            .and_then(|id| {
+                id.trim_matches(|c| c == '<' || c == '>')
+                    .rsplit("item")
+                    .next()
+            })
+            .and_then(|n| n.parse::<usize>().ok())
+            .context("batch part is missing its Content-ID")?;
+
+        let (head, payload) = http.split_once("\n\n").unwrap_or((http, ""));
+        let status = head
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse::<u16>().ok())
+            .context("batch part is missing an HTTP status line")?;
+        let payload = payload.trim();
+        let body = if payload.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(payload).unwrap_or_else(|_| Value::String(payload.to_string()))
+        };
+
+        responses.push((index, BatchResponse { status, body }));
+    }
+
+    Ok(responses)
+}
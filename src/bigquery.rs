@@ -0,0 +1,346 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::{header, Method};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use crate::{
+    circuit::{guarded, Service},
+    client::{bigquery_root_url, get_http_client},
+    payload,
+    upload::multipart_body,
+    InvokeError,
+};
+
+/// Scope `sheets_to_bigquery` needs on top of the Sheets ones.
+pub const SCOPE: &str = "https://www.googleapis.com/auth/bigquery";
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const JOB_POLL_ATTEMPTS: u32 = 120;
+
+/// A BigQuery table, as `projectId.datasetId.tableId`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRef {
+    pub project_id: String,
+    pub dataset_id: String,
+    pub table_id: String,
+}
+
+impl TableRef {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "projectId": self.project_id,
+            "datasetId": self.dataset_id,
+            "tableId": self.table_id,
+        })
+    }
+
+    fn url(&self) -> Result<Url> {
+        bigquery_url(&format!(
+            "projects/{}/datasets/{}/tables/{}",
+            urlencoding::encode(&self.project_id),
+            urlencoding::encode(&self.dataset_id),
+            urlencoding::encode(&self.table_id)
+        ))
+    }
+}
+
+/// BigQuery column names for a header row: letters, digits and underscores,
+/// not starting with a digit, and unique regardless of case.
+pub fn field_names(headers: &[Value]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(headers.len());
+    for (i, header) in headers.iter().enumerate() {
+        let header = match header {
+            Value::String(s) => s.clone(),
+            Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        let mut name = String::new();
+        for c in header.trim().chars() {
+            if c.is_ascii_alphanumeric() {
+                name.push(c);
+            } else if !name.is_empty() && !name.ends_with('_') {
+                name.push('_');
+            }
+        }
+        let mut name = name.trim_end_matches('_').to_string();
+        if name.is_empty() {
+            name = format!("column_{}", i + 1);
+        } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+
+        let taken = |candidate: &str| names.iter().any(|n| n.eq_ignore_ascii_case(candidate));
+        let mut unique = name.clone();
+        let mut suffix = 2;
+        while taken(&unique) {
+            unique = format!("{}_{}", name, suffix);
+            suffix += 1;
+        }
+        names.push(unique);
+    }
+    names
+}
+
+/// The narrowest BigQuery type holding every non-empty cell of a column.
+fn column_type<'a>(cells: impl Iterator<Item = &'a Value>) -> &'static str {
+    let mut kind = None;
+    for cell in cells {
+        let cell_kind = match cell {
+            Value::Null => continue,
+            Value::String(s) if s.is_empty() => continue,
+            Value::Bool(_) => "BOOLEAN",
+            Value::Number(n) if n.is_i64() || n.as_f64().is_some_and(|f| f.fract() == 0.0) => {
+                "INTEGER"
+            }
+            Value::Number(_) => "FLOAT",
+            _ => return "STRING",
+        };
+        kind = Some(match (kind, cell_kind) {
+            (None, k) => k,
+            (Some(a), b) if a == b => a,
+            (Some("INTEGER" | "FLOAT"), "INTEGER" | "FLOAT") => "FLOAT",
+            _ => return "STRING",
+        });
+    }
+    kind.unwrap_or("STRING")
+}
+
+/// A table schema for `rows` (header row excluded) with the given column
+/// names, typing each column from its values.
+pub fn infer_schema(names: &[String], rows: &[Vec<Value>]) -> Vec<Value> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let kind = column_type(rows.iter().filter_map(|row| row.get(i)));
+            json!({ "name": name, "type": kind, "mode": "NULLABLE" })
+        })
+        .collect()
+}
+
+/// Converts one cell to the JSON BigQuery expects for a column of `kind`;
+/// empty cells are left out.
+fn field_value(cell: &Value, kind: &str) -> Option<Value> {
+    match cell {
+        Value::Null => None,
+        Value::String(s) if s.is_empty() => None,
+        Value::Number(n) if kind == "INTEGER" => n
+            .as_i64()
+            .or_else(|| n.as_f64().map(|f| f as i64))
+            .map(Value::from),
+        Value::Number(_) | Value::Bool(_) if kind != "STRING" => Some(cell.clone()),
+        Value::String(s) => Some(json!(s)),
+        other => Some(json!(other.to_string())),
+    }
+}
+
+/// The rows as newline-delimited JSON records for a load job, skipping rows
+/// with no values.
+pub fn ndjson_rows(schema: &[Value], rows: &[Vec<Value>]) -> String {
+    let mut text = String::new();
+    for row in rows {
+        let record = schema
+            .iter()
+            .zip(row)
+            .filter_map(|(field, cell)| {
+                let name = field["name"].as_str()?;
+                let value = field_value(cell, field["type"].as_str().unwrap_or("STRING"))?;
+                Some((name.to_string(), value))
+            })
+            .collect::<Map<_, _>>();
+        if !record.is_empty() {
+            text.push_str(&Value::Object(record).to_string());
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Definition of a table that reads `range` of the spreadsheet live at
+/// query time, skipping its header row.
+pub fn external_table(
+    table: &TableRef,
+    spreadsheet_id: &str,
+    range: &str,
+    schema: &[Value],
+) -> Value {
+    json!({
+        "tableReference": table.to_json(),
+        "externalDataConfiguration": {
+            "sourceFormat": "GOOGLE_SHEETS",
+            "sourceUris": [format!("https://docs.google.com/spreadsheets/d/{}", spreadsheet_id)],
+            "googleSheetsOptions": { "range": range, "skipLeadingRows": "1" },
+            "schema": { "fields": schema },
+        },
+    })
+}
+
+/// Creates the table `definition` describes.
+pub async fn create_table(
+    access_token: &str,
+    table: &TableRef,
+    definition: &Value,
+) -> Result<Value> {
+    let url = bigquery_url(&format!(
+        "projects/{}/datasets/{}/tables",
+        urlencoding::encode(&table.project_id),
+        urlencoding::encode(&table.dataset_id)
+    ))?;
+    bigquery_request(access_token, Method::POST, url, Some(definition)).await
+}
+
+/// Deletes the table if it exists.
+pub async fn delete_table(access_token: &str, table: &TableRef) -> Result<()> {
+    match bigquery_request(access_token, Method::DELETE, table.url()?, None).await {
+        Ok(_) => Ok(()),
+        Err(e)
+            if matches!(
+                e.downcast_ref::<InvokeError>(),
+                Some(InvokeError::NotFound { .. })
+            ) =>
+        {
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Loads newline-delimited JSON rows into the table, creating it if needed,
+/// and waits for the job to finish. Returns the finished job.
+pub async fn load_rows(
+    access_token: &str,
+    job_project: &str,
+    table: &TableRef,
+    schema: &[Value],
+    rows: String,
+    replace: bool,
+) -> Result<Value> {
+    let metadata = json!({
+        "configuration": {
+            "load": {
+                "destinationTable": table.to_json(),
+                "schema": { "fields": schema },
+                "sourceFormat": "NEWLINE_DELIMITED_JSON",
+                "createDisposition": "CREATE_IF_NEEDED",
+                "writeDisposition": if replace { "WRITE_TRUNCATE" } else { "WRITE_APPEND" },
+            }
+        }
+    });
+    let mut url = Url::parse(&format!(
+        "{}upload/bigquery/v2/projects/{}/jobs",
+        bigquery_root_url(),
+        urlencoding::encode(job_project)
+    ))?;
+    url.query_pairs_mut().append_pair("uploadType", "multipart");
+    let boundary = format!("load_{:016x}", rand::random::<u64>());
+    let body = multipart_body(
+        &boundary,
+        &metadata,
+        "application/octet-stream",
+        rows.as_bytes(),
+    );
+    let client = get_http_client()?;
+
+    let mut job = guarded(Service::BigQuery, async {
+        payload::log_request("POST", url.as_str(), Some(&metadata));
+        let response = client
+            .post(url.clone())
+            .bearer_auth(access_token)
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/related; boundary={}", boundary),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(InvokeError::from_response(response).await);
+        }
+        let status = response.status().as_u16();
+        let job = response
+            .json::<Value>()
+            .await
+            .map_err(|e| InvokeError::GoogleApi(e.to_string()))?;
+        payload::log_response(url.as_str(), status, &job);
+        Ok(job)
+    })
+    .await?;
+
+    let job_id = job["jobReference"]["jobId"]
+        .as_str()
+        .context("load job has no ID")?
+        .to_string();
+    let mut attempts = 0;
+    while job["status"]["state"] != "DONE" {
+        attempts += 1;
+        if attempts > JOB_POLL_ATTEMPTS {
+            anyhow::bail!(
+                "load job {} is still running; check it in the BigQuery console",
+                job_id
+            );
+        }
+        tokio::time::sleep(JOB_POLL_INTERVAL).await;
+        let mut url = bigquery_url(&format!(
+            "projects/{}/jobs/{}",
+            urlencoding::encode(job_project),
+            urlencoding::encode(&job_id)
+        ))?;
+        if let Some(location) = job["jobReference"]["location"].as_str() {
+            url.query_pairs_mut().append_pair("location", location);
+        }
+        job = bigquery_request(access_token, Method::GET, url, None).await?;
+    }
+    if let Some(message) = job["status"]["errorResult"]["message"].as_str() {
+        anyhow::bail!("load job {} failed: {}", job_id, message);
+    }
+    Ok(job)
+}
+
+fn bigquery_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!(
+        "{}bigquery/v2/{}",
+        bigquery_root_url(),
+        path
+    ))?)
+}
+
+async fn bigquery_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<&Value>,
+) -> Result<Value> {
+    let client = get_http_client()?;
+    guarded(Service::BigQuery, async {
+        payload::log_request(method.as_str(), url.as_str(), body);
+        let mut request = client
+            .request(method, url.clone())
+            .bearer_auth(access_token);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(InvokeError::from_response(response).await);
+        }
+        let status = response.status().as_u16();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+        let value = match text.trim() {
+            "" => Value::Null,
+            text => {
+                serde_json::from_str(text).map_err(|e| InvokeError::GoogleApi(e.to_string()))?
+            }
+        };
+        payload::log_response(url.as_str(), status, &value);
+        Ok(value)
+    })
+    .await
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use reqwest::{header, StatusCode};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{
+    circuit::{guarded, Service},
+    client::get_http_client,
+    payload, InvokeError,
+};
+
+/// Responses the global [`EtagCache`] keeps before evicting the least recently used.
+const ETAG_CACHE_CAPACITY: usize = 512;
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    pub etag: String,
+    pub body: Value,
+    used: Instant,
+}
+
+/// In-memory cache of JSON GET responses keyed by URL.
+///
+/// Entries are only stored when Google returns an `ETag`; subsequent reads send
+/// `If-None-Match` and reuse the cached body when the server answers `304`.
+/// Every read is revalidated, so writes need no invalidation; the least
+/// recently used entry is dropped once `capacity` URLs are cached.
+#[derive(Debug)]
+pub struct EtagCache {
+    capacity: usize,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl EtagCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn global() -> &'static EtagCache {
+        static CACHE: OnceLock<EtagCache> = OnceLock::new();
+        CACHE.get_or_init(|| EtagCache::new(ETAG_CACHE_CAPACITY))
+    }
+
+    pub(crate) fn lookup(&self, url: &str) -> Option<CachedResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(url)?;
+        entry.used = Instant::now();
+        Some(entry.clone())
+    }
+
+    pub(crate) fn store(&self, url: &str, etag: String, body: Value) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity && !entries.contains_key(url) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(url, _)| url.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        let used = Instant::now();
+        entries.insert(url.to_string(), CachedResponse { etag, body, used });
+    }
+
+    /// Performs an authenticated GET, revalidating any cached copy with `If-None-Match`.
+    pub async fn get_json(&self, service: Service, access_token: &str, url: &str) -> Result<Value> {
+        guarded(service, self.fetch(access_token, url)).await
+    }
+
+    async fn fetch(&self, access_token: &str, url: &str) -> Result<Value> {
+        let cached = self.lookup(url);
+
+        let mut request = get_http_client()?.get(url).bearer_auth(access_token);
+        if let Some(cached) = &cached {
+            request = request.header(header::IF_NONE_MATCH, &cached.etag);
+        }
+
+        payload::log_request("GET", url, None);
+        let response = request
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                debug!("ETag cache hit for {}", url);
+                return Ok(cached.body);
+            }
+        }
+
+        if !response.status().is_success() {
+            return Err(InvokeError::from_response(response).await.into());
+        }
+
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let status = response.status().as_u16();
+        let body = response
+            .json::<Value>()
+            .await
+            .map_err(|e| InvokeError::GoogleApi(e.to_string()))?;
+        payload::log_response(url, status, &body);
+
+        if let Some(etag) = etag {
+            self.store(url, etag, body.clone());
+        }
+
+        Ok(body)
+    }
+}
+
+/// Entries beyond which inserts first drop expired entries.
+const RESPONSE_CACHE_SWEEP_LEN: usize = 1024;
+
+/// Short-lived in-memory cache of read results, for agents that re-read the
+/// same metadata many times a minute.
+///
+/// Keys start with the resource they were read from (`spreadsheet/<id>/` or
+/// `drive/`), so a write can drop everything read from what it touched with
+/// [`ResponseCache::invalidate_prefix`].
+#[derive(Debug)]
+pub struct ResponseCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Value)>>,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The value stored under `key`, unless it has expired.
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((stored, value)) if stored.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: String, value: Value) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= RESPONSE_CACHE_SWEEP_LEN {
+            entries.retain(|_, (stored, _)| stored.elapsed() < self.ttl);
+        }
+        entries.insert(key, (Instant::now(), value));
+    }
+
+    /// Drops every entry whose key starts with `prefix`.
+    pub fn invalidate_prefix(&self, prefix: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(prefix));
+    }
+}
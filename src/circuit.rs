@@ -0,0 +1,257 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use tracing::warn;
+
+use crate::{
+    client::{note_failure, TransientFailure},
+    quota::QuotaTracker,
+    InvokeError,
+};
+
+const FAILURE_THRESHOLD: u32 = 5;
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    Sheets,
+    Drive,
+    Gmail,
+    Calendar,
+    Docs,
+    Slides,
+    Tasks,
+    AppsScript,
+    Chat,
+    Meet,
+    Admin,
+    Classroom,
+    #[cfg(feature = "youtube")]
+    YouTube,
+    BigQuery,
+}
+
+impl Service {
+    /// Short name used as a metrics label.
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::Sheets => "sheets",
+            Service::Drive => "drive",
+            Service::Gmail => "gmail",
+            Service::Calendar => "calendar",
+            Service::Docs => "docs",
+            Service::Slides => "slides",
+            Service::Tasks => "tasks",
+            Service::AppsScript => "apps_script",
+            Service::Chat => "chat",
+            Service::Meet => "meet",
+            Service::Admin => "admin",
+            Service::Classroom => "classroom",
+            #[cfg(feature = "youtube")]
+            Service::YouTube => "youtube",
+            Service::BigQuery => "bigquery",
+        }
+    }
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Service::Sheets => write!(f, "Google Sheets API"),
+            Service::Drive => write!(f, "Google Drive API"),
+            Service::Gmail => write!(f, "Gmail API"),
+            Service::Calendar => write!(f, "Google Calendar API"),
+            Service::Docs => write!(f, "Google Docs API"),
+            Service::Slides => write!(f, "Google Slides API"),
+            Service::Tasks => write!(f, "Google Tasks API"),
+            Service::AppsScript => write!(f, "Google Apps Script API"),
+            Service::Chat => write!(f, "Google Chat API"),
+            Service::Meet => write!(f, "Google Meet API"),
+            Service::Admin => write!(f, "Google Admin SDK Directory API"),
+            Service::Classroom => write!(f, "Google Classroom API"),
+            #[cfg(feature = "youtube")]
+            Service::YouTube => write!(f, "YouTube Data API"),
+            Service::BigQuery => write!(f, "BigQuery API"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// When the trial call of a half-open breaker was let through
+    probe_started: Option<Instant>,
+}
+
+/// Fails fast after repeated consecutive outages of a service instead of letting
+/// every call hang through full retry cycles. After the cooldown one trial call
+/// is let through while the others keep failing fast; a success closes the
+/// breaker, a failure reopens it. A trial call that never reports back, e.g.
+/// because it was cancelled, is replaced by another after a further cooldown.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    service: Service,
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(service: Service, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            service,
+            failure_threshold,
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    pub fn for_service(service: Service) -> &'static CircuitBreaker {
+        static SHEETS: OnceLock<CircuitBreaker> = OnceLock::new();
+        static DRIVE: OnceLock<CircuitBreaker> = OnceLock::new();
+        static GMAIL: OnceLock<CircuitBreaker> = OnceLock::new();
+        static CALENDAR: OnceLock<CircuitBreaker> = OnceLock::new();
+        static DOCS: OnceLock<CircuitBreaker> = OnceLock::new();
+        static SLIDES: OnceLock<CircuitBreaker> = OnceLock::new();
+        static TASKS: OnceLock<CircuitBreaker> = OnceLock::new();
+        static APPS_SCRIPT: OnceLock<CircuitBreaker> = OnceLock::new();
+        static CHAT: OnceLock<CircuitBreaker> = OnceLock::new();
+        static MEET: OnceLock<CircuitBreaker> = OnceLock::new();
+        static ADMIN: OnceLock<CircuitBreaker> = OnceLock::new();
+        static CLASSROOM: OnceLock<CircuitBreaker> = OnceLock::new();
+        #[cfg(feature = "youtube")]
+        static YOUTUBE: OnceLock<CircuitBreaker> = OnceLock::new();
+        static BIGQUERY: OnceLock<CircuitBreaker> = OnceLock::new();
+        let cell = match service {
+            Service::Sheets => &SHEETS,
+            Service::Drive => &DRIVE,
+            Service::Gmail => &GMAIL,
+            Service::Calendar => &CALENDAR,
+            Service::Docs => &DOCS,
+            Service::Slides => &SLIDES,
+            Service::Tasks => &TASKS,
+            Service::AppsScript => &APPS_SCRIPT,
+            Service::Chat => &CHAT,
+            Service::Meet => &MEET,
+            Service::Admin => &ADMIN,
+            Service::Classroom => &CLASSROOM,
+            #[cfg(feature = "youtube")]
+            Service::YouTube => &YOUTUBE,
+            Service::BigQuery => &BIGQUERY,
+        };
+        cell.get_or_init(|| CircuitBreaker::new(service, FAILURE_THRESHOLD, COOLDOWN))
+    }
+
+    /// Returns an error while the breaker is open, and while it is half-open
+    /// for every call but the trial one.
+    pub fn check(&self) -> Result<(), InvokeError> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let Some(until) = state.open_until else {
+            return Ok(());
+        };
+        let retry_after = match state.probe_started {
+            _ if until > now => until - now,
+            Some(started) if now < started + self.cooldown => started + self.cooldown - now,
+            _ => {
+                state.probe_started = Some(now);
+                return Ok(());
+            }
+        };
+        Err(InvokeError::ServiceUnavailable {
+            service: self.service.to_string(),
+            retry_after: retry_after.as_secs().max(1),
+        })
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::default();
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.failure_threshold {
+            warn!(
+                "{} failed {} times in a row; failing fast for {}s",
+                self.service,
+                state.consecutive_failures,
+                self.cooldown.as_secs()
+            );
+            state.open_until = Some(Instant::now() + self.cooldown);
+            state.probe_started = None;
+        }
+    }
+}
+
+/// Whether an error indicates the service itself is unhealthy (connection
+/// failures, 5xx) rather than a problem with the request.
+pub(crate) fn is_outage(error: &anyhow::Error) -> bool {
+    if let Some(error) = error.downcast_ref::<google_sheets4::Error>() {
+        return match error {
+            google_sheets4::Error::HttpError(_) | google_sheets4::Error::Io(_) => true,
+            google_sheets4::Error::Failure(response) => response.status().is_server_error(),
+            google_sheets4::Error::BadRequest(value) => value["error"]["code"]
+                .as_u64()
+                .is_some_and(|code| code >= 500),
+            _ => false,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<InvokeError>() {
+        return match error {
+            InvokeError::Api { code, .. } => *code >= 500,
+            InvokeError::Transport(_) => true,
+            _ => false,
+        };
+    }
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request() || e.is_body())
+}
+
+/// Whether an error counts toward opening the breaker: outages, and Google
+/// rate-limiting the service (429).
+pub(crate) fn trips_breaker(error: &anyhow::Error) -> bool {
+    is_outage(error)
+        || matches!(
+            TransientFailure::classify(error),
+            Some(TransientFailure::RateLimited { .. })
+        )
+}
+
+/// Runs a Google API call through the service's circuit breaker, after
+/// waiting for room in the caller's quota.
+pub async fn guarded<T, E>(service: Service, call: impl Future<Output = Result<T, E>>) -> Result<T>
+where
+    E: Into<anyhow::Error>,
+{
+    let breaker = CircuitBreaker::for_service(service);
+    breaker.check()?;
+    QuotaTracker::global().acquire(service).await;
+
+    match call.await {
+        Ok(value) => {
+            breaker.record_success();
+            Ok(value)
+        }
+        Err(e) => {
+            let error = e.into();
+            note_failure(service, &error);
+            if let Some(TransientFailure::RateLimited { .. }) = TransientFailure::classify(&error) {
+                QuotaTracker::global().record_rate_limited(service);
+            }
+            if trips_breaker(&error) {
+                breaker.record_failure();
+            } else {
+                breaker.record_success();
+            }
+            Err(error)
+        }
+    }
+}
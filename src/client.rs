@@ -1,50 +1,571 @@
+use std::{
+    cell::Cell, fs::File, future::Future, io::BufReader, net::SocketAddr, path::PathBuf,
+    str::FromStr, sync::OnceLock, time::Duration,
+};
+
+use anyhow::{Context, Result};
+use google_docs1::Docs;
 use google_drive3::DriveHub;
-use google_sheets4::Sheets;
-
-pub fn get_drive_client(
-    access_token: &str,
-) -> DriveHub<
-    google_drive3::hyper_rustls::HttpsConnector<
-        google_drive3::hyper_util::client::legacy::connect::HttpConnector,
-    >,
-> {
-    let hub = DriveHub::new(
-        google_drive3::hyper_util::client::legacy::Client::builder(
-            google_drive3::hyper_util::rt::TokioExecutor::new(),
-        )
-        .build(
-            google_sheets4::hyper_rustls::HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .unwrap()
-                .https_or_http()
-                .enable_http1()
-                .build(),
-        ),
-        access_token.to_string(),
-    );
+use google_sheets4::{
+    hyper_rustls::{HttpsConnector, HttpsConnectorBuilder},
+    hyper_util::{
+        client::legacy::{connect::HttpConnector, Client},
+        rt::{TokioExecutor, TokioTimer},
+    },
+    Sheets,
+};
+
+use crate::{
+    circuit::{is_outage, Service},
+    quota::QuotaLimits,
+    InvokeError,
+};
+
+/// Pooled hyper client shared by every API hub.
+pub(crate) type HubClient = google_sheets4::common::Client<HttpsConnector<HttpConnector>>;
+
+pub type DriveClient = DriveHub<HttpsConnector<HttpConnector>>;
+pub type SheetsClient = Sheets<HttpsConnector<HttpConnector>>;
+pub type DocsClient = Docs<HttpsConnector<HttpConnector>>;
+
+/// Which trust anchors the https connectors start from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TlsRoots {
+    /// The operating system certificate store
+    #[default]
+    Native,
+    /// The Mozilla root program bundled via webpki-roots
+    Webpki,
+}
+
+impl FromStr for TlsRoots {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "native" => Ok(Self::Native),
+            "webpki" => Ok(Self::Webpki),
+            other => Err(format!(
+                "unknown TLS roots `{}` (expected native|webpki)",
+                other
+            )),
+        }
+    }
+}
+
+/// Process-wide settings for the HTTP clients talking to Google.
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    pub tls_roots: TlsRoots,
+    /// Extra PEM-encoded CA certificates, e.g. for TLS-intercepting proxies
+    pub extra_ca_certs: Vec<PathBuf>,
+    /// GCP project billed for quota (`X-Goog-User-Project`)
+    pub quota_project: Option<String>,
+    /// Per-user call rates above which Google calls are delayed
+    pub quota_limits: QuotaLimits,
+    pub pool: PoolOptions,
+    /// Overrides `https://sheets.googleapis.com/`, e.g. for private or regional endpoints
+    pub sheets_root_url: Option<String>,
+    /// Overrides `https://www.googleapis.com/` for Drive and its batch endpoint
+    pub drive_root_url: Option<String>,
+    /// Overrides `https://gmail.googleapis.com/`
+    pub gmail_root_url: Option<String>,
+    /// Overrides `https://www.googleapis.com/` for Calendar
+    pub calendar_root_url: Option<String>,
+    /// Overrides `https://docs.googleapis.com/`
+    pub docs_root_url: Option<String>,
+    /// Overrides `https://slides.googleapis.com/`
+    pub slides_root_url: Option<String>,
+    /// Overrides `https://tasks.googleapis.com/`
+    pub tasks_root_url: Option<String>,
+    /// Overrides `https://script.googleapis.com/`
+    pub apps_script_root_url: Option<String>,
+    /// Overrides `https://chat.googleapis.com/`
+    pub chat_root_url: Option<String>,
+    /// Overrides `https://meet.googleapis.com/`
+    pub meet_root_url: Option<String>,
+    /// Overrides `https://admin.googleapis.com/`
+    pub admin_root_url: Option<String>,
+    /// Overrides `https://classroom.googleapis.com/`
+    pub classroom_root_url: Option<String>,
+    /// Overrides `https://youtube.googleapis.com/`
+    #[cfg(feature = "youtube")]
+    pub youtube_root_url: Option<String>,
+    /// Overrides `https://youtubeanalytics.googleapis.com/`
+    #[cfg(feature = "youtube")]
+    pub youtube_analytics_root_url: Option<String>,
+    /// Overrides `https://bigquery.googleapis.com/`
+    pub bigquery_root_url: Option<String>,
+}
+
+/// Connection pool tuning for the shared HTTP clients.
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// Idle connections are closed after this long, so quiet sessions hold no sockets
+    pub idle_timeout: Duration,
+    pub max_idle_per_host: usize,
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(90),
+            max_idle_per_host: 32,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+static CLIENT_OPTIONS: OnceLock<ClientOptions> = OnceLock::new();
+static TLS_CONFIG: OnceLock<rustls::ClientConfig> = OnceLock::new();
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+static HUB_CLIENT: OnceLock<HubClient> = OnceLock::new();
+
+/// Installs the client options. Must be called before the first Google call;
+/// trust roots, certificate files and the HTTP clients are built eagerly so
+/// misconfiguration fails at startup.
+pub fn init_client_options(options: ClientOptions) -> Result<()> {
+    let config = build_tls_config(&options)?;
+    let http = build_http_client(&options, config.clone())?;
+    let hub = build_hub_client(&options, config.clone());
+    let _ = TLS_CONFIG.set(config);
+    let _ = HTTP_CLIENT.set(http);
+    let _ = HUB_CLIENT.set(hub);
+    let _ = CLIENT_OPTIONS.set(options);
+    Ok(())
+}
+
+fn client_options() -> &'static ClientOptions {
+    CLIENT_OPTIONS.get_or_init(ClientOptions::default)
+}
+
+pub fn quota_project() -> Option<&'static str> {
+    client_options().quota_project.as_deref()
+}
+
+pub fn quota_limits() -> &'static QuotaLimits {
+    &client_options().quota_limits
+}
+
+/// Attributes a hub call to the configured quota project. The generated hubs
+/// cannot set custom headers, so this uses the equivalent `$userProject`
+/// system parameter.
+macro_rules! with_quota_project {
+    ($call:expr) => {
+        match $crate::client::quota_project() {
+            Some(project) => $call.param("$userProject", project),
+            None => $call,
+        }
+    };
+}
+pub(crate) use with_quota_project;
+
+fn load_pem_certs(path: &PathBuf) -> Result<Vec<rustls::pki_types::CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("opening CA bundle {}", path.display()))?;
+    rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("parsing CA bundle {}", path.display()))
+}
+
+fn build_tls_config(options: &ClientOptions) -> Result<rustls::ClientConfig> {
+    let mut roots = rustls::RootCertStore::empty();
+    match options.tls_roots {
+        TlsRoots::Native => {
+            // Like hyper-rustls, skip OS certificates rustls cannot parse
+            roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+            if roots.is_empty() && options.extra_ca_certs.is_empty() {
+                anyhow::bail!(
+                    "no CA certificates found in the OS store; use --tls-roots webpki or --ca-cert"
+                );
+            }
+        }
+        TlsRoots::Webpki => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+    for path in &options.extra_ca_certs {
+        for cert in load_pem_certs(path)? {
+            roots.add(cert)?;
+        }
+    }
+
+    let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+    Ok(rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth())
+}
+
+/// The TLS settings shared by reqwest and the hub connectors, so both trust
+/// the same roots. Built from the default options when `init_client_options`
+/// was never called, as in tests.
+fn tls_config() -> Result<rustls::ClientConfig> {
+    if let Some(config) = TLS_CONFIG.get() {
+        return Ok(config.clone());
+    }
+    let config = build_tls_config(client_options())?;
+    Ok(TLS_CONFIG.get_or_init(|| config).clone())
+}
+
+fn build_http_client(
+    options: &ClientOptions,
+    tls: rustls::ClientConfig,
+) -> Result<reqwest::Client> {
+    // Google only compresses responses when the user agent mentions gzip
+    let pool = &options.pool;
+    let mut builder = reqwest::Client::builder()
+        .use_preconfigured_tls(tls)
+        .gzip(true)
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION"),
+            " (gzip)"
+        ))
+        .pool_idle_timeout(pool.idle_timeout)
+        .pool_max_idle_per_host(pool.max_idle_per_host)
+        .tcp_keepalive(pool.tcp_keepalive);
+    if let Some(project) = &options.quota_project {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            "x-goog-user-project",
+            project
+                .parse()
+                .with_context(|| format!("invalid quota project `{}`", project))?,
+        );
+        builder = builder.default_headers(headers);
+    }
+    builder.build().context("building the HTTP client")
+}
+
+/// Shared reqwest client for raw Google REST calls made outside the generated hubs.
+pub fn get_http_client() -> Result<&'static reqwest::Client> {
+    if let Some(client) = HTTP_CLIENT.get() {
+        return Ok(client);
+    }
+    let client = build_http_client(client_options(), tls_config()?)?;
+    Ok(HTTP_CLIENT.get_or_init(|| client))
+}
+
+/// A client for fetching caller-supplied URLs: the shared TLS roots, but no
+/// proxy, no Google headers and no redirects followed, with `domain` pinned
+/// to the vetted `addr` so a second DNS answer cannot point elsewhere.
+pub(crate) fn pinned_client(domain: Option<&str>, addr: SocketAddr) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .use_preconfigured_tls(tls_config()?)
+        .no_proxy()
+        .redirect(reqwest::redirect::Policy::none())
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ));
+    if let Some(domain) = domain {
+        builder = builder.resolve(domain, addr);
+    }
+    builder.build().context("building the HTTP client")
+}
+
+/// Builds the TLS connector used by the API hubs. Both HTTP/1.1 and HTTP/2 are
+/// offered over ALPN so concurrent calls to googleapis.com can share a connection.
+fn https_connector(
+    options: &ClientOptions,
+    tls: rustls::ClientConfig,
+) -> HttpsConnector<HttpConnector> {
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    http.set_keepalive(options.pool.tcp_keepalive);
+
+    HttpsConnectorBuilder::new()
+        .with_tls_config(tls)
+        .https_or_http()
+        .enable_http1()
+        .enable_http2()
+        .wrap_connector(http)
+}
+
+fn build_hub_client(options: &ClientOptions, tls: rustls::ClientConfig) -> HubClient {
+    let pool = &options.pool;
+    Client::builder(TokioExecutor::new())
+        .pool_timer(TokioTimer::new())
+        .pool_idle_timeout(pool.idle_timeout)
+        .pool_max_idle_per_host(pool.max_idle_per_host)
+        .build(https_connector(options, tls))
+}
+
+/// The pooled client behind every hub.
+///
+/// Hub calls are not compressed: the hubs hold this concrete hyper client and
+/// read response bodies themselves, leaving nowhere to decode gzip. Reads that
+/// can be large (`read_values`, `batch_read_values`, `get_sheet_info`,
+/// `list_files` and CSV streaming) therefore use [`get_http_client`] instead.
+pub(crate) fn hub_client() -> Result<HubClient> {
+    if let Some(client) = HUB_CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = build_hub_client(client_options(), tls_config()?);
+    Ok(HUB_CLIENT.get_or_init(|| client).clone())
+}
+
+/// Resolves a service root URL: mock backend first, then the configured
+/// override, then the public default. Always ends with `/`.
+fn root_url(configured: &Option<String>, default: &str) -> String {
+    #[cfg(feature = "mock-google")]
+    if let Some(url) = crate::mock::mock_root_url() {
+        return url;
+    }
+    match configured {
+        Some(url) if url.ends_with('/') => url.clone(),
+        Some(url) => format!("{}/", url),
+        None => default.to_string(),
+    }
+}
+
+/// Root URL of the Sheets API, e.g. `https://sheets.googleapis.com/`.
+pub fn sheets_root_url() -> String {
+    root_url(
+        &client_options().sheets_root_url,
+        "https://sheets.googleapis.com/",
+    )
+}
+
+/// Root URL of the Drive API and its batch endpoint, e.g. `https://www.googleapis.com/`.
+pub fn drive_root_url() -> String {
+    root_url(
+        &client_options().drive_root_url,
+        "https://www.googleapis.com/",
+    )
+}
+
+/// Root URL of the Gmail API, e.g. `https://gmail.googleapis.com/`.
+pub fn gmail_root_url() -> String {
+    root_url(
+        &client_options().gmail_root_url,
+        "https://gmail.googleapis.com/",
+    )
+}
+
+/// Root URL of the Calendar API, e.g. `https://www.googleapis.com/`.
+pub fn calendar_root_url() -> String {
+    root_url(
+        &client_options().calendar_root_url,
+        "https://www.googleapis.com/",
+    )
+}
+
+/// Root URL of the Docs API, e.g. `https://docs.googleapis.com/`.
+pub fn docs_root_url() -> String {
+    root_url(
+        &client_options().docs_root_url,
+        "https://docs.googleapis.com/",
+    )
+}
+
+/// Root URL of the Slides API, e.g. `https://slides.googleapis.com/`.
+pub fn slides_root_url() -> String {
+    root_url(
+        &client_options().slides_root_url,
+        "https://slides.googleapis.com/",
+    )
+}
+
+/// Root URL of the Tasks API, e.g. `https://tasks.googleapis.com/`.
+pub fn tasks_root_url() -> String {
+    root_url(
+        &client_options().tasks_root_url,
+        "https://tasks.googleapis.com/",
+    )
+}
+
+/// Root URL of the Apps Script API, e.g. `https://script.googleapis.com/`.
+pub fn apps_script_root_url() -> String {
+    root_url(
+        &client_options().apps_script_root_url,
+        "https://script.googleapis.com/",
+    )
+}
+
+/// Root URL of the Chat API, e.g. `https://chat.googleapis.com/`.
+pub fn chat_root_url() -> String {
+    root_url(
+        &client_options().chat_root_url,
+        "https://chat.googleapis.com/",
+    )
+}
+
+/// Root URL of the Meet API, e.g. `https://meet.googleapis.com/`.
+pub fn meet_root_url() -> String {
+    root_url(
+        &client_options().meet_root_url,
+        "https://meet.googleapis.com/",
+    )
+}
+
+/// Root URL of the Admin SDK, e.g. `https://admin.googleapis.com/`.
+pub fn admin_root_url() -> String {
+    root_url(
+        &client_options().admin_root_url,
+        "https://admin.googleapis.com/",
+    )
+}
+
+/// Root URL of the Classroom API, e.g. `https://classroom.googleapis.com/`.
+pub fn classroom_root_url() -> String {
+    root_url(
+        &client_options().classroom_root_url,
+        "https://classroom.googleapis.com/",
+    )
+}
+
+/// Root URL of the BigQuery API, e.g. `https://bigquery.googleapis.com/`.
+pub fn bigquery_root_url() -> String {
+    root_url(
+        &client_options().bigquery_root_url,
+        "https://bigquery.googleapis.com/",
+    )
+}
+
+/// Root URL of the YouTube Data API, e.g. `https://youtube.googleapis.com/`.
+#[cfg(feature = "youtube")]
+pub fn youtube_root_url() -> String {
+    root_url(
+        &client_options().youtube_root_url,
+        "https://youtube.googleapis.com/",
+    )
+}
+
+/// Root URL of the YouTube Analytics API, e.g.
+/// `https://youtubeanalytics.googleapis.com/`.
+#[cfg(feature = "youtube")]
+pub fn youtube_analytics_root_url() -> String {
+    root_url(
+        &client_options().youtube_analytics_root_url,
+        "https://youtubeanalytics.googleapis.com/",
+    )
+}
+
+pub fn get_drive_client(access_token: &str) -> Result<DriveClient> {
+    let mut hub = DriveHub::new(hub_client()?, access_token.to_string());
+    hub.base_url(format!("{}drive/v3/", drive_root_url()));
+    hub.root_url(drive_root_url());
+    Ok(hub)
+}
+
+pub fn get_sheets_client(access_token: &str) -> Result<SheetsClient> {
+    Ok(sheets_hub(hub_client()?, access_token))
+}
+
+pub fn get_docs_client(access_token: &str) -> Result<DocsClient> {
+    Ok(docs_hub(hub_client()?, access_token))
+}
+
+pub(crate) fn sheets_hub(client: HubClient, access_token: &str) -> SheetsClient {
+    let mut hub = Sheets::new(client, access_token.to_string());
+    hub.base_url(sheets_root_url());
+    hub.root_url(sheets_root_url());
     hub
 }
 
-pub fn get_sheets_client(
-    access_token: &str,
-) -> Sheets<
-    google_sheets4::hyper_rustls::HttpsConnector<
-        google_sheets4::hyper_util::client::legacy::connect::HttpConnector,
-    >,
-> {
-    let hub = Sheets::new(
-        google_sheets4::hyper_util::client::legacy::Client::builder(
-            google_sheets4::hyper_util::rt::TokioExecutor::new(),
-        )
-        .build(
-            google_sheets4::hyper_rustls::HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .unwrap()
-                .https_or_http()
-                .enable_http1()
-                .build(),
-        ),
-        access_token.to_string(),
-    );
+pub(crate) fn docs_hub(client: HubClient, access_token: &str) -> DocsClient {
+    let mut hub = Docs::new(client, access_token.to_string());
+    hub.base_url(docs_root_url());
+    hub.root_url(docs_root_url());
     hub
 }
+
+/// How tool calls are retried when Google rate-limits them or is briefly
+/// unavailable.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled for each further one
+    pub base_delay: Duration,
+    /// Longest backoff, and the longest `Retry-After` that is waited out
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait before retry number `attempt` (0-based): the server's
+    /// `Retry-After` when it sent one, otherwise exponential backoff with
+    /// jitter over the upper half of the interval. `None` when the server asks
+    /// for a longer wait than `max_delay`, which is better reported than slept.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_delay).then_some(retry_after);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        Some(backoff / 2 + backoff.mul_f64(rand::random::<f64>() / 2.0))
+    }
+}
+
+/// A failed Google call that might succeed if made again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransientFailure {
+    /// 401: the access token expired or was revoked
+    Unauthenticated,
+    /// 429 or a `rateLimitExceeded` 403; the request was not carried out
+    RateLimited { retry_after: Option<Duration> },
+    /// Connection failure or 5xx; the request may or may not have been applied
+    Unavailable,
+}
+
+impl TransientFailure {
+    pub fn classify(error: &anyhow::Error) -> Option<Self> {
+        let from_hub = error
+            .downcast_ref::<google_sheets4::Error>()
+            .map(InvokeError::from_hub_error);
+        match from_hub
+            .as_ref()
+            .or_else(|| error.downcast_ref::<InvokeError>())
+        {
+            Some(InvokeError::Unauthenticated { .. } | InvokeError::Api { code: 401, .. }) => {
+                Some(Self::Unauthenticated)
+            }
+            Some(InvokeError::RateLimited { retry_after, .. }) => Some(Self::RateLimited {
+                retry_after: retry_after.map(Duration::from_secs),
+            }),
+            // An open circuit breaker is our own decision to stop calling
+            Some(InvokeError::ServiceUnavailable { .. }) => None,
+            _ if is_outage(error) => Some(Self::Unavailable),
+            _ => None,
+        }
+    }
+}
+
+tokio::task_local! {
+    static LAST_FAILURE: Cell<Option<(Service, TransientFailure)>>;
+}
+
+/// Remembers a transient failure of a Google call for the enclosing
+/// `track_failures`, since tool handlers turn errors into ordinary responses.
+pub(crate) fn note_failure(service: Service, error: &anyhow::Error) {
+    if let Some(failure) = TransientFailure::classify(error) {
+        let _ = LAST_FAILURE.try_with(|last| last.set(Some((service, failure))));
+    }
+}
+
+/// Runs `call`, returning its output with the last transient failure any
+/// Google call inside it reported.
+pub(crate) async fn track_failures<T>(
+    call: impl Future<Output = T>,
+) -> (T, Option<(Service, TransientFailure)>) {
+    LAST_FAILURE
+        .scope(Cell::new(None), async {
+            let output = call.await;
+            (output, LAST_FAILURE.with(Cell::get))
+        })
+        .await
+}
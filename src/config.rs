@@ -0,0 +1,365 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::Write,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::{
+    logging::{LogFormat, LogRotation},
+    scopes::expand_scope,
+    servers::workspace::WorkspaceService,
+    token_store::TokenStoreKind,
+};
+
+/// Settings read from `config.toml` (or `config.json`). Every field is optional;
+/// command-line flags and environment variables take precedence over anything
+/// set here.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Services started by `serve` when `--services` is not given
+    pub services: Vec<WorkspaceService>,
+    pub credentials: Credentials,
+    /// Named credential sets selected with `--profile` or `--account`
+    pub profiles: HashMap<String, Credentials>,
+    pub sheets: SheetsConfig,
+    pub apps_script: AppsScriptConfig,
+    pub tools: ToolsConfig,
+    pub logging: LoggingConfig,
+    pub transport: TransportConfig,
+    /// OAuth scopes `auth login` requests per service instead of the full
+    /// default scope, e.g. `sheets = ["spreadsheets.readonly"]`
+    pub scopes: HashMap<WorkspaceService, Vec<String>>,
+    pub tokens: TokensConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Credentials {
+    /// Email of the Google account these credentials belong to
+    pub account: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub refresh_token: Option<String>,
+    /// OAuth client JSON downloaded from the Cloud console, read for whichever
+    /// of `client_id` and `client_secret` is not set
+    pub client_secrets_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokensConfig {
+    /// Where `auth login` saves refresh tokens and servers look them up
+    pub store: Option<TokenStoreKind>,
+    /// Encrypted token file used by the `file` store
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SheetsConfig {
+    /// Spreadsheet used when a request does not name one
+    pub spreadsheet_id: Option<String>,
+    /// Google Cloud project BigQuery jobs run and bill in; `sheets_to_bigquery`
+    /// is only served when set
+    pub bigquery_project: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppsScriptConfig {
+    /// Script project `run_script` calls when a request does not name one
+    pub script_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolsConfig {
+    pub read_only: Option<bool>,
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    /// Default deadline in seconds for each tool call
+    pub timeout_secs: Option<u64>,
+    /// Per-tool deadlines in seconds, keyed by tool name
+    pub timeouts: HashMap<String, u64>,
+    /// Default page size of the Drive listing tools
+    pub page_size: Option<u64>,
+    /// Seconds to cache the results of read-heavy tools; 0 or unset disables
+    pub cache_ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub level: Option<String>,
+    pub format: Option<LogFormat>,
+    pub file: Option<PathBuf>,
+    pub rotation: Option<LogRotation>,
+}
+
+/// Defaults for the `--transport`, `--host` and `--port` flags of the server commands.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransportConfig {
+    pub kind: Option<TransportKind>,
+    pub host: Option<IpAddr>,
+    pub port: Option<u16>,
+}
+
+/// How a server talks to its MCP client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    #[default]
+    Stdio,
+    Sse,
+}
+
+impl FromStr for TransportKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "stdio" => Ok(Self::Stdio),
+            "sse" => Ok(Self::Sse),
+            other => Err(format!(
+                "unknown transport `{}` (expected stdio|sse)",
+                other
+            )),
+        }
+    }
+}
+
+impl Config {
+    /// Loads `path`, or the default location when `path` is `None`. A missing
+    /// default file yields an empty config; a missing explicit file is an error.
+    /// Files ending in `.json` are read as JSON, anything else as TOML.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                Some(path) if path.with_extension("json").exists() => path.with_extension("json"),
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let config = if path.extension().is_some_and(|ext| ext == "json") {
+            Self::parse_json(&contents)
+        } else {
+            Self::parse(&contents)
+        };
+        config.with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    pub fn parse_json(contents: &str) -> Result<Self> {
+        Ok(serde_json::from_str(contents)?)
+    }
+
+    /// The OAuth scopes to request for `service`: the configured ones, with
+    /// short names expanded, or else the service's full default scopes.
+    pub fn scopes_for(&self, service: WorkspaceService) -> Vec<String> {
+        match self.scopes.get(&service) {
+            Some(scopes) if !scopes.is_empty() => {
+                scopes.iter().map(|scope| expand_scope(scope)).collect()
+            }
+            _ => service
+                .oauth_scopes()
+                .iter()
+                .map(|scope| scope.to_string())
+                .collect(),
+        }
+    }
+
+    /// Picks the credentials to use: the named profile, else the profile whose
+    /// `account` matches, else the top-level `[credentials]` table.
+    pub fn credentials_for(
+        &self,
+        profile: Option<&str>,
+        account: Option<&str>,
+    ) -> Result<(Option<&str>, &Credentials)> {
+        if let Some(name) = profile {
+            let (name, credentials) = self
+                .profiles
+                .get_key_value(name)
+                .with_context(|| format!("Profile `{}` not found in config file", name))?;
+            return Ok((Some(name.as_str()), credentials));
+        }
+
+        if let Some(account) = account {
+            let (name, credentials) = self
+                .profiles
+                .iter()
+                .find(|(_, c)| {
+                    c.account
+                        .as_deref()
+                        .is_some_and(|a| a.eq_ignore_ascii_case(account))
+                })
+                .with_context(|| format!("No profile for account `{}` in config file", account))?;
+            return Ok((Some(name.as_str()), credentials));
+        }
+
+        Ok((None, &self.credentials))
+    }
+}
+
+impl Credentials {
+    /// Fills in `client_id` and `client_secret` from `client_secrets_file`
+    /// where they are not set.
+    pub fn load_client_secrets(&mut self) -> Result<()> {
+        let Some(path) = &self.client_secrets_file else {
+            return Ok(());
+        };
+        if self.client_id.is_some() && self.client_secret.is_some() {
+            return Ok(());
+        }
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let (client_id, client_secret) = parse_client_secrets(&contents)
+            .with_context(|| format!("Invalid OAuth client file {}", path.display()))?;
+        self.client_id.get_or_insert(client_id);
+        self.client_secret.get_or_insert(client_secret);
+        Ok(())
+    }
+
+    /// The client ID, client secret and refresh token, each from these
+    /// credentials or else the `GOOGLE_CLIENT_ID`, `GOOGLE_CLIENT_SECRET` and
+    /// `GOOGLE_REFRESH_TOKEN` environment variables. `None` unless all three
+    /// are found.
+    pub fn refresh_credentials(&self) -> Option<(String, String, String)> {
+        self.refresh_credentials_with(|var| std::env::var(var).ok())
+    }
+
+    /// Like `refresh_credentials`, reading variables through `env`.
+    pub fn refresh_credentials_with(
+        &self,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<(String, String, String)> {
+        let resolve = |value: &Option<String>, var: &str| value.clone().or_else(|| env(var));
+        Some((
+            resolve(&self.client_id, "GOOGLE_CLIENT_ID")?,
+            resolve(&self.client_secret, "GOOGLE_CLIENT_SECRET")?,
+            resolve(&self.refresh_token, "GOOGLE_REFRESH_TOKEN")?,
+        ))
+    }
+}
+
+/// Reads the client ID and secret from an OAuth client JSON file, which holds
+/// them under `installed` for desktop apps or `web` for web applications.
+pub fn parse_client_secrets(contents: &str) -> Result<(String, String)> {
+    let json: serde_json::Value = serde_json::from_str(contents)?;
+    let client = json
+        .get("installed")
+        .or_else(|| json.get("web"))
+        .context("expected an `installed` or `web` client")?;
+    let field = |name: &str| {
+        client
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .with_context(|| format!("`{}` missing", name))
+    };
+    Ok((field("client_id")?, field("client_secret")?))
+}
+
+/// Stores `credentials` in the config file at `path`, as the named profile or
+/// as the top-level `[credentials]` table, keeping the rest of the file's
+/// settings. Without a `refresh_token` any old one is dropped from the table,
+/// as the token then lives in a token store. Comments are not preserved.
+pub fn save_credentials(
+    path: &Path,
+    profile: Option<&str>,
+    credentials: &Credentials,
+) -> Result<()> {
+    let mut document = match std::fs::read_to_string(path) {
+        Ok(contents) => contents
+            .parse::<toml::Table>()
+            .with_context(|| format!("Invalid config file {}", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read config file {}", path.display()))
+        }
+    };
+
+    let table = match profile {
+        Some(name) => table_entry(table_entry(&mut document, "profiles")?, name)?,
+        None => table_entry(&mut document, "credentials")?,
+    };
+    for (key, value) in [
+        ("account", &credentials.account),
+        ("client_id", &credentials.client_id),
+        ("client_secret", &credentials.client_secret),
+        ("refresh_token", &credentials.refresh_token),
+    ] {
+        if let Some(value) = value {
+            table.insert(key.to_string(), toml::Value::String(value.clone()));
+        }
+    }
+    if credentials.refresh_token.is_none() {
+        table.remove("refresh_token");
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    write_atomically(path, &toml::to_string(&document)?)
+}
+
+fn table_entry<'a>(table: &'a mut toml::Table, key: &str) -> Result<&'a mut toml::Table> {
+    table
+        .entry(key)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .with_context(|| format!("`{}` in the config file is not a table", key))
+}
+
+/// Writes `contents` to a sibling temp file and renames it over `path`, so
+/// readers never observe a half-written file. The file is only readable by
+/// its owner, as it holds secrets.
+pub fn write_atomically(path: &Path, contents: &str) -> Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options
+        .open(&tmp_path)
+        .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// `$XDG_CONFIG_HOME/mcp-google-workspace/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// `$XDG_CONFIG_HOME/mcp-google-workspace`, falling back to `~/.config`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("mcp-google-workspace"))
+}
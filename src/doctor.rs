@@ -0,0 +1,419 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use reqwest::{header, StatusCode};
+use serde_json::Value;
+
+#[cfg(feature = "youtube")]
+use crate::client::youtube_root_url;
+use crate::{
+    client::{
+        admin_root_url, apps_script_root_url, calendar_root_url, chat_root_url, classroom_root_url,
+        docs_root_url, drive_root_url, get_http_client, gmail_root_url, meet_root_url,
+        sheets_root_url, slides_root_url, tasks_root_url,
+    },
+    scopes::TOKENINFO_URL,
+    servers::workspace::WorkspaceService,
+};
+
+/// Beyond this, token validation and signed requests start failing
+const MAX_CLOCK_SKEW_SECS: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The outcome of one diagnostic, with a suggested fix when it did not pass.
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn problem(
+        status: Status,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = match self.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        write!(f, "[{:>4}] {}: {}", marker, self.name, self.detail)?;
+        if let Some(fix) = &self.fix {
+            write!(f, "\n       fix: {}", fix)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scopes that satisfy each service, broadest first.
+fn accepted_scopes(service: WorkspaceService) -> &'static [&'static str] {
+    match service {
+        WorkspaceService::Sheets => &[
+            "https://www.googleapis.com/auth/spreadsheets",
+            "https://www.googleapis.com/auth/drive",
+            "https://www.googleapis.com/auth/spreadsheets.readonly",
+            "https://www.googleapis.com/auth/drive.readonly",
+        ],
+        WorkspaceService::Drive => &[
+            "https://www.googleapis.com/auth/drive",
+            "https://www.googleapis.com/auth/drive.file",
+            "https://www.googleapis.com/auth/drive.readonly",
+            "https://www.googleapis.com/auth/drive.metadata.readonly",
+        ],
+        WorkspaceService::Gmail => &[
+            "https://mail.google.com/",
+            "https://www.googleapis.com/auth/gmail.modify",
+            "https://www.googleapis.com/auth/gmail.readonly",
+            "https://www.googleapis.com/auth/gmail.send",
+        ],
+        WorkspaceService::Calendar => &[
+            "https://www.googleapis.com/auth/calendar",
+            "https://www.googleapis.com/auth/calendar.events",
+            "https://www.googleapis.com/auth/calendar.readonly",
+        ],
+        WorkspaceService::Docs => &[
+            "https://www.googleapis.com/auth/documents",
+            "https://www.googleapis.com/auth/drive",
+            "https://www.googleapis.com/auth/drive.file",
+            "https://www.googleapis.com/auth/documents.readonly",
+            "https://www.googleapis.com/auth/drive.readonly",
+        ],
+        WorkspaceService::Slides => &[
+            "https://www.googleapis.com/auth/presentations",
+            "https://www.googleapis.com/auth/drive",
+            "https://www.googleapis.com/auth/drive.file",
+            "https://www.googleapis.com/auth/presentations.readonly",
+            "https://www.googleapis.com/auth/drive.readonly",
+        ],
+        WorkspaceService::Tasks => &[
+            "https://www.googleapis.com/auth/tasks",
+            "https://www.googleapis.com/auth/tasks.readonly",
+        ],
+        WorkspaceService::AppsScript => &[
+            "https://www.googleapis.com/auth/spreadsheets",
+            "https://www.googleapis.com/auth/drive",
+            "https://www.googleapis.com/auth/documents",
+        ],
+        WorkspaceService::Chat => &[
+            "https://www.googleapis.com/auth/chat.messages",
+            "https://www.googleapis.com/auth/chat.messages.readonly",
+            "https://www.googleapis.com/auth/chat.messages.create",
+        ],
+        WorkspaceService::Meet => &[
+            "https://www.googleapis.com/auth/meetings.space.readonly",
+            "https://www.googleapis.com/auth/meetings.space.created",
+        ],
+        WorkspaceService::Admin => &[
+            "https://www.googleapis.com/auth/admin.directory.user.readonly",
+            "https://www.googleapis.com/auth/admin.directory.user",
+            "https://www.googleapis.com/auth/admin.directory.group.readonly",
+            "https://www.googleapis.com/auth/admin.directory.group.member",
+            "https://www.googleapis.com/auth/admin.directory.group",
+        ],
+        WorkspaceService::Classroom => &[
+            "https://www.googleapis.com/auth/classroom.courses.readonly",
+            "https://www.googleapis.com/auth/classroom.courses",
+            "https://www.googleapis.com/auth/classroom.coursework.students",
+            "https://www.googleapis.com/auth/classroom.coursework.students.readonly",
+            "https://www.googleapis.com/auth/classroom.announcements",
+        ],
+        #[cfg(feature = "youtube")]
+        WorkspaceService::YouTube => &[
+            "https://www.googleapis.com/auth/youtube",
+            "https://www.googleapis.com/auth/youtube.force-ssl",
+            "https://www.googleapis.com/auth/youtube.readonly",
+            "https://www.googleapis.com/auth/yt-analytics.readonly",
+        ],
+    }
+}
+
+/// Runs every diagnostic for `services`. Token-dependent checks are reported as
+/// failures when no access token could be obtained.
+pub async fn run_checks(access_token: Option<&str>, services: &[WorkspaceService]) -> Vec<Check> {
+    let mut checks = vec![check_network_and_clock().await];
+
+    let Some(access_token) = access_token else {
+        checks.push(Check::problem(
+            Status::Fail,
+            "credentials",
+            "no access token or refresh credentials available",
+            "pass --access-token, set GOOGLE_ACCESS_TOKEN, or configure client_id, client_secret and refresh_token",
+        ));
+        return checks;
+    };
+
+    let scopes = match check_token(access_token).await {
+        Ok((check, scopes)) => {
+            checks.push(check);
+            scopes
+        }
+        Err(check) => {
+            checks.push(check);
+            return checks;
+        }
+    };
+
+    for service in services {
+        checks.push(check_scopes(*service, &scopes));
+        checks.push(check_api_enabled(*service, access_token).await);
+    }
+    checks
+}
+
+/// The shared HTTP client, or a failed check named `name` when the TLS
+/// settings cannot be loaded.
+fn http_client(name: &str) -> Result<&'static reqwest::Client, Check> {
+    get_http_client().map_err(|e| {
+        Check::problem(
+            Status::Fail,
+            name,
+            format!("cannot set up TLS: {:#}", e),
+            "check --tls-roots and the files given with --ca-cert",
+        )
+    })
+}
+
+async fn check_network_and_clock() -> Check {
+    let client = match http_client("network") {
+        Ok(client) => client,
+        Err(check) => return check,
+    };
+    let response = match client.head(sheets_root_url()).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return Check::problem(
+                Status::Fail,
+                "network",
+                format!("cannot reach {}: {}", sheets_root_url(), e),
+                "check connectivity, proxy settings (HTTPS_PROXY) and --ca-cert for TLS-intercepting proxies",
+            )
+        }
+    };
+
+    let server_time = response
+        .headers()
+        .get(header::DATE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| DateTime::parse_from_rfc2822(v).ok());
+    let Some(server_time) = server_time else {
+        return Check::ok(
+            "network",
+            "Google APIs reachable (no Date header to check clock skew)",
+        );
+    };
+
+    let skew = (Utc::now() - server_time.with_timezone(&Utc)).num_seconds();
+    if skew.abs() > MAX_CLOCK_SKEW_SECS {
+        Check::problem(
+            Status::Warn,
+            "clock",
+            format!("local clock is {}s off Google's", skew),
+            "enable NTP time synchronisation (e.g. `timedatectl set-ntp true`)",
+        )
+    } else {
+        Check::ok(
+            "network",
+            format!("Google APIs reachable, clock skew {}s", skew),
+        )
+    }
+}
+
+async fn check_token(access_token: &str) -> Result<(Check, Vec<String>), Check> {
+    let response = http_client("credentials")?
+        .get(TOKENINFO_URL)
+        .query(&[("access_token", access_token)])
+        .send()
+        .await
+        .map_err(|e| {
+            Check::problem(
+                Status::Fail,
+                "credentials",
+                format!("token check failed: {}", e),
+                "retry once network access to oauth2.googleapis.com works",
+            )
+        })?;
+
+    if !response.status().is_success() {
+        return Err(Check::problem(
+            Status::Fail,
+            "credentials",
+            "access token is invalid or expired",
+            "run `mcp-google refresh` for a new token, or re-authorise if the refresh token was revoked",
+        ));
+    }
+
+    let info = response.json::<Value>().await.unwrap_or_default();
+    let scopes = info["scope"]
+        .as_str()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let expires_in = info["expires_in"]
+        .as_str()
+        .and_then(|s| s.parse::<u64>().ok())
+        .or_else(|| info["expires_in"].as_u64())
+        .unwrap_or(0);
+    let who = info["email"].as_str().unwrap_or("unknown account");
+
+    Ok((
+        Check::ok(
+            "credentials",
+            format!("token valid for {} ({}s left)", who, expires_in),
+        ),
+        scopes,
+    ))
+}
+
+pub(crate) fn check_scopes(service: WorkspaceService, granted: &[String]) -> Check {
+    let name = format!("{} scopes", service);
+    let accepted = accepted_scopes(service);
+    match accepted.iter().find(|s| granted.iter().any(|g| g == *s)) {
+        Some(scope) => Check::ok(name, format!("granted {}", scope)),
+        None => Check::problem(
+            Status::Fail,
+            name,
+            format!(
+                "none of the required scopes granted (have: {})",
+                granted.join(" ")
+            ),
+            format!("re-authorise with the {} scope", accepted[0]),
+        ),
+    }
+}
+
+async fn check_api_enabled(service: WorkspaceService, access_token: &str) -> Check {
+    let name = format!("{} API", service);
+    // A probe that succeeds (Drive, Gmail, Calendar, Tasks, Chat, Meet, Admin,
+    // Classroom) or 404s (Sheets, Docs, Slides, Apps Script) proves the API is enabled
+    let url = match service {
+        WorkspaceService::Sheets => format!("{}v4/spreadsheets/doctor-probe", sheets_root_url()),
+        WorkspaceService::Drive => format!("{}drive/v3/about?fields=user", drive_root_url()),
+        WorkspaceService::Gmail => format!("{}gmail/v1/users/me/profile", gmail_root_url()),
+        WorkspaceService::Calendar => format!(
+            "{}calendar/v3/users/me/calendarList?maxResults=1",
+            calendar_root_url()
+        ),
+        WorkspaceService::Docs => format!("{}v1/documents/doctor-probe", docs_root_url()),
+        WorkspaceService::Slides => {
+            format!("{}v1/presentations/doctor-probe", slides_root_url())
+        }
+        WorkspaceService::Tasks => {
+            format!("{}tasks/v1/users/@me/lists?maxResults=1", tasks_root_url())
+        }
+        WorkspaceService::AppsScript => {
+            format!("{}v1/projects/doctor-probe", apps_script_root_url())
+        }
+        WorkspaceService::Chat => format!("{}v1/spaces?pageSize=1", chat_root_url()),
+        WorkspaceService::Meet => {
+            format!("{}v2/conferenceRecords?pageSize=1", meet_root_url())
+        }
+        WorkspaceService::Admin => format!(
+            "{}admin/directory/v1/users?customer=my_customer&maxResults=1",
+            admin_root_url()
+        ),
+        WorkspaceService::Classroom => format!("{}v1/courses?pageSize=1", classroom_root_url()),
+        #[cfg(feature = "youtube")]
+        WorkspaceService::YouTube => format!(
+            "{}youtube/v3/channels?part=id&mine=true",
+            youtube_root_url()
+        ),
+    };
+
+    let client = match http_client(&name) {
+        Ok(client) => client,
+        Err(check) => return check,
+    };
+    let response = match client.get(&url).bearer_auth(access_token).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return Check::problem(
+                Status::Fail,
+                name,
+                format!("request failed: {}", e),
+                "check network access to the API endpoint",
+            )
+        }
+    };
+
+    let status = response.status();
+    if status.is_success() || status == StatusCode::NOT_FOUND {
+        return Check::ok(name, "enabled");
+    }
+
+    let body = response.json::<Value>().await.unwrap_or_default();
+    let message = body["error"]["message"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let disabled = body["error"]["details"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|d| d["reason"] == "SERVICE_DISABLED")
+        || body["error"]["errors"][0]["reason"] == "accessNotConfigured";
+
+    if disabled {
+        Check::problem(
+            Status::Fail,
+            name,
+            "API is not enabled for this project",
+            format!(
+                "enable it with `gcloud services enable {}`",
+                match service {
+                    WorkspaceService::Sheets => "sheets.googleapis.com",
+                    WorkspaceService::Drive => "drive.googleapis.com",
+                    WorkspaceService::Gmail => "gmail.googleapis.com",
+                    WorkspaceService::Calendar => "calendar-json.googleapis.com",
+                    WorkspaceService::Docs => "docs.googleapis.com",
+                    WorkspaceService::Slides => "slides.googleapis.com",
+                    WorkspaceService::Tasks => "tasks.googleapis.com",
+                    WorkspaceService::AppsScript => "script.googleapis.com",
+                    WorkspaceService::Chat => "chat.googleapis.com",
+                    WorkspaceService::Meet => "meet.googleapis.com",
+                    WorkspaceService::Admin => "admin.googleapis.com",
+                    WorkspaceService::Classroom => "classroom.googleapis.com",
+                    #[cfg(feature = "youtube")]
+                    WorkspaceService::YouTube => "youtube.googleapis.com",
+                }
+            ),
+        )
+    } else {
+        Check::problem(
+            Status::Warn,
+            name,
+            format!("probe returned {}: {}", status, message),
+            "check the token's scopes and the project's API quota",
+        )
+    }
+}
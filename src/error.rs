@@ -0,0 +1,216 @@
+use reqwest::{header, Response};
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum InvokeError {
+    #[error("Serde error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Environment variable missing: {0}")]
+    EnvVarMissing(String),
+
+    #[error("Google API error: {0}")]
+    GoogleApi(String),
+
+    /// Google could not be reached, or the connection broke mid-response
+    #[error("Could not reach Google: {0}")]
+    Transport(String),
+
+    #[error("Token parse error: {0}")]
+    TokenParse(String),
+
+    #[error("User info error: {0}")]
+    UserInfo(String),
+
+    #[error("JWT error: {0}")]
+    Jwt(String),
+
+    #[error("Rate limited by Google{}: {message}", fmt_retry_after(.retry_after))]
+    RateLimited {
+        retry_after: Option<u64>,
+        message: String,
+    },
+
+    #[error("Not found: {resource}")]
+    NotFound { resource: String },
+
+    #[error("Permission denied: {message}")]
+    PermissionDenied { message: String },
+
+    #[error("Unauthenticated: {message}")]
+    Unauthenticated { message: String },
+
+    #[error("{service} unavailable, retry after {retry_after}s")]
+    ServiceUnavailable { service: String, retry_after: u64 },
+
+    #[error("Google API error {code} ({status}): {message}{}", fmt_reason(.reason))]
+    Api {
+        code: u16,
+        status: String,
+        message: String,
+        reason: Option<String>,
+        domain: Option<String>,
+        help_links: Vec<String>,
+    },
+}
+
+fn fmt_retry_after(retry_after: &Option<u64>) -> String {
+    retry_after
+        .map(|secs| format!(", retry after {}s", secs))
+        .unwrap_or_default()
+}
+
+fn fmt_reason(reason: &Option<String>) -> String {
+    reason
+        .as_ref()
+        .map(|r| format!(" [{}]", r))
+        .unwrap_or_default()
+}
+
+/// Google's structured error envelope: `{"error": {"code", "status", "message", "errors", "details"}}`.
+#[derive(Debug, Default, Deserialize)]
+struct GoogleErrorBody {
+    #[serde(default)]
+    code: u16,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    errors: Vec<GoogleErrorItem>,
+    #[serde(default)]
+    details: Vec<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GoogleErrorItem {
+    reason: Option<String>,
+    domain: Option<String>,
+}
+
+impl InvokeError {
+    /// Classifies a Google error payload. `retry_after` comes from the
+    /// `Retry-After` header when available and overrides any `RetryInfo` detail.
+    pub fn from_google_json(value: &Value, http_status: u16, retry_after: Option<u64>) -> Self {
+        let error = match value.get("error") {
+            // OAuth endpoints reply with {"error": "invalid_grant", "error_description": ...}
+            Some(Value::String(code)) => {
+                let message = value
+                    .get("error_description")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(code)
+                    .to_string();
+                return match http_status {
+                    400 | 401 => Self::Unauthenticated { message },
+                    _ => Self::GoogleApi(message),
+                };
+            }
+            Some(error) => GoogleErrorBody::deserialize(error).unwrap_or_default(),
+            None => return Self::GoogleApi(value.to_string()),
+        };
+
+        let code = if error.code != 0 {
+            error.code
+        } else {
+            http_status
+        };
+        let reason = error
+            .errors
+            .iter()
+            .find_map(|e| e.reason.clone())
+            .or_else(|| {
+                error
+                    .details
+                    .iter()
+                    .find_map(|d| d.get("reason").and_then(|r| r.as_str()).map(String::from))
+            });
+        let domain = error.errors.iter().find_map(|e| e.domain.clone());
+        let help_links = error
+            .details
+            .iter()
+            .filter_map(|d| d.get("links").and_then(|l| l.as_array()))
+            .flatten()
+            .filter_map(|l| l.get("url").and_then(|u| u.as_str()).map(String::from))
+            .collect::<Vec<_>>();
+        let retry_after = retry_after.or_else(|| {
+            error.details.iter().find_map(|d| {
+                d.get("retryDelay")
+                    .and_then(|r| r.as_str())
+                    .and_then(|r| r.trim_end_matches('s').parse::<f64>().ok())
+                    .map(|secs| secs.ceil() as u64)
+            })
+        });
+
+        let rate_limited = code == 429
+            || error.status == "RESOURCE_EXHAUSTED"
+            || matches!(
+                reason.as_deref(),
+                Some("rateLimitExceeded" | "userRateLimitExceeded" | "RATE_LIMIT_EXCEEDED")
+            );
+
+        if rate_limited {
+            Self::RateLimited {
+                retry_after,
+                message: error.message,
+            }
+        } else if code == 404 || error.status == "NOT_FOUND" {
+            Self::NotFound {
+                resource: error.message,
+            }
+        } else if code == 401 || error.status == "UNAUTHENTICATED" {
+            Self::Unauthenticated {
+                message: error.message,
+            }
+        } else if code == 403 || error.status == "PERMISSION_DENIED" {
+            Self::PermissionDenied {
+                message: error.message,
+            }
+        } else {
+            Self::Api {
+                code,
+                status: error.status,
+                message: error.message,
+                reason,
+                domain,
+                help_links,
+            }
+        }
+    }
+
+    /// Converts a non-success reqwest response into a typed error.
+    pub async fn from_response(response: Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok());
+        let text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value) => Self::from_google_json(&value, status, retry_after),
+            Err(_) => Self::GoogleApi(text),
+        }
+    }
+
+    /// Converts an error returned by the generated API hubs.
+    pub fn from_hub_error(error: &google_sheets4::Error) -> Self {
+        match error {
+            google_sheets4::Error::BadRequest(value) => Self::from_google_json(value, 400, None),
+            google_sheets4::Error::Failure(response) => Self::Api {
+                code: response.status().as_u16(),
+                status: response.status().to_string(),
+                message: "request failed".to_string(),
+                reason: None,
+                domain: None,
+                help_links: Vec::new(),
+            },
+            other => Self::GoogleApi(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use reqwest::Response;
+use serde::Deserialize;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use url::Url;
+
+use crate::{
+    circuit::{guarded, Service},
+    client::{drive_root_url, get_http_client},
+    payload, InvokeError,
+};
+
+/// Prefix of the MIME types of Docs, Sheets, Slides and other native files,
+/// which have no bytes of their own and must be exported to a concrete format.
+pub const GOOGLE_APPS_MIME_PREFIX: &str = "application/vnd.google-apps.";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMetadata {
+    pub id: String,
+    pub name: String,
+    pub mime_type: String,
+}
+
+impl FileMetadata {
+    pub fn is_google_native(&self) -> bool {
+        self.mime_type.starts_with(GOOGLE_APPS_MIME_PREFIX)
+    }
+}
+
+/// Maps a short format name to the MIME type Drive exports it as.
+pub fn export_mime_type(format: &str) -> Option<&'static str> {
+    Some(match format.to_ascii_lowercase().as_str() {
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ods" => "application/vnd.oasis.opendocument.spreadsheet",
+        "csv" => "text/csv",
+        "tsv" => "text/tab-separated-values",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        "md" => "text/markdown",
+        "txt" => "text/plain",
+        "html" => "text/html",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "pdf" => "application/pdf",
+        _ => return None,
+    })
+}
+
+/// The format a native file is exported as when none is requested.
+pub fn default_export_format(mime_type: &str) -> &'static str {
+    match mime_type.strip_prefix(GOOGLE_APPS_MIME_PREFIX) {
+        Some("spreadsheet") => "xlsx",
+        Some("document") => "docx",
+        Some("presentation") => "pptx",
+        _ => "pdf",
+    }
+}
+
+async fn send(access_token: &str, url: Url) -> Result<Response> {
+    let client = get_http_client()?;
+    guarded(Service::Drive, async {
+        payload::log_request("GET", url.as_str(), None);
+        let response = client
+            .get(url)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(InvokeError::from_response(response).await);
+        }
+        Ok(response)
+    })
+    .await
+}
+
+pub async fn get_metadata(access_token: &str, file_id: &str) -> Result<FileMetadata> {
+    let mut url = Url::parse(&format!(
+        "{}drive/v3/files/{}",
+        drive_root_url(),
+        urlencoding::encode(file_id)
+    ))?;
+    url.query_pairs_mut()
+        .append_pair("fields", "id,name,mimeType")
+        .append_pair("supportsAllDrives", "true");
+
+    Ok(send(access_token, url).await?.json().await?)
+}
+
+/// Starts downloading a file's content: native files are exported as `format`
+/// (or their default format), binary files are fetched as-is. Returns the
+/// response to stream from and the file extension to save it under.
+pub async fn open_download(
+    access_token: &str,
+    file: &FileMetadata,
+    format: Option<&str>,
+) -> Result<(Response, Option<String>)> {
+    let base = format!(
+        "{}drive/v3/files/{}",
+        drive_root_url(),
+        urlencoding::encode(&file.id)
+    );
+
+    if !file.is_google_native() {
+        let mut url = Url::parse(&base)?;
+        url.query_pairs_mut()
+            .append_pair("alt", "media")
+            .append_pair("supportsAllDrives", "true");
+        return Ok((send(access_token, url).await?, None));
+    }
+
+    let format = format.unwrap_or_else(|| default_export_format(&file.mime_type));
+    let mime_type = export_mime_type(format)
+        .with_context(|| format!("Unsupported export format `{}`", format))?;
+    let mut url = Url::parse(&format!("{}/export", base))?;
+    url.query_pairs_mut().append_pair("mimeType", mime_type);
+
+    Ok((
+        send(access_token, url).await?,
+        Some(format.to_ascii_lowercase()),
+    ))
+}
+
+/// A file's content read fully into memory.
+#[derive(Debug)]
+pub struct Download {
+    pub file: FileMetadata,
+    /// Set when a native file was exported
+    pub format: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+/// Downloads or exports `file_id` into memory, failing once the content grows
+/// beyond `max_bytes` so large files are written to disk instead.
+pub async fn download_bytes(
+    access_token: &str,
+    file_id: &str,
+    format: Option<&str>,
+    max_bytes: usize,
+) -> Result<Download> {
+    let file = get_metadata(access_token, file_id).await?;
+    let (response, format) = open_download(access_token, &file, format).await?;
+    let Some(bytes) = read_limited(response, max_bytes).await? else {
+        anyhow::bail!(
+            "{} is larger than {} bytes; pass out_dir to save it to disk instead",
+            file.name,
+            max_bytes
+        );
+    };
+
+    Ok(Download {
+        file,
+        format,
+        bytes,
+    })
+}
+
+/// Downloads the content of one revision of a binary (non-native) file.
+pub async fn download_revision(
+    access_token: &str,
+    file_id: &str,
+    revision_id: &str,
+    max_bytes: usize,
+) -> Result<Vec<u8>> {
+    let mut url = Url::parse(&format!(
+        "{}drive/v3/files/{}/revisions/{}",
+        drive_root_url(),
+        urlencoding::encode(file_id),
+        urlencoding::encode(revision_id)
+    ))?;
+    url.query_pairs_mut().append_pair("alt", "media");
+
+    read_limited(send(access_token, url).await?, max_bytes)
+        .await?
+        .with_context(|| {
+            format!(
+                "revision {} is larger than {} bytes",
+                revision_id, max_bytes
+            )
+        })
+}
+
+/// Reads a response body into memory, giving up with `None` as soon as it
+/// grows beyond `max_bytes`.
+pub async fn read_limited(mut response: Response, max_bytes: usize) -> Result<Option<Vec<u8>>> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| InvokeError::Transport(e.to_string()))?
+    {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(bytes))
+}
+
+/// Replaces characters that are unsafe in file names on common platforms.
+pub fn sanitize_file_name(name: &str) -> String {
+    let sanitized = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    match sanitized.trim() {
+        "" | "." | ".." => "untitled".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// The name to save a download of `file` under: its sanitized Drive name,
+/// with the export format's extension added when it lacks one.
+pub fn file_name(file: &FileMetadata, extension: Option<&str>) -> String {
+    let mut name = sanitize_file_name(&file.name);
+    if let Some(extension) = extension {
+        if !name
+            .to_ascii_lowercase()
+            .ends_with(&format!(".{}", extension))
+        {
+            name = format!("{}.{}", name, extension);
+        }
+    }
+    name
+}
+
+/// Creates `path` for writing. Existing files are refused unless `overwrite`
+/// is set, and symlinks always are, so a download never writes through one.
+pub(crate) async fn open_output(path: &Path, overwrite: bool) -> Result<tokio::fs::File> {
+    if let Ok(metadata) = tokio::fs::symlink_metadata(path).await {
+        if metadata.file_type().is_symlink() {
+            anyhow::bail!(
+                "{} is a symlink; refusing to write through it",
+                path.display()
+            );
+        }
+        if !overwrite {
+            anyhow::bail!(
+                "{} already exists; set overwrite to replace it",
+                path.display()
+            );
+        }
+    }
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    options
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to create {}", path.display()))
+}
+
+/// Resolves a caller's `out_dir` within the download directory `root`.
+/// Relative directories are taken from `root`, and whatever part already
+/// exists is canonicalized, so neither `..` nor symlinks lead outside it.
+pub fn resolve_out_dir(out_dir: &Path, root: Option<&Path>) -> Result<PathBuf> {
+    let root = root
+        .context("writing downloads to disk is disabled; start the server with --download-dir")?;
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("download directory {}", root.display()))?;
+
+    // Directories still to be created cannot be symlinks, but must not climb out
+    let mut existing = root.join(out_dir);
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                missing.push(name.to_owned());
+                existing = parent.to_path_buf();
+            }
+            _ => anyhow::bail!("invalid out_dir `{}`", out_dir.display()),
+        }
+    }
+    let mut dir = existing
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", existing.display()))?;
+    if !dir.starts_with(&root) {
+        anyhow::bail!("{} is outside the download directory", out_dir.display());
+    }
+    dir.extend(missing.iter().rev());
+    Ok(dir)
+}
+
+/// Downloads or exports `file_id` into `out_dir`, streaming the body to disk.
+/// An existing file of the same name is only replaced with `overwrite`.
+/// Returns the path written.
+pub async fn download_to_dir(
+    access_token: &str,
+    file_id: &str,
+    format: Option<&str>,
+    out_dir: &Path,
+    overwrite: bool,
+) -> Result<PathBuf> {
+    let file = get_metadata(access_token, file_id).await?;
+    let (mut response, extension) = open_download(access_token, &file, format).await?;
+
+    let name = file_name(&file, extension.as_deref());
+
+    tokio::fs::create_dir_all(out_dir)
+        .await
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    let path = out_dir.join(name);
+    let mut out = open_output(&path, overwrite).await?;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| InvokeError::Transport(e.to_string()))?
+    {
+        out.write_all(&chunk).await?;
+    }
+    out.flush().await?;
+
+    Ok(path)
+}
@@ -1,32 +1,37 @@
+pub mod a1;
+pub mod audit;
 mod auth;
+pub mod batch;
+pub mod bigquery;
+pub mod cache;
+pub mod circuit;
 pub mod client;
+pub mod config;
+pub mod doctor;
+mod error;
+pub mod export;
+pub mod links;
 pub mod logging;
+pub mod markdown;
+pub mod metrics;
+#[cfg(feature = "mock-google")]
+pub mod mock;
+pub mod payload;
+pub mod query;
+pub mod quota;
+pub mod redact;
+pub mod relay;
+#[cfg(feature = "mock-google")]
+pub mod replay;
+pub mod scopes;
 pub mod servers;
+pub mod streaming;
+pub mod token_store;
+pub mod upload;
 
 #[cfg(test)]
 mod tests;
 
 // Re-export servers
-pub use auth::GoogleAuthService;
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-pub enum InvokeError {
-    #[error("Serde error: {0}")]
-    Serde(#[from] serde_json::Error),
-
-    #[error("Environment variable missing: {0}")]
-    EnvVarMissing(String),
-
-    #[error("Google API error: {0}")]
-    GoogleApi(String),
-
-    #[error("Token parse error: {0}")]
-    TokenParse(String),
-
-    #[error("User info error: {0}")]
-    UserInfo(String),
-
-    #[error("JWT error: {0}")]
-    Jwt(String),
-}
+pub use auth::{GoogleAuthService, TokenProvider};
+pub use error::InvokeError;
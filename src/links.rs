@@ -0,0 +1,98 @@
+//! Google links pasted where tools expect file IDs. Docs, Sheets, Slides and
+//! Drive URLs (`https://docs.google.com/spreadsheets/d/<id>/edit#gid=0`,
+//! `https://drive.google.com/open?id=<id>`, ...) are reduced to the ID, and
+//! a spreadsheet link's `gid` to the sheet it points at.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use url::Url;
+
+/// Arguments holding the ID of a Drive file, or a list of them.
+pub const ID_ARGUMENTS: &[&str] = &[
+    "spreadsheet_id",
+    "destination_spreadsheet_id",
+    "document_id",
+    "presentation_id",
+    "file_id",
+    "file_ids",
+    "folder_id",
+    "parent_id",
+    "drive_file_ids",
+];
+
+/// The file ID in a Google link, or `input` unchanged when it is not one.
+pub fn file_id(input: &str) -> String {
+    let input = input.trim();
+    let Some(url) = google_url(input) else {
+        return input.to_string();
+    };
+    let mut segments = url.path_segments().into_iter().flatten();
+    while let Some(segment) = segments.next() {
+        if matches!(segment, "d" | "folders") {
+            // Published links (`/d/e/...`) carry no file ID
+            return match segments.next() {
+                Some(id) if !id.is_empty() && id != "e" => id.to_string(),
+                _ => input.to_string(),
+            };
+        }
+    }
+    url.query_pairs()
+        .find(|(key, _)| key == "id")
+        .map(|(_, id)| id.into_owned())
+        .unwrap_or_else(|| input.to_string())
+}
+
+/// The sheet ID a spreadsheet link's `gid` selects, from its fragment or query.
+pub fn sheet_gid(input: &str) -> Option<i32> {
+    let url = google_url(input.trim())?;
+    let from_fragment = url.fragment().and_then(|fragment| {
+        fragment
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("gid="))
+            .map(str::to_string)
+    });
+    let from_query = || {
+        url.query_pairs()
+            .find(|(key, _)| key == "gid")
+            .map(|(_, gid)| gid.into_owned())
+    };
+    from_fragment.or_else(from_query)?.parse().ok()
+}
+
+/// Replaces links in the ID arguments of a tool call with their IDs. A
+/// spreadsheet link's `gid` also becomes the `sheet_id` of tools that take
+/// one, unless the call already names a sheet.
+pub fn resolve_arguments(arguments: &mut HashMap<String, Value>) {
+    let gid = arguments
+        .get("spreadsheet_id")
+        .and_then(Value::as_str)
+        .and_then(sheet_gid);
+    for key in ID_ARGUMENTS {
+        match arguments.get_mut(*key) {
+            Some(Value::String(id)) => *id = file_id(id),
+            Some(Value::Array(ids)) => {
+                for id in ids {
+                    if let Value::String(id) = id {
+                        *id = file_id(id);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(gid) = gid {
+        if !arguments.contains_key("sheet") && !arguments.contains_key("sheet_id") {
+            arguments.insert("sheet_id".to_string(), Value::from(gid));
+        }
+    }
+}
+
+fn google_url(input: &str) -> Option<Url> {
+    if !input.starts_with("http://") && !input.starts_with("https://") {
+        return None;
+    }
+    let url = Url::parse(input).ok()?;
+    let host = url.host_str()?;
+    (host == "google.com" || host.ends_with(".google.com")).then_some(url)
+}
@@ -1,18 +1,243 @@
-use tracing_subscriber::{filter::FilterFn, prelude::*, EnvFilter};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{filter::FilterFn, fmt::writer::BoxMakeWriter, prelude::*, EnvFilter};
+
+use crate::{payload, redact::RedactingMakeWriter};
+
+/// How log lines are rendered on stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines with targets
+    #[default]
+    Text,
+    /// Single-line abbreviated output
+    Compact,
+    /// Multi-line output for local debugging
+    Pretty,
+    /// One JSON object per line with timestamp, level, fields and the active
+    /// spans (tool name, request ID), for shipping to log aggregators
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "compact" => Ok(Self::Compact),
+            "pretty" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "unknown log format `{}` (expected text|compact|pretty|json)",
+                other
+            )),
+        }
+    }
+}
+
+/// When a log file is rolled over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogRotation {
+    /// A new file per day, suffixed with the date
+    #[default]
+    Daily,
+    /// A new file per hour, suffixed with the date and hour
+    Hourly,
+    /// Roll over once the file exceeds this many bytes, keeping
+    /// `ROTATED_FILES_KEPT` older files as `<file>.1`, `<file>.2`, ...
+    Size(u64),
+    /// Always append to the same file
+    Never,
+}
+
+impl FromStr for LogRotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        match lower.as_str() {
+            "daily" => return Ok(Self::Daily),
+            "hourly" => return Ok(Self::Hourly),
+            "never" => return Ok(Self::Never),
+            _ => {}
+        }
+
+        let (number, multiplier) = if let Some(n) = lower.strip_suffix("gb") {
+            (n, 1 << 30)
+        } else if let Some(n) = lower.strip_suffix("mb") {
+            (n, 1 << 20)
+        } else if let Some(n) = lower.strip_suffix("kb") {
+            (n, 1 << 10)
+        } else {
+            (lower.as_str(), 1)
+        };
+        match number.trim().parse::<u64>() {
+            Ok(n) if n > 0 => Ok(Self::Size(n * multiplier)),
+            _ => Err(format!(
+                "invalid log rotation `{}` (expected daily|hourly|never or a size like 50MB)",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LogRotation {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    /// Filter directives such as `info` or `mcp_google_workspace=debug`. When
+    /// unset, `RUST_LOG` is used, falling back to `info`.
+    pub level: Option<String>,
+    pub format: LogFormat,
+    /// Write logs to this file instead of stderr
+    pub file: Option<PathBuf>,
+    pub rotation: LogRotation,
+    /// Emit the trace-level request/response payload lines regardless of `level`
+    pub payloads: bool,
+}
+
+const ROTATED_FILES_KEPT: usize = 5;
+
+/// A log file that is renamed to `<file>.1` (shifting older copies up) once it
+/// grows past `max_bytes`.
+struct SizeRotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    file: File,
+    written: u64,
+}
+
+impl SizeRotatingFile {
+    fn open(path: &Path, max_bytes: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            file,
+            written,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for index in (1..ROTATED_FILES_KEPT).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn file_writer(path: &Path, rotation: LogRotation) -> Result<BoxMakeWriter> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+
+    let rotation = match rotation {
+        LogRotation::Size(max_bytes) => {
+            let file = SizeRotatingFile::open(path, max_bytes)
+                .with_context(|| format!("Failed to open log file {}", path.display()))?;
+            return Ok(BoxMakeWriter::new(RedactingMakeWriter::new(Mutex::new(
+                file,
+            ))));
+        }
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Log file path {} has no file name", path.display()))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(file_name.to_string_lossy())
+        .build(dir)
+        .with_context(|| format!("Failed to open log file {}", path.display()))?;
+    Ok(BoxMakeWriter::new(RedactingMakeWriter::new(appender)))
+}
 
 /// Initialize logging with sensible defaults for the agents library.
 /// This will:
 /// - Set up logging with the specified log level
 /// - Filter out noisy logs from dependencies like hyper
 /// - Format logs in a human-readable format
+/// - Mask OAuth tokens and client secrets in every line
 pub fn init_logging(level: &str) {
-    let filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new(level))
-        // Filter out noisy hyper logs
-        .add_directive("hyper=off".parse().unwrap())
-        .add_directive("rustyline=off".parse().unwrap())
-        .add_directive("h2=off".parse().unwrap())
-        .add_directive("rustls=off".parse().unwrap());
+    init_logging_with(&LogOptions {
+        level: Some(level.to_string()),
+        ..Default::default()
+    })
+    .expect("stderr logging cannot fail to initialize");
+}
+
+/// Like [`init_logging`], with the level, output format and destination taken
+/// from `options`.
+pub fn init_logging_with(options: &LogOptions) -> Result<()> {
+    let filter = match &options.level {
+        Some(level) => EnvFilter::new(level),
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    }
+    // Filter out noisy hyper logs
+    .add_directive("hyper=off".parse().unwrap())
+    .add_directive("rustyline=off".parse().unwrap())
+    .add_directive("h2=off".parse().unwrap())
+    .add_directive("rustls=off".parse().unwrap());
+    let filter = if options.payloads {
+        filter.add_directive(format!("{}=trace", payload::TARGET).parse().unwrap())
+    } else {
+        filter
+    };
 
     // Only show our crate's logs and any errors from other crates
     let _crate_filter = FilterFn::new(|metadata| {
@@ -21,8 +246,29 @@ pub fn init_logging(level: &str) {
             || metadata.level() <= &tracing::Level::ERROR
     });
 
+    let writer = match &options.file {
+        Some(path) => file_writer(path, options.rotation)?,
+        None => BoxMakeWriter::new(RedactingMakeWriter::default()),
+    };
+
+    // MCP hosts capture stderr as plain text; escape codes only help terminals
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(options.file.is_none() && io::stderr().is_terminal());
+    let layer = match options.format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Json => layer
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    };
+
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer().with_filter(filter))
+        .with(layer.with_filter(filter))
         // .with(filter)
         .init();
+    Ok(())
 }
@@ -1,71 +1,1490 @@
-use anyhow::Result;
-use async_mcp::transport::ServerStdioTransport;
-use clap::{Parser, Subcommand};
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use async_mcp::{sse::http_server::run_http_server, transport::ServerStdioTransport};
+use clap::{
+    parser::ValueSource, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand,
+    ValueEnum,
+};
+#[cfg(feature = "youtube")]
+use mcp_google_workspace::servers::youtube;
 use mcp_google_workspace::{
-    logging::init_logging,
-    servers::{drive, sheets},
-    GoogleAuthService,
+    audit::AuditLog,
+    bigquery,
+    cache::ResponseCache,
+    client::{init_client_options, ClientOptions, PoolOptions, RetryPolicy, TlsRoots},
+    config::{
+        default_path, save_credentials, write_atomically, Config, Credentials, TransportKind,
+    },
+    doctor::{run_checks, Status},
+    export::download_to_dir,
+    logging::{init_logging_with, LogFormat, LogOptions, LogRotation},
+    metrics::serve_metrics,
+    payload::{init_payload_logging, PayloadLogOptions},
+    quota::QuotaLimits,
+    relay::{free_loopback_port, relay},
+    scopes::{describe_missing, granted_scopes, missing_scopes, required_scopes, tool_scopes},
+    servers::{
+        admin, apps_script, calendar, chat, classroom, docs, drive, gmail, list_service_tools,
+        list_tools, meet, sheets, slides, tasks,
+        workspace::{self, WorkspaceService},
+        ServerOptions, Timeouts,
+    },
+    token_store::{
+        open_store, StoredCredentials, StoredToken, TokenStore, TokenStoreKind, DEFAULT_ACCOUNT,
+    },
+    upload::{UploadPolicy, DEFAULT_RESUMABLE_THRESHOLD},
+    GoogleAuthService, TokenProvider,
 };
+use tracing::{info, warn};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Config file (default ~/.config/mcp-google-workspace/config.toml)
+    #[arg(long, global = true, env = "MCP_GOOGLE_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Log filter, e.g. `info` or `mcp_google_workspace=debug` (default: RUST_LOG, then info)
+    #[arg(long, global = true, env = "MCP_GOOGLE_LOG_LEVEL")]
+    log_level: Option<String>,
+
+    /// Log output format (text|compact|pretty|json)
+    #[arg(long, global = true, env = "MCP_GOOGLE_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// Write logs to this file instead of stderr
+    #[arg(long, global = true, env = "MCP_GOOGLE_LOG_FILE")]
+    log_file: Option<PathBuf>,
+
+    /// When to roll the log file over (daily|hourly|never, or a size like 50MB)
+    #[arg(long, global = true, env = "MCP_GOOGLE_LOG_ROTATION")]
+    log_rotation: Option<LogRotation>,
+
+    /// Log Google API request parameters and truncated response bodies at trace level
+    #[arg(long, global = true, env = "MCP_GOOGLE_LOG_PAYLOADS")]
+    log_payloads: bool,
+
+    /// Extra payload fields whose values may be logged, e.g. `values`
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_LOG_PAYLOAD_FIELDS",
+        value_delimiter = ','
+    )]
+    log_payload_fields: Vec<String>,
+
+    /// Truncate logged payloads to this many bytes
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_LOG_PAYLOAD_MAX_BYTES",
+        default_value_t = 2048
+    )]
+    log_payload_max_bytes: usize,
+
+    /// Spreadsheet used by Sheets tools when a request names none
+    #[arg(long, global = true, env = "SPREADSHEET_ID")]
+    spreadsheet_id: Option<String>,
+
+    /// Apps Script project run by `run_script` when a request names none
+    #[arg(long, global = true, env = "MCP_GOOGLE_SCRIPT_ID")]
+    script_id: Option<String>,
+
+    /// Google Cloud project BigQuery jobs run in; enables `sheets_to_bigquery`
+    #[arg(long, global = true, env = "MCP_GOOGLE_BIGQUERY_PROJECT")]
+    bigquery_project: Option<String>,
+
+    /// Drive uploads larger than this many bytes use a resumable session
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_UPLOAD_RESUMABLE_THRESHOLD",
+        default_value_t = DEFAULT_RESUMABLE_THRESHOLD
+    )]
+    upload_resumable_threshold: u64,
+
+    /// Let `upload_file` read local files under these directories; `path`
+    /// uploads are refused otherwise
+    #[arg(
+        long = "upload-dir",
+        global = true,
+        env = "MCP_GOOGLE_UPLOAD_DIRS",
+        value_delimiter = ','
+    )]
+    upload_dirs: Vec<PathBuf>,
+
+    /// Let `upload_file` fetch public http(s) URLs
+    #[arg(long, global = true, env = "MCP_GOOGLE_ALLOW_UPLOAD_URLS")]
+    allow_upload_urls: bool,
+
+    /// Let `download_file` write files under this directory; `out_dir`
+    /// downloads are refused otherwise
+    #[arg(long, global = true, env = "MCP_GOOGLE_DOWNLOAD_DIR")]
+    download_dir: Option<PathBuf>,
+
+    /// Use the credentials of this config file profile
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_PROFILE",
+        conflicts_with = "account"
+    )]
+    profile: Option<String>,
+
+    /// Use the config file profile belonging to this Google account
+    #[arg(long, global = true, env = "MCP_GOOGLE_ACCOUNT")]
+    account: Option<String>,
+
+    /// Credentials of the selected profile, resolved from the config file
+    #[arg(skip)]
+    credentials: Credentials,
+
+    /// Where OAuth tokens are kept (config|keyring|file)
+    #[arg(long, global = true, env = "MCP_GOOGLE_TOKEN_STORE")]
+    token_store: Option<TokenStoreKind>,
+
+    /// Encrypted token file of the `file` store (default ~/.config/mcp-google-workspace/tokens.enc)
+    #[arg(long, global = true, env = "MCP_GOOGLE_TOKEN_FILE")]
+    token_file: Option<PathBuf>,
+
+    /// Passphrase the token file is encrypted with
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_TOKEN_PASSPHRASE",
+        hide_env_values = true
+    )]
+    token_passphrase: Option<String>,
+
+    /// The selected account's tokens, when they were loaded from the token store
+    #[arg(skip)]
+    stored_credentials: Option<StoredCredentials>,
+
+    /// Default deadline in seconds for each tool call
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_TIMEOUT_SECS",
+        default_value_t = 60
+    )]
+    timeout_secs: u64,
+
+    /// Per-tool deadline overrides, e.g. `read_values=120`
+    #[arg(
+        long = "tool-timeout",
+        global = true,
+        env = "MCP_GOOGLE_TOOL_TIMEOUTS",
+        value_delimiter = ',',
+        value_parser = parse_tool_timeout
+    )]
+    tool_timeouts: Vec<(String, u64)>,
+
+    /// Retries of tool calls that Google rate-limited or failed transiently; 0 disables
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_MAX_RETRIES",
+        default_value_t = 3
+    )]
+    max_retries: u32,
+
+    /// Backoff in milliseconds before the first retry, doubled for each further one
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_RETRY_BASE_DELAY_MS",
+        default_value_t = 500
+    )]
+    retry_base_delay_ms: u64,
+
+    /// Longest backoff in seconds; longer `Retry-After` waits are not retried
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_RETRY_MAX_DELAY_SECS",
+        default_value_t = 30
+    )]
+    retry_max_delay_secs: u64,
+
+    /// Sheets API calls each user may make per 100 seconds before further calls
+    /// are delayed; 0 disables throttling
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_SHEETS_QUOTA",
+        default_value_t = 100
+    )]
+    sheets_quota: u32,
+
+    /// Drive API calls each user may make per 100 seconds before further calls
+    /// are delayed; 0 disables throttling
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_DRIVE_QUOTA",
+        default_value_t = 1000
+    )]
+    drive_quota: u32,
+
+    /// Only expose tools that never modify Workspace data
+    #[arg(long, global = true, env = "MCP_GOOGLE_READ_ONLY")]
+    read_only: bool,
+
+    /// Only expose tools matching these globs, e.g. `read_*,get_sheet_info`
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_ALLOW_TOOLS",
+        value_delimiter = ','
+    )]
+    allow_tools: Vec<String>,
+
+    /// Hide tools matching these globs, e.g. `*clear*`
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_DENY_TOOLS",
+        value_delimiter = ','
+    )]
+    deny_tools: Vec<String>,
+
+    /// Default page size of the Drive listing tools (list_files, search_files, ...)
+    #[arg(long, global = true, env = "MCP_GOOGLE_PAGE_SIZE")]
+    page_size: Option<u64>,
+
+    /// Cache read_values, get_sheet_info and list_files results for this many
+    /// seconds; writes through the server invalidate what they touch
+    #[arg(long, global = true, env = "MCP_GOOGLE_CACHE_TTL_SECS")]
+    cache_ttl_secs: Option<u64>,
+
+    /// Start serving even when the configured credentials lack scopes the tools need
+    #[arg(long, global = true, env = "MCP_GOOGLE_SKIP_SCOPE_CHECK")]
+    skip_scope_check: bool,
+
+    /// Append a JSONL record of every mutating tool call to this file
+    #[arg(long, global = true, env = "MCP_GOOGLE_AUDIT_LOG")]
+    audit_log: Option<PathBuf>,
+
+    /// Serve Prometheus metrics on this address, e.g. `127.0.0.1:9464`
+    #[arg(long, global = true, env = "MCP_GOOGLE_METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Trust anchors for TLS connections (native|webpki)
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_TLS_ROOTS",
+        default_value = "native"
+    )]
+    tls_roots: TlsRoots,
+
+    /// Additional PEM CA bundle to trust, e.g. for a TLS-intercepting proxy
+    #[arg(
+        long = "ca-cert",
+        global = true,
+        env = "MCP_GOOGLE_CA_CERTS",
+        value_delimiter = ','
+    )]
+    ca_certs: Vec<PathBuf>,
+
+    /// GCP project to attribute quota and billing to (X-Goog-User-Project)
+    #[arg(long, global = true, env = "GOOGLE_CLOUD_QUOTA_PROJECT")]
+    quota_project: Option<String>,
+
+    /// Close pooled connections idle for longer than this many seconds
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_POOL_IDLE_TIMEOUT_SECS",
+        default_value_t = 90
+    )]
+    pool_idle_timeout_secs: u64,
+
+    /// Maximum idle pooled connections kept per host
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_POOL_MAX_IDLE_PER_HOST",
+        default_value_t = 32
+    )]
+    pool_max_idle_per_host: usize,
+
+    /// TCP keepalive interval in seconds (0 disables keepalive)
+    #[arg(
+        long,
+        global = true,
+        env = "MCP_GOOGLE_TCP_KEEPALIVE_SECS",
+        default_value_t = 60
+    )]
+    tcp_keepalive_secs: u64,
+
+    /// Override the Sheets API root URL (default https://sheets.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_SHEETS_API_URL")]
+    sheets_api_url: Option<String>,
+
+    /// Override the Drive API root URL (default https://www.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_DRIVE_API_URL")]
+    drive_api_url: Option<String>,
+
+    /// Override the Gmail API root URL (default https://gmail.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_GMAIL_API_URL")]
+    gmail_api_url: Option<String>,
+
+    /// Override the Calendar API root URL (default https://www.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_CALENDAR_API_URL")]
+    calendar_api_url: Option<String>,
+
+    /// Override the Docs API root URL (default https://docs.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_DOCS_API_URL")]
+    docs_api_url: Option<String>,
+
+    /// Override the Slides API root URL (default https://slides.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_SLIDES_API_URL")]
+    slides_api_url: Option<String>,
+
+    /// Override the Tasks API root URL (default https://tasks.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_TASKS_API_URL")]
+    tasks_api_url: Option<String>,
+
+    /// Override the Apps Script API root URL (default https://script.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_APPS_SCRIPT_API_URL")]
+    apps_script_api_url: Option<String>,
+
+    /// Override the Chat API root URL (default https://chat.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_CHAT_API_URL")]
+    chat_api_url: Option<String>,
+
+    /// Override the Meet API root URL (default https://meet.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_MEET_API_URL")]
+    meet_api_url: Option<String>,
+
+    /// Override the Admin SDK root URL (default https://admin.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_ADMIN_API_URL")]
+    admin_api_url: Option<String>,
+
+    /// Override the Classroom API root URL (default https://classroom.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_CLASSROOM_API_URL")]
+    classroom_api_url: Option<String>,
+
+    /// Override the BigQuery API root URL (default https://bigquery.googleapis.com/)
+    #[arg(long, global = true, env = "MCP_GOOGLE_BIGQUERY_API_URL")]
+    bigquery_api_url: Option<String>,
+
+    /// Override the YouTube Data API root URL (default https://youtube.googleapis.com/)
+    #[cfg(feature = "youtube")]
+    #[arg(long, global = true, env = "MCP_GOOGLE_YOUTUBE_API_URL")]
+    youtube_api_url: Option<String>,
+
+    /// Override the YouTube Analytics API root URL (default https://youtubeanalytics.googleapis.com/)
+    #[cfg(feature = "youtube")]
+    #[arg(long, global = true, env = "MCP_GOOGLE_YOUTUBE_ANALYTICS_API_URL")]
+    youtube_analytics_api_url: Option<String>,
+}
+
+/// Whether the user set `id` explicitly, on the command line or via its env var.
+fn user_set(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
+
+impl Cli {
+    /// Fills in settings from the config file wherever no flag or env var was given.
+    fn apply_config(&mut self, config: &Config, matches: &ArgMatches) -> Result<()> {
+        let tools = &config.tools;
+        if let (false, Some(secs)) = (user_set(matches, "timeout_secs"), tools.timeout_secs) {
+            self.timeout_secs = secs;
+        }
+        if let (false, Some(read_only)) = (user_set(matches, "read_only"), tools.read_only) {
+            self.read_only = read_only;
+        }
+        if !user_set(matches, "allow_tools") {
+            self.allow_tools = tools.allow.clone();
+        }
+        if !user_set(matches, "deny_tools") {
+            self.deny_tools = tools.deny.clone();
+        }
+        if self.spreadsheet_id.is_none() {
+            self.spreadsheet_id = config.sheets.spreadsheet_id.clone();
+        }
+        if self.script_id.is_none() {
+            self.script_id = config.apps_script.script_id.clone();
+        }
+        if self.bigquery_project.is_none() {
+            self.bigquery_project = config.sheets.bigquery_project.clone();
+        }
+        if self.page_size.is_none() {
+            self.page_size = tools.page_size;
+        }
+        if self.cache_ttl_secs.is_none() {
+            self.cache_ttl_secs = tools.cache_ttl_secs;
+        }
+        if self.token_store.is_none() {
+            self.token_store = config.tokens.store;
+        }
+        if self.token_file.is_none() {
+            self.token_file = config.tokens.file.clone();
+        }
+        // Later entries win when collected, so flags override the file per tool
+        let mut tool_timeouts = tools
+            .timeouts
+            .iter()
+            .map(|(name, secs)| (name.clone(), *secs))
+            .collect::<Vec<_>>();
+        tool_timeouts.append(&mut self.tool_timeouts);
+        self.tool_timeouts = tool_timeouts;
+
+        if let Commands::Serve { services, .. } | Commands::Workspace { services, .. } =
+            &mut self.command
+        {
+            if services.is_empty() {
+                *services = if config.services.is_empty() {
+                    WorkspaceService::ALL.to_vec()
+                } else {
+                    config.services.clone()
+                };
+            }
+        }
+
+        if let (Some(transport), Some((_, sub_matches))) =
+            (self.command.transport_args_mut(), matches.subcommand())
+        {
+            let defaults = &config.transport;
+            if let (false, Some(kind)) = (user_set(sub_matches, "transport"), defaults.kind) {
+                transport.transport = kind;
+            }
+            if let (false, Some(host)) = (user_set(sub_matches, "host"), defaults.host) {
+                transport.host = host;
+            }
+            if let (false, Some(port)) = (user_set(sub_matches, "port"), defaults.port) {
+                transport.port = port;
+            }
+        }
+
+        let (profile, credentials) =
+            config.credentials_for(self.profile.as_deref(), self.account.as_deref())?;
+        let mut credentials = credentials.clone();
+        credentials.load_client_secrets()?;
+        self.profile = profile.map(str::to_string);
+        self.account = credentials.account.clone().or(self.account.take());
+
+        // A refresh token given in the config file or environment wins over the store
+        if credentials.refresh_token.is_none() && std::env::var_os("GOOGLE_REFRESH_TOKEN").is_none()
+        {
+            if let Some(store) = self.open_token_store()? {
+                let account = self.token_account();
+                if let Some(token) = store.load(&account)? {
+                    credentials.refresh_token = Some(token.refresh_token.clone());
+                    self.stored_credentials = Some(StoredCredentials {
+                        store,
+                        account,
+                        token,
+                    });
+                }
+            }
+        }
+
+        if let Commands::Auth {
+            command:
+                AuthCommand::Login {
+                    client_id,
+                    client_secret,
+                    ..
+                },
+        } = &mut self.command
+        {
+            *client_id = client_id.take().or_else(|| credentials.client_id.clone());
+            *client_secret = client_secret
+                .take()
+                .or_else(|| credentials.client_secret.clone());
+        }
+
+        if let Commands::Refresh {
+            client_id,
+            client_secret,
+            refresh_token,
+            ..
+        } = &mut self.command
+        {
+            *client_id = client_id.take().or_else(|| credentials.client_id.clone());
+            *client_secret = client_secret
+                .take()
+                .or_else(|| credentials.client_secret.clone());
+            *refresh_token = refresh_token
+                .take()
+                .or_else(|| credentials.refresh_token.clone());
+        }
+
+        self.credentials = credentials;
+        Ok(())
+    }
+
+    fn open_token_store(&self) -> Result<Option<Arc<dyn TokenStore>>> {
+        open_store(
+            self.token_store.unwrap_or_default(),
+            self.token_file.as_deref(),
+            self.token_passphrase.as_deref(),
+        )
+    }
+
+    /// The key the selected credentials' tokens are stored under: the account,
+    /// else the profile name.
+    fn token_account(&self) -> String {
+        self.account
+            .clone()
+            .or_else(|| self.profile.clone())
+            .unwrap_or_else(|| DEFAULT_ACCOUNT.to_string())
+    }
+
+    fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            tls_roots: self.tls_roots,
+            extra_ca_certs: self.ca_certs.clone(),
+            quota_project: self.quota_project.clone(),
+            quota_limits: QuotaLimits {
+                sheets: (self.sheets_quota > 0).then_some(self.sheets_quota),
+                drive: (self.drive_quota > 0).then_some(self.drive_quota),
+            },
+            pool: PoolOptions {
+                idle_timeout: Duration::from_secs(self.pool_idle_timeout_secs),
+                max_idle_per_host: self.pool_max_idle_per_host,
+                tcp_keepalive: (self.tcp_keepalive_secs > 0)
+                    .then(|| Duration::from_secs(self.tcp_keepalive_secs)),
+            },
+            sheets_root_url: self.sheets_api_url.clone(),
+            drive_root_url: self.drive_api_url.clone(),
+            gmail_root_url: self.gmail_api_url.clone(),
+            calendar_root_url: self.calendar_api_url.clone(),
+            docs_root_url: self.docs_api_url.clone(),
+            slides_root_url: self.slides_api_url.clone(),
+            tasks_root_url: self.tasks_api_url.clone(),
+            apps_script_root_url: self.apps_script_api_url.clone(),
+            chat_root_url: self.chat_api_url.clone(),
+            meet_root_url: self.meet_api_url.clone(),
+            admin_root_url: self.admin_api_url.clone(),
+            classroom_root_url: self.classroom_api_url.clone(),
+            #[cfg(feature = "youtube")]
+            youtube_root_url: self.youtube_api_url.clone(),
+            #[cfg(feature = "youtube")]
+            youtube_analytics_root_url: self.youtube_analytics_api_url.clone(),
+            bigquery_root_url: self.bigquery_api_url.clone(),
+        }
+    }
+
+    fn server_options(&self) -> Result<ServerOptions> {
+        let audit_log = match &self.audit_log {
+            Some(path) => Some(Arc::new(AuditLog::open(path)?)),
+            None => None,
+        };
+
+        let mut timeouts = Timeouts {
+            default: Duration::from_secs(self.timeout_secs),
+            ..Default::default()
+        };
+        timeouts.per_tool.extend(
+            self.tool_timeouts
+                .iter()
+                .map(|(name, secs)| (name.clone(), Duration::from_secs(*secs))),
+        );
+
+        Ok(ServerOptions {
+            timeouts,
+            read_only: self.read_only,
+            allow_tools: self.allow_tools.clone(),
+            deny_tools: self.deny_tools.clone(),
+            audit_log,
+            default_spreadsheet_id: self.spreadsheet_id.clone(),
+            default_script_id: self.script_id.clone(),
+            bigquery_project: self.bigquery_project.clone(),
+            upload_resumable_threshold: Some(self.upload_resumable_threshold),
+            uploads: UploadPolicy {
+                dirs: self
+                    .upload_dirs
+                    .iter()
+                    .map(|dir| {
+                        dir.canonicalize()
+                            .with_context(|| format!("upload directory {}", dir.display()))
+                    })
+                    .collect::<Result<_>>()?,
+                urls: self.allow_upload_urls,
+            },
+            download_dir: self
+                .download_dir
+                .as_ref()
+                .map(|dir| {
+                    dir.canonicalize()
+                        .with_context(|| format!("download directory {}", dir.display()))
+                })
+                .transpose()?,
+            token_provider: self.token_provider()?,
+            account: self.account.clone(),
+            namespace: None,
+            retry: RetryPolicy {
+                max_retries: self.max_retries,
+                base_delay: Duration::from_millis(self.retry_base_delay_ms),
+                max_delay: Duration::from_secs(self.retry_max_delay_secs),
+            },
+            page_size: self.page_size,
+            cache: self
+                .cache_ttl_secs
+                .filter(|secs| *secs > 0)
+                .map(|secs| Arc::new(ResponseCache::new(Duration::from_secs(secs)))),
+        })
+    }
+
+    /// A provider that keeps servers supplied with fresh access tokens, when
+    /// refresh credentials are configured (config file or token store first,
+    /// then the `GOOGLE_*` environment variables).
+    fn token_provider(&self) -> Result<Option<Arc<TokenProvider>>> {
+        let Some((client_id, client_secret, refresh_token)) =
+            self.credentials.refresh_credentials()
+        else {
+            return Ok(None);
+        };
+        let provider = match &self.stored_credentials {
+            Some(stored) => TokenProvider::from_store(client_id, client_secret, stored.clone())?,
+            None => TokenProvider::new(client_id, client_secret, refresh_token)?,
+        };
+        Ok(Some(Arc::new(provider)))
+    }
+}
+
+fn parse_tool_timeout(s: &str) -> Result<(String, u64), String> {
+    let (name, secs) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <tool>=<seconds>, got `{}`", s))?;
+    let secs = secs
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("invalid timeout for {}: {}", name, e))?;
+    Ok((name.trim().to_string(), secs))
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Serve the tools of one or more Google services from a single server
+    Serve {
+        /// Services to expose (default: all, or `services` from the config file)
+        #[arg(long, value_delimiter = ',')]
+        services: Vec<WorkspaceService>,
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Serve all services from one server with namespaced tool names
+    /// (`drive.list_files`, `sheets.read_values`, ...)
+    Workspace {
+        /// Services to expose (default: all, or `services` from the config file)
+        #[arg(long, value_delimiter = ',')]
+        services: Vec<WorkspaceService>,
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
     /// Start the Google Drive server
-    Drive,
+    Drive {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
     /// Start the Google Sheets server
-    Sheets,
+    Sheets {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Gmail server
+    Gmail {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Calendar server
+    Calendar {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Docs server
+    Docs {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Slides server
+    Slides {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Tasks server
+    Tasks {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Apps Script server
+    AppsScript {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Chat server
+    Chat {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Meet server
+    Meet {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Workspace Admin Directory server
+    Admin {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the Google Classroom server
+    Classroom {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// Start the YouTube server for channel owners
+    #[cfg(feature = "youtube")]
+    #[command(name = "youtube")]
+    YouTube {
+        #[command(flatten)]
+        transport: TransportArgs,
+    },
+    /// List the tools a server registers, with their input schemas
+    Tools {
+        /// Which server's tools to list
+        #[arg(long, value_enum)]
+        server: ServerKind,
+        /// Print the tool definitions as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Download Drive files or export spreadsheets and documents to disk
+    Export {
+        /// Drive files to download (default: the --spreadsheet-id spreadsheet)
+        #[arg(long = "file-id", value_delimiter = ',')]
+        file_ids: Vec<String>,
+        /// Export format for Google-native files, e.g. xlsx, csv, pdf, docx
+        #[arg(long)]
+        format: Option<String>,
+        /// Directory to write files into
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+        /// Replace files that already exist in the directory
+        #[arg(long)]
+        overwrite: bool,
+        /// Access token; refreshed from the configured credentials if absent
+        #[arg(long, env = "GOOGLE_ACCESS_TOKEN")]
+        access_token: Option<String>,
+    },
+    /// Print the OAuth scopes the registered tools need, honouring --read-only
+    /// and the allow/deny lists
+    RequiredScopes {
+        /// Services whose tools to cover (default: all)
+        #[arg(long, value_delimiter = ',')]
+        services: Vec<WorkspaceService>,
+        /// Also list each tool with the scopes it accepts
+        #[arg(long)]
+        tools: bool,
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check credentials, scopes, API enablement, clock skew and connectivity
+    Doctor {
+        /// Access token to check; refreshed from the configured credentials if absent
+        #[arg(long, env = "GOOGLE_ACCESS_TOKEN")]
+        access_token: Option<String>,
+        /// Services whose scopes and APIs to check (default: all)
+        #[arg(long, value_delimiter = ',')]
+        services: Vec<WorkspaceService>,
+    },
+    /// Manage stored OAuth credentials
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommand,
+    },
     Refresh {
         /// Google OAuth client ID
         #[arg(long, env = "GOOGLE_CLIENT_ID")]
-        client_id: String,
+        client_id: Option<String>,
         /// Google OAuth client secret
         #[arg(long, env = "GOOGLE_CLIENT_SECRET")]
-        client_secret: String,
+        client_secret: Option<String>,
         /// Refresh token
         #[arg(long, env = "GOOGLE_REFRESH_TOKEN")]
-        refresh_token: String,
+        refresh_token: Option<String>,
+        /// Output format: json, env (KEY=value), dotenv (KEY="value") or file:<path>
+        #[arg(long, default_value = "json")]
+        output: TokenOutput,
+        /// Prefix variables with `export` so the output can be sourced by a shell
+        #[arg(long)]
+        export: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthCommand {
+    /// Authorize in the browser and save the refresh token to the config file
+    Login {
+        /// Google OAuth client ID (a "Desktop app" client)
+        #[arg(long, env = "GOOGLE_CLIENT_ID")]
+        client_id: Option<String>,
+        /// Google OAuth client secret
+        #[arg(long, env = "GOOGLE_CLIENT_SECRET")]
+        client_secret: Option<String>,
+        /// Services to request access to (default: all)
+        #[arg(long, value_delimiter = ',')]
+        services: Vec<WorkspaceService>,
+        /// Request only the scopes the services' tools need, honouring
+        /// --read-only and the allow/deny lists, instead of each service's full scope
+        #[arg(long)]
+        minimal_scopes: bool,
+        /// Local port for the OAuth redirect; 0 picks a free port
+        #[arg(long, default_value_t = 0)]
+        port: u16,
+        /// Only print the consent URL instead of opening a browser
+        #[arg(long)]
+        no_browser: bool,
+        /// Save the credentials as this profile instead of `[credentials]`
+        #[arg(long)]
+        save_as: Option<String>,
     },
 }
 
+#[derive(Debug, Args)]
+struct TransportArgs {
+    /// Transport to serve MCP over
+    #[arg(long, env = "MCP_GOOGLE_TRANSPORT", default_value = "stdio")]
+    transport: TransportKind,
+    /// Address the SSE transport listens on
+    #[arg(long, env = "MCP_GOOGLE_HOST", default_value = "127.0.0.1")]
+    host: IpAddr,
+    /// Port for the SSE transport
+    #[arg(long, env = "MCP_GOOGLE_PORT", default_value_t = 3000)]
+    port: u16,
+}
+
+impl Commands {
+    fn transport_args_mut(&mut self) -> Option<&mut TransportArgs> {
+        match self {
+            Commands::Serve { transport, .. }
+            | Commands::Workspace { transport, .. }
+            | Commands::Drive { transport }
+            | Commands::Sheets { transport }
+            | Commands::Gmail { transport }
+            | Commands::Calendar { transport }
+            | Commands::Docs { transport }
+            | Commands::Slides { transport }
+            | Commands::Tasks { transport }
+            | Commands::AppsScript { transport }
+            | Commands::Chat { transport }
+            | Commands::Meet { transport }
+            | Commands::Admin { transport }
+            | Commands::Classroom { transport } => Some(transport),
+            #[cfg(feature = "youtube")]
+            Commands::YouTube { transport } => Some(transport),
+            _ => None,
+        }
+    }
+
+    /// The services a serving command exposes.
+    fn served_services(&self) -> Option<Vec<WorkspaceService>> {
+        match self {
+            Commands::Serve { services, .. } | Commands::Workspace { services, .. } => {
+                Some(services.clone())
+            }
+            Commands::Drive { .. } => Some(vec![WorkspaceService::Drive]),
+            Commands::Sheets { .. } => Some(vec![WorkspaceService::Sheets]),
+            Commands::Gmail { .. } => Some(vec![WorkspaceService::Gmail]),
+            Commands::Calendar { .. } => Some(vec![WorkspaceService::Calendar]),
+            Commands::Docs { .. } => Some(vec![WorkspaceService::Docs]),
+            Commands::Slides { .. } => Some(vec![WorkspaceService::Slides]),
+            Commands::Tasks { .. } => Some(vec![WorkspaceService::Tasks]),
+            Commands::AppsScript { .. } => Some(vec![WorkspaceService::AppsScript]),
+            Commands::Chat { .. } => Some(vec![WorkspaceService::Chat]),
+            Commands::Meet { .. } => Some(vec![WorkspaceService::Meet]),
+            Commands::Admin { .. } => Some(vec![WorkspaceService::Admin]),
+            Commands::Classroom { .. } => Some(vec![WorkspaceService::Classroom]),
+            #[cfg(feature = "youtube")]
+            Commands::YouTube { .. } => Some(vec![WorkspaceService::YouTube]),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ServerKind {
+    Sheets,
+    Drive,
+    Gmail,
+    Calendar,
+    Docs,
+    Slides,
+    Tasks,
+    AppsScript,
+    Chat,
+    Meet,
+    Admin,
+    Classroom,
+    #[cfg(feature = "youtube")]
+    #[value(name = "youtube")]
+    YouTube,
+    /// All services with namespaced tool names, as `workspace` serves them
+    Workspace,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    init_logging("debug");
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    let config = Config::load(cli.config.as_deref())?;
+    cli.apply_config(&config, &matches)?;
+
+    init_logging_with(&LogOptions {
+        level: cli.log_level.clone().or(config.logging.level.clone()),
+        format: cli.log_format.or(config.logging.format).unwrap_or_default(),
+        file: cli.log_file.clone().or(config.logging.file.clone()),
+        rotation: cli
+            .log_rotation
+            .or(config.logging.rotation)
+            .unwrap_or_default(),
+        payloads: cli.log_payloads,
+    })?;
+    init_payload_logging(PayloadLogOptions {
+        enabled: cli.log_payloads,
+        max_body_bytes: cli.log_payload_max_bytes,
+        extra_fields: cli.log_payload_fields.clone(),
+    });
 
-    let cli = Cli::parse();
+    init_client_options(cli.client_options())?;
+    let options = cli.server_options()?;
+
+    match (&cli.profile, &cli.account) {
+        (Some(profile), Some(account)) => info!("Using profile {} ({})", profile, account),
+        (Some(profile), None) => info!("Using profile {}", profile),
+        (None, Some(account)) => info!("Using account {}", account),
+        (None, None) => info!("Using default credentials"),
+    }
+
+    if let Some(addr) = cli.metrics_addr {
+        tokio::spawn(async move {
+            if let Err(e) = serve_metrics(addr).await {
+                tracing::error!("Metrics endpoint failed: {}", e);
+            }
+        });
+    }
+
+    if let (false, Some(services)) = (cli.skip_scope_check, cli.command.served_services()) {
+        check_token_scopes(&services, &options).await?;
+    }
 
     match cli.command {
-        Commands::Drive => {
-            let server = drive::build(ServerStdioTransport)?;
-            let server_handle = tokio::spawn(async move { server.listen().await });
+        Commands::Serve {
+            services,
+            transport,
+        } => serve(services, false, &transport, options).await?,
+        Commands::Workspace {
+            services,
+            transport,
+        } => serve(services, true, &transport, options).await?,
+        Commands::Drive { transport } => {
+            serve(vec![WorkspaceService::Drive], false, &transport, options).await?
+        }
+        Commands::Sheets { transport } => {
+            serve(vec![WorkspaceService::Sheets], false, &transport, options).await?
+        }
+        Commands::Gmail { transport } => {
+            serve(vec![WorkspaceService::Gmail], false, &transport, options).await?
+        }
+        Commands::Calendar { transport } => {
+            serve(vec![WorkspaceService::Calendar], false, &transport, options).await?
+        }
+        Commands::Docs { transport } => {
+            serve(vec![WorkspaceService::Docs], false, &transport, options).await?
+        }
+        Commands::Slides { transport } => {
+            serve(vec![WorkspaceService::Slides], false, &transport, options).await?
+        }
+        Commands::Tasks { transport } => {
+            serve(vec![WorkspaceService::Tasks], false, &transport, options).await?
+        }
+        Commands::AppsScript { transport } => {
+            serve(
+                vec![WorkspaceService::AppsScript],
+                false,
+                &transport,
+                options,
+            )
+            .await?
+        }
+        Commands::Chat { transport } => {
+            serve(vec![WorkspaceService::Chat], false, &transport, options).await?
+        }
+        Commands::Meet { transport } => {
+            serve(vec![WorkspaceService::Meet], false, &transport, options).await?
+        }
+        Commands::Admin { transport } => {
+            serve(vec![WorkspaceService::Admin], false, &transport, options).await?
+        }
+        Commands::Classroom { transport } => {
+            serve(
+                vec![WorkspaceService::Classroom],
+                false,
+                &transport,
+                options,
+            )
+            .await?
+        }
+        #[cfg(feature = "youtube")]
+        Commands::YouTube { transport } => {
+            serve(vec![WorkspaceService::YouTube], false, &transport, options).await?
+        }
+        Commands::Tools { server, json } => {
+            let tools = match server {
+                ServerKind::Sheets => list_tools(sheets::build_with_options, options).await?,
+                ServerKind::Drive => list_tools(drive::build_with_options, options).await?,
+                ServerKind::Gmail => list_tools(gmail::build_with_options, options).await?,
+                ServerKind::Calendar => list_tools(calendar::build_with_options, options).await?,
+                ServerKind::Docs => list_tools(docs::build_with_options, options).await?,
+                ServerKind::Slides => list_tools(slides::build_with_options, options).await?,
+                ServerKind::Tasks => list_tools(tasks::build_with_options, options).await?,
+                ServerKind::AppsScript => {
+                    list_tools(apps_script::build_with_options, options).await?
+                }
+                ServerKind::Chat => list_tools(chat::build_with_options, options).await?,
+                ServerKind::Meet => list_tools(meet::build_with_options, options).await?,
+                ServerKind::Admin => list_tools(admin::build_with_options, options).await?,
+                ServerKind::Classroom => list_tools(classroom::build_with_options, options).await?,
+                #[cfg(feature = "youtube")]
+                ServerKind::YouTube => list_tools(youtube::build_with_options, options).await?,
+                ServerKind::Workspace => {
+                    list_tools(
+                        |transport, options| {
+                            workspace::build_namespaced(transport, WorkspaceService::ALL, options)
+                        },
+                        options,
+                    )
+                    .await?
+                }
+            };
 
-            server_handle
-                .await?
-                .map_err(|e| anyhow::anyhow!("Drive server error: {:#?}", e))?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tools)?);
+            } else {
+                for tool in tools {
+                    println!("{}", tool.name);
+                    if let Some(description) = &tool.description {
+                        println!("  {}", description);
+                    }
+                    let schema = serde_json::to_string_pretty(&tool.input_schema)?;
+                    for line in schema.lines() {
+                        println!("  {}", line);
+                    }
+                    println!();
+                }
+            }
         }
-        Commands::Sheets => {
-            let server = sheets::build(ServerStdioTransport)?;
-            let server_handle = tokio::spawn(async move { server.listen().await });
+        Commands::Export {
+            mut file_ids,
+            format,
+            out,
+            overwrite,
+            access_token,
+        } => {
+            if file_ids.is_empty() {
+                file_ids.extend(cli.spreadsheet_id.clone());
+            }
+            anyhow::ensure!(
+                !file_ids.is_empty(),
+                "pass --file-id or --spreadsheet-id to choose what to export"
+            );
+
+            let access_token = match access_token {
+                Some(token) => token,
+                None => refresh_access_token(options.token_provider.as_deref())
+                    .await
+                    .context("pass --access-token or configure refresh credentials")?,
+            };
 
-            server_handle
+            for file_id in &file_ids {
+                let path =
+                    download_to_dir(&access_token, file_id, format.as_deref(), &out, overwrite)
+                        .await?;
+                println!("{}", path.display());
+            }
+        }
+        Commands::RequiredScopes {
+            mut services,
+            tools,
+            json,
+        } => {
+            if services.is_empty() {
+                services = WorkspaceService::ALL.to_vec();
+            }
+            let names = list_service_tools(&services, options)
                 .await?
-                .map_err(|e| anyhow::anyhow!("Sheets server error: {:#?}", e))?;
+                .into_iter()
+                .map(|tool| tool.name)
+                .collect::<Vec<_>>();
+            let scopes = required_scopes(names.iter().map(String::as_str));
+
+            if json {
+                let tools = names
+                    .iter()
+                    .filter_map(|name| Some((name.as_str(), tool_scopes(name)?)))
+                    .collect::<std::collections::BTreeMap<_, _>>();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "scopes": scopes,
+                        "tools": tools,
+                    }))?
+                );
+            } else {
+                for scope in &scopes {
+                    println!("{}", scope);
+                }
+                if tools {
+                    println!();
+                    for name in &names {
+                        if let Some(requirement) = tool_scopes(name) {
+                            println!("{}: {}", name, requirement.accepted.join(" | "));
+                        }
+                    }
+                }
+            }
+        }
+        Commands::Doctor {
+            access_token,
+            mut services,
+        } => {
+            if services.is_empty() {
+                services = WorkspaceService::ALL.to_vec();
+            }
+            let access_token = match access_token {
+                Some(token) => Some(token),
+                None => refresh_access_token(options.token_provider.as_deref()).await,
+            };
+
+            let checks = run_checks(access_token.as_deref(), &services).await;
+            for check in &checks {
+                println!("{}", check);
+            }
+
+            let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+            if failed > 0 {
+                anyhow::bail!("{} check(s) failed", failed);
+            }
+        }
+        Commands::Auth {
+            command:
+                AuthCommand::Login {
+                    client_id,
+                    client_secret,
+                    services,
+                    minimal_scopes,
+                    port,
+                    no_browser,
+                    save_as,
+                },
+        } => {
+            let client_id = required(client_id, "--client-id")?;
+            let client_secret = required(client_secret, "--client-secret")?;
+            let services = if services.is_empty() {
+                WorkspaceService::ALL.to_vec()
+            } else {
+                services
+            };
+            let service_scopes = if minimal_scopes {
+                let tools = list_service_tools(&services, options).await?;
+                required_scopes(tools.iter().map(|tool| tool.name.as_str()))
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect()
+            } else {
+                let mut scopes = services
+                    .iter()
+                    .flat_map(|s| config.scopes_for(*s))
+                    .collect::<Vec<_>>();
+                // `sheets_to_bigquery` is served alongside the Sheets tools
+                // once a BigQuery project is configured
+                if options.bigquery_project.is_some()
+                    && services.contains(&WorkspaceService::Sheets)
+                {
+                    scopes.push(bigquery::SCOPE.to_string());
+                }
+                scopes
+            };
+            let mut scopes = vec!["email"];
+            scopes.extend(service_scopes.iter().map(String::as_str));
+
+            let store = open_store(
+                cli.token_store.unwrap_or_default(),
+                cli.token_file.as_deref(),
+                cli.token_passphrase.as_deref(),
+            )?;
+            let auth_service = GoogleAuthService::new(client_id.clone(), client_secret.clone())?;
+            let token_response = auth_service.login(&scopes, port, !no_browser).await?;
+            let refresh_token = token_response
+                .refresh_token
+                .clone()
+                .context("Google did not return a refresh token")?;
+            let account = auth_service
+                .account_email(&token_response.access_token)
+                .await;
+
+            let path = match &cli.config {
+                Some(path) => path.clone(),
+                None => default_path().context("Cannot determine the config file location")?,
+            };
+            let profile = save_as.as_deref();
+            let refresh_token = match &store {
+                Some(store) => {
+                    let key = account
+                        .clone()
+                        .or_else(|| save_as.clone())
+                        .unwrap_or_else(|| DEFAULT_ACCOUNT.to_string());
+                    let expires_at = chrono::Utc::now()
+                        + chrono::Duration::seconds(token_response.expires_in as i64);
+                    store.save(
+                        &key,
+                        &StoredToken {
+                            refresh_token,
+                            access_token: Some(token_response.access_token.clone()),
+                            expires_at: Some(expires_at),
+                        },
+                    )?;
+                    None
+                }
+                None => Some(refresh_token),
+            };
+            save_credentials(
+                &path,
+                profile,
+                &Credentials {
+                    account: account.clone(),
+                    client_id: Some(client_id),
+                    client_secret: Some(client_secret),
+                    refresh_token,
+                    ..Default::default()
+                },
+            )?;
+
+            let target = match profile {
+                Some(name) => format!("profile `{}`", name),
+                None => "[credentials]".to_string(),
+            };
+            let authorized = match account {
+                Some(account) => format!("Authorized {}", account),
+                None => "Authorized".to_string(),
+            };
+            match cli.token_store.unwrap_or_default() {
+                TokenStoreKind::Config => {
+                    eprintln!("{}; saved to {} in {}", authorized, target, path.display())
+                }
+                kind => eprintln!(
+                    "{}; saved the tokens to the {:?} store and the client to {} in {}",
+                    authorized,
+                    kind,
+                    target,
+                    path.display()
+                ),
+            }
         }
         Commands::Refresh {
             client_id,
             client_secret,
             refresh_token,
+            output,
+            export,
         } => {
-            let auth_service = GoogleAuthService::new(client_id, client_secret).unwrap();
-            let token_response = auth_service.refresh_token(&refresh_token).await.unwrap();
-            println!("Token response: {:#?}", token_response);
+            let client_id = required(client_id, "--client-id")?;
+            let client_secret = required(client_secret, "--client-secret")?;
+            let refresh_token = required(refresh_token, "--refresh-token")?;
+
+            let auth_service = GoogleAuthService::new(client_id, client_secret)?;
+            let token_response = auth_service
+                .refresh_token(&refresh_token)
+                .await
+                .context("Could not refresh the access token")?;
+            let expires_at =
+                chrono::Utc::now() + chrono::Duration::seconds(token_response.expires_in as i64);
+            let mut vars = vec![
+                ("GOOGLE_ACCESS_TOKEN", token_response.access_token.clone()),
+                ("GOOGLE_TOKEN_EXPIRES_AT", expires_at.to_rfc3339()),
+            ];
+            // Google only returns a refresh token when it rotates it
+            if let Some(rotated) = &token_response.refresh_token {
+                vars.push(("GOOGLE_REFRESH_TOKEN", rotated.clone()));
+            }
+
+            match output {
+                TokenOutput::Json => {
+                    println!("{}", serde_json::to_string_pretty(&token_response)?)
+                }
+                TokenOutput::Env => print!("{}", render_env(&vars, false, export)),
+                TokenOutput::Dotenv => print!("{}", render_env(&vars, true, export)),
+                TokenOutput::File(path) => {
+                    write_atomically(&path, &render_env(&vars, true, export))?;
+                    eprintln!("Wrote access token to {}", path.display());
+                }
+            }
         }
     }
 
     Ok(())
 }
+
+/// Where and how `refresh` prints the new token.
+#[derive(Debug, Clone)]
+enum TokenOutput {
+    Json,
+    Env,
+    Dotenv,
+    File(PathBuf),
+}
+
+impl FromStr for TokenOutput {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "env" => Ok(Self::Env),
+            "dotenv" => Ok(Self::Dotenv),
+            _ => match s.strip_prefix("file:") {
+                Some(path) if !path.is_empty() => Ok(Self::File(PathBuf::from(path))),
+                _ => Err(format!(
+                    "unknown output `{}` (expected json|env|dotenv|file:<path>)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+fn render_env(vars: &[(&str, String)], quoted: bool, export: bool) -> String {
+    let prefix = if export { "export " } else { "" };
+    vars.iter()
+        .map(|(name, value)| {
+            if quoted {
+                format!("{}{}=\"{}\"\n", prefix, name, value)
+            } else {
+                format!("{}{}={}\n", prefix, name, value)
+            }
+        })
+        .collect()
+}
+
+/// Serves the tools of `services` from one server over the chosen transport,
+/// prefixing tool names with their service when `namespaced`.
+async fn serve(
+    services: Vec<WorkspaceService>,
+    namespaced: bool,
+    args: &TransportArgs,
+    options: ServerOptions,
+) -> Result<()> {
+    info!("Serving {:?} over {:?}", services, args.transport);
+    match args.transport {
+        TransportKind::Stdio => {
+            let server = if namespaced {
+                workspace::build_namespaced(ServerStdioTransport, &services, options)?
+            } else {
+                workspace::build_with_options(ServerStdioTransport, &services, options)?
+            };
+            server
+                .listen()
+                .await
+                .map_err(|e| anyhow::anyhow!("Workspace server error: {:#?}", e))?;
+        }
+        TransportKind::Sse => {
+            // The SSE server only takes a port, so other bind addresses are
+            // served by relaying connections to it on a loopback port.
+            let port = if args.host.is_loopback() {
+                args.port
+            } else {
+                let listener = tokio::net::TcpListener::bind((args.host, args.port))
+                    .await
+                    .with_context(|| format!("Failed to bind {}:{}", args.host, args.port))?;
+                let port = free_loopback_port()?;
+                let target = SocketAddr::from(([127, 0, 0, 1], port));
+                tokio::spawn(async move {
+                    if let Err(e) = relay(listener, target).await {
+                        tracing::error!("Relay stopped: {}", e);
+                    }
+                });
+                port
+            };
+            info!("Listening for SSE clients on {}:{}", args.host, args.port);
+            run_http_server(port, None, move |transport| {
+                let services = services.clone();
+                let options = options.clone();
+                async move {
+                    if namespaced {
+                        workspace::build_namespaced(transport, &services, options)
+                    } else {
+                        workspace::build_with_options(transport, &services, options)
+                    }
+                }
+            })
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Fails fast when the configured credentials lack scopes the tools of
+/// `services` need, rather than letting calls hit 403s mid-session. Without
+/// configured credentials clients send their own tokens, so there is nothing
+/// to check; a token check that cannot complete only warns.
+async fn check_token_scopes(services: &[WorkspaceService], options: &ServerOptions) -> Result<()> {
+    let Some(provider) = &options.token_provider else {
+        return Ok(());
+    };
+    let granted = match provider.access_token().await {
+        Ok(access_token) => granted_scopes(&access_token).await,
+        Err(e) => Err(e.into()),
+    };
+    let granted = match granted {
+        Ok(granted) => granted,
+        Err(e) => {
+            warn!("Could not check the token's scopes: {:#}", e);
+            return Ok(());
+        }
+    };
+
+    let tools = list_service_tools(services, options.clone()).await?;
+    let missing = missing_scopes(tools.iter().map(|tool| tool.name.as_str()), &granted);
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "{}\n(pass --skip-scope-check to start anyway)",
+            describe_missing(&missing)
+        );
+    }
+    Ok(())
+}
+
+fn required(value: Option<String>, flag: &str) -> Result<String> {
+    value.with_context(|| format!("{} is required (flag, env var or config file)", flag))
+}
+
+/// An access token from the token provider servers use, when refresh
+/// credentials are configured. Failures go to stderr, keeping stdout for
+/// command output.
+async fn refresh_access_token(provider: Option<&TokenProvider>) -> Option<String> {
+    match provider?.access_token().await {
+        Ok(token) => Some(token),
+        Err(e) => {
+            eprintln!("Could not refresh access token: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,883 @@
+//! Conversion between Markdown and Google Docs, covering what people write
+//! in notes and reports: headings, paragraphs, nested lists, emphasis,
+//! inline code, code blocks, links, quotes and pipe tables.
+//! `create_doc_from_markdown` turns Markdown into Docs `batchUpdate`
+//! requests, and `read_doc_as_markdown` renders a document back.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+/// Characters a backslash makes literal.
+const ESCAPABLE: &str = "\\`*_{}[]()#+-.!|~<>";
+/// Font for inline code and code blocks.
+const CODE_FONT: &str = "Courier New";
+/// Indent of block quotes, in points.
+const QUOTE_INDENT: f64 = 36.0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Italic,
+    Strikethrough,
+    Code,
+    Link(String),
+}
+
+/// Text with the styles of its spans, as UTF-16 offsets since that is what
+/// Docs indexes count.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inline {
+    pub text: String,
+    pub styles: Vec<(u64, u64, Style)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Heading {
+        level: u8,
+        text: Inline,
+    },
+    Paragraph(Inline),
+    Quote(Inline),
+    ListItem {
+        ordered: bool,
+        /// Levels below the top of its list
+        depth: usize,
+        text: Inline,
+    },
+    Code(String),
+    /// Rows of cells, the first being the header
+    Table(Vec<Vec<Inline>>),
+}
+
+fn utf16_len(text: &str) -> u64 {
+    text.encode_utf16().count() as u64
+}
+
+impl Inline {
+    pub fn parse(markdown: &str) -> Self {
+        let mut inline = Inline::default();
+        inline.push_markdown(markdown);
+        inline
+    }
+
+    pub fn len(&self) -> u64 {
+        utf16_len(&self.text)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn push_styled(&mut self, markdown: &str, style: Style) {
+        let start = self.len();
+        self.push_markdown(markdown);
+        self.style_from(start, style);
+    }
+
+    fn push_literal(&mut self, text: &str, style: Style) {
+        let start = self.len();
+        self.text.push_str(text);
+        self.style_from(start, style);
+    }
+
+    fn style_from(&mut self, start: u64, style: Style) {
+        let end = self.len();
+        if end > start {
+            self.styles.push((start, end, style));
+        }
+    }
+
+    fn push_markdown(&mut self, markdown: &str) {
+        let mut rest = markdown;
+        'scan: while let Some(c) = rest.chars().next() {
+            let after = &rest[c.len_utf8()..];
+            match c {
+                '\\' => {
+                    if let Some(next) = after.chars().next().filter(|n| ESCAPABLE.contains(*n)) {
+                        self.text.push(next);
+                        rest = &after[next.len_utf8()..];
+                        continue;
+                    }
+                }
+                '`' => {
+                    if let Some(end) = after.find('`') {
+                        self.push_literal(&after[..end], Style::Code);
+                        rest = &after[end + 1..];
+                        continue;
+                    }
+                }
+                '[' => {
+                    if let Some((label, url, tail)) = split_link(after) {
+                        self.push_styled(label, Style::Link(url.to_string()));
+                        rest = tail;
+                        continue;
+                    }
+                }
+                '<' => {
+                    if let Some(end) = after.find('>') {
+                        let url = &after[..end];
+                        let is_url = ["http://", "https://", "mailto:"]
+                            .iter()
+                            .any(|scheme| url.starts_with(scheme));
+                        if is_url && !url.contains(char::is_whitespace) {
+                            self.push_literal(url, Style::Link(url.to_string()));
+                            rest = &after[end + 1..];
+                            continue;
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            let previous = self.text.chars().last();
+            for (delimiter, style) in [
+                ("**", Style::Bold),
+                ("__", Style::Bold),
+                ("~~", Style::Strikethrough),
+                ("*", Style::Italic),
+                ("_", Style::Italic),
+            ] {
+                if let Some((inner, tail)) = split_emphasis(rest, delimiter, previous) {
+                    self.push_styled(inner, style);
+                    rest = tail;
+                    continue 'scan;
+                }
+            }
+
+            self.text.push(c);
+            rest = after;
+        }
+    }
+}
+
+/// Splits `label](url)rest` after a `[`, ignoring a link title.
+fn split_link(after: &str) -> Option<(&str, &str, &str)> {
+    let mut depth = 0;
+    let mut label_end = None;
+    let mut escaped = false;
+    for (at, c) in after.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => depth += 1,
+            ']' if depth == 0 => {
+                label_end = Some(at);
+                break;
+            }
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    let label_end = label_end?;
+    let target = after[label_end + 1..].strip_prefix('(')?;
+    let target_end = target.find(')')?;
+    let url = target[..target_end].split_whitespace().next()?;
+    let url = url.trim_start_matches('<').trim_end_matches('>');
+    Some((&after[..label_end], url, &target[target_end + 1..]))
+}
+
+/// Splits `rest` into the text between an opening `delimiter` and its
+/// closing one, and what follows. Underscores only count at word boundaries
+/// so `snake_case_names` stay as they are.
+fn split_emphasis<'a>(
+    rest: &'a str,
+    delimiter: &str,
+    previous: Option<char>,
+) -> Option<(&'a str, &'a str)> {
+    let inner = rest.strip_prefix(delimiter)?;
+    let underscore = delimiter.starts_with('_');
+    if inner.starts_with(char::is_whitespace)
+        || (underscore && previous.is_some_and(char::is_alphanumeric))
+    {
+        return None;
+    }
+
+    let marker = delimiter.chars().next()?;
+    let single = delimiter.len() == 1;
+    for (at, _) in inner.match_indices(delimiter).filter(|(at, _)| *at > 0) {
+        let before = inner[..at].chars().last();
+        let tail = &inner[at + delimiter.len()..];
+        let next = tail.chars().next();
+        if before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        // A single `*` next to another is part of a `**` pair
+        if single && (before == Some(marker) || next == Some(marker)) {
+            continue;
+        }
+        if underscore && next.is_some_and(char::is_alphanumeric) {
+            continue;
+        }
+        return Some((&inner[..at], tail));
+    }
+    None
+}
+
+fn heading(line: &str) -> Option<Block> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = &line[level..];
+    if !(1..=6).contains(&level) || !(text.is_empty() || text.starts_with(' ')) {
+        return None;
+    }
+    let text = text.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with(' ') => stripped.trim_end(),
+        _ => text,
+    };
+    Some(Block::Heading {
+        level: level as u8,
+        text: Inline::parse(text),
+    })
+}
+
+fn is_rule(line: &str) -> bool {
+    let line = line.replace(' ', "");
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| line.chars().all(|c| c == *marker))
+}
+
+/// Splits a list item into its indent, whether it is numbered, and its text.
+fn list_item(line: &str) -> Option<(usize, bool, &str)> {
+    let body = line.trim_start();
+    let indent = line[..line.len() - body.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let digits = body.chars().take_while(char::is_ascii_digit).count();
+    let (ordered, text) = if digits > 0 {
+        let text = body[digits..]
+            .strip_prefix('.')
+            .or_else(|| body[digits..].strip_prefix(')'))?;
+        (true, text)
+    } else {
+        let text = body
+            .strip_prefix('-')
+            .or_else(|| body.strip_prefix('*'))
+            .or_else(|| body.strip_prefix('+'))?;
+        (false, text)
+    };
+    if !(text.is_empty() || text.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let text = text.trim();
+    // Task list checkboxes have no Docs equivalent besides their text
+    let text = ["[ ] ", "[x] ", "[X] "]
+        .iter()
+        .find_map(|checkbox| text.strip_prefix(checkbox))
+        .unwrap_or(text);
+    Some((indent, ordered, text))
+}
+
+fn is_table_separator(line: &str) -> bool {
+    let line = line.trim();
+    line.contains('|')
+        && line.contains('-')
+        && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+fn table_cells(line: &str) -> Vec<Inline> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(stripped) if !stripped.ends_with('\\') => stripped,
+        _ => line,
+    };
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut escaped = false;
+    for c in line.chars() {
+        match c {
+            '|' if !escaped => cells.push(Inline::parse(std::mem::take(&mut cell).trim())),
+            _ => {
+                escaped = c == '\\' && !escaped;
+                cell.push(c);
+            }
+        }
+    }
+    cells.push(Inline::parse(cell.trim()));
+    cells
+}
+
+/// Reads `markdown` into blocks. Horizontal rules and HTML comments are
+/// dropped since a document has nowhere to put them.
+pub fn parse(markdown: &str) -> Vec<Block> {
+    let lines = markdown.lines().collect::<Vec<_>>();
+    let mut blocks = Vec::new();
+    let mut paragraph = Vec::new();
+    // Indents of the open list levels, outermost first
+    let mut list_indents: Vec<usize> = Vec::new();
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(Inline::parse(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    };
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        i += 1;
+
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+            continue;
+        }
+
+        if let Some(fence) = ["```", "~~~"]
+            .into_iter()
+            .find(|fence| trimmed.starts_with(fence))
+        {
+            flush(&mut paragraph, &mut blocks);
+            list_indents.clear();
+            let mut code = Vec::new();
+            while i < lines.len() && !lines[i].trim().starts_with(fence) {
+                code.push(lines[i]);
+                i += 1;
+            }
+            i += 1;
+            blocks.push(Block::Code(code.join("\n")));
+            continue;
+        }
+
+        // Setext headings underline the paragraph before them
+        if !paragraph.is_empty() && (trimmed.chars().all(|c| c == '=') || trimmed == "---") {
+            let text = paragraph.join(" ");
+            paragraph.clear();
+            blocks.push(Block::Heading {
+                level: if trimmed.starts_with('=') { 1 } else { 2 },
+                text: Inline::parse(&text),
+            });
+            continue;
+        }
+
+        if let Some(block) = heading(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            list_indents.clear();
+            blocks.push(block);
+            continue;
+        }
+
+        if is_rule(trimmed) || trimmed.starts_with("<!--") {
+            flush(&mut paragraph, &mut blocks);
+            list_indents.clear();
+            continue;
+        }
+
+        if let Some((indent, ordered, text)) = list_item(line) {
+            flush(&mut paragraph, &mut blocks);
+            while list_indents.last().is_some_and(|open| *open > indent) {
+                list_indents.pop();
+            }
+            if list_indents.last().is_none_or(|open| *open < indent) {
+                list_indents.push(indent);
+            }
+            blocks.push(Block::ListItem {
+                ordered,
+                depth: list_indents.len() - 1,
+                text: Inline::parse(text),
+            });
+            continue;
+        }
+
+        if let Some(first) = trimmed.strip_prefix('>') {
+            flush(&mut paragraph, &mut blocks);
+            list_indents.clear();
+            let mut quote = vec![first.trim()];
+            while let Some(next) = lines.get(i).and_then(|line| line.trim().strip_prefix('>')) {
+                quote.push(next.trim());
+                i += 1;
+            }
+            blocks.push(Block::Quote(Inline::parse(&quote.join(" "))));
+            continue;
+        }
+
+        if trimmed.starts_with('|') && lines.get(i).is_some_and(|next| is_table_separator(next)) {
+            flush(&mut paragraph, &mut blocks);
+            list_indents.clear();
+            let mut rows = vec![table_cells(trimmed)];
+            i += 1;
+            while let Some(row) = lines.get(i).filter(|line| line.trim().starts_with('|')) {
+                rows.push(table_cells(row));
+                i += 1;
+            }
+            blocks.push(Block::Table(rows));
+            continue;
+        }
+
+        list_indents.clear();
+        paragraph.push(trimmed);
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+/// Tracks where the next block goes while building requests.
+struct Writer {
+    index: u64,
+    requests: Vec<Value>,
+}
+
+impl Writer {
+    fn insert(&mut self, text: &str, index: u64) {
+        self.requests.push(json!({
+            "insertText": {"text": text, "location": {"index": index}}
+        }));
+    }
+
+    fn text_styles(&mut self, start: u64, styles: &[(u64, u64, Style)]) {
+        for (from, to, style) in styles {
+            let (text_style, fields) = match style {
+                Style::Bold => (json!({"bold": true}), "bold"),
+                Style::Italic => (json!({"italic": true}), "italic"),
+                Style::Strikethrough => (json!({"strikethrough": true}), "strikethrough"),
+                Style::Code => (
+                    json!({"weightedFontFamily": {"fontFamily": CODE_FONT}}),
+                    "weightedFontFamily",
+                ),
+                Style::Link(url) => (json!({"link": {"url": url}}), "link"),
+            };
+            self.requests.push(json!({
+                "updateTextStyle": {
+                    "range": {"startIndex": start + from, "endIndex": start + to},
+                    "textStyle": text_style,
+                    "fields": fields,
+                }
+            }));
+        }
+    }
+
+    fn paragraph(&mut self, text: &Inline, style: Option<(Value, &str)>) {
+        let start = self.index;
+        let end = start + text.len() + 1;
+        self.insert(&format!("{}\n", text.text), start);
+        self.text_styles(start, &text.styles);
+        if let Some((paragraph_style, fields)) = style {
+            self.requests.push(json!({
+                "updateParagraphStyle": {
+                    "range": {"startIndex": start, "endIndex": end},
+                    "paragraphStyle": paragraph_style,
+                    "fields": fields,
+                }
+            }));
+        }
+        self.index = end;
+    }
+
+    /// Docs nests bullets by the tabs leading each paragraph, and removes
+    /// those tabs when it creates the bullets.
+    fn list(&mut self, ordered: bool, items: &[(usize, &Inline)]) {
+        let start = self.index;
+        let mut text = String::new();
+        let mut styles = Vec::new();
+        let mut tabs = 0;
+        for (depth, item) in items {
+            text.push_str(&"\t".repeat(*depth));
+            let offset = utf16_len(&text);
+            text.push_str(&item.text);
+            text.push('\n');
+            styles.extend(
+                item.styles
+                    .iter()
+                    .map(|(from, to, style)| (offset + from, offset + to, style.clone())),
+            );
+            tabs += *depth as u64;
+        }
+
+        let end = start + utf16_len(&text);
+        self.insert(&text, start);
+        self.text_styles(start, &styles);
+        let preset = if ordered {
+            "NUMBERED_DECIMAL_ALPHA_ROMAN"
+        } else {
+            "BULLET_DISC_CIRCLE_SQUARE"
+        };
+        self.requests.push(json!({
+            "createParagraphBullets": {
+                "range": {"startIndex": start, "endIndex": end},
+                "bulletPreset": preset,
+            }
+        }));
+        self.index = end - tabs;
+    }
+
+    /// `insertTable` puts a newline before the table, and every row and
+    /// cell starts with a marker while each empty cell holds a newline, so
+    /// the first cell's text goes 4 past the insertion index. Cells are
+    /// filled from the last so the earlier ones keep their positions.
+    fn table(&mut self, rows: &[Vec<Inline>]) {
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let start = self.index;
+        self.requests.push(json!({
+            "insertTable": {
+                "rows": rows.len(),
+                "columns": columns,
+                "location": {"index": start},
+            }
+        }));
+
+        let row_size = 1 + 2 * columns as u64;
+        let mut written = 0;
+        for (r, row) in rows.iter().enumerate().rev() {
+            for (c, cell) in row.iter().enumerate().rev() {
+                if cell.is_empty() {
+                    continue;
+                }
+                let at = start + 4 + r as u64 * row_size + 2 * c as u64;
+                self.insert(&cell.text, at);
+                self.text_styles(at, &cell.styles);
+                if r == 0 {
+                    self.text_styles(at, &[(0, cell.len(), Style::Bold)]);
+                }
+                written += cell.len();
+            }
+        }
+        self.index = start + 2 + rows.len() as u64 * row_size + written;
+    }
+}
+
+/// Requests writing `blocks` into the body of a new, empty document, whose
+/// text starts at index 1.
+pub fn requests(blocks: &[Block]) -> Vec<Value> {
+    let mut writer = Writer {
+        index: 1,
+        requests: Vec::new(),
+    };
+
+    let mut i = 0;
+    while i < blocks.len() {
+        match &blocks[i] {
+            Block::Heading { level, text } => writer.paragraph(
+                text,
+                Some((
+                    json!({"namedStyleType": format!("HEADING_{}", level)}),
+                    "namedStyleType",
+                )),
+            ),
+            Block::Paragraph(text) => writer.paragraph(text, None),
+            Block::Quote(text) => {
+                let indent = json!({"magnitude": QUOTE_INDENT, "unit": "PT"});
+                writer.paragraph(
+                    text,
+                    Some((
+                        json!({"indentStart": indent, "indentFirstLine": indent}),
+                        "indentStart,indentFirstLine",
+                    )),
+                )
+            }
+            Block::Code(code) => {
+                let mut text = Inline {
+                    text: code.clone(),
+                    styles: Vec::new(),
+                };
+                text.style_from(0, Style::Code);
+                writer.paragraph(&text, None)
+            }
+            Block::ListItem { ordered, .. } => {
+                // A top-level item of the other kind starts a new list
+                let mut items = Vec::new();
+                while let Some(Block::ListItem {
+                    ordered: kind,
+                    depth,
+                    text,
+                }) = blocks.get(i)
+                {
+                    if *depth == 0 && kind != ordered && !items.is_empty() {
+                        break;
+                    }
+                    items.push((*depth, text));
+                    i += 1;
+                }
+                writer.list(*ordered, &items);
+                continue;
+            }
+            Block::Table(rows) => writer.table(rows),
+        }
+        i += 1;
+    }
+    writer.requests
+}
+
+/// How a run of document text reads in Markdown.
+#[derive(Debug, Default, PartialEq)]
+struct RunStyle<'a> {
+    bold: bool,
+    italic: bool,
+    strikethrough: bool,
+    code: bool,
+    link: Option<&'a str>,
+}
+
+impl<'a> RunStyle<'a> {
+    fn of(style: &'a Value) -> Self {
+        let font = style["weightedFontFamily"]["fontFamily"]
+            .as_str()
+            .unwrap_or_default();
+        Self {
+            bold: style["bold"] == true,
+            italic: style["italic"] == true,
+            strikethrough: style["strikethrough"] == true,
+            code: ["Mono", "Courier", "Consolas", "Code", "Inconsolata"]
+                .iter()
+                .any(|name| font.contains(name)),
+            link: style["link"]["url"].as_str(),
+        }
+    }
+
+    /// Wraps `text` in this style's markers, keeping surrounding whitespace
+    /// outside them as Markdown requires.
+    fn render(&self, text: &str) -> String {
+        let core = text.trim();
+        if core.is_empty() {
+            return text.to_string();
+        }
+        let leading = &text[..text.len() - text.trim_start().len()];
+        let trailing = &text[text.trim_end().len()..];
+
+        let mut rendered = match (self.code, core.contains('`')) {
+            (true, false) => format!("`{}`", core),
+            (true, true) => format!("`` {} ``", core),
+            (false, _) => escape(core),
+        };
+        if self.strikethrough {
+            rendered = format!("~~{}~~", rendered);
+        }
+        if self.italic {
+            rendered = format!("_{}_", rendered);
+        }
+        if self.bold {
+            rendered = format!("**{}**", rendered);
+        }
+        if let Some(url) = self.link {
+            rendered = format!("[{}]({})", rendered, url);
+        }
+        format!("{}{}{}", leading, rendered, trailing)
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A paragraph's text runs as Markdown, with neighbouring runs of the same
+/// style merged so their markers are not repeated.
+fn paragraph_markdown(paragraph: &Value) -> String {
+    let mut runs: Vec<(RunStyle, String)> = Vec::new();
+    for run in paragraph["elements"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|element| &element["textRun"])
+    {
+        let Some(content) = run["content"].as_str() else {
+            continue;
+        };
+        let style = RunStyle::of(&run["textStyle"]);
+        match runs.last_mut() {
+            Some((last, text)) if *last == style => text.push_str(content),
+            _ => runs.push((style, content.to_string())),
+        }
+    }
+    runs.iter()
+        .map(|(style, text)| style.render(text))
+        .collect::<String>()
+        .trim_end_matches('\n')
+        .to_string()
+}
+
+/// Whether every visible run of the paragraph is in a monospace font.
+fn is_code(paragraph: &Value) -> bool {
+    let mut runs = paragraph["elements"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|element| &element["textRun"])
+        .filter(|run| {
+            run["content"]
+                .as_str()
+                .is_some_and(|content| !content.trim().is_empty())
+        })
+        .peekable();
+    runs.peek().is_some() && runs.all(|run| RunStyle::of(&run["textStyle"]).code)
+}
+
+fn paragraph_text(paragraph: &Value) -> String {
+    paragraph["elements"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|element| element["textRun"]["content"].as_str())
+        .collect::<String>()
+        .trim_end_matches('\n')
+        .to_string()
+}
+
+fn table_markdown(table: &Value) -> String {
+    let rows = table["tableRows"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|row| {
+            row["tableCells"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|cell| {
+                    cell["content"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter(|content| content["paragraph"].is_object())
+                        .map(|content| paragraph_markdown(&content["paragraph"]))
+                        .filter(|text| !text.trim().is_empty())
+                        .collect::<Vec<_>>()
+                        .join("<br>")
+                        .replace('\u{b}', "<br>")
+                        .replace('|', "\\|")
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let line = |cells: &[String]| {
+        let mut cells = cells.to_vec();
+        cells.resize(columns, String::new());
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = Vec::new();
+    for (r, row) in rows.iter().enumerate() {
+        lines.push(line(row));
+        if r == 0 {
+            lines.push(line(&vec!["---".to_string(); columns]));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Whether a list level is numbered rather than bulleted.
+fn is_numbered(document: &Value, list_id: &str, level: u64) -> bool {
+    let glyph =
+        &document["lists"][list_id]["listProperties"]["nestingLevels"][level as usize]["glyphType"];
+    glyph
+        .as_str()
+        .is_some_and(|glyph| !matches!(glyph, "GLYPH_TYPE_UNSPECIFIED" | "NONE"))
+}
+
+/// Renders a `documents.get` response as Markdown: headings, paragraphs,
+/// nested lists with their numbering, emphasis, links, monospace text as
+/// code, indented paragraphs as quotes and tables. Images, tables of
+/// contents and section breaks are left out.
+pub fn from_document(document: &Value) -> String {
+    // Blocks, and whether each continues the block before it without a blank line
+    let mut blocks: Vec<(String, bool)> = Vec::new();
+    let mut code: Vec<String> = Vec::new();
+    let mut counters: HashMap<(String, u64), u64> = HashMap::new();
+    let mut previous_list = None;
+
+    for element in document["body"]["content"].as_array().into_iter().flatten() {
+        let paragraph = &element["paragraph"];
+        if paragraph.is_object() && is_code(paragraph) {
+            code.push(paragraph_text(paragraph).replace('\u{b}', "\n"));
+            continue;
+        }
+        if !code.is_empty() {
+            blocks.push((format!("```\n{}\n```", code.join("\n")), false));
+            code.clear();
+        }
+
+        if element["table"].is_object() {
+            blocks.push((table_markdown(&element["table"]), false));
+            previous_list = None;
+            continue;
+        }
+        if !paragraph.is_object() {
+            continue;
+        }
+
+        let text = paragraph_markdown(paragraph);
+        if text.trim().is_empty() {
+            let is_rule = paragraph["elements"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|element| element["horizontalRule"].is_object());
+            if is_rule {
+                blocks.push(("---".to_string(), false));
+            }
+            continue;
+        }
+
+        if let Some(list_id) = paragraph["bullet"]["listId"].as_str() {
+            let level = paragraph["bullet"]["nestingLevel"].as_u64().unwrap_or(0);
+            // Deeper levels start counting again under each new item
+            counters.retain(|(list, at), _| list != list_id || *at <= level);
+            let count = counters.entry((list_id.to_string(), level)).or_default();
+            *count += 1;
+
+            let marker = if is_numbered(document, list_id, level) {
+                format!("{}.", count)
+            } else {
+                "-".to_string()
+            };
+            let item = format!(
+                "{}{} {}",
+                "    ".repeat(level as usize),
+                marker,
+                text.trim().replace('\u{b}', " ")
+            );
+            blocks.push((item, previous_list.as_deref() == Some(list_id)));
+            previous_list = Some(list_id.to_string());
+            continue;
+        }
+        previous_list = None;
+
+        let style = &paragraph["paragraphStyle"];
+        let text = text.trim().replace('\u{b}', "  \n");
+        let level = match style["namedStyleType"].as_str() {
+            Some("TITLE") => Some(1),
+            Some(style) => style
+                .strip_prefix("HEADING_")
+                .and_then(|level| level.parse::<usize>().ok()),
+            None => None,
+        };
+        let block = match level {
+            Some(level) => format!("{} {}", "#".repeat(level), text.replace("  \n", " ")),
+            None if style["indentStart"]["magnitude"].as_f64().unwrap_or(0.0) > 0.0 => {
+                format!("> {}", text.replace('\n', "\n> "))
+            }
+            None => text,
+        };
+        blocks.push((block, false));
+    }
+    if !code.is_empty() {
+        blocks.push((format!("```\n{}\n```", code.join("\n")), false));
+    }
+
+    let mut markdown = String::new();
+    for (block, continues) in blocks {
+        if !markdown.is_empty() {
+            markdown.push_str(if continues { "\n" } else { "\n\n" });
+        }
+        markdown.push_str(&block);
+    }
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+    markdown
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    net::SocketAddr,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use anyhow::Result;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+use tracing::{debug, info};
+
+/// Upper bounds (seconds) of the tool latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+#[derive(Debug, Default, Clone)]
+struct ToolStats {
+    calls: u64,
+    errors: u64,
+    latency_sum: f64,
+    latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    response_bytes: u64,
+}
+
+/// Process-wide counters for tool calls and Google API retries.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    tools: Mutex<BTreeMap<String, ToolStats>>,
+    retries: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Metrics {
+    pub fn global() -> &'static Metrics {
+        static METRICS: OnceLock<Metrics> = OnceLock::new();
+        METRICS.get_or_init(Metrics::default)
+    }
+
+    pub fn record_call(
+        &self,
+        tool: &str,
+        elapsed: Duration,
+        is_error: bool,
+        response_bytes: usize,
+    ) {
+        let mut tools = self.tools.lock().unwrap();
+        let stats = tools.entry(tool.to_string()).or_default();
+        let secs = elapsed.as_secs_f64();
+
+        stats.calls += 1;
+        stats.errors += is_error as u64;
+        stats.latency_sum += secs;
+        stats.response_bytes += response_bytes as u64;
+        for (bucket, bound) in stats.latency_buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+    }
+
+    pub fn record_retry(&self, service: &str) {
+        *self
+            .retries
+            .lock()
+            .unwrap()
+            .entry(service.to_string())
+            .or_default() += 1;
+    }
+
+    /// Renders all counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let tools = self.tools.lock().unwrap().clone();
+        let retries = self.retries.lock().unwrap().clone();
+        let mut out = String::new();
+
+        out.push_str("# TYPE mcp_tool_calls_total counter\n");
+        for (tool, stats) in &tools {
+            let _ = writeln!(
+                out,
+                "mcp_tool_calls_total{{tool=\"{}\"}} {}",
+                tool, stats.calls
+            );
+        }
+        out.push_str("# TYPE mcp_tool_errors_total counter\n");
+        for (tool, stats) in &tools {
+            let _ = writeln!(
+                out,
+                "mcp_tool_errors_total{{tool=\"{}\"}} {}",
+                tool, stats.errors
+            );
+        }
+        out.push_str("# TYPE mcp_tool_response_bytes_total counter\n");
+        for (tool, stats) in &tools {
+            let _ = writeln!(
+                out,
+                "mcp_tool_response_bytes_total{{tool=\"{}\"}} {}",
+                tool, stats.response_bytes
+            );
+        }
+        out.push_str("# TYPE mcp_tool_duration_seconds histogram\n");
+        for (tool, stats) in &tools {
+            for (count, bound) in stats.latency_buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "mcp_tool_duration_seconds_bucket{{tool=\"{}\",le=\"{}\"}} {}",
+                    tool, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "mcp_tool_duration_seconds_bucket{{tool=\"{}\",le=\"+Inf\"}} {}",
+                tool, stats.calls
+            );
+            let _ = writeln!(
+                out,
+                "mcp_tool_duration_seconds_sum{{tool=\"{}\"}} {}",
+                tool, stats.latency_sum
+            );
+            let _ = writeln!(
+                out,
+                "mcp_tool_duration_seconds_count{{tool=\"{}\"}} {}",
+                tool, stats.calls
+            );
+        }
+        out.push_str("# TYPE mcp_google_api_retries_total counter\n");
+        for (service, count) in &retries {
+            let _ = writeln!(
+                out,
+                "mcp_google_api_retries_total{{service=\"{}\"}} {}",
+                service, count
+            );
+        }
+        out
+    }
+}
+
+/// Serves `GET /metrics` on `addr`. Runs until the listener fails.
+pub async fn serve_metrics(addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("Serving metrics on http://{}/metrics", addr);
+
+    loop {
+        let (mut stream, peer) = listener.accept().await?;
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+            let response = if path == "/metrics" {
+                let body = Metrics::global().render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            };
+
+            if let Err(e) = stream.write_all(response.as_bytes()).await {
+                debug!("Failed to write metrics response to {}: {}", peer, e);
+            }
+        });
+    }
+}
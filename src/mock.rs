@@ -0,0 +1,223 @@
+//! Local stand-in for the Google APIs, enabled with the `mock-google` feature so
+//! the tool surface can be exercised without credentials or network access.
+
+use std::sync::OnceLock;
+
+use serde_json::json;
+use tokio::sync::OnceCell;
+use wiremock::{
+    matchers::{method, path, path_regex, query_param},
+    Mock, MockServer, Request, Respond, ResponseTemplate,
+};
+
+pub const MOCK_SPREADSHEET_ID: &str = "mock-spreadsheet";
+pub const MOCK_ACCESS_TOKEN: &str = "mock-access-token";
+
+static MOCK_ROOT_URL: OnceLock<String> = OnceLock::new();
+static MOCK_SERVER: OnceCell<MockServer> = OnceCell::const_new();
+
+/// Root URL that `client` should use instead of googleapis.com, once started.
+pub fn mock_root_url() -> Option<String> {
+    MOCK_ROOT_URL.get().cloned()
+}
+
+/// Routes all clients to `url`. Only the first backend started in a process wins.
+pub(crate) fn set_mock_root_url(url: String) -> bool {
+    MOCK_ROOT_URL.set(url).is_ok()
+}
+
+/// Starts the shared mock server (once per process) and routes all clients to it.
+pub async fn start_mock_google() -> &'static MockServer {
+    MOCK_SERVER
+        .get_or_init(|| async {
+            let server = MockServer::start().await;
+            mount_fixtures(&server).await;
+            set_mock_root_url(format!("{}/", server.uri()));
+            server
+        })
+        .await
+}
+
+async fn mount_fixtures(server: &MockServer) {
+    let spreadsheet = json!({
+        "spreadsheetId": MOCK_SPREADSHEET_ID,
+        "spreadsheetUrl": format!("https://docs.google.com/spreadsheets/d/{}/edit", MOCK_SPREADSHEET_ID),
+        "properties": {"title": "Mock Spreadsheet"},
+        "sheets": [
+            {"properties": {"sheetId": 0, "title": "Sheet1", "index": 0, "gridProperties": {"rowCount": 1000, "columnCount": 26}}},
+            {"properties": {"sheetId": 1, "title": "Data", "index": 1, "gridProperties": {"rowCount": 100, "columnCount": 30, "frozenRowCount": 1}}}
+        ]
+    });
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/spreadsheets/[^/]+$"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("etag", "\"mock-etag\"")
+                .set_body_json(&spreadsheet),
+        )
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v4/spreadsheets"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&spreadsheet))
+        .mount(server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/spreadsheets/[^/]+/values/[^/]+$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "range": "Sheet1!A1:C3",
+            "majorDimension": "ROWS",
+            "values": [["name", "email", "score"], ["Ada", "ada@example.com", "42"], ["Linus", "linus@example.com", "7"]]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("PUT"))
+        .and(path_regex(r"^/v4/spreadsheets/[^/]+/values/[^/]+$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "spreadsheetId": MOCK_SPREADSHEET_ID,
+            "updatedRange": "Sheet1!A1",
+            "updatedRows": 1,
+            "updatedColumns": 1,
+            "updatedCells": 1
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path_regex(r"^/v4/spreadsheets/[^/]+/values/[^/]+:append$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "spreadsheetId": MOCK_SPREADSHEET_ID,
+            "tableRange": "Sheet1!A1:C3",
+            "updates": {
+                "spreadsheetId": MOCK_SPREADSHEET_ID,
+                "updatedRange": "Sheet1!A4:C4",
+                "updatedRows": 1,
+                "updatedColumns": 3,
+                "updatedCells": 3
+            }
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path_regex(r"^/v4/spreadsheets/[^/]+/values/[^/]+:clear$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "spreadsheetId": MOCK_SPREADSHEET_ID,
+            "clearedRange": "Sheet1!A1:ZZ1000"
+        })))
+        .mount(server)
+        .await;
+
+    // The second page of the listing; more specific, so it is tried first
+    Mock::given(method("GET"))
+        .and(path("/drive/v3/files"))
+        .and(query_param("pageToken", "mock-page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "files": [
+                {"id": "mock-file", "name": "notes.txt", "mimeType": "text/plain", "modifiedTime": "2023-12-31T00:00:00.000Z"}
+            ]
+        })))
+        .with_priority(1)
+        .mount(server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/drive/v3/files"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "nextPageToken": "mock-page-2",
+            "files": [
+                {"id": MOCK_SPREADSHEET_ID, "name": "Mock Spreadsheet", "mimeType": "application/vnd.google-apps.spreadsheet", "modifiedTime": "2024-01-01T00:00:00.000Z"},
+                {"id": "mock-folder", "name": "Reports", "mimeType": "application/vnd.google-apps.folder", "modifiedTime": "2024-01-02T00:00:00.000Z"}
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/drive/v3/files"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "mock-new-folder",
+            "name": "Archive",
+            "mimeType": "application/vnd.google-apps.folder",
+            "parents": ["root"]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/drive/v3/files/[^/]+$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "mock-file",
+            "name": "Notes",
+            "mimeType": "text/plain",
+            "parents": ["root"]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("PATCH"))
+        .and(path_regex(r"^/drive/v3/files/[^/]+$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "mock-file",
+            "name": "Notes",
+            "mimeType": "text/plain",
+            "parents": ["mock-folder"]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/drive/v3/changes/startPageToken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"startPageToken": "41"})))
+        .mount(server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/drive/v3/changes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "newStartPageToken": "42",
+            "changes": [
+                {"changeType": "file", "removed": false, "time": "2024-01-03T00:00:00.000Z", "fileId": "mock-file",
+                 "file": {"id": "mock-file", "name": "notes.txt", "mimeType": "text/plain"}}
+            ]
+        })))
+        .mount(server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/batch/drive/v3"))
+        .respond_with(BatchResponder)
+        .mount(server)
+        .await;
+}
+
+/// Answers every part of a Drive batch request with `200 OK`.
+struct BatchResponder;
+
+impl Respond for BatchResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body = String::from_utf8_lossy(&request.body);
+        let mut response = String::new();
+        for (index, part) in body.split("Content-ID: <item").skip(1).enumerate() {
+            let file_id = part
+                .split("/files/")
+                .nth(1)
+                .and_then(|rest| rest.split(['/', '?', ' ']).next())
+                .unwrap_or_default();
+            response.push_str(&format!(
+                "--batch_mock\r\nContent-Type: application/http\r\nContent-ID: <response-item{}>\r\n\r\nHTTP/1.1 200 OK\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{}\r\n",
+                index,
+                json!({"id": file_id})
+            ));
+        }
+        response.push_str("--batch_mock--\r\n");
+
+        ResponseTemplate::new(200)
+            .insert_header("content-type", "multipart/mixed; boundary=batch_mock")
+            .set_body_string(response)
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::OnceLock;
+
+use serde_json::Value;
+use tracing::trace;
+
+use crate::redact::redact;
+
+/// Log target for payload lines, so they can be enabled independently of the
+/// rest of the crate's logging.
+pub const TARGET: &str = "mcp_google_workspace::payload";
+
+/// Fields whose values are logged by default. Everything else, including cell
+/// values and file contents, is masked unless explicitly allowed.
+const DEFAULT_FIELDS: &[&str] = &[
+    "range",
+    "majorDimension",
+    "valueInputOption",
+    "spreadsheetId",
+    "sheetId",
+    "title",
+    "id",
+    "name",
+    "mimeType",
+    "trashed",
+    "role",
+    "type",
+    "error",
+    "code",
+    "message",
+    "status",
+    "reason",
+    "domain",
+    "updatedRange",
+    "updatedRows",
+    "updatedColumns",
+    "updatedCells",
+    "nextPageToken",
+];
+
+/// Opt-in logging of Google API request parameters and response bodies.
+#[derive(Debug, Clone)]
+pub struct PayloadLogOptions {
+    pub enabled: bool,
+    /// Bodies are truncated to this many bytes after masking
+    pub max_body_bytes: usize,
+    /// Fields logged in addition to `DEFAULT_FIELDS`
+    pub extra_fields: Vec<String>,
+}
+
+impl Default for PayloadLogOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_body_bytes: 2048,
+            extra_fields: Vec::new(),
+        }
+    }
+}
+
+impl PayloadLogOptions {
+    fn is_allowed(&self, field: &str) -> bool {
+        DEFAULT_FIELDS.contains(&field) || self.extra_fields.iter().any(|f| f == field)
+    }
+}
+
+static OPTIONS: OnceLock<PayloadLogOptions> = OnceLock::new();
+
+/// Installs the payload logging options. Must be called before the first call.
+pub fn init_payload_logging(options: PayloadLogOptions) {
+    let _ = OPTIONS.set(options);
+}
+
+fn enabled_options() -> Option<&'static PayloadLogOptions> {
+    OPTIONS.get().filter(|options| options.enabled)
+}
+
+pub(crate) fn is_enabled() -> bool {
+    enabled_options().is_some()
+}
+
+/// Masks the values of fields that are not allowed, keeping the structure so
+/// malformed payloads remain recognisable.
+pub(crate) fn mask_fields(value: &Value, options: &PayloadLogOptions) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = if options.is_allowed(key) {
+                        value.clone()
+                    } else if value.is_object() || value.is_array() {
+                        mask_fields(value, options)
+                    } else {
+                        Value::String("***".to_string())
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| mask_fields(v, options)).collect())
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {
+            Value::String("***".to_string())
+        }
+    }
+}
+
+pub(crate) fn truncate(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… ({} bytes truncated)", &text[..end], text.len() - end)
+}
+
+fn render(body: &Value, options: &PayloadLogOptions) -> String {
+    let masked = mask_fields(body, options).to_string();
+    truncate(&redact(&masked), options.max_body_bytes)
+}
+
+/// Logs an outgoing request at trace level.
+pub(crate) fn log_request(method: &str, url: &str, body: Option<&Value>) {
+    let Some(options) = enabled_options() else {
+        return;
+    };
+    let body = body.map(|b| render(b, options)).unwrap_or_default();
+    trace!(target: TARGET, method, url = %redact(url), body = %body, "Google API request");
+}
+
+/// Logs a JSON response body at trace level.
+pub(crate) fn log_response(url: &str, status: u16, body: &Value) {
+    let Some(options) = enabled_options() else {
+        return;
+    };
+    trace!(target: TARGET, url = %redact(url), status, body = %render(body, options), "Google API response");
+}
+
+/// Logs a tool call's arguments or result text at trace level. Non-JSON text
+/// is only redacted and truncated.
+pub(crate) fn log_tool_payload(tool: &str, direction: &str, text: &str) {
+    let Some(options) = enabled_options() else {
+        return;
+    };
+    let body = match serde_json::from_str::<Value>(text) {
+        Ok(json) => render(&json, options),
+        Err(_) => truncate(&redact(text), options.max_body_bytes),
+    };
+    trace!(target: TARGET, tool, direction, body = %body, "Tool payload");
+}
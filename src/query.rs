@@ -0,0 +1,947 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::{json, Number, Value};
+
+use crate::a1::{column_index, column_letters};
+
+/// A SQL-like query over the rows of a range, evaluated locally:
+/// `SELECT cols|*|aggregates [WHERE ...] [GROUP BY ...] [ORDER BY ...] [LIMIT n [OFFSET m]]`.
+/// Columns are named by their header cell or by column letter.
+#[derive(Debug, Clone)]
+pub struct Query {
+    selection: Selection,
+    filter: Option<Expr>,
+    group_by: Vec<usize>,
+    order_by: Vec<(Item, bool)>,
+    limit: Option<usize>,
+    offset: usize,
+    /// Header cells, indexed like the row cells
+    headers: Vec<String>,
+    /// Index of the range's first column in the sheet, for naming columns by letter
+    first_column: usize,
+}
+
+/// The result table of a [`Query`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// A standalone WHERE condition, e.g. `Email = 'x@y.com' AND Status != 'done'`,
+/// for tools that pick rows without running a whole [`Query`].
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parses `condition` against the range's header cells and the sheet
+    /// index of its first column, like [`Query::parse`].
+    pub fn parse(condition: &str, headers: &[String], first_column: usize) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(condition)?,
+            pos: 0,
+            headers,
+            first_column,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected {} after the condition", describe(token));
+        }
+        Ok(Filter { expr })
+    }
+
+    pub fn matches(&self, row: &[Value]) -> bool {
+        self.expr.eval(row)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Selection {
+    All,
+    Items(Vec<(Item, String)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Column(usize),
+    /// `None` is `COUNT(*)`
+    Aggregate(Aggregate, Option<usize>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Column(usize),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Compare(Operand, CompareOp, Operand),
+    Like {
+        operand: Operand,
+        pattern: Vec<char>,
+        negated: bool,
+    },
+    In {
+        operand: Operand,
+        values: Vec<Value>,
+        negated: bool,
+    },
+    IsNull {
+        operand: Operand,
+        negated: bool,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    /// A column name in double quotes or backticks
+    Quoted(String),
+    Str(String),
+    Number(f64),
+    Symbol(&'static str),
+}
+
+impl Query {
+    /// Parses `sql` against the range's header cells (empty when it has
+    /// none) and the sheet index of its first column.
+    pub fn parse(sql: &str, headers: &[String], first_column: usize) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(sql)?,
+            pos: 0,
+            headers,
+            first_column,
+        };
+        let query = parser.query()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected {} after the query", describe(token));
+        }
+        query.validate()?;
+        Ok(query)
+    }
+
+    /// Whether `row` passes the WHERE clause.
+    pub fn matches(&self, row: &[Value]) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter.eval(row))
+    }
+
+    /// Whether the `index`th (0-based) matching row can still make it into
+    /// the result, so unordered queries need not keep rows past their LIMIT.
+    pub fn keeps(&self, index: usize) -> bool {
+        self.is_grouped()
+            || !self.order_by.is_empty()
+            || self.limit.is_none_or(|limit| index < self.offset + limit)
+    }
+
+    /// Groups, orders and projects the rows that matched.
+    pub fn evaluate(&self, rows: &[Vec<Value>]) -> QueryResult {
+        let groups = if !self.is_grouped() {
+            rows.iter().map(|row| vec![row.as_slice()]).collect()
+        } else if self.group_by.is_empty() {
+            vec![rows.iter().map(Vec::as_slice).collect()]
+        } else {
+            let mut groups: Vec<Vec<&[Value]>> = Vec::new();
+            let mut index = HashMap::new();
+            for row in rows {
+                let key = self
+                    .group_by
+                    .iter()
+                    .map(|column| cell(row, *column))
+                    .collect::<Vec<_>>();
+                let group = *index
+                    .entry(serde_json::to_string(&key).unwrap_or_default())
+                    .or_insert_with(|| {
+                        groups.push(Vec::new());
+                        groups.len() - 1
+                    });
+                groups[group].push(row.as_slice());
+            }
+            groups
+        };
+
+        let width = self
+            .headers
+            .len()
+            .max(rows.iter().map(Vec::len).max().unwrap_or(0));
+        let columns = match &self.selection {
+            Selection::All => (0..width).map(|i| self.label(i)).collect(),
+            Selection::Items(items) => items.iter().map(|(_, label)| label.clone()).collect(),
+        };
+
+        let mut output = groups
+            .iter()
+            .map(|group| {
+                let values = match &self.selection {
+                    Selection::All => (0..width).map(|i| cell(group[0], i)).collect(),
+                    Selection::Items(items) => {
+                        items.iter().map(|(item, _)| item.eval(group)).collect()
+                    }
+                };
+                let keys = self
+                    .order_by
+                    .iter()
+                    .map(|(item, _)| item.eval(group))
+                    .collect::<Vec<_>>();
+                (keys, values)
+            })
+            .collect::<Vec<_>>();
+
+        output.sort_by(|(a, _), (b, _)| {
+            a.iter()
+                .zip(b)
+                .zip(&self.order_by)
+                .map(|((a, b), (_, descending))| sort_cmp(a, b, *descending))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        QueryResult {
+            columns,
+            rows: output
+                .into_iter()
+                .skip(self.offset)
+                .take(self.limit.unwrap_or(usize::MAX))
+                .map(|(_, values)| values)
+                .collect(),
+        }
+    }
+
+    fn is_grouped(&self) -> bool {
+        let aggregate = |item: &Item| matches!(item, Item::Aggregate(..));
+        let selects_aggregate = match &self.selection {
+            Selection::All => false,
+            Selection::Items(items) => items.iter().any(|(item, _)| aggregate(item)),
+        };
+        selects_aggregate
+            || !self.group_by.is_empty()
+            || self.order_by.iter().any(|(item, _)| aggregate(item))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.is_grouped() {
+            return Ok(());
+        }
+        let items = match &self.selection {
+            Selection::All => bail!("SELECT * cannot be combined with GROUP BY or aggregates"),
+            Selection::Items(items) => items,
+        };
+        for item in items
+            .iter()
+            .map(|(item, _)| item)
+            .chain(self.order_by.iter().map(|(item, _)| item))
+        {
+            if let Item::Column(column) = item {
+                if !self.group_by.contains(column) {
+                    bail!(
+                        "column `{}` must appear in GROUP BY or inside an aggregate",
+                        self.label(*column)
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn label(&self, column: usize) -> String {
+        label(&self.headers, self.first_column, column)
+    }
+}
+
+fn label(headers: &[String], first_column: usize, column: usize) -> String {
+    headers
+        .get(column)
+        .cloned()
+        .unwrap_or_else(|| column_letters(first_column + column))
+}
+
+impl Item {
+    fn eval(&self, group: &[&[Value]]) -> Value {
+        let (aggregate, column) = match self {
+            Item::Column(column) => {
+                return group
+                    .first()
+                    .map(|row| cell(row, *column))
+                    .unwrap_or(Value::Null)
+            }
+            Item::Aggregate(aggregate, column) => (*aggregate, *column),
+        };
+        let Some(column) = column else {
+            return json!(group.len());
+        };
+        let values = group
+            .iter()
+            .map(|row| cell(row, column))
+            .filter(|value| !is_null(value));
+
+        match aggregate {
+            Aggregate::Count => json!(values.count()),
+            Aggregate::Sum | Aggregate::Avg => {
+                let numbers = values
+                    .filter_map(|value| number(&value))
+                    .collect::<Vec<_>>();
+                if numbers.is_empty() {
+                    return Value::Null;
+                }
+                let sum = numbers.iter().sum::<f64>();
+                match aggregate {
+                    Aggregate::Sum => number_value(sum),
+                    _ => number_value(sum / numbers.len() as f64),
+                }
+            }
+            Aggregate::Min => values
+                .min_by(|a, b| sort_cmp(a, b, false))
+                .unwrap_or(Value::Null),
+            Aggregate::Max => values
+                .max_by(|a, b| sort_cmp(a, b, false))
+                .unwrap_or(Value::Null),
+        }
+    }
+}
+
+impl Operand {
+    fn value(&self, row: &[Value]) -> Value {
+        match self {
+            Operand::Column(column) => cell(row, *column),
+            Operand::Literal(value) => value.clone(),
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, row: &[Value]) -> bool {
+        match self {
+            Expr::Compare(left, op, right) => {
+                let (left, right) = (left.value(row), right.value(row));
+                if is_null(&left) || is_null(&right) {
+                    return false;
+                }
+                let ordering = compare(&left, &right);
+                match op {
+                    CompareOp::Eq => ordering == Some(Ordering::Equal),
+                    CompareOp::Ne => ordering != Some(Ordering::Equal),
+                    CompareOp::Lt => ordering == Some(Ordering::Less),
+                    CompareOp::Le => ordering.is_some_and(Ordering::is_le),
+                    CompareOp::Gt => ordering == Some(Ordering::Greater),
+                    CompareOp::Ge => ordering.is_some_and(Ordering::is_ge),
+                }
+            }
+            Expr::Like {
+                operand,
+                pattern,
+                negated,
+            } => {
+                let value = operand.value(row);
+                !is_null(&value) && like(&text(&value), pattern) != *negated
+            }
+            Expr::In {
+                operand,
+                values,
+                negated,
+            } => {
+                let value = operand.value(row);
+                !is_null(&value)
+                    && values
+                        .iter()
+                        .any(|candidate| compare(&value, candidate) == Some(Ordering::Equal))
+                        != *negated
+            }
+            Expr::IsNull { operand, negated } => is_null(&operand.value(row)) != *negated,
+            Expr::And(left, right) => left.eval(row) && right.eval(row),
+            Expr::Or(left, right) => left.eval(row) || right.eval(row),
+            Expr::Not(expr) => !expr.eval(row),
+        }
+    }
+}
+
+/// Whether the first of `rows` looks like column names: every cell is text,
+/// none is blank or a number, and no name repeats.
+pub fn infer_header(rows: &[Vec<Value>]) -> bool {
+    let Some(first) = rows.first() else {
+        return false;
+    };
+    let mut seen = HashSet::new();
+    !first.is_empty()
+        && first.iter().all(|cell| match cell.as_str().map(str::trim) {
+            Some(name) => {
+                !name.is_empty() && name.parse::<f64>().is_err() && seen.insert(name.to_lowercase())
+            }
+            None => false,
+        })
+}
+
+fn cell(row: &[Value], column: usize) -> Value {
+    row.get(column).cloned().unwrap_or(Value::Null)
+}
+
+/// Empty cells come back as `""`, or not at all at the end of a row.
+fn is_null(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.is_empty(),
+        _ => false,
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whole numbers stay integers in the output rather than becoming `3.0`.
+fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < 9_007_199_254_740_992.0 {
+        json!(number as i64)
+    } else {
+        Number::from_f64(number).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Compares numbers numerically, also against text that parses as a number,
+/// and text or booleans with their own kind. Other pairs do not compare.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Number(_), _) | (_, Value::Number(_)) => number(a)?.partial_cmp(&number(b)?),
+        _ => None,
+    }
+}
+
+/// A total order for ORDER BY, MIN and MAX: numbers, then text
+/// (case-insensitively), then booleans, with empty cells always last.
+fn sort_cmp(a: &Value, b: &Value, descending: bool) -> Ordering {
+    let rank = |value: &Value| match value {
+        _ if is_null(value) => 3,
+        Value::Number(_) => 0,
+        Value::String(_) => 1,
+        _ => 2,
+    };
+    let (a_rank, b_rank) = (rank(a), rank(b));
+    if a_rank != b_rank || a_rank == 3 {
+        return a_rank.cmp(&b_rank);
+    }
+    let ordering = match (a, b) {
+        (Value::Number(_), Value::Number(_)) => number(a)
+            .unwrap_or(0.0)
+            .total_cmp(&number(b).unwrap_or(0.0)),
+        (Value::String(a), Value::String(b)) => {
+            a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b))
+        }
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    };
+    if descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// Case-insensitive LIKE: `%` matches any run of characters, `_` exactly one.
+fn like(text: &str, pattern: &[char]) -> bool {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    // matched[j]: whether the text so far matches the first j pattern characters
+    let mut matched = vec![false; pattern.len() + 1];
+    matched[0] = true;
+    for j in 0..pattern.len() {
+        matched[j + 1] = matched[j] && pattern[j] == '%';
+    }
+    for c in text {
+        let mut next = vec![false; pattern.len() + 1];
+        for j in 0..pattern.len() {
+            next[j + 1] = match pattern[j] {
+                '%' => next[j] || matched[j + 1],
+                '_' => matched[j],
+                p => matched[j] && p == c,
+            };
+        }
+        matched = next;
+    }
+    matched[pattern.len()]
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>> {
+    let chars = sql.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let number = chars[start..i].iter().collect::<String>();
+            tokens.push(Token::Number(
+                number
+                    .parse()
+                    .with_context(|| format!("invalid number `{}`", number))?,
+            ));
+        } else if matches!(c, '\'' | '"' | '`') {
+            // A doubled quote stands for the quote character itself
+            let mut quoted = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => bail!("unterminated {} in query", c),
+                    Some(&q) if q == c && chars.get(i + 1) == Some(&c) => {
+                        quoted.push(c);
+                        i += 2;
+                    }
+                    Some(&q) if q == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&other) => {
+                        quoted.push(other);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(match c {
+                '\'' => Token::Str(quoted),
+                _ => Token::Quoted(quoted),
+            });
+        } else {
+            let pair = chars[i..chars.len().min(i + 2)].iter().collect::<String>();
+            let symbol = ["<=", ">=", "!=", "<>", "=="]
+                .into_iter()
+                .find(|symbol| *symbol == pair)
+                .or_else(|| {
+                    ["=", "<", ">", "(", ")", ",", "*", "-"]
+                        .into_iter()
+                        .find(|symbol| symbol.starts_with(c))
+                })
+                .with_context(|| format!("unexpected `{}` in query", c))?;
+            i += symbol.len();
+            // `==` is accepted as a spelling of `=`
+            tokens.push(Token::Symbol(if symbol == "==" { "=" } else { symbol }));
+        }
+    }
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Ident(name) => format!("`{}`", name),
+        Token::Quoted(name) => format!("\"{}\"", name),
+        Token::Str(text) => format!("'{}'", text),
+        Token::Number(number) => format!("`{}`", number),
+        Token::Symbol(symbol) => format!("`{}`", symbol),
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    headers: &'a [String],
+    first_column: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .context("unexpected end of query")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(name)) if name.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Consumes `keyword` if it comes next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if !self.keyword(keyword) {
+            bail!("expected {} {}", keyword, self.found());
+        }
+        Ok(())
+    }
+
+    /// Consumes `symbol` if it comes next.
+    fn symbol(&mut self, symbol: &'static str) -> bool {
+        let found = self.peek() == Some(&Token::Symbol(symbol));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &'static str) -> Result<()> {
+        if !self.symbol(symbol) {
+            bail!("expected `{}` {}", symbol, self.found());
+        }
+        Ok(())
+    }
+
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(token) => format!("but found {}", describe(token)),
+            None => "at the end of the query".to_string(),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query> {
+        self.expect_keyword("SELECT")?;
+        let selection = if self.symbol("*") {
+            Selection::All
+        } else {
+            let mut items = Vec::new();
+            loop {
+                let item = self.item()?;
+                let label = if self.keyword("AS") {
+                    match self.next()? {
+                        Token::Ident(name) | Token::Quoted(name) | Token::Str(name) => name,
+                        other => bail!("expected an alias after AS but found {}", describe(&other)),
+                    }
+                } else {
+                    self.item_label(&item)
+                };
+                items.push((item, label));
+                if !self.symbol(",") {
+                    break;
+                }
+            }
+            Selection::Items(items)
+        };
+
+        let filter = if self.keyword("WHERE") {
+            Some(self.or()?)
+        } else {
+            None
+        };
+
+        let mut group_by = Vec::new();
+        if self.keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            loop {
+                group_by.push(self.column()?);
+                if !self.symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        let mut order_by = Vec::new();
+        if self.keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let alias = match (&selection, self.peek()) {
+                    (Selection::Items(items), Some(Token::Ident(name) | Token::Quoted(name))) => {
+                        items
+                            .iter()
+                            .find(|(_, label)| label.to_lowercase() == name.to_lowercase())
+                            .map(|(item, _)| item.clone())
+                    }
+                    _ => None,
+                };
+                let item = match alias {
+                    Some(item)
+                        if !matches!(self.tokens.get(self.pos + 1), Some(Token::Symbol("("))) =>
+                    {
+                        self.pos += 1;
+                        item
+                    }
+                    _ => self.item()?,
+                };
+                let descending = if self.keyword("DESC") {
+                    true
+                } else {
+                    self.keyword("ASC");
+                    false
+                };
+                order_by.push((item, descending));
+                if !self.symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        let mut limit = None;
+        let mut offset = 0;
+        if self.keyword("LIMIT") {
+            limit = Some(self.count("LIMIT")?);
+            if self.keyword("OFFSET") {
+                offset = self.count("OFFSET")?;
+            }
+        }
+
+        Ok(Query {
+            selection,
+            filter,
+            group_by,
+            order_by,
+            limit,
+            offset,
+            headers: self.headers.to_vec(),
+            first_column: self.first_column,
+        })
+    }
+
+    fn count(&mut self, clause: &str) -> Result<usize> {
+        match self.next()? {
+            Token::Number(number) if number >= 0.0 && number.fract() == 0.0 => Ok(number as usize),
+            other => bail!(
+                "{} takes a whole number of rows, not {}",
+                clause,
+                describe(&other)
+            ),
+        }
+    }
+
+    /// A column or an aggregate such as `COUNT(*)` or `SUM(Total)`.
+    fn item(&mut self) -> Result<Item> {
+        let aggregate = match (self.peek(), self.tokens.get(self.pos + 1)) {
+            (Some(Token::Ident(name)), Some(Token::Symbol("("))) => {
+                match name.to_ascii_uppercase().as_str() {
+                    "COUNT" => Aggregate::Count,
+                    "SUM" => Aggregate::Sum,
+                    "AVG" => Aggregate::Avg,
+                    "MIN" => Aggregate::Min,
+                    "MAX" => Aggregate::Max,
+                    _ => bail!(
+                        "unknown function `{}` (expected COUNT, SUM, AVG, MIN or MAX)",
+                        name
+                    ),
+                }
+            }
+            _ => return Ok(Item::Column(self.column()?)),
+        };
+        self.pos += 2;
+        let column = if aggregate == Aggregate::Count && self.symbol("*") {
+            None
+        } else {
+            Some(self.column()?)
+        };
+        self.expect_symbol(")")?;
+        Ok(Item::Aggregate(aggregate, column))
+    }
+
+    fn item_label(&self, item: &Item) -> String {
+        let (aggregate, column) = match item {
+            Item::Column(column) => return self.label(*column),
+            Item::Aggregate(aggregate, column) => (aggregate, column),
+        };
+        let name = match aggregate {
+            Aggregate::Count => "COUNT",
+            Aggregate::Sum => "SUM",
+            Aggregate::Avg => "AVG",
+            Aggregate::Min => "MIN",
+            Aggregate::Max => "MAX",
+        };
+        match column {
+            Some(column) => format!("{}({})", name, self.label(*column)),
+            None => format!("{}(*)", name),
+        }
+    }
+
+    fn label(&self, column: usize) -> String {
+        label(self.headers, self.first_column, column)
+    }
+
+    /// A column named by its header, matched case-insensitively, or else by
+    /// its upper-case letter.
+    fn column(&mut self) -> Result<usize> {
+        let (name, quoted) = match self.next()? {
+            Token::Ident(name) => (name, false),
+            Token::Quoted(name) => (name, true),
+            other => bail!("expected a column name but found {}", describe(&other)),
+        };
+        let lower = name.to_lowercase();
+        if let Some(column) = self
+            .headers
+            .iter()
+            .position(|header| header.to_lowercase() == lower)
+        {
+            return Ok(column);
+        }
+        if let Some(column) = column_index(&name).filter(|_| !quoted) {
+            if column >= self.first_column {
+                return Ok(column - self.first_column);
+            }
+        }
+        if self.headers.is_empty() {
+            bail!(
+                "unknown column `{}`; without a header row, name columns by letter",
+                name
+            );
+        }
+        bail!(
+            "unknown column `{}` (columns: {})",
+            name,
+            self.headers.join(", ")
+        )
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.keyword("OR") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        while self.keyword("AND") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if self.symbol("(") {
+            let expr = self.or()?;
+            self.expect_symbol(")")?;
+            return Ok(expr);
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Expr> {
+        let operand = self.operand()?;
+
+        if self.keyword("IS") {
+            let negated = self.keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull { operand, negated });
+        }
+        let negated = self.keyword("NOT");
+        if self.keyword("LIKE") {
+            let pattern = match self.next()? {
+                Token::Str(pattern) => pattern.to_lowercase().chars().collect(),
+                other => bail!("LIKE takes a quoted pattern, not {}", describe(&other)),
+            };
+            return Ok(Expr::Like {
+                operand,
+                pattern,
+                negated,
+            });
+        }
+        if self.keyword("IN") {
+            self.expect_symbol("(")?;
+            let mut values = Vec::new();
+            loop {
+                values.push(self.literal()?);
+                if !self.symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol(")")?;
+            return Ok(Expr::In {
+                operand,
+                values,
+                negated,
+            });
+        }
+        if negated {
+            bail!("expected LIKE or IN after NOT {}", self.found());
+        }
+
+        let op = match self.peek() {
+            Some(Token::Symbol("=")) => CompareOp::Eq,
+            Some(Token::Symbol("!=" | "<>")) => CompareOp::Ne,
+            Some(Token::Symbol("<")) => CompareOp::Lt,
+            Some(Token::Symbol("<=")) => CompareOp::Le,
+            Some(Token::Symbol(">")) => CompareOp::Gt,
+            Some(Token::Symbol(">=")) => CompareOp::Ge,
+            _ => bail!("expected a comparison {}", self.found()),
+        };
+        self.pos += 1;
+        Ok(Expr::Compare(operand, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        match self.peek() {
+            Some(Token::Ident(name))
+                if !["TRUE", "FALSE", "NULL"]
+                    .iter()
+                    .any(|keyword| name.eq_ignore_ascii_case(keyword)) =>
+            {
+                Ok(Operand::Column(self.column()?))
+            }
+            Some(Token::Quoted(_)) => Ok(Operand::Column(self.column()?)),
+            _ => Ok(Operand::Literal(self.literal()?)),
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value> {
+        let negative = self.symbol("-");
+        Ok(match self.next()? {
+            Token::Number(number) => number_value(if negative { -number } else { number }),
+            Token::Str(text) if !negative => Value::String(text),
+            Token::Ident(name) if !negative && name.eq_ignore_ascii_case("TRUE") => json!(true),
+            Token::Ident(name) if !negative && name.eq_ignore_ascii_case("FALSE") => json!(false),
+            Token::Ident(name) if !negative && name.eq_ignore_ascii_case("NULL") => Value::Null,
+            other => bail!("expected a value but found {}", describe(&other)),
+        })
+    }
+}
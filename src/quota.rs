@@ -0,0 +1,208 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    future::Future,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::{circuit::Service, client::quota_limits};
+
+/// Google's per-user quotas are counted over 100-second windows.
+pub const QUOTA_WINDOW: Duration = Duration::from_secs(100);
+/// Tracked (user, service) pairs beyond which idle ones are dropped.
+const QUOTA_SWEEP_LEN: usize = 1024;
+
+/// Calls each user may make per quota window before further calls are
+/// delayed; `None` leaves a service unthrottled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaLimits {
+    pub sheets: Option<u32>,
+    pub drive: Option<u32>,
+}
+
+impl Default for QuotaLimits {
+    /// Google's published defaults: 60 Sheets requests per minute per user,
+    /// 1000 Drive queries per 100 seconds per user.
+    fn default() -> Self {
+        Self {
+            sheets: Some(100),
+            drive: Some(1000),
+        }
+    }
+}
+
+impl QuotaLimits {
+    pub fn for_service(&self, service: Service) -> Option<u32> {
+        match service {
+            Service::Sheets => self.sheets,
+            Service::Drive => self.drive,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct UserWindow {
+    /// When each call in the window was (or is scheduled to be) made, oldest first
+    calls: VecDeque<Instant>,
+    throttled: u64,
+    waited: Duration,
+    rate_limited: u64,
+}
+
+impl UserWindow {
+    fn expire(&mut self, now: Instant, window: Duration) {
+        while self.calls.front().is_some_and(|at| *at + window <= now) {
+            self.calls.pop_front();
+        }
+    }
+}
+
+/// One service's counters in [`QuotaTracker::status`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct QuotaStatus {
+    /// Calls made or scheduled in the current window
+    pub calls: usize,
+    pub limit: Option<u32>,
+    /// Calls waiting for a free slot
+    pub queued: usize,
+    /// Calls delayed so far to stay under the limit
+    pub throttled: u64,
+    pub throttle_wait_ms: u64,
+    /// Calls Google rejected as rate-limited regardless
+    pub rate_limited: u64,
+}
+
+tokio::task_local! {
+    static QUOTA_USER: String;
+}
+
+/// Runs `call` with its Google calls counted against `user`'s quota.
+pub(crate) async fn as_user<T>(user: String, call: impl Future<Output = T>) -> T {
+    QUOTA_USER.scope(user, call).await
+}
+
+fn current_user() -> String {
+    QUOTA_USER.try_with(String::clone).unwrap_or_default()
+}
+
+/// Counts Google API calls per user and service over sliding 100-second
+/// windows, and spaces calls out once a user reaches a service's limit so
+/// bulk jobs queue locally instead of running into 429s.
+#[derive(Debug)]
+pub struct QuotaTracker {
+    limits: QuotaLimits,
+    window: Duration,
+    users: Mutex<HashMap<(String, Service), UserWindow>>,
+}
+
+impl QuotaTracker {
+    pub fn new(limits: QuotaLimits, window: Duration) -> Self {
+        Self {
+            limits,
+            window,
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn global() -> &'static QuotaTracker {
+        static TRACKER: OnceLock<QuotaTracker> = OnceLock::new();
+        TRACKER.get_or_init(|| QuotaTracker::new(quota_limits().clone(), QUOTA_WINDOW))
+    }
+
+    /// Waits until the current user may call `service` without exceeding its
+    /// limit, and counts the call.
+    pub async fn acquire(&self, service: Service) {
+        if let Some(wait) = self.reserve(&current_user(), service, Instant::now()) {
+            debug!(
+                service = service.name(),
+                wait_ms = wait.as_millis() as u64,
+                "Near the per-user quota, delaying call"
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Schedules a call by `user` at the earliest time from `now` that keeps
+    /// the window under the limit, returning how long to wait when that is later.
+    pub fn reserve(&self, user: &str, service: Service, now: Instant) -> Option<Duration> {
+        let mut users = self.users.lock().unwrap();
+        if users.len() >= QUOTA_SWEEP_LEN {
+            users.retain(|_, usage| {
+                usage.expire(now, self.window);
+                !usage.calls.is_empty()
+            });
+        }
+        let usage = users.entry((user.to_string(), service)).or_default();
+        usage.expire(now, self.window);
+
+        // Scheduled times only grow, so the call `limit` places back is the one
+        // whose slot this call takes over
+        let at = match self.limits.for_service(service).map(|limit| limit as usize) {
+            Some(limit) if limit > 0 && usage.calls.len() >= limit => {
+                (usage.calls[usage.calls.len() - limit] + self.window).max(now)
+            }
+            _ => now,
+        };
+        usage.calls.push_back(at);
+
+        let wait = at - now;
+        if wait.is_zero() {
+            return None;
+        }
+        usage.throttled += 1;
+        usage.waited += wait;
+        Some(wait)
+    }
+
+    /// Counts a call of the current user that Google rejected as rate-limited.
+    pub fn record_rate_limited(&self, service: Service) {
+        self.users
+            .lock()
+            .unwrap()
+            .entry((current_user(), service))
+            .or_default()
+            .rate_limited += 1;
+    }
+
+    /// The current user's counters per service, including every throttled service.
+    pub fn status(&self) -> BTreeMap<&'static str, QuotaStatus> {
+        self.status_of(&current_user(), Instant::now())
+    }
+
+    pub fn status_of(&self, user: &str, now: Instant) -> BTreeMap<&'static str, QuotaStatus> {
+        let mut status = BTreeMap::new();
+        for service in [Service::Sheets, Service::Drive] {
+            status.insert(
+                service.name(),
+                QuotaStatus {
+                    limit: self.limits.for_service(service),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let mut users = self.users.lock().unwrap();
+        for ((owner, service), usage) in users.iter_mut() {
+            if owner != user {
+                continue;
+            }
+            usage.expire(now, self.window);
+            status.insert(
+                service.name(),
+                QuotaStatus {
+                    calls: usage.calls.len(),
+                    limit: self.limits.for_service(*service),
+                    queued: usage.calls.iter().filter(|at| **at > now).count(),
+                    throttled: usage.throttled,
+                    throttle_wait_ms: usage.waited.as_millis() as u64,
+                    rate_limited: usage.rate_limited,
+                },
+            );
+        }
+        status
+    }
+}
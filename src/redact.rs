@@ -0,0 +1,89 @@
+use std::{
+    io::{self, Write},
+    sync::OnceLock,
+};
+
+use regex::Regex;
+use tracing_subscriber::fmt::MakeWriter;
+
+fn patterns() -> &'static [(Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            // JSON fields and Debug output, e.g. "access_token": "..." or access_token: "..."
+            (
+                r#"("?(?:access_token|refresh_token|client_secret|id_token)"?\s*[:=]\s*)"[^"]*""#,
+                "${1}\"***\"",
+            ),
+            // Query strings and form bodies
+            (
+                r"\b((?:access_token|refresh_token|client_secret|id_token)=)[^&\s]+",
+                "${1}***",
+            ),
+            (r"(?i)\b(bearer\s+)[A-Za-z0-9._~+/\-]+=*", "${1}***"),
+            // Google access tokens, refresh tokens and OAuth client secrets
+            (r"\bya29\.[A-Za-z0-9._\-]+", "ya29.***"),
+            (r"\b1//[A-Za-z0-9._\-]+", "1//***"),
+            (r"\bGOCSPX-[A-Za-z0-9._\-]+", "GOCSPX-***"),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement))
+        .collect()
+    })
+}
+
+/// Masks anything that looks like an OAuth token or client secret.
+pub fn redact(text: &str) -> String {
+    let mut redacted = text.to_string();
+    for (pattern, replacement) in patterns() {
+        if pattern.is_match(&redacted) {
+            redacted = pattern.replace_all(&redacted, *replacement).into_owned();
+        }
+    }
+    redacted
+}
+
+/// `MakeWriter` that redacts every formatted log line before handing it to the
+/// wrapped writer (stderr by default).
+#[derive(Debug, Clone, Copy)]
+pub struct RedactingMakeWriter<M = fn() -> io::Stderr> {
+    inner: M,
+}
+
+impl Default for RedactingMakeWriter {
+    fn default() -> Self {
+        Self { inner: io::stderr }
+    }
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        Self { inner }
+    }
+}
+
+pub struct RedactingWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        self.inner.write_all(redact(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter {
+            inner: self.inner.make_writer(),
+        }
+    }
+}
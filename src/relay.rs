@@ -0,0 +1,31 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::debug;
+
+/// Accepts connections on `listener` and pipes each one to `target` until
+/// either side closes. Used to expose the SSE server, which only listens on a
+/// port, on another bind address.
+pub async fn relay(listener: TcpListener, target: SocketAddr) -> Result<()> {
+    loop {
+        let (mut inbound, peer) = listener.accept().await?;
+        tokio::spawn(async move {
+            let result = async {
+                let mut outbound = TcpStream::connect(target).await?;
+                tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await
+            }
+            .await;
+            if let Err(e) = result {
+                debug!("Connection from {} closed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// A port that is currently free on the loopback interface.
+pub fn free_loopback_port() -> Result<u16> {
+    Ok(std::net::TcpListener::bind(("127.0.0.1", 0))?
+        .local_addr()?
+        .port())
+}
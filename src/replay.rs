@@ -0,0 +1,244 @@
+//! Record/replay of Google API traffic for deterministic integration tests.
+//!
+//! In record mode a local proxy forwards requests to googleapis.com and appends
+//! each request/response pair, with tokens and configured values scrubbed, to a
+//! JSON cassette. In replay mode a mock server answers only requests that match a
+//! recorded interaction exactly (method, path, query and body), so regressions in
+//! request construction surface as failed calls.
+
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tracing::warn;
+use wiremock::{Match, Mock, MockServer, Request, ResponseTemplate};
+
+use crate::{client::get_http_client, mock::set_mock_root_url, redact::redact};
+
+/// One recorded request/response pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    /// Percent-decoded request path, e.g. `/v4/spreadsheets/{id}/values/Sheet1!A1`
+    pub path: String,
+    /// Percent-decoded query parameters
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    #[serde(default)]
+    pub request_body: Option<String>,
+    pub status: u16,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    pub response_body: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading cassette {}", path.display()))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing cassette {}", path.display()))
+    }
+}
+
+fn decode(value: &str) -> String {
+    urlencoding::decode(value)
+        .map(|v| v.into_owned())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+/// Matches a request only if it is identical to a recorded interaction.
+struct InteractionMatcher(Interaction);
+
+impl Match for InteractionMatcher {
+    fn matches(&self, request: &Request) -> bool {
+        let query = request
+            .url
+            .query_pairs()
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect::<BTreeMap<_, _>>();
+        let body = String::from_utf8_lossy(&request.body);
+        let expected_body = self.0.request_body.as_deref().unwrap_or_default();
+
+        request.method.as_str().eq_ignore_ascii_case(&self.0.method)
+            && decode(request.url.path()) == self.0.path
+            && query == self.0.query
+            && body.trim() == expected_body.trim()
+    }
+}
+
+/// Starts a strict replay server for `cassette` and routes all clients to it.
+pub async fn start_replay(cassette: &Path) -> Result<MockServer> {
+    let cassette = Cassette::load(cassette)?;
+    let server = MockServer::start().await;
+
+    for interaction in cassette.interactions {
+        let mut response = ResponseTemplate::new(interaction.status)
+            .set_body_string(interaction.response_body.clone());
+        if let Some(content_type) = &interaction.content_type {
+            response = response.insert_header("content-type", content_type.as_str());
+        }
+        Mock::given(InteractionMatcher(interaction))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+    }
+
+    set_mock_root_url(format!("{}/", server.uri()));
+    Ok(server)
+}
+
+/// Recording proxy state shared between connections.
+struct Recorder {
+    cassette_path: PathBuf,
+    cassette: Mutex<Cassette>,
+    /// Real values (IDs, emails) replaced by placeholders before saving
+    substitutions: Vec<(String, String)>,
+}
+
+impl Recorder {
+    fn scrub(&self, text: &str) -> String {
+        let mut scrubbed = redact(text);
+        for (real, placeholder) in &self.substitutions {
+            scrubbed = scrubbed.replace(real.as_str(), placeholder);
+        }
+        scrubbed
+    }
+
+    fn record(&self, interaction: Interaction) {
+        let mut cassette = self.cassette.lock().unwrap();
+        cassette.interactions.push(interaction);
+        if let Err(e) = cassette.save(&self.cassette_path) {
+            warn!("Failed to save cassette: {}", e);
+        }
+    }
+}
+
+/// Starts a local proxy that forwards to googleapis.com and records every
+/// interaction into `cassette`. `substitutions` maps real values to placeholders.
+pub async fn start_recorder(
+    cassette: &Path,
+    substitutions: Vec<(String, String)>,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let recorder = Arc::new(Recorder {
+        cassette_path: cassette.to_path_buf(),
+        cassette: Mutex::new(Cassette::default()),
+        substitutions,
+    });
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let recorder = recorder.clone();
+            tokio::spawn(async move {
+                if let Err(e) = proxy_connection(stream, &recorder).await {
+                    warn!("Recording proxy error: {}", e);
+                }
+            });
+        }
+    });
+
+    set_mock_root_url(format!("http://{}/", addr));
+    Ok(addr)
+}
+
+async fn proxy_connection(stream: TcpStream, recorder: &Recorder) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().context("missing method")?.to_string();
+    let target = parts.next().context("missing request target")?.to_string();
+
+    let mut headers = Vec::new();
+    let mut content_length = 0usize;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let (name, value) = (name.trim().to_ascii_lowercase(), value.trim().to_string());
+            if name == "content-length" {
+                content_length = value.parse()?;
+            }
+            headers.push((name, value));
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).await?;
+
+    // Sheets lives on its own host; everything else is under www.googleapis.com
+    let upstream = if target.starts_with("/v4/") {
+        "https://sheets.googleapis.com"
+    } else {
+        "https://www.googleapis.com"
+    };
+    let url = url::Url::parse(&format!("{}{}", upstream, target))?;
+
+    let mut forward = get_http_client()?.request(method.parse()?, url.clone());
+    for (name, value) in &headers {
+        if matches!(name.as_str(), "authorization" | "content-type") {
+            forward = forward.header(name.as_str(), value.as_str());
+        }
+    }
+    let response = forward.body(body.clone()).send().await?;
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let response_body = response.bytes().await?;
+
+    let request_body = String::from_utf8_lossy(&body);
+    recorder.record(Interaction {
+        method: method.clone(),
+        path: recorder.scrub(&decode(url.path())),
+        query: url
+            .query_pairs()
+            .map(|(k, v)| (k.into_owned(), recorder.scrub(&v)))
+            .collect(),
+        request_body: (!request_body.is_empty()).then(|| recorder.scrub(&request_body)),
+        status,
+        content_type: content_type.clone(),
+        response_body: recorder.scrub(&String::from_utf8_lossy(&response_body)),
+    });
+
+    let mut stream = reader.into_inner();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reqwest::StatusCode::from_u16(status)?
+            .canonical_reason()
+            .unwrap_or(""),
+        content_type.as_deref().unwrap_or("application/json"),
+        response_body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&response_body).await?;
+    stream.flush().await?;
+    Ok(())
+}
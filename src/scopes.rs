@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{client::get_http_client, InvokeError};
+
+pub const TOKENINFO_URL: &str = "https://oauth2.googleapis.com/tokeninfo";
+
+const AUTH: &str = "https://www.googleapis.com/auth/";
+const DRIVE: &str = "https://www.googleapis.com/auth/drive";
+const DRIVE_FILE: &str = "https://www.googleapis.com/auth/drive.file";
+const DRIVE_READONLY: &str = "https://www.googleapis.com/auth/drive.readonly";
+const DRIVE_METADATA_READONLY: &str = "https://www.googleapis.com/auth/drive.metadata.readonly";
+const SPREADSHEETS: &str = "https://www.googleapis.com/auth/spreadsheets";
+const SPREADSHEETS_READONLY: &str = "https://www.googleapis.com/auth/spreadsheets.readonly";
+const BIGQUERY: &str = "https://www.googleapis.com/auth/bigquery";
+const CLOUD_PLATFORM: &str = "https://www.googleapis.com/auth/cloud-platform";
+const MAIL: &str = "https://mail.google.com/";
+const GMAIL_MODIFY: &str = "https://www.googleapis.com/auth/gmail.modify";
+const GMAIL_COMPOSE: &str = "https://www.googleapis.com/auth/gmail.compose";
+const GMAIL_SEND: &str = "https://www.googleapis.com/auth/gmail.send";
+const GMAIL_READONLY: &str = "https://www.googleapis.com/auth/gmail.readonly";
+const CALENDAR: &str = "https://www.googleapis.com/auth/calendar";
+const CALENDAR_READONLY: &str = "https://www.googleapis.com/auth/calendar.readonly";
+const CALENDAR_EVENTS: &str = "https://www.googleapis.com/auth/calendar.events";
+const DOCUMENTS: &str = "https://www.googleapis.com/auth/documents";
+const DOCUMENTS_READONLY: &str = "https://www.googleapis.com/auth/documents.readonly";
+const PRESENTATIONS: &str = "https://www.googleapis.com/auth/presentations";
+const PRESENTATIONS_READONLY: &str = "https://www.googleapis.com/auth/presentations.readonly";
+const TASKS: &str = "https://www.googleapis.com/auth/tasks";
+const TASKS_READONLY: &str = "https://www.googleapis.com/auth/tasks.readonly";
+const ADMIN_USER: &str = "https://www.googleapis.com/auth/admin.directory.user";
+const ADMIN_USER_READONLY: &str = "https://www.googleapis.com/auth/admin.directory.user.readonly";
+const ADMIN_GROUP: &str = "https://www.googleapis.com/auth/admin.directory.group";
+const ADMIN_GROUP_READONLY: &str = "https://www.googleapis.com/auth/admin.directory.group.readonly";
+const ADMIN_GROUP_MEMBER: &str = "https://www.googleapis.com/auth/admin.directory.group.member";
+const ADMIN_GROUP_MEMBER_READONLY: &str =
+    "https://www.googleapis.com/auth/admin.directory.group.member.readonly";
+const CLASSROOM_COURSES: &str = "https://www.googleapis.com/auth/classroom.courses";
+const CLASSROOM_COURSES_READONLY: &str =
+    "https://www.googleapis.com/auth/classroom.courses.readonly";
+const CLASSROOM_COURSEWORK: &str = "https://www.googleapis.com/auth/classroom.coursework.students";
+const CLASSROOM_COURSEWORK_READONLY: &str =
+    "https://www.googleapis.com/auth/classroom.coursework.students.readonly";
+const CLASSROOM_COURSEWORK_ME: &str = "https://www.googleapis.com/auth/classroom.coursework.me";
+const CLASSROOM_COURSEWORK_ME_READONLY: &str =
+    "https://www.googleapis.com/auth/classroom.coursework.me.readonly";
+const CLASSROOM_ANNOUNCEMENTS: &str = "https://www.googleapis.com/auth/classroom.announcements";
+const YOUTUBE: &str = "https://www.googleapis.com/auth/youtube";
+const YOUTUBE_FORCE_SSL: &str = "https://www.googleapis.com/auth/youtube.force-ssl";
+const YOUTUBE_READONLY: &str = "https://www.googleapis.com/auth/youtube.readonly";
+const YT_ANALYTICS_READONLY: &str = "https://www.googleapis.com/auth/yt-analytics.readonly";
+const MEETINGS_SPACE_CREATED: &str = "https://www.googleapis.com/auth/meetings.space.created";
+const MEETINGS_SPACE_READONLY: &str = "https://www.googleapis.com/auth/meetings.space.readonly";
+const CHAT_SPACES: &str = "https://www.googleapis.com/auth/chat.spaces";
+const CHAT_SPACES_READONLY: &str = "https://www.googleapis.com/auth/chat.spaces.readonly";
+const CHAT_MESSAGES: &str = "https://www.googleapis.com/auth/chat.messages";
+const CHAT_MESSAGES_READONLY: &str = "https://www.googleapis.com/auth/chat.messages.readonly";
+const CHAT_MESSAGES_CREATE: &str = "https://www.googleapis.com/auth/chat.messages.create";
+const CHAT_MESSAGES_REACTIONS: &str = "https://www.googleapis.com/auth/chat.messages.reactions";
+const CHAT_MESSAGES_REACTIONS_CREATE: &str =
+    "https://www.googleapis.com/auth/chat.messages.reactions.create";
+
+/// The scopes a tool can run with: any one of `accepted` is enough, and
+/// `minimal`, the narrowest of them, is the one to ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ScopeRequirement {
+    pub minimal: &'static str,
+    pub accepted: &'static [&'static str],
+}
+
+impl ScopeRequirement {
+    const fn new(accepted: &'static [&'static str]) -> Self {
+        // Listed broadest first, so the last scope is the narrowest
+        Self {
+            minimal: accepted[accepted.len() - 1],
+            accepted,
+        }
+    }
+
+    pub fn is_satisfied_by(&self, granted: &[String]) -> bool {
+        self.accepted.iter().any(|s| granted.iter().any(|g| g == s))
+    }
+}
+
+const SHEETS_READ: ScopeRequirement =
+    ScopeRequirement::new(&[SPREADSHEETS, DRIVE, DRIVE_READONLY, SPREADSHEETS_READONLY]);
+const SHEETS_WRITE: ScopeRequirement = ScopeRequirement::new(&[DRIVE, SPREADSHEETS]);
+const BIGQUERY_LOAD: ScopeRequirement = ScopeRequirement::new(&[CLOUD_PLATFORM, BIGQUERY]);
+const DRIVE_LIST: ScopeRequirement =
+    ScopeRequirement::new(&[DRIVE, DRIVE_READONLY, DRIVE_METADATA_READONLY]);
+const DRIVE_READ: ScopeRequirement = ScopeRequirement::new(&[DRIVE, DRIVE_READONLY]);
+const DRIVE_CREATE: ScopeRequirement = ScopeRequirement::new(&[DRIVE, DRIVE_FILE]);
+const DRIVE_WRITE: ScopeRequirement = ScopeRequirement::new(&[DRIVE]);
+const GMAIL_READ: ScopeRequirement = ScopeRequirement::new(&[MAIL, GMAIL_MODIFY, GMAIL_READONLY]);
+const GMAIL_SEND_ONLY: ScopeRequirement =
+    ScopeRequirement::new(&[MAIL, GMAIL_MODIFY, GMAIL_COMPOSE, GMAIL_SEND]);
+const GMAIL_DRAFT: ScopeRequirement = ScopeRequirement::new(&[MAIL, GMAIL_MODIFY, GMAIL_COMPOSE]);
+const GMAIL_LABEL: ScopeRequirement = ScopeRequirement::new(&[MAIL, GMAIL_MODIFY]);
+const CALENDAR_READ: ScopeRequirement = ScopeRequirement::new(&[CALENDAR, CALENDAR_READONLY]);
+const CALENDAR_WRITE: ScopeRequirement = ScopeRequirement::new(&[CALENDAR, CALENDAR_EVENTS]);
+const DOCS_READ: ScopeRequirement =
+    ScopeRequirement::new(&[DOCUMENTS, DRIVE, DRIVE_READONLY, DOCUMENTS_READONLY]);
+const DOCS_WRITE: ScopeRequirement = ScopeRequirement::new(&[DRIVE, DOCUMENTS]);
+const SLIDES_READ: ScopeRequirement =
+    ScopeRequirement::new(&[PRESENTATIONS, DRIVE, DRIVE_READONLY, PRESENTATIONS_READONLY]);
+const SLIDES_WRITE: ScopeRequirement = ScopeRequirement::new(&[DRIVE, PRESENTATIONS]);
+const TASKS_READ: ScopeRequirement = ScopeRequirement::new(&[TASKS, TASKS_READONLY]);
+const TASKS_WRITE: ScopeRequirement = ScopeRequirement::new(&[TASKS]);
+const ADMIN_USERS_READ: ScopeRequirement =
+    ScopeRequirement::new(&[ADMIN_USER, ADMIN_USER_READONLY]);
+const ADMIN_GROUPS_READ: ScopeRequirement =
+    ScopeRequirement::new(&[ADMIN_GROUP, ADMIN_GROUP_READONLY]);
+const ADMIN_MEMBERS_READ: ScopeRequirement = ScopeRequirement::new(&[
+    ADMIN_GROUP,
+    ADMIN_GROUP_MEMBER,
+    ADMIN_GROUP_READONLY,
+    ADMIN_GROUP_MEMBER_READONLY,
+]);
+const ADMIN_MEMBERS_WRITE: ScopeRequirement =
+    ScopeRequirement::new(&[ADMIN_GROUP, ADMIN_GROUP_MEMBER]);
+const CLASSROOM_COURSES_READ: ScopeRequirement =
+    ScopeRequirement::new(&[CLASSROOM_COURSES, CLASSROOM_COURSES_READONLY]);
+const CLASSROOM_COURSEWORK_READ: ScopeRequirement = ScopeRequirement::new(&[
+    CLASSROOM_COURSEWORK,
+    CLASSROOM_COURSEWORK_READONLY,
+    CLASSROOM_COURSEWORK_ME,
+    CLASSROOM_COURSEWORK_ME_READONLY,
+]);
+const CLASSROOM_COURSEWORK_WRITE: ScopeRequirement = ScopeRequirement::new(&[CLASSROOM_COURSEWORK]);
+const CLASSROOM_ANNOUNCE: ScopeRequirement = ScopeRequirement::new(&[CLASSROOM_ANNOUNCEMENTS]);
+const YOUTUBE_READ: ScopeRequirement =
+    ScopeRequirement::new(&[YOUTUBE, YOUTUBE_FORCE_SSL, YOUTUBE_READONLY]);
+const YOUTUBE_WRITE: ScopeRequirement = ScopeRequirement::new(&[YOUTUBE, YOUTUBE_FORCE_SSL]);
+const YOUTUBE_ANALYTICS: ScopeRequirement = ScopeRequirement::new(&[YT_ANALYTICS_READONLY]);
+const MEET_CREATE: ScopeRequirement = ScopeRequirement::new(&[MEETINGS_SPACE_CREATED]);
+const MEET_READ: ScopeRequirement = ScopeRequirement::new(&[MEETINGS_SPACE_READONLY]);
+const CHAT_SPACES_READ: ScopeRequirement =
+    ScopeRequirement::new(&[CHAT_SPACES, CHAT_SPACES_READONLY]);
+const CHAT_MESSAGES_READ: ScopeRequirement =
+    ScopeRequirement::new(&[CHAT_MESSAGES, CHAT_MESSAGES_READONLY]);
+const CHAT_POST: ScopeRequirement = ScopeRequirement::new(&[CHAT_MESSAGES, CHAT_MESSAGES_CREATE]);
+const CHAT_REACT: ScopeRequirement = ScopeRequirement::new(&[
+    CHAT_MESSAGES,
+    CHAT_MESSAGES_REACTIONS,
+    CHAT_MESSAGES_REACTIONS_CREATE,
+]);
+
+/// What `tool` needs to run. Namespaced names (`sheets.read_values`) are
+/// accepted; unknown tools yield `None`.
+pub fn tool_scopes(tool: &str) -> Option<ScopeRequirement> {
+    let name = tool.rsplit('.').next().unwrap_or(tool);
+    Some(match name {
+        "read_values" | "get_sheet_info" | "batch_read_values" | "export_values"
+        | "query_sheet" | "read_table" | "get_cell_notes" | "search_metadata" => SHEETS_READ,
+        "write_values"
+        | "append_values"
+        | "append_record"
+        | "update_rows_where"
+        | "create_spreadsheet"
+        | "clear_values"
+        | "batch_update"
+        | "batch_write_values"
+        | "format_cells"
+        | "create_pivot_table"
+        | "sort_range"
+        | "set_basic_filter"
+        | "clear_basic_filter"
+        | "remove_duplicate_rows"
+        | "trim_whitespace"
+        | "freeze_panes"
+        | "hide_sheet"
+        | "show_sheet"
+        | "set_tab_color"
+        | "set_cell_note"
+        | "set_metadata"
+        | "delete_metadata"
+        | "duplicate_sheet"
+        | "copy_sheet_to_spreadsheet"
+        | "copy_range_between_spreadsheets" => SHEETS_WRITE,
+        // The Sheets tools it is served with already need a Sheets scope
+        "sheets_to_bigquery" => BIGQUERY_LOAD,
+
+        "list_spreadsheets" | "list_files" | "search_files" | "list_revisions" | "get_revision"
+        | "get_changes" | "resolve_shortcut" => DRIVE_LIST,
+        "download_file" => DRIVE_READ,
+        "upload_file" | "create_folder" | "create_shortcut" => DRIVE_CREATE,
+        "trash_files" | "share_files" | "move_file" | "copy_file" | "rename_file"
+        | "delete_file" | "restore_revision" => DRIVE_WRITE,
+
+        "list_threads" | "search_messages" | "read_message" | "read_thread" | "list_labels" => {
+            GMAIL_READ
+        }
+        "send_email" => GMAIL_SEND_ONLY,
+        "create_draft" => GMAIL_DRAFT,
+        "modify_labels" => GMAIL_LABEL,
+
+        "list_calendars" | "list_events" | "query_free_busy" | "find_meeting_slots" => {
+            CALENDAR_READ
+        }
+        "create_event" | "update_event" | "delete_event" | "respond_to_event" => CALENDAR_WRITE,
+
+        "read_document" | "read_doc_as_markdown" => DOCS_READ,
+        "create_document"
+        | "create_doc_from_markdown"
+        | "insert_text"
+        | "replace_text"
+        | "format_text" => DOCS_WRITE,
+
+        "list_slides" => SLIDES_READ,
+        "create_presentation" | "add_slide" | "replace_placeholders" | "insert_image" => {
+            SLIDES_WRITE
+        }
+
+        "list_task_lists" | "list_tasks" => TASKS_READ,
+        "create_task" | "complete_task" | "set_task_due_date" | "delete_task" => TASKS_WRITE,
+
+        "list_spaces" => CHAT_SPACES_READ,
+        "list_messages" => CHAT_MESSAGES_READ,
+        "post_message" => CHAT_POST,
+        "add_reaction" => CHAT_REACT,
+
+        "create_meeting_space" => MEET_CREATE,
+        "get_meeting_space" | "list_meeting_artifacts" => MEET_READ,
+
+        "list_users" | "get_user" => ADMIN_USERS_READ,
+        "list_groups" => ADMIN_GROUPS_READ,
+        "list_group_members" => ADMIN_MEMBERS_READ,
+        "add_group_member" | "remove_group_member" | "set_group_member_role" => ADMIN_MEMBERS_WRITE,
+
+        "list_courses" => CLASSROOM_COURSES_READ,
+        "list_coursework" | "list_submissions" => CLASSROOM_COURSEWORK_READ,
+        "create_assignment" => CLASSROOM_COURSEWORK_WRITE,
+        "create_announcement" => CLASSROOM_ANNOUNCE,
+
+        "list_channel_videos" | "get_video" => YOUTUBE_READ,
+        "update_video_metadata" => YOUTUBE_WRITE,
+        "get_channel_analytics" => YOUTUBE_ANALYTICS,
+
+        _ => return None,
+    })
+}
+
+/// Whether `tool` runs without calling Google, and so needs no scopes at all.
+pub fn needs_no_scopes(tool: &str) -> bool {
+    matches!(tool.rsplit('.').next().unwrap_or(tool), "get_quota_status")
+}
+
+/// The fewest scopes that cover every one of `tools`: each tool's minimal
+/// scope, less those made redundant by a broader scope another tool needs.
+pub fn required_scopes<'a>(tools: impl IntoIterator<Item = &'a str>) -> Vec<&'static str> {
+    let requirements = tools
+        .into_iter()
+        .filter_map(tool_scopes)
+        .collect::<Vec<_>>();
+    let mut scopes = requirements.iter().map(|r| r.minimal).collect::<Vec<_>>();
+    scopes.sort_unstable();
+    scopes.dedup();
+
+    let mut i = 0;
+    while i < scopes.len() {
+        let scope = scopes[i];
+        let covered_elsewhere = requirements
+            .iter()
+            .filter(|r| r.minimal == scope)
+            .all(|r| r.accepted.iter().any(|s| *s != scope && scopes.contains(s)));
+        if covered_elsewhere {
+            scopes.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    scopes
+}
+
+/// Each of `tools` the `granted` scopes do not cover, with what it needs.
+pub fn missing_scopes<'a>(
+    tools: impl IntoIterator<Item = &'a str>,
+    granted: &[String],
+) -> BTreeMap<&'a str, ScopeRequirement> {
+    tools
+        .into_iter()
+        .filter_map(|tool| Some((tool, tool_scopes(tool)?)))
+        .filter(|(_, requirement)| !requirement.is_satisfied_by(granted))
+        .collect()
+}
+
+/// Expands a short scope name like `drive.readonly` to its URL.
+pub fn expand_scope(scope: &str) -> String {
+    if scope.contains("://") || matches!(scope, "email" | "openid" | "profile") {
+        scope.to_string()
+    } else {
+        format!("{}{}", AUTH, scope)
+    }
+}
+
+/// The scopes Google granted `access_token`, looked up with the tokeninfo
+/// endpoint.
+pub async fn granted_scopes(access_token: &str) -> Result<Vec<String>> {
+    let response = get_http_client()?
+        .get(TOKENINFO_URL)
+        .query(&[("access_token", access_token)])
+        .send()
+        .await
+        .map_err(|e| InvokeError::Transport(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(InvokeError::from_response(response).await).context("token check failed");
+    }
+
+    let info = response.json::<Value>().await?;
+    Ok(info["scope"]
+        .as_str()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Explains which tools `granted` does not cover and how to fix it: grant the
+/// missing scopes with `auth login`, or hide the tools.
+pub fn describe_missing(missing: &BTreeMap<&str, ScopeRequirement>) -> String {
+    let tools = missing.keys().copied().collect::<Vec<_>>();
+    let scopes = required_scopes(tools.iter().copied());
+    format!(
+        "the access token lacks the scopes {} tool(s) need: {}\n\
+         grant them with `mcp-google auth login --minimal-scopes` (needs {}), \
+         or hide those tools with --read-only or --deny-tools",
+        tools.len(),
+        tools.join(", "),
+        scopes.join(" ")
+    )
+}
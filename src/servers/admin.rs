@@ -0,0 +1,318 @@
+use anyhow::Result;
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
+
+use super::{api_name, json_response, rest_request, ServerOptions, ToolRegistry};
+use crate::{circuit::Service, client::admin_root_url};
+
+const DEFAULT_MAX_RESULTS: u64 = 100;
+/// The account's own customer, i.e. every domain the admin manages
+const MY_CUSTOMER: &str = "my_customer";
+/// Listing users returns whole profiles; these are what callers ask about
+const USER_LIST_FIELDS: &str = "nextPageToken,users(id,primaryEmail,name/fullName,isAdmin,isDelegatedAdmin,suspended,archived,orgUnitPath,lastLoginTime,creationTime)";
+
+// Tool Arguments
+
+fn default_max_results() -> u64 {
+    DEFAULT_MAX_RESULTS
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GroupRole {
+    Owner,
+    Manager,
+    #[default]
+    Member,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListUsersArgs {
+    /// Directory search, e.g. `orgUnitPath=/Sales isSuspended=false` or `name:'Jane*'`
+    pub query: Option<String>,
+    /// Only this domain's users; defaults to every domain of the account
+    pub domain: Option<String>,
+    #[serde(default = "default_max_results")]
+    #[schemars(range(min = 1, max = 500))]
+    pub max_results: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetUserArgs {
+    /// Primary email, alias or user ID
+    pub user_key: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListGroupsArgs {
+    /// Only the groups this user (email or ID) belongs to
+    pub user_key: Option<String>,
+    /// Group search, e.g. `email:sales*`
+    pub query: Option<String>,
+    /// Only this domain's groups; defaults to every domain of the account
+    pub domain: Option<String>,
+    #[serde(default = "default_max_results")]
+    #[schemars(range(min = 1, max = 200))]
+    pub max_results: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListGroupMembersArgs {
+    /// Group email, alias or ID
+    pub group_key: String,
+    /// Only members with these roles
+    #[serde(default)]
+    pub roles: Vec<GroupRole>,
+    /// Also list the members of nested groups
+    #[serde(default)]
+    pub include_derived_membership: bool,
+    #[serde(default = "default_max_results")]
+    #[schemars(range(min = 1, max = 200))]
+    pub max_results: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AddGroupMemberArgs {
+    /// Group email, alias or ID
+    pub group_key: String,
+    /// Email of the user or group to add
+    pub email: String,
+    #[serde(default)]
+    pub role: GroupRole,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GroupMemberArgs {
+    /// Group email, alias or ID
+    pub group_key: String,
+    /// Member email or ID
+    pub member_key: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetGroupMemberRoleArgs {
+    /// Group email, alias or ID
+    pub group_key: String,
+    /// Member email or ID
+    pub member_key: String,
+    pub role: GroupRole,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "admin": {
+                    "version": "directory_v1",
+                    "description": "Google Admin SDK Directory API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_admin_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.tool(
+        "list_users",
+        "List or search the users of the Workspace account with their email, name, admin and suspension status, org unit and last login",
+        |ctx, args: ListUsersArgs| async move {
+            let mut url = directory_url("users")?;
+            {
+                let mut query = url.query_pairs_mut();
+                let (param, value) = directory_owner(args.domain.as_deref());
+                query
+                    .append_pair(param, value)
+                    .append_pair("maxResults", &args.max_results.to_string())
+                    .append_pair("orderBy", "email")
+                    .append_pair("fields", USER_LIST_FIELDS);
+                if let Some(search) = &args.query {
+                    query.append_pair("query", search);
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = directory_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "get_user",
+        "Get a user's full directory profile: names, emails and aliases, org unit, phones, organisations, admin status and login history",
+        |ctx, args: GetUserArgs| async move {
+            ctx.span.record("resource", args.user_key.as_str());
+            let url = directory_url(&format!("users/{}", urlencoding::encode(&args.user_key)))?;
+            let body = directory_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "list_groups",
+        "List or search the account's groups, or the groups one user belongs to",
+        |ctx, args: ListGroupsArgs| async move {
+            let mut url = directory_url("groups")?;
+            {
+                let mut query = url.query_pairs_mut();
+                match &args.user_key {
+                    Some(user_key) => query.append_pair("userKey", user_key),
+                    None => {
+                        let (param, value) = directory_owner(args.domain.as_deref());
+                        query.append_pair(param, value)
+                    }
+                };
+                query.append_pair("maxResults", &args.max_results.to_string());
+                if let Some(search) = &args.query {
+                    query.append_pair("query", search);
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = directory_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "list_group_members",
+        "List a group's members with their roles, optionally including members of nested groups",
+        |ctx, args: ListGroupMembersArgs| async move {
+            ctx.span.record("resource", args.group_key.as_str());
+            let mut url = members_url(&args.group_key, None)?;
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("maxResults", &args.max_results.to_string());
+                if !args.roles.is_empty() {
+                    let roles = args.roles.iter().map(api_name).collect::<Vec<_>>();
+                    query.append_pair("roles", &roles.join(","));
+                }
+                if args.include_derived_membership {
+                    query.append_pair("includeDerivedMembership", "true");
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = directory_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "add_group_member",
+        "Add a user or group to a group as a member, manager or owner",
+        |ctx, args: AddGroupMemberArgs| async move {
+            ctx.span.record("resource", args.group_key.as_str());
+            let url = members_url(&args.group_key, None)?;
+            let body = json!({ "email": args.email, "role": api_name(&args.role) });
+            let member =
+                directory_request(ctx.access_token(), Method::POST, url, Some(body)).await?;
+            json_response(&member)
+        },
+    );
+
+    tools.mutating_tool(
+        "remove_group_member",
+        "Remove a member from a group",
+        |ctx, args: GroupMemberArgs| async move {
+            ctx.span.record("resource", args.group_key.as_str());
+            let url = members_url(&args.group_key, Some(&args.member_key))?;
+            directory_request(ctx.access_token(), Method::DELETE, url, None).await?;
+            json_response(&json!({"removed": args.member_key, "group": args.group_key}))
+        },
+    );
+
+    tools.mutating_tool(
+        "set_group_member_role",
+        "Change a group member's role between member, manager and owner",
+        |ctx, args: SetGroupMemberRoleArgs| async move {
+            ctx.span.record("resource", args.group_key.as_str());
+            let url = members_url(&args.group_key, Some(&args.member_key))?;
+            let body = json!({ "role": api_name(&args.role) });
+            let member =
+                directory_request(ctx.access_token(), Method::PATCH, url, Some(body)).await?;
+            json_response(&member)
+        },
+    );
+
+    Ok(())
+}
+
+fn directory_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!(
+        "{}admin/directory/v1/{}",
+        admin_root_url(),
+        path
+    ))?)
+}
+
+/// URL of a group's members collection, or of one member in it.
+fn members_url(group_key: &str, member_key: Option<&str>) -> Result<Url> {
+    let mut path = format!("groups/{}/members", urlencoding::encode(group_key));
+    if let Some(member_key) = member_key {
+        path.push('/');
+        path.push_str(&urlencoding::encode(member_key));
+    }
+    directory_url(&path)
+}
+
+async fn directory_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::Admin, access_token, method, url, body).await
+}
+
+/// The query parameter scoping a user or group listing: one domain when
+/// given, else the whole account.
+pub(crate) fn directory_owner(domain: Option<&str>) -> (&'static str, &str) {
+    match domain {
+        Some(domain) => ("domain", domain),
+        None => ("customer", MY_CUSTOMER),
+    }
+}
+
+pub(crate) fn list_admin_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://admin.googleapis.com/admin/directory/v1/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "admin".to_string(),
+            description: Some("Google Admin SDK Directory API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+use super::{json_response, rest_request, ServerOptions, ToolRegistry};
+use crate::{circuit::Service, client::apps_script_root_url};
+
+// Tool Arguments
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RunScriptArgs {
+    /// Name of the top-level function to call
+    pub function: String,
+    /// Positional arguments: strings, numbers, booleans, arrays or objects
+    #[serde(default)]
+    pub parameters: Vec<Value>,
+    /// Script to run; defaults to the configured script
+    pub script_id: Option<String>,
+    /// Run the most recently saved code instead of the deployed version;
+    /// only works for the script's owner
+    #[serde(default)]
+    pub dev_mode: bool,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "apps_script": {
+                    "version": "v1",
+                    "description": "Google Apps Script API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_apps_script_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.mutating_tool(
+        "run_script",
+        "Call a function of an Apps Script project deployed as an API executable, e.g. a company-specific spreadsheet macro, and return its result. The access token needs every scope the script uses.",
+        |ctx, args: RunScriptArgs| async move {
+            let script_id = args
+                .script_id
+                .as_deref()
+                .or(ctx.options.default_script_id.as_deref())
+                .context("script_id required: pass it or configure --script-id")?;
+            ctx.span.record("resource", script_id);
+            let url = Url::parse(&format!(
+                "{}v1/scripts/{}:run",
+                apps_script_root_url(),
+                urlencoding::encode(script_id)
+            ))?;
+            let request = json!({
+                "function": args.function,
+                "parameters": args.parameters,
+                "devMode": args.dev_mode,
+            });
+
+            let operation = rest_request(
+                Service::AppsScript,
+                ctx.access_token(),
+                Method::POST,
+                url,
+                Some(request),
+            )
+            .await?;
+            json_response(&json!({ "result": script_result(&operation)? }))
+        },
+    );
+
+    Ok(())
+}
+
+/// Extracts the function's return value from a `scripts.run` operation, or
+/// turns an exception the script threw into an error naming where it happened.
+pub(crate) fn script_result(operation: &Value) -> Result<Value> {
+    let Some(error) = operation.get("error") else {
+        return Ok(operation["response"]["result"].clone());
+    };
+
+    let details = &error["details"][0];
+    let message = details["errorMessage"]
+        .as_str()
+        .or(error["message"].as_str())
+        .unwrap_or("unknown error");
+    let mut description = match details["errorType"].as_str() {
+        Some(kind) => format!("{}: {}", kind, message),
+        None => message.to_string(),
+    };
+    let trace = details["scriptStackTraceElements"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|frame| {
+            format!(
+                "{} (line {})",
+                frame["function"].as_str().unwrap_or("?"),
+                frame["lineNumber"]
+            )
+        })
+        .collect::<Vec<_>>();
+    if !trace.is_empty() {
+        description.push_str(&format!(" at {}", trace.join(" < ")));
+    }
+    anyhow::bail!("script failed with {}", description)
+}
+
+pub(crate) fn list_apps_script_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://script.googleapis.com/v1/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "apps_script".to_string(),
+            description: Some("Google Apps Script API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
@@ -0,0 +1,738 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Utc, Weekday};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use super::{api_name, json_response, rest_request, NoArgs, ServerOptions, ToolRegistry};
+use crate::{circuit::Service, client::calendar_root_url};
+
+const PRIMARY_CALENDAR: &str = "primary";
+const DEFAULT_MAX_RESULTS: u64 = 25;
+/// Longest date window `find_meeting_slots` searches
+const MAX_SLOT_SEARCH_DAYS: i64 = 31;
+/// Free time around a slot beyond this no longer improves its rank
+const MAX_SLOT_BUFFER_MINUTES: i64 = 60;
+
+// Tool Arguments
+
+/// Who receives email notifications about the change
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SendUpdates {
+    All,
+    ExternalOnly,
+    #[default]
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseStatus {
+    Accepted,
+    Declined,
+    Tentative,
+}
+
+fn default_calendar_id() -> String {
+    PRIMARY_CALENDAR.to_string()
+}
+
+fn default_calendar_ids() -> Vec<String> {
+    vec![PRIMARY_CALENDAR.to_string()]
+}
+
+fn default_max_results() -> u64 {
+    DEFAULT_MAX_RESULTS
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListEventsArgs {
+    #[serde(default = "default_calendar_id")]
+    pub calendar_id: String,
+    /// RFC 3339 lower bound on event end, e.g. 2024-05-01T00:00:00Z
+    pub time_min: Option<String>,
+    /// RFC 3339 upper bound on event start
+    pub time_max: Option<String>,
+    /// Free-text search over event fields
+    pub query: Option<String>,
+    #[serde(default = "default_max_results")]
+    pub max_results: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct QueryFreeBusyArgs {
+    /// Calendar IDs or email addresses
+    #[serde(default = "default_calendar_ids")]
+    pub calendar_ids: Vec<String>,
+    /// RFC 3339 start of the range
+    pub time_min: String,
+    /// RFC 3339 end of the range
+    pub time_max: String,
+    /// IANA time zone for the response, e.g. Europe/Berlin
+    pub time_zone: Option<String>,
+}
+
+fn default_work_start() -> String {
+    "09:00".to_string()
+}
+
+fn default_work_end() -> String {
+    "17:00".to_string()
+}
+
+fn default_utc_offset() -> String {
+    "+00:00".to_string()
+}
+
+fn default_step_minutes() -> u32 {
+    30
+}
+
+fn default_max_slots() -> usize {
+    10
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindMeetingSlotsArgs {
+    /// Email addresses or calendar IDs that must all be free; the user's
+    /// primary calendar is always included
+    #[serde(default)]
+    pub attendees: Vec<String>,
+    /// Attendees who should be free if possible; slots suiting more of them rank higher
+    #[serde(default)]
+    pub optional_attendees: Vec<String>,
+    pub duration_minutes: u32,
+    /// First day to search, YYYY-MM-DD
+    pub date_from: String,
+    /// Last day to search (inclusive), YYYY-MM-DD
+    pub date_to: String,
+    /// Start of the working day, HH:MM
+    #[serde(default = "default_work_start")]
+    pub work_start: String,
+    /// End of the working day, HH:MM
+    #[serde(default = "default_work_end")]
+    pub work_end: String,
+    /// UTC offset of the dates and working hours, e.g. -05:00
+    #[serde(default = "default_utc_offset")]
+    pub utc_offset: String,
+    #[serde(default)]
+    pub include_weekends: bool,
+    /// Minutes between candidate start times
+    #[serde(default = "default_step_minutes")]
+    pub step_minutes: u32,
+    #[serde(default = "default_max_slots")]
+    pub max_results: usize,
+}
+
+/// The event fields a tool sets; absent fields are left out of the request.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EventFields {
+    /// Event title
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    /// RFC 3339 date-time, or YYYY-MM-DD for an all-day event
+    pub start: Option<String>,
+    /// RFC 3339 date-time, or YYYY-MM-DD (exclusive) for an all-day event
+    pub end: Option<String>,
+    /// IANA time zone for start and end, e.g. America/New_York
+    pub time_zone: Option<String>,
+    /// Attendee email addresses
+    pub attendees: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateEventArgs {
+    #[serde(default = "default_calendar_id")]
+    pub calendar_id: String,
+    /// Event title
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    /// RFC 3339 date-time, or YYYY-MM-DD for an all-day event
+    pub start: String,
+    /// RFC 3339 date-time, or YYYY-MM-DD (exclusive) for an all-day event
+    pub end: String,
+    /// IANA time zone for start and end, e.g. America/New_York
+    pub time_zone: Option<String>,
+    /// Attendee email addresses
+    pub attendees: Option<Vec<String>>,
+    #[serde(default)]
+    pub send_updates: SendUpdates,
+    /// Attach a Google Meet conference to the event
+    #[serde(default)]
+    pub add_meet_link: bool,
+}
+
+impl CreateEventArgs {
+    fn fields(&self) -> EventFields {
+        EventFields {
+            summary: Some(self.summary.clone()),
+            description: self.description.clone(),
+            location: self.location.clone(),
+            start: Some(self.start.clone()),
+            end: Some(self.end.clone()),
+            time_zone: self.time_zone.clone(),
+            attendees: self.attendees.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UpdateEventArgs {
+    #[serde(default = "default_calendar_id")]
+    pub calendar_id: String,
+    pub event_id: String,
+    #[serde(flatten)]
+    pub fields: EventFields,
+    #[serde(default)]
+    pub send_updates: SendUpdates,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeleteEventArgs {
+    #[serde(default = "default_calendar_id")]
+    pub calendar_id: String,
+    pub event_id: String,
+    #[serde(default)]
+    pub send_updates: SendUpdates,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RespondToEventArgs {
+    #[serde(default = "default_calendar_id")]
+    pub calendar_id: String,
+    pub event_id: String,
+    pub response: ResponseStatus,
+    #[serde(default)]
+    pub send_updates: SendUpdates,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "calendar": {
+                    "version": "v3",
+                    "description": "Google Calendar API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_calendar_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.tool(
+        "list_calendars",
+        "List the calendars on the user's calendar list with their IDs and access roles",
+        |ctx, _: NoArgs| async move {
+            let url = calendar_url("users/me/calendarList")?;
+            let body = calendar_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "list_events",
+        "List events in a calendar, expanded into single instances and ordered by start time",
+        |ctx, args: ListEventsArgs| async move {
+            let mut url = events_url(&args.calendar_id, None)?;
+            {
+                let mut query = url.query_pairs_mut();
+                query
+                    .append_pair("singleEvents", "true")
+                    .append_pair("orderBy", "startTime")
+                    .append_pair("maxResults", &args.max_results.to_string());
+                for (value, param) in [
+                    (&args.time_min, "timeMin"),
+                    (&args.time_max, "timeMax"),
+                    (&args.query, "q"),
+                    (&args.page_token, "pageToken"),
+                ] {
+                    if let Some(value) = value {
+                        query.append_pair(param, value);
+                    }
+                }
+            }
+            let body = calendar_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "query_free_busy",
+        "Return the busy intervals of one or more calendars within a time range",
+        |ctx, args: QueryFreeBusyArgs| async move {
+            let mut calendar_ids = args.calendar_ids;
+            if calendar_ids.is_empty() {
+                calendar_ids = default_calendar_ids();
+            }
+
+            let mut request = json!({
+                "timeMin": args.time_min,
+                "timeMax": args.time_max,
+                "items": calendar_ids
+                    .iter()
+                    .map(|id| json!({"id": id}))
+                    .collect::<Vec<_>>(),
+            });
+            if let Some(time_zone) = args.time_zone {
+                request["timeZone"] = json!(time_zone);
+            }
+
+            let url = calendar_url("freeBusy")?;
+            let body =
+                calendar_request(ctx.access_token(), Method::POST, url, Some(request)).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "find_meeting_slots",
+        "Find times when all attendees are free for a meeting of the given length, within working hours over a date range. Slots are ranked by how many optional attendees can make it, then by free time around them, then by date.",
+        |ctx, args: FindMeetingSlotsArgs| async move {
+            let search = SlotSearch::parse(&args)?;
+            let (time_min, time_max) = search.range();
+            let request = json!({
+                "timeMin": time_min.to_rfc3339(),
+                "timeMax": time_max.to_rfc3339(),
+                "items": search
+                    .calendars()
+                    .map(|id| json!({"id": id}))
+                    .collect::<Vec<_>>(),
+            });
+
+            let url = calendar_url("freeBusy")?;
+            let free_busy =
+                calendar_request(ctx.access_token(), Method::POST, url, Some(request)).await?;
+            json_response(&search.rank(&free_busy, Utc::now()))
+        },
+    );
+
+    tools.mutating_tool(
+        "create_event",
+        "Create a calendar event, optionally inviting attendees",
+        |ctx, args: CreateEventArgs| async move {
+            let mut event = event_body(&args.fields())?;
+            let mut url = events_url(&args.calendar_id, None)?;
+            append_send_updates(&mut url, args.send_updates);
+            if args.add_meet_link {
+                event["conferenceData"] = json!({
+                    "createRequest": {
+                        "requestId": format!("{:016x}", rand::random::<u64>()),
+                        "conferenceSolutionKey": {"type": "hangoutsMeet"}
+                    }
+                });
+                url.query_pairs_mut()
+                    .append_pair("conferenceDataVersion", "1");
+            }
+
+            let body = calendar_request(ctx.access_token(), Method::POST, url, Some(event)).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "update_event",
+        "Update an event; only the fields provided are changed",
+        |ctx, args: UpdateEventArgs| async move {
+            let patch = event_body(&args.fields)?;
+            if patch.as_object().is_some_and(|fields| fields.is_empty()) {
+                anyhow::bail!("no event fields to update");
+            }
+            let mut url = events_url(&args.calendar_id, Some(&args.event_id))?;
+            append_send_updates(&mut url, args.send_updates);
+
+            let body =
+                calendar_request(ctx.access_token(), Method::PATCH, url, Some(patch)).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "delete_event",
+        "Delete an event from a calendar",
+        |ctx, args: DeleteEventArgs| async move {
+            let mut url = events_url(&args.calendar_id, Some(&args.event_id))?;
+            append_send_updates(&mut url, args.send_updates);
+
+            calendar_request(ctx.access_token(), Method::DELETE, url, None).await?;
+            json_response(&json!({"deleted": args.event_id}))
+        },
+    );
+
+    tools.mutating_tool(
+        "respond_to_event",
+        "Accept, decline or tentatively accept an event invitation",
+        |ctx, args: RespondToEventArgs| async move {
+            let url = events_url(&args.calendar_id, Some(&args.event_id))?;
+            let event =
+                calendar_request(ctx.access_token(), Method::GET, url.clone(), None).await?;
+            let attendees = respond_as_self(&event, args.response)?;
+
+            let mut url = url;
+            append_send_updates(&mut url, args.send_updates);
+            let body = calendar_request(
+                ctx.access_token(),
+                Method::PATCH,
+                url,
+                Some(json!({"attendees": attendees})),
+            )
+            .await?;
+            json_response(&body)
+        },
+    );
+
+    Ok(())
+}
+
+fn calendar_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!(
+        "{}calendar/v3/{}",
+        calendar_root_url(),
+        path
+    ))?)
+}
+
+/// URL of a calendar's events collection, or of one event in it.
+fn events_url(calendar_id: &str, event_id: Option<&str>) -> Result<Url> {
+    let mut path = format!("calendars/{}/events", urlencoding::encode(calendar_id));
+    if let Some(event_id) = event_id {
+        path.push('/');
+        path.push_str(&urlencoding::encode(event_id));
+    }
+    calendar_url(&path)
+}
+
+fn append_send_updates(url: &mut Url, send_updates: SendUpdates) {
+    url.query_pairs_mut()
+        .append_pair("sendUpdates", &api_name(&send_updates));
+}
+
+async fn calendar_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::Calendar, access_token, method, url, body).await
+}
+
+/// Converts a tool's start/end argument into a Calendar `EventDateTime`:
+/// `YYYY-MM-DD` becomes an all-day `date`, anything else an RFC 3339 `dateTime`.
+pub(crate) fn event_time(value: &str, time_zone: Option<&str>) -> Result<Value> {
+    if chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() {
+        return Ok(json!({"date": value}));
+    }
+    // Without an explicit time zone the offset in the timestamp is required
+    if time_zone.is_none() {
+        chrono::DateTime::parse_from_rfc3339(value).with_context(|| {
+            format!(
+                "invalid time `{}`: expected YYYY-MM-DD or an RFC 3339 date-time",
+                value
+            )
+        })?;
+    }
+    let mut time = json!({"dateTime": value});
+    if let Some(time_zone) = time_zone {
+        time["timeZone"] = json!(time_zone);
+    }
+    Ok(time)
+}
+
+/// Builds the `Event` fields present in the tool arguments, so the same body
+/// works for inserts and for PATCH updates that leave other fields alone.
+pub(crate) fn event_body(fields: &EventFields) -> Result<Value> {
+    let mut event = Map::new();
+    for (field, value) in [
+        ("summary", &fields.summary),
+        ("description", &fields.description),
+        ("location", &fields.location),
+    ] {
+        if let Some(value) = value {
+            event.insert(field.to_string(), json!(value));
+        }
+    }
+
+    let time_zone = fields.time_zone.as_deref();
+    for (field, value) in [("start", &fields.start), ("end", &fields.end)] {
+        if let Some(value) = value {
+            event.insert(field.to_string(), event_time(value, time_zone)?);
+        }
+    }
+
+    if let Some(attendees) = &fields.attendees {
+        event.insert(
+            "attendees".to_string(),
+            attendees
+                .iter()
+                .map(|email| json!({"email": email}))
+                .collect(),
+        );
+    }
+
+    Ok(Value::Object(event))
+}
+
+/// Returns the event's attendee list with the caller's own entry set to
+/// `response`, for a PATCH that answers the invitation.
+pub(crate) fn respond_as_self(event: &Value, response: ResponseStatus) -> Result<Vec<Value>> {
+    let mut attendees = event["attendees"].as_array().cloned().unwrap_or_default();
+    let me = attendees
+        .iter_mut()
+        .find(|attendee| attendee["self"].as_bool().unwrap_or(false))
+        .context("you are not an attendee of this event")?;
+    me["responseStatus"] = json!(response);
+    Ok(attendees)
+}
+
+type Busy = Vec<(DateTime<Utc>, DateTime<Utc>)>;
+
+/// A validated `find_meeting_slots` request.
+#[derive(Debug)]
+pub(crate) struct SlotSearch {
+    required: Vec<String>,
+    optional: Vec<String>,
+    days: Vec<NaiveDate>,
+    work_start: NaiveTime,
+    work_end: NaiveTime,
+    offset: FixedOffset,
+    duration: Duration,
+    step: Duration,
+    max_results: usize,
+}
+
+impl SlotSearch {
+    pub fn parse(args: &FindMeetingSlotsArgs) -> Result<Self> {
+        let date = |value: &str| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .with_context(|| format!("invalid date `{}`: expected YYYY-MM-DD", value))
+        };
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .with_context(|| format!("invalid time `{}`: expected HH:MM", value))
+        };
+        let offset =
+            DateTime::parse_from_rfc3339(&format!("2000-01-01T00:00:00{}", args.utc_offset))
+                .with_context(|| {
+                    format!(
+                        "invalid UTC offset `{}`: expected e.g. +02:00",
+                        args.utc_offset
+                    )
+                })?
+                .offset()
+                .to_owned();
+
+        let (from, to) = (date(&args.date_from)?, date(&args.date_to)?);
+        if to < from {
+            anyhow::bail!("date_to is before date_from");
+        }
+        if (to - from).num_days() >= MAX_SLOT_SEARCH_DAYS {
+            anyhow::bail!("search at most {} days at a time", MAX_SLOT_SEARCH_DAYS);
+        }
+        let (work_start, work_end) = (time(&args.work_start)?, time(&args.work_end)?);
+        if work_end <= work_start {
+            anyhow::bail!("work_end must be after work_start");
+        }
+        if args.duration_minutes == 0 || args.step_minutes == 0 {
+            anyhow::bail!("duration_minutes and step_minutes must be positive");
+        }
+
+        let days = from
+            .iter_days()
+            .take_while(|day| *day <= to)
+            .filter(|day| {
+                args.include_weekends || !matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+            })
+            .collect::<Vec<_>>();
+        if days.is_empty() {
+            anyhow::bail!("no weekdays between date_from and date_to; set include_weekends");
+        }
+
+        let mut required = vec![PRIMARY_CALENDAR.to_string()];
+        for attendee in &args.attendees {
+            if !required.contains(attendee) {
+                required.push(attendee.clone());
+            }
+        }
+        Ok(Self {
+            required,
+            optional: args.optional_attendees.clone(),
+            days,
+            work_start,
+            work_end,
+            offset,
+            duration: Duration::minutes(args.duration_minutes.into()),
+            step: Duration::minutes(args.step_minutes.into()),
+            max_results: args.max_results,
+        })
+    }
+
+    /// Every calendar whose free/busy is needed.
+    pub fn calendars(&self) -> impl Iterator<Item = &String> {
+        self.required.iter().chain(&self.optional)
+    }
+
+    fn at(&self, day: NaiveDate, time: NaiveTime) -> DateTime<FixedOffset> {
+        day.and_time(time)
+            .and_local_timezone(self.offset)
+            .single()
+            .expect("fixed offsets are unambiguous")
+    }
+
+    /// The span of working hours searched, for the free/busy query.
+    pub fn range(&self) -> (DateTime<FixedOffset>, DateTime<FixedOffset>) {
+        (
+            self.at(self.days[0], self.work_start),
+            self.at(self.days[self.days.len() - 1], self.work_end),
+        )
+    }
+
+    /// Ranks the slots starting after `now` in which every required attendee
+    /// is free according to a `freeBusy` response.
+    pub fn rank(&self, free_busy: &Value, now: DateTime<Utc>) -> Value {
+        let mut busy = HashMap::new();
+        let mut unchecked = Vec::new();
+        for id in self.calendars() {
+            let calendar = &free_busy["calendars"][id];
+            if calendar["errors"].as_array().is_some_and(|e| !e.is_empty()) || calendar.is_null() {
+                unchecked.push(id.clone());
+                continue;
+            }
+            let intervals = calendar["busy"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|interval| {
+                    let parse = |field: &str| {
+                        DateTime::parse_from_rfc3339(interval[field].as_str()?)
+                            .ok()
+                            .map(|time| time.with_timezone(&Utc))
+                    };
+                    Some((parse("start")?, parse("end")?))
+                })
+                .collect::<Busy>();
+            busy.insert(id.as_str(), intervals);
+        }
+
+        let required_busy = self
+            .required
+            .iter()
+            .filter_map(|id| busy.get(id.as_str()))
+            .flatten()
+            .copied()
+            .collect::<Busy>();
+        let is_free = |intervals: &Busy, start: DateTime<Utc>, end: DateTime<Utc>| {
+            intervals
+                .iter()
+                .all(|(from, to)| *to <= start || *from >= end)
+        };
+
+        let mut slots = Vec::new();
+        for day in &self.days {
+            let day_end = self.at(*day, self.work_end);
+            let mut start = self.at(*day, self.work_start);
+            while start + self.duration <= day_end {
+                let end = start + self.duration;
+                let (utc_start, utc_end) = (start.with_timezone(&Utc), end.with_timezone(&Utc));
+                if utc_start >= now && is_free(&required_busy, utc_start, utc_end) {
+                    let optional_free = self
+                        .optional
+                        .iter()
+                        .filter(|id| {
+                            busy.get(id.as_str())
+                                .is_some_and(|intervals| is_free(intervals, utc_start, utc_end))
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    let buffer = slot_buffer(&required_busy, utc_start, utc_end);
+                    slots.push((optional_free, buffer, start, end));
+                }
+                start += self.step;
+            }
+        }
+
+        slots.sort_by(|a, b| {
+            b.0.len()
+                .cmp(&a.0.len())
+                .then(b.1.cmp(&a.1))
+                .then(a.2.cmp(&b.2))
+        });
+        slots.truncate(self.max_results);
+
+        json!({
+            "slots": slots
+                .into_iter()
+                .map(|(optional_free, _, start, end)| json!({
+                    "start": start.to_rfc3339(),
+                    "end": end.to_rfc3339(),
+                    "optionalAttendeesFree": optional_free,
+                }))
+                .collect::<Vec<_>>(),
+            // Calendars Google could not report on, e.g. outside the user's domain
+            "uncheckedCalendars": unchecked,
+        })
+    }
+}
+
+/// Minutes of free time on the tighter side of a slot, capped so a slot far
+/// from any meeting is not preferred over a merely comfortable one.
+fn slot_buffer(busy: &Busy, start: DateTime<Utc>, end: DateTime<Utc>) -> i64 {
+    let before = busy
+        .iter()
+        .filter(|(_, to)| *to <= start)
+        .map(|(_, to)| (start - *to).num_minutes())
+        .min();
+    let after = busy
+        .iter()
+        .filter(|(from, _)| *from >= end)
+        .map(|(from, _)| (*from - end).num_minutes())
+        .min();
+    [before, after]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(MAX_SLOT_BUFFER_MINUTES)
+        .min(MAX_SLOT_BUFFER_MINUTES)
+}
+
+pub(crate) fn list_calendar_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://www.googleapis.com/calendar/v3/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "calendar".to_string(),
+            description: Some("Google Calendar API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
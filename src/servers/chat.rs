@@ -0,0 +1,275 @@
+use anyhow::Result;
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+use super::{json_response, rest_request, ServerOptions, ToolRegistry};
+use crate::{circuit::Service, client::chat_root_url};
+
+const DEFAULT_PAGE_SIZE: u64 = 100;
+const DEFAULT_MESSAGE_PAGE_SIZE: u64 = 25;
+
+// Tool Arguments
+
+fn default_page_size() -> u64 {
+    DEFAULT_PAGE_SIZE
+}
+
+fn default_message_page_size() -> u64 {
+    DEFAULT_MESSAGE_PAGE_SIZE
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListSpacesArgs {
+    /// e.g. `spaceType = "SPACE"` to leave out direct messages
+    pub filter: Option<String>,
+    #[serde(default = "default_page_size")]
+    #[schemars(range(min = 1, max = 1000))]
+    pub page_size: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListMessagesArgs {
+    /// Space name (`spaces/AAAA…`) or ID
+    pub space: String,
+    /// Only messages in this thread (`spaces/…/threads/…`)
+    pub thread: Option<String>,
+    /// Only messages created after this RFC 3339 timestamp
+    pub since: Option<String>,
+    #[serde(default = "default_true")]
+    pub newest_first: bool,
+    #[serde(default = "default_message_page_size")]
+    #[schemars(range(min = 1, max = 1000))]
+    pub page_size: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PostMessageArgs {
+    /// Space name (`spaces/AAAA…`) or ID
+    pub space: String,
+    /// Message text, with Chat formatting such as *bold* and <users/all>
+    pub text: Option<String>,
+    /// Cards v2 `card` objects (header, sections, widgets). Google only
+    /// accepts cards from a Chat app authenticating as itself
+    #[serde(default)]
+    pub cards: Vec<Value>,
+    /// Thread to reply in: a thread name from an earlier message
+    /// (`spaces/…/threads/…`), or a key of your own that starts a thread on
+    /// first use and continues it afterwards, e.g. a job ID
+    pub thread: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AddReactionArgs {
+    /// Message name, `spaces/…/messages/…`
+    pub message: String,
+    /// A Unicode emoji, e.g. 👍
+    pub emoji: String,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "chat": {
+                    "version": "v1",
+                    "description": "Google Chat API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_chat_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.tool(
+        "list_spaces",
+        "List the Chat spaces and direct messages the user is a member of, with their names",
+        |ctx, args: ListSpacesArgs| async move {
+            let mut url = chat_url("spaces")?;
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("pageSize", &args.page_size.to_string());
+                if let Some(filter) = &args.filter {
+                    query.append_pair("filter", filter);
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = chat_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "list_messages",
+        "Read the messages of a Chat space, newest first by default, optionally only one thread or those since a time",
+        |ctx, args: ListMessagesArgs| async move {
+            let space = space_name(&args.space);
+            ctx.span.record("resource", space.as_str());
+            let mut url = chat_url(&format!("{}/messages", space))?;
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("pageSize", &args.page_size.to_string());
+                if args.newest_first {
+                    query.append_pair("orderBy", "createTime desc");
+                }
+                if let Some(filter) = message_filter(args.since.as_deref(), args.thread.as_deref())
+                {
+                    query.append_pair("filter", &filter);
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = chat_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "post_message",
+        "Post a message or cards to a Chat space, optionally as a reply in a thread; returns the message with its thread name for follow-ups",
+        |ctx, args: PostMessageArgs| async move {
+            let space = space_name(&args.space);
+            ctx.span.record("resource", space.as_str());
+            let body = message_body(&args)?;
+            let mut url = chat_url(&format!("{}/messages", space))?;
+            if args.thread.is_some() {
+                url.query_pairs_mut()
+                    .append_pair("messageReplyOption", "REPLY_MESSAGE_FALLBACK_TO_NEW_THREAD");
+            }
+            let message = chat_request(ctx.access_token(), Method::POST, url, Some(body)).await?;
+            json_response(&message)
+        },
+    );
+
+    tools.mutating_tool(
+        "add_reaction",
+        "React to a Chat message with an emoji",
+        |ctx, args: AddReactionArgs| async move {
+            if !args.message.starts_with("spaces/") || !args.message.contains("/messages/") {
+                anyhow::bail!(
+                    "message must be a message name like spaces/AAAA/messages/BBBB, got `{}`",
+                    args.message
+                );
+            }
+            ctx.span.record("resource", args.message.as_str());
+            let url = chat_url(&format!("{}/reactions", args.message))?;
+            let body = json!({ "emoji": { "unicode": args.emoji } });
+            let reaction = chat_request(ctx.access_token(), Method::POST, url, Some(body)).await?;
+            json_response(&reaction)
+        },
+    );
+
+    Ok(())
+}
+
+fn chat_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!("{}v1/{}", chat_root_url(), path))?)
+}
+
+async fn chat_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::Chat, access_token, method, url, body).await
+}
+
+/// The resource name of a space given either its name or its bare ID.
+pub(crate) fn space_name(space: &str) -> String {
+    let space = space.trim().trim_matches('/');
+    if space.starts_with("spaces/") {
+        space.to_string()
+    } else {
+        format!("spaces/{}", urlencoding::encode(space))
+    }
+}
+
+/// The `spaces.messages.list` filter for the given bounds, if any.
+pub(crate) fn message_filter(since: Option<&str>, thread: Option<&str>) -> Option<String> {
+    let mut clauses = Vec::new();
+    if let Some(since) = since {
+        clauses.push(format!("createTime > \"{}\"", since));
+    }
+    if let Some(thread) = thread {
+        clauses.push(format!("thread.name = {}", thread));
+    }
+    (!clauses.is_empty()).then(|| clauses.join(" AND "))
+}
+
+/// Builds the message to post from the tool's arguments. Thread names are
+/// passed as such; anything else is a caller-chosen thread key.
+pub(crate) fn message_body(args: &PostMessageArgs) -> Result<Value> {
+    let text = args.text.as_deref().filter(|text| !text.is_empty());
+    if text.is_none() && args.cards.is_empty() {
+        anyhow::bail!("text or cards required");
+    }
+
+    let mut body = json!({});
+    if let Some(text) = text {
+        body["text"] = json!(text);
+    }
+    if !args.cards.is_empty() {
+        let cards = args
+            .cards
+            .iter()
+            .enumerate()
+            .map(|(i, card)| json!({ "cardId": format!("card-{}", i + 1), "card": card }))
+            .collect::<Vec<_>>();
+        body["cardsV2"] = json!(cards);
+    }
+    if let Some(thread) = &args.thread {
+        body["thread"] = if thread.starts_with("spaces/") {
+            json!({ "name": thread })
+        } else {
+            json!({ "threadKey": thread })
+        };
+    }
+    Ok(body)
+}
+
+pub(crate) fn list_chat_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://chat.googleapis.com/v1/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "chat".to_string(),
+            description: Some("Google Chat API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
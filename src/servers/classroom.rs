@@ -0,0 +1,375 @@
+use anyhow::{Context, Result};
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use chrono::{Datelike, Timelike};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
+
+use super::{api_name, json_response, rest_request, ServerOptions, ToolRegistry};
+use crate::{circuit::Service, client::classroom_root_url};
+
+const DEFAULT_PAGE_SIZE: u64 = 50;
+
+// Tool Arguments
+
+fn default_page_size() -> u64 {
+    DEFAULT_PAGE_SIZE
+}
+
+fn default_course_states() -> Vec<CourseState> {
+    vec![CourseState::Active]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CourseState {
+    Active,
+    Archived,
+    Provisioned,
+    Declined,
+    Suspended,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CourseWorkState {
+    Published,
+    Draft,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SubmissionState {
+    New,
+    Created,
+    TurnedIn,
+    Returned,
+    ReclaimedByStudent,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListCoursesArgs {
+    /// Only courses this teacher (email, ID or `me`) teaches
+    pub teacher_id: Option<String>,
+    /// Only courses this student (email, ID or `me`) is enrolled in
+    pub student_id: Option<String>,
+    #[serde(default = "default_course_states")]
+    pub course_states: Vec<CourseState>,
+    #[serde(default = "default_page_size")]
+    #[schemars(range(min = 1, max = 1000))]
+    pub page_size: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListCourseWorkArgs {
+    pub course_id: String,
+    /// Defaults to published coursework only
+    #[serde(default)]
+    pub states: Vec<CourseWorkState>,
+    #[serde(default = "default_page_size")]
+    #[schemars(range(min = 1, max = 1000))]
+    pub page_size: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListSubmissionsArgs {
+    pub course_id: String,
+    /// Coursework ID, or `-` for every piece of coursework in the course
+    pub coursework_id: String,
+    /// Only this student's submissions (email, ID or `me`)
+    pub user_id: Option<String>,
+    /// Only submissions in these states, e.g. `TURNED_IN` to find work to grade
+    #[serde(default)]
+    pub states: Vec<SubmissionState>,
+    /// Only late (`true`) or only on-time (`false`) submissions
+    pub late: Option<bool>,
+    #[serde(default = "default_page_size")]
+    #[schemars(range(min = 1, max = 1000))]
+    pub page_size: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateAnnouncementArgs {
+    pub course_id: String,
+    pub text: String,
+    /// Links to attach
+    #[serde(default)]
+    pub links: Vec<String>,
+    /// Drive files to attach, shared view-only
+    #[serde(default)]
+    pub drive_file_ids: Vec<String>,
+    /// Save as a draft for the teacher to review instead of posting
+    #[serde(default)]
+    pub draft: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateAssignmentArgs {
+    pub course_id: String,
+    pub title: String,
+    /// Instructions shown to students
+    pub description: Option<String>,
+    /// Leave out for an ungraded assignment
+    pub max_points: Option<f64>,
+    /// Due date-time in RFC 3339, e.g. `2026-10-30T17:00:00-07:00`
+    pub due: Option<String>,
+    /// Topic to file the assignment under
+    pub topic_id: Option<String>,
+    /// Links to attach
+    #[serde(default)]
+    pub links: Vec<String>,
+    /// Drive files to attach, shared view-only
+    #[serde(default)]
+    pub drive_file_ids: Vec<String>,
+    /// Save as a draft for the teacher to review instead of assigning it
+    #[serde(default)]
+    pub draft: bool,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "classroom": {
+                    "version": "v1",
+                    "description": "Google Classroom API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_classroom_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.tool(
+        "list_courses",
+        "List Classroom courses, by default the active ones, optionally only those a teacher or student is in",
+        |ctx, args: ListCoursesArgs| async move {
+            let mut url = classroom_url("courses")?;
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("pageSize", &args.page_size.to_string());
+                for state in &args.course_states {
+                    query.append_pair("courseStates", &api_name(state));
+                }
+                if let Some(teacher) = &args.teacher_id {
+                    query.append_pair("teacherId", teacher);
+                }
+                if let Some(student) = &args.student_id {
+                    query.append_pair("studentId", student);
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = classroom_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "list_coursework",
+        "List a course's assignments and questions with their due dates and points, newest first",
+        |ctx, args: ListCourseWorkArgs| async move {
+            ctx.span.record("resource", args.course_id.as_str());
+            let mut url = course_url(&args.course_id, "courseWork")?;
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("pageSize", &args.page_size.to_string());
+                for state in &args.states {
+                    query.append_pair("courseWorkStates", &api_name(state));
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = classroom_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "list_submissions",
+        "List student submissions for a piece of coursework (or all of a course's with `-`), with their state, grades and lateness",
+        |ctx, args: ListSubmissionsArgs| async move {
+            ctx.span.record("resource", args.course_id.as_str());
+            let path = format!(
+                "courseWork/{}/studentSubmissions",
+                urlencoding::encode(&args.coursework_id)
+            );
+            let mut url = course_url(&args.course_id, &path)?;
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("pageSize", &args.page_size.to_string());
+                for state in &args.states {
+                    query.append_pair("states", &api_name(state));
+                }
+                if let Some(user) = &args.user_id {
+                    query.append_pair("userId", user);
+                }
+                match args.late {
+                    Some(true) => {
+                        query.append_pair("late", "LATE_ONLY");
+                    }
+                    Some(false) => {
+                        query.append_pair("late", "NOT_LATE_ONLY");
+                    }
+                    None => {}
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = classroom_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "create_announcement",
+        "Post an announcement to a course's stream, optionally with links and Drive files attached, or save it as a draft",
+        |ctx, args: CreateAnnouncementArgs| async move {
+            ctx.span.record("resource", args.course_id.as_str());
+            let mut body = json!({
+                "text": args.text,
+                "state": if args.draft { "DRAFT" } else { "PUBLISHED" },
+            });
+            let materials = materials(&args.links, &args.drive_file_ids);
+            if !materials.is_empty() {
+                body["materials"] = json!(materials);
+            }
+            let url = course_url(&args.course_id, "announcements")?;
+            let announcement =
+                classroom_request(ctx.access_token(), Method::POST, url, Some(body)).await?;
+            json_response(&announcement)
+        },
+    );
+
+    tools.mutating_tool(
+        "create_assignment",
+        "Create an assignment in a course with instructions, points, a due time and attachments, or save it as a draft",
+        |ctx, args: CreateAssignmentArgs| async move {
+            ctx.span.record("resource", args.course_id.as_str());
+            let body = assignment_body(&args)?;
+            let url = course_url(&args.course_id, "courseWork")?;
+            let assignment =
+                classroom_request(ctx.access_token(), Method::POST, url, Some(body)).await?;
+            json_response(&assignment)
+        },
+    );
+
+    Ok(())
+}
+
+fn classroom_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!("{}v1/{}", classroom_root_url(), path))?)
+}
+
+fn course_url(course_id: &str, path: &str) -> Result<Url> {
+    classroom_url(&format!(
+        "courses/{}/{}",
+        urlencoding::encode(course_id),
+        path
+    ))
+}
+
+async fn classroom_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::Classroom, access_token, method, url, body).await
+}
+
+/// Classroom `Material`s for the given links and Drive files.
+pub(crate) fn materials(links: &[String], drive_file_ids: &[String]) -> Vec<Value> {
+    let links = links.iter().map(|url| json!({ "link": { "url": url } }));
+    let files = drive_file_ids
+        .iter()
+        .map(|id| json!({ "driveFile": { "driveFile": { "id": id }, "shareMode": "VIEW" } }));
+    links.chain(files).collect()
+}
+
+/// Splits an RFC 3339 due time into the UTC `dueDate` and `dueTime` fields
+/// Classroom stores it as.
+pub(crate) fn due_fields(value: &str) -> Result<(Value, Value)> {
+    let due = chrono::DateTime::parse_from_rfc3339(value)
+        .with_context(|| {
+            format!(
+                "invalid due time `{}`: expected an RFC 3339 date-time such as 2026-10-30T17:00:00Z",
+                value
+            )
+        })?
+        .with_timezone(&chrono::Utc);
+    Ok((
+        json!({ "year": due.year(), "month": due.month(), "day": due.day() }),
+        json!({ "hours": due.hour(), "minutes": due.minute() }),
+    ))
+}
+
+/// Builds the `CourseWork` of a new assignment from the tool's arguments.
+pub(crate) fn assignment_body(args: &CreateAssignmentArgs) -> Result<Value> {
+    let mut body = json!({
+        "title": args.title,
+        "workType": "ASSIGNMENT",
+        "state": if args.draft { "DRAFT" } else { "PUBLISHED" },
+    });
+    if let Some(description) = &args.description {
+        body["description"] = json!(description);
+    }
+    if let Some(max_points) = args.max_points {
+        body["maxPoints"] = json!(max_points);
+    }
+    if let Some(due) = &args.due {
+        let (date, time) = due_fields(due)?;
+        body["dueDate"] = date;
+        body["dueTime"] = time;
+    }
+    if let Some(topic_id) = &args.topic_id {
+        body["topicId"] = json!(topic_id);
+    }
+    let materials = materials(&args.links, &args.drive_file_ids);
+    if !materials.is_empty() {
+        body["materials"] = json!(materials);
+    }
+    Ok(body)
+}
+
+pub(crate) fn list_classroom_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://classroom.googleapis.com/v1/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "classroom".to_string(),
+            description: Some("Google Classroom API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
@@ -0,0 +1,481 @@
+use anyhow::Result;
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{CallToolResponse, ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use google_docs1::api::{BatchUpdateDocumentRequest, Document};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use super::{json_response, ServerOptions, ToolRegistry};
+use crate::{
+    circuit::{guarded, Service},
+    client::{with_quota_project, DocsClient},
+    markdown,
+};
+
+const DOCUMENT_FIELDS: &str = "documentId,title,revisionId";
+
+// Tool Arguments
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    #[default]
+    Text,
+    Blocks,
+}
+
+/// Named style applied to the paragraphs in the range, e.g. HEADING_1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum NamedStyle {
+    #[serde(rename = "NORMAL_TEXT")]
+    NormalText,
+    #[serde(rename = "TITLE")]
+    Title,
+    #[serde(rename = "SUBTITLE")]
+    Subtitle,
+    #[serde(rename = "HEADING_1")]
+    Heading1,
+    #[serde(rename = "HEADING_2")]
+    Heading2,
+    #[serde(rename = "HEADING_3")]
+    Heading3,
+    #[serde(rename = "HEADING_4")]
+    Heading4,
+    #[serde(rename = "HEADING_5")]
+    Heading5,
+    #[serde(rename = "HEADING_6")]
+    Heading6,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadDocumentArgs {
+    pub document_id: String,
+    #[serde(default)]
+    pub format: DocumentFormat,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadDocAsMarkdownArgs {
+    pub document_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateDocumentArgs {
+    pub title: String,
+    /// Initial body text
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateDocFromMarkdownArgs {
+    pub title: String,
+    /// Headings, paragraphs, nested bulleted and numbered lists, **bold**,
+    /// *italic*, ~~strikethrough~~, `code`, code blocks, [links](url),
+    /// > quotes and pipe tables
+    pub markdown: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct InsertTextArgs {
+    pub document_id: String,
+    pub text: String,
+    /// 1-based character index from read_document's blocks
+    #[schemars(range(min = 1))]
+    pub index: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReplaceTextArgs {
+    pub document_id: String,
+    pub find: String,
+    pub replace: String,
+    #[serde(default = "default_true")]
+    pub match_case: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FormatTextArgs {
+    pub document_id: String,
+    #[schemars(range(min = 1))]
+    pub start_index: u64,
+    /// Exclusive end of the range
+    #[schemars(range(min = 1))]
+    pub end_index: u64,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    /// Size in points
+    pub font_size: Option<f64>,
+    pub link_url: Option<String>,
+    pub paragraph_style: Option<NamedStyle>,
+}
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "docs": {
+                    "version": "v1",
+                    "description": "Google Docs API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_docs_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.tool(
+        "read_document",
+        "Read a document as plain text, or as structured blocks (headings, paragraphs, list items, tables) with their character ranges",
+        |ctx, args: ReadDocumentArgs| async move {
+            let docs = ctx.docs();
+
+            let call = docs.documents().get(&args.document_id);
+            let result = guarded(Service::Docs, with_quota_project!(call).doit()).await?;
+            let document = serde_json::to_value(&result.1)?;
+
+            let blocks = document_blocks(&document);
+            let body = match args.format {
+                DocumentFormat::Blocks => json!({
+                    "documentId": document["documentId"],
+                    "title": document["title"],
+                    "blocks": blocks,
+                }),
+                DocumentFormat::Text => json!({
+                    "documentId": document["documentId"],
+                    "title": document["title"],
+                    "text": blocks_to_text(&blocks),
+                }),
+            };
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "read_doc_as_markdown",
+        "Read a document as clean Markdown, keeping its headings, lists, tables, links and emphasis",
+        |ctx, args: ReadDocAsMarkdownArgs| async move {
+            let call = ctx.docs().documents().get(&args.document_id);
+            let result = guarded(Service::Docs, with_quota_project!(call).doit()).await?;
+            let document = serde_json::to_value(&result.1)?;
+
+            json_response(&json!({
+                "documentId": document["documentId"],
+                "title": document["title"],
+                "markdown": markdown::from_document(&document),
+            }))
+        },
+    );
+
+    tools.mutating_tool(
+        "create_document",
+        "Create a new document, optionally with initial text",
+        |ctx, args: CreateDocumentArgs| async move {
+            let docs = ctx.docs();
+            let created = create_empty_document(docs, args.title).await?;
+            let document_id = created.document_id.clone().unwrap_or_default();
+
+            // The body of a new document cannot be set on create
+            if let Some(text) = &args.text {
+                if !text.is_empty() {
+                    let request = batch_update_request(vec![insert_text_request(text, None)])?;
+                    let call = docs.documents().batch_update(request, &document_id);
+                    guarded(Service::Docs, with_quota_project!(call).doit()).await?;
+                }
+            }
+
+            json_response(&serde_json::to_value(&created)?)
+        },
+    );
+
+    tools.mutating_tool(
+        "create_doc_from_markdown",
+        "Create a new document from Markdown, keeping its headings, lists, bold and italic text, links, code and tables as document formatting",
+        |ctx, args: CreateDocFromMarkdownArgs| async move {
+            let docs = ctx.docs();
+            let requests = markdown::requests(&markdown::parse(&args.markdown));
+            let created = create_empty_document(docs, args.title).await?;
+            let document_id = created.document_id.clone().unwrap_or_default();
+
+            if !requests.is_empty() {
+                let call = docs
+                    .documents()
+                    .batch_update(batch_update_request(requests)?, &document_id);
+                guarded(Service::Docs, with_quota_project!(call).doit()).await?;
+            }
+
+            json_response(&serde_json::to_value(&created)?)
+        },
+    );
+
+    tools.mutating_tool(
+        "insert_text",
+        "Insert text at a character index, or at the end of the document when no index is given",
+        |ctx, args: InsertTextArgs| async move {
+            let requests = vec![insert_text_request(&args.text, args.index)];
+            apply_batch_update(ctx.docs(), &args.document_id, requests).await
+        },
+    );
+
+    tools.mutating_tool(
+        "replace_text",
+        "Replace every occurrence of a string in the document",
+        |ctx, args: ReplaceTextArgs| async move {
+            if args.find.is_empty() {
+                anyhow::bail!("find required");
+            }
+
+            let requests = vec![json!({
+                "replaceAllText": {
+                    "containsText": {"text": args.find, "matchCase": args.match_case},
+                    "replaceText": args.replace
+                }
+            })];
+            apply_batch_update(ctx.docs(), &args.document_id, requests).await
+        },
+    );
+
+    tools.mutating_tool(
+        "format_text",
+        "Apply bold/italic/underline, font size or link to a character range, and optionally a heading style to its paragraphs",
+        |ctx, args: FormatTextArgs| async move {
+            let requests = format_requests(&args)?;
+            apply_batch_update(ctx.docs(), &args.document_id, requests).await
+        },
+    );
+
+    Ok(())
+}
+
+async fn create_empty_document(docs: &DocsClient, title: String) -> Result<Document> {
+    let document = Document {
+        title: Some(title),
+        ..Default::default()
+    };
+    let call = docs
+        .documents()
+        .create(document)
+        .param("fields", DOCUMENT_FIELDS);
+    let (_, created) = guarded(Service::Docs, with_quota_project!(call).doit()).await?;
+    Ok(created)
+}
+
+/// Converts JSON requests into the hub's typed batchUpdate body.
+fn batch_update_request(requests: Vec<Value>) -> Result<BatchUpdateDocumentRequest> {
+    Ok(serde_json::from_value(json!({ "requests": requests }))?)
+}
+
+async fn apply_batch_update(
+    docs: &DocsClient,
+    document_id: &str,
+    requests: Vec<Value>,
+) -> Result<CallToolResponse> {
+    let request = batch_update_request(requests)?;
+    let call = docs.documents().batch_update(request, document_id);
+    let (_, response) = guarded(Service::Docs, with_quota_project!(call).doit()).await?;
+    json_response(&serde_json::to_value(&response)?)
+}
+
+/// An `insertText` request at `index`, or at the end of the body.
+pub(crate) fn insert_text_request(text: &str, index: Option<u64>) -> Value {
+    let mut request = json!({ "text": text });
+    match index {
+        Some(index) => request["location"] = json!({ "index": index }),
+        None => request["endOfSegmentLocation"] = json!({}),
+    }
+    json!({ "insertText": request })
+}
+
+/// Builds the `updateTextStyle` and `updateParagraphStyle` requests for
+/// `format_text`, with field masks naming only the properties being set.
+pub(crate) fn format_requests(args: &FormatTextArgs) -> Result<Vec<Value>> {
+    if args.end_index <= args.start_index {
+        anyhow::bail!("end_index must be greater than start_index");
+    }
+    let range = json!({"startIndex": args.start_index, "endIndex": args.end_index});
+
+    let mut style = Map::new();
+    let mut fields = Vec::new();
+    for (value, field) in [
+        (args.bold, "bold"),
+        (args.italic, "italic"),
+        (args.underline, "underline"),
+    ] {
+        if let Some(value) = value {
+            style.insert(field.to_string(), json!(value));
+            fields.push(field);
+        }
+    }
+    if let Some(size) = args.font_size {
+        style.insert(
+            "fontSize".to_string(),
+            json!({"magnitude": size, "unit": "PT"}),
+        );
+        fields.push("fontSize");
+    }
+    if let Some(url) = &args.link_url {
+        style.insert("link".to_string(), json!({ "url": url }));
+        fields.push("link");
+    }
+
+    let mut requests = Vec::new();
+    if !fields.is_empty() {
+        requests.push(json!({
+            "updateTextStyle": {
+                "range": range,
+                "textStyle": style,
+                "fields": fields.join(","),
+            }
+        }));
+    }
+    if let Some(named_style) = args.paragraph_style {
+        requests.push(json!({
+            "updateParagraphStyle": {
+                "range": range,
+                "paragraphStyle": {"namedStyleType": named_style},
+                "fields": "namedStyleType",
+            }
+        }));
+    }
+
+    if requests.is_empty() {
+        anyhow::bail!("no formatting given");
+    }
+    Ok(requests)
+}
+
+/// Concatenates the text runs of a paragraph.
+fn paragraph_text(paragraph: &Value) -> String {
+    paragraph["elements"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|element| element["textRun"]["content"].as_str())
+        .collect()
+}
+
+/// Flattens a document's body into blocks: paragraphs (with their named style
+/// and whether they are list items) and tables as rows of cell text.
+pub(crate) fn document_blocks(document: &Value) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    for element in document["body"]["content"].as_array().into_iter().flatten() {
+        if element["paragraph"].is_object() {
+            let paragraph = &element["paragraph"];
+            let text = paragraph_text(paragraph);
+            let kind = if paragraph["bullet"].is_object() {
+                "list_item"
+            } else {
+                match paragraph["paragraphStyle"]["namedStyleType"].as_str() {
+                    Some(style) if style.starts_with("HEADING") || style == "TITLE" => "heading",
+                    _ => "paragraph",
+                }
+            };
+            blocks.push(json!({
+                "type": kind,
+                "style": paragraph["paragraphStyle"]["namedStyleType"],
+                "text": text.trim_end_matches('\n'),
+                "startIndex": element["startIndex"],
+                "endIndex": element["endIndex"],
+            }));
+        } else if element["table"].is_object() {
+            let rows: Vec<Vec<String>> = element["table"]["tableRows"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|row| {
+                    row["tableCells"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(|cell| {
+                            cell["content"]
+                                .as_array()
+                                .into_iter()
+                                .flatten()
+                                .map(|content| paragraph_text(&content["paragraph"]))
+                                .collect::<String>()
+                                .trim_end_matches('\n')
+                                .to_string()
+                        })
+                        .collect()
+                })
+                .collect();
+            blocks.push(json!({
+                "type": "table",
+                "rows": rows,
+                "startIndex": element["startIndex"],
+                "endIndex": element["endIndex"],
+            }));
+        }
+    }
+    blocks
+}
+
+/// Renders blocks as plain text; table cells are tab-separated.
+pub(crate) fn blocks_to_text(blocks: &[Value]) -> String {
+    let mut text = String::new();
+    for block in blocks {
+        match block["type"].as_str() {
+            Some("table") => {
+                for row in block["rows"].as_array().into_iter().flatten() {
+                    let cells: Vec<&str> = row
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|cell| cell.as_str())
+                        .collect();
+                    text.push_str(&cells.join("\t"));
+                    text.push('\n');
+                }
+            }
+            Some("list_item") => {
+                text.push_str("- ");
+                text.push_str(block["text"].as_str().unwrap_or_default());
+                text.push('\n');
+            }
+            _ => {
+                text.push_str(block["text"].as_str().unwrap_or_default());
+                text.push('\n');
+            }
+        }
+    }
+    text
+}
+
+pub(crate) fn list_docs_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://docs.googleapis.com/v1/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "docs".to_string(),
+            description: Some("Google Docs API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
@@ -1,102 +1,1195 @@
-use anyhow::Result;
+use std::path::Path;
+
+use anyhow::{Context, Result};
 use async_mcp::{
-    server::Server,
+    server::{Server, ServerBuilder},
     transport::Transport,
     types::{
-        CallToolRequest, CallToolResponse, ListRequest, Resource, ResourcesListResponse,
-        ServerCapabilities, Tool, ToolResponseContent,
+        CallToolResponse, Resource, ResourcesListResponse, ServerCapabilities, ToolResponseContent,
     },
 };
-use serde_json::json;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use url::Url;
 
-use crate::client::get_drive_client;
+use super::{
+    json_response, register_quota_tool,
+    resources::{resource_capabilities, transport_notifier, with_resources, ResourceScope},
+    rest_request, ServerOptions, ToolRegistry,
+};
+use crate::{
+    batch::{execute_drive_batch, BatchRequest, BatchResponse},
+    cache::EtagCache,
+    circuit::Service,
+    client::drive_root_url,
+    export::{
+        download_bytes, download_revision, download_to_dir, export_mime_type, get_metadata,
+        resolve_out_dir,
+    },
+    upload::{
+        convertible_content_type, update_content, upload_file, UploadSource,
+        DEFAULT_RESUMABLE_THRESHOLD,
+    },
+    InvokeError,
+};
+
+// Default field mask for listings; full File objects are mostly noise for agents
+const FILE_LIST_FIELDS: &str = "nextPageToken,files(id,name,mimeType,modifiedTime,parents)";
+/// Fields returned for single-file operations
+const FILE_FIELDS: &str = "id,name,mimeType,parents,webViewLink,shortcutDetails";
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+const SHORTCUT_MIME_TYPE: &str = "application/vnd.google-apps.shortcut";
+const DOCUMENT_MIME_TYPE: &str = "application/vnd.google-apps.document";
+/// Larger downloads must be written to disk with `out_dir`
+const MAX_INLINE_DOWNLOAD_BYTES: usize = 10 * 1024 * 1024;
+const REVISION_FIELDS: &str = "id,mimeType,modifiedTime,keepForever,published,size,originalFilename,lastModifyingUser(displayName,emailAddress)";
+const CHANGE_LIST_FIELDS: &str = "nextPageToken,newStartPageToken,changes(changeType,removed,time,fileId,driveId,file(id,name,mimeType,modifiedTime,trashed,parents))";
+/// restore_revision holds the old content in memory while re-uploading it
+const MAX_RESTORE_BYTES: usize = 100 * 1024 * 1024;
+
+// Tool Arguments
 
-fn get_access_token(req: &CallToolRequest) -> Result<&str> {
-    req.meta
-        .as_ref()
-        .and_then(|v| v.get("access_token"))
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Missing or invalid access_token"))
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GranteeType {
+    #[default]
+    User,
+    Group,
+    Domain,
+    Anyone,
 }
 
-pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
-    let mut server = Server::builder(transport)
-        .capabilities(ServerCapabilities {
-            tools: Some(json!({
-                "drive": {
-                    "version": "v3",
-                    "description": "Google Drive API operations"
-                }
-            })),
-            ..Default::default()
-        })
-        .request_handler("resources/list", |_req: ListRequest| {
-            Box::pin(async move { Ok(list_drive_resources()) })
-        });
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Reader,
+    Commenter,
+    Writer,
+}
+
+fn default_order_by() -> String {
+    "modifiedTime desc".to_string()
+}
+
+fn default_file_list_fields() -> String {
+    FILE_LIST_FIELDS.to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListFilesArgs {
+    pub mime_type: Option<String>,
+    /// Drive search query, e.g. "name contains 'budget'", combined with mime_type
+    pub query: Option<String>,
+    pub page_size: Option<u64>,
+    #[serde(default = "default_order_by")]
+    pub order_by: String,
+    /// Field mask for the response
+    #[serde(default = "default_file_list_fields")]
+    pub fields: String,
+    /// nextPageToken from a previous call, to fetch the following page
+    pub page_token: Option<String>,
+    /// Replace shortcuts with the files they point to, each marked with its shortcutId
+    #[serde(default)]
+    pub resolve_shortcuts: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchFilesArgs {
+    /// Text contained in the file name
+    pub name_contains: Option<String>,
+    /// Text contained in the name, description or content
+    pub full_text: Option<String>,
+    /// Exact MIME type, e.g. application/vnd.google-apps.document
+    pub mime_type: Option<String>,
+    /// Only direct children of this folder
+    pub parent_id: Option<String>,
+    /// Owner email address
+    pub owner: Option<String>,
+    pub starred: Option<bool>,
+    #[serde(default)]
+    pub trashed: bool,
+    /// YYYY-MM-DD or RFC 3339 date-time
+    pub modified_after: Option<String>,
+    pub page_size: Option<u64>,
+    /// nextPageToken from a previous call
+    pub page_token: Option<String>,
+    /// Defaults to modifiedTime desc; not supported together with full_text
+    pub order_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrashFilesArgs {
+    /// IDs of the files to trash
+    pub file_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShareFilesArgs {
+    pub file_ids: Vec<String>,
+    #[serde(rename = "type", default)]
+    pub grantee_type: GranteeType,
+    #[serde(default)]
+    pub role: Role,
+    /// Required for user and group grants
+    pub email_address: Option<String>,
+    /// Required for domain grants
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub send_notification: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DownloadFileArgs {
+    pub file_id: String,
+    /// Export format for Google-native files, e.g. docx, pdf, md, xlsx, csv, pptx; defaults to the Office format
+    pub format: Option<String>,
+    /// Write the file into this directory, relative to the server's --download-dir, and return its path instead of the content
+    pub out_dir: Option<String>,
+    /// Replace a file of the same name in out_dir instead of failing
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UploadFileArgs {
+    /// File content, base64-encoded
+    pub content_base64: Option<String>,
+    /// Local file to upload, within a directory the server allows with --upload-dir
+    pub path: Option<String>,
+    /// Public http(s) URL to fetch the content from, if the server allows URL uploads
+    pub url: Option<String>,
+    /// File name in Drive; defaults to the path or URL's file name
+    pub name: Option<String>,
+    /// Content type; defaults to the URL's Content-Type or application/octet-stream
+    pub mime_type: Option<String>,
+    /// IDs of the folders to create the file in
+    pub parents: Option<Vec<String>>,
+    /// Convert the file to a Google Doc, reading images and PDFs with OCR,
+    /// and return the extracted text
+    #[serde(default)]
+    pub convert: bool,
+    /// OCR language hint for convert, as an ISO 639-1 code such as 'en' or 'de'
+    pub ocr_language: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateFolderArgs {
+    /// Folder name
+    pub name: String,
+    /// IDs of the folders to create it in; defaults to My Drive
+    pub parents: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateShortcutArgs {
+    /// ID of the file or folder the shortcut points to
+    pub target_id: String,
+    /// Shortcut name; defaults to the target's name
+    pub name: Option<String>,
+    /// IDs of the folders to create it in; defaults to My Drive
+    pub parents: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ResolveShortcutArgs {
+    /// ID of the shortcut
+    pub file_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MoveFileArgs {
+    /// ID of the file to move
+    pub file_id: String,
+    /// ID of the destination folder
+    pub folder_id: String,
+    /// Folders to remove the file from; defaults to all current parents
+    pub remove_parents: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CopyFileArgs {
+    /// ID of the file to copy
+    pub file_id: String,
+    /// Name of the copy; defaults to "Copy of <name>"
+    pub name: Option<String>,
+    /// IDs of the folders to put the copy in; defaults to the original's
+    pub parents: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RenameFileArgs {
+    /// ID of the file to rename
+    pub file_id: String,
+    /// New name
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeleteFileArgs {
+    /// ID of the file to delete
+    pub file_id: String,
+    /// Skip the trash; the file cannot be recovered
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListRevisionsArgs {
+    pub file_id: String,
+    pub page_size: Option<u64>,
+    /// nextPageToken from a previous call, to fetch the following page
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RevisionArgs {
+    pub file_id: String,
+    pub revision_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetChangesArgs {
+    /// nextPageToken or newStartPageToken from a previous call
+    pub page_token: Option<String>,
+    /// Follow a shared drive's changes instead of the user's
+    pub drive_id: Option<String>,
+    #[serde(default = "default_true")]
+    pub include_removed: bool,
+    pub page_size: Option<u64>,
+}
+
+pub fn build<T: Transport + Clone>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport + Clone>(
+    transport: T,
+    options: ServerOptions,
+) -> Result<Server<T>> {
+    let notifier = transport_notifier(&transport);
+    let server = Server::builder(transport).capabilities(ServerCapabilities {
+        tools: Some(json!({
+            "drive": {
+                "version": "v3",
+                "description": "Google Drive API operations"
+            }
+        })),
+        resources: Some(resource_capabilities()),
+        ..Default::default()
+    });
+    let mut server = with_resources(
+        server,
+        ResourceScope {
+            drive: true,
+            sheets: false,
+        },
+        list_drive_resources().resources,
+        options.token_provider.clone(),
+        notifier,
+    );
+
+    register_tools(&mut server, &options)?;
+    register_quota_tool(&mut server, &options);
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+    let page_size = options.page_size;
 
     // List files
-    server.register_tool(
-        Tool {
-            name: "list_files".to_string(),
-            description: Some("List files in Google Drive with filters".to_string()),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "mime_type": {"type": "string"},
-                    "query": {"type": "string"},
-                    "page_size": {"type": "integer", "default": 10},
-                    "order_by": {"type": "string", "default": "modifiedTime desc"}
+    tools.paged_tool(
+        "list_files",
+        "List files in Google Drive with filters",
+        page_size.unwrap_or(10),
+        move |ctx, args: ListFilesArgs| async move {
+            let query = list_query(&args);
+            let mut url = Url::parse(&format!("{}drive/v3/files", drive_root_url()))?;
+            url.query_pairs_mut()
+                .append_pair("q", &query)
+                .append_pair(
+                    "pageSize",
+                    &args.page_size.or(page_size).unwrap_or(10).to_string(),
+                )
+                .append_pair("orderBy", &args.order_by);
+            // Resolving shortcuts needs their targets' IDs
+            if args.resolve_shortcuts {
+                url.query_pairs_mut()
+                    .append_pair("fields", &with_shortcut_details(&args.fields));
+            } else {
+                url.query_pairs_mut().append_pair("fields", &args.fields);
+            }
+            if let Some(token) = &args.page_token {
+                url.query_pairs_mut().append_pair("pageToken", token);
+            }
+
+            // Listings are repeated often within a session; revalidate via ETag
+            let key = format!("drive/{}", url);
+            let body = ctx
+                .cached(
+                    &key,
+                    EtagCache::global().get_json(Service::Drive, ctx.access_token(), url.as_str()),
+                )
+                .await?;
+            let result: google_drive3::api::FileList = serde_json::from_value(body)?;
+            let mut text = serde_json::to_value(&result)?;
+            if args.resolve_shortcuts {
+                if let Some(files) = text["files"].as_array_mut() {
+                    resolve_shortcuts(ctx.access_token(), files, &args.fields).await?;
                 }
-            }),
-        },
-        move |req: CallToolRequest| {
-            Box::pin(async move {
-                let access_token = get_access_token(&req)?;
-                let args = req.arguments.clone().unwrap_or_default();
+            }
 
-                let result = async {
-                    let drive = get_drive_client(access_token);
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&text)?,
+                }],
+                is_error: None,
+                // Surfaced in meta too, so callers can page without parsing the text
+                meta: result
+                    .next_page_token
+                    .as_ref()
+                    .map(|token| json!({ "nextPageToken": token })),
+            })
+        },
+    );
 
-                    let mut query = String::new();
-                    if let Some(mime_type) = args.get("mime_type").and_then(|v| v.as_str()) {
-                        query.push_str(&format!("mimeType='{}'", mime_type));
+    // Search files with structured filters
+    tools.paged_tool(
+        "search_files",
+        "Search Drive with structured filters, combined with AND. Values are escaped, so no Drive query syntax is needed.",
+        page_size.unwrap_or(25),
+        move |ctx, args: SearchFilesArgs| async move {
+            let mut url = Url::parse(&format!("{}drive/v3/files", drive_root_url()))?;
+            {
+                let mut query = url.query_pairs_mut();
+                query
+                    .append_pair("q", &search_query(&args)?)
+                    .append_pair(
+                        "pageSize",
+                        &args.page_size.or(page_size).unwrap_or(25).to_string(),
+                    )
+                    .append_pair("fields", FILE_LIST_FIELDS)
+                    .append_pair("supportsAllDrives", "true")
+                    .append_pair("includeItemsFromAllDrives", "true");
+                // Drive rejects orderBy on full-text searches, which are ranked by relevance
+                match &args.order_by {
+                    Some(order_by) => {
+                        query.append_pair("orderBy", order_by);
+                    }
+                    None if args.full_text.is_none() => {
+                        query.append_pair("orderBy", "modifiedTime desc");
                     }
+                    None => {}
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
 
-                    let result = drive
-                        .files()
-                        .list()
-                        .q(&query)
-                        .page_size(
-                            args.get("page_size").and_then(|v| v.as_u64()).unwrap_or(10) as i32
-                        )
-                        .order_by(
-                            args.get("order_by")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("modifiedTime desc"),
-                        )
-                        .doit()
-                        .await?;
+            let body =
+                rest_request(Service::Drive, ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    // Trash many files in one batch request
+    tools.mutating_tool(
+        "trash_files",
+        "Move multiple Drive files to the trash in a single batch request. Results are reported per file.",
+        |ctx, TrashFilesArgs { file_ids }| async move {
+            let requests = file_ids
+                .iter()
+                .map(|id| BatchRequest {
+                    method: "PATCH",
+                    path: format!(
+                        "/drive/v3/files/{}?supportsAllDrives=true&fields=id,name,trashed",
+                        urlencoding::encode(id)
+                    ),
+                    body: Some(json!({"trashed": true})),
+                })
+                .collect::<Vec<_>>();
+            let responses = execute_drive_batch(ctx.access_token(), &requests).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&batch_results(&file_ids, responses))?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    // Share many files with the same grantee in one batch request
+    tools.mutating_tool(
+        "share_files",
+        "Grant a user, group, domain or anyone access to multiple Drive files in a single batch request. Results are reported per file.",
+        |ctx, args: ShareFilesArgs| async move {
+            let mut permission = json!({"type": args.grantee_type, "role": args.role});
+            match args.grantee_type {
+                GranteeType::User | GranteeType::Group => {
+                    permission["emailAddress"] = json!(args
+                        .email_address
+                        .context("email_address required for user and group grants")?);
+                }
+                GranteeType::Domain => {
+                    permission["domain"] =
+                        json!(args.domain.context("domain required for domain grants")?);
+                }
+                GranteeType::Anyone => {}
+            }
 
-                    Ok(CallToolResponse {
-                        content: vec![ToolResponseContent::Text {
-                            text: serde_json::to_string(&result.1)?,
-                        }],
-                        is_error: None,
-                        meta: None,
+            let requests = args
+                .file_ids
+                .iter()
+                .map(|id| BatchRequest {
+                    method: "POST",
+                    path: format!(
+                        "/drive/v3/files/{}/permissions?supportsAllDrives=true&sendNotificationEmail={}",
+                        urlencoding::encode(id),
+                        args.send_notification
+                    ),
+                    body: Some(permission.clone()),
+                })
+                .collect::<Vec<_>>();
+            let responses = execute_drive_batch(ctx.access_token(), &requests).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&batch_results(&args.file_ids, responses))?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    // Download or export a file, inline as base64 or to a local directory
+    tools.tool(
+        "download_file",
+        "Download a Drive file, exporting Google Docs (docx, pdf, md, txt), Sheets (xlsx, csv, pdf) and Slides (pptx, pdf) to a concrete format. Content is returned as base64, or written to out_dir for large files where the server allows it.",
+        |ctx, args: DownloadFileArgs| async move {
+            let file_id = args.file_id.as_str();
+            let format = args.format.as_deref();
+
+            let body = match &args.out_dir {
+                Some(out_dir) => {
+                    let out_dir =
+                        resolve_out_dir(Path::new(out_dir), ctx.options.download_dir.as_deref())?;
+                    let path = download_to_dir(
+                        ctx.access_token(),
+                        file_id,
+                        format,
+                        &out_dir,
+                        args.overwrite,
+                    )
+                    .await?;
+                    json!({ "id": file_id, "path": path })
+                }
+                None => {
+                    let download = download_bytes(
+                        ctx.access_token(),
+                        file_id,
+                        format,
+                        MAX_INLINE_DOWNLOAD_BYTES,
+                    )
+                    .await?;
+                    let mime_type = match &download.format {
+                        Some(format) => export_mime_type(format).unwrap_or_default().to_string(),
+                        None => download.file.mime_type.clone(),
+                    };
+                    json!({
+                        "id": download.file.id,
+                        "name": download.file.name,
+                        "mimeType": mime_type,
+                        "size": download.bytes.len(),
+                        "contentBase64": STANDARD.encode(&download.bytes),
                     })
                 }
-                .await;
+            };
 
-                handle_result(result)
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&body)?,
+                }],
+                is_error: None,
+                meta: None,
             })
         },
     );
 
-    Ok(server.build())
+    // Upload a file from base64 content, a local path or a URL
+    let resumable_threshold = options
+        .upload_resumable_threshold
+        .unwrap_or(DEFAULT_RESUMABLE_THRESHOLD);
+    tools.mutating_tool(
+        "upload_file",
+        "Upload a file to Drive from base64 content or, where the server allows them, a local file path or a URL to fetch. Large files are sent with a resumable upload. With convert, images and PDFs become Google Docs through OCR and their text is returned.",
+        move |ctx, args: UploadFileArgs| async move {
+            let source = upload_source(&args)?;
+            let content = source.load(&ctx.options.uploads).await?;
+
+            let name = args
+                .name
+                .or(content.file_name)
+                .context("name required when uploading base64 content")?;
+            let mut content_type = args.mime_type.or(content.content_type);
+            if args.convert && content_type.is_none() {
+                // Drive only converts content whose type it is told
+                content_type = convertible_content_type(&name).map(str::to_string);
+            }
+
+            let mut metadata = json!({ "name": name });
+            if args.convert {
+                metadata["mimeType"] = json!(DOCUMENT_MIME_TYPE);
+            } else if let Some(content_type) = &content_type {
+                metadata["mimeType"] = json!(content_type);
+            }
+            if let Some(parents) = args.parents {
+                metadata["parents"] = json!(parents);
+            }
+
+            let mut file = upload_file(
+                ctx.access_token(),
+                &metadata,
+                content_type.as_deref(),
+                &content.bytes,
+                resumable_threshold,
+                args.ocr_language.as_deref().filter(|_| args.convert),
+            )
+            .await?;
+
+            if args.convert {
+                let file_id = file["id"].as_str().context("upload returned no file ID")?;
+                let text = download_bytes(
+                    ctx.access_token(),
+                    file_id,
+                    Some("txt"),
+                    MAX_INLINE_DOWNLOAD_BYTES,
+                )
+                .await?;
+                let text = String::from_utf8_lossy(&text.bytes);
+                // Docs exports plain text with a byte order mark
+                file["text"] = json!(text.trim_start_matches('\u{feff}'));
+            }
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&file)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    // Create a folder
+    tools.mutating_tool(
+        "create_folder",
+        "Create a folder in Google Drive",
+        |ctx, args: CreateFolderArgs| async move {
+            let mut metadata = json!({"name": args.name, "mimeType": FOLDER_MIME_TYPE});
+            if let Some(parents) = args.parents {
+                metadata["parents"] = json!(parents);
+            }
+
+            let folder = rest_request(
+                Service::Drive,
+                ctx.access_token(),
+                Method::POST,
+                files_url(None, "")?,
+                Some(metadata),
+            )
+            .await?;
+            json_response(&folder)
+        },
+    );
+
+    // Create a shortcut to a file or folder
+    tools.mutating_tool(
+        "create_shortcut",
+        "Create a Drive shortcut to a file or folder, e.g. to surface a shared file in another folder without copying it",
+        |ctx, args: CreateShortcutArgs| async move {
+            let name = match args.name {
+                Some(name) => name,
+                None => {
+                    let target = rest_request(
+                        Service::Drive,
+                        ctx.access_token(),
+                        Method::GET,
+                        files_url(Some(&args.target_id), "")?,
+                        None,
+                    )
+                    .await?;
+                    target["name"].as_str().unwrap_or_default().to_string()
+                }
+            };
+            let mut metadata = json!({
+                "name": name,
+                "mimeType": SHORTCUT_MIME_TYPE,
+                "shortcutDetails": { "targetId": args.target_id },
+            });
+            if let Some(parents) = args.parents {
+                metadata["parents"] = json!(parents);
+            }
+
+            let shortcut = rest_request(
+                Service::Drive,
+                ctx.access_token(),
+                Method::POST,
+                files_url(None, "")?,
+                Some(metadata),
+            )
+            .await?;
+            json_response(&shortcut)
+        },
+    );
+
+    // Look up the file a shortcut points to
+    tools.tool(
+        "resolve_shortcut",
+        "Get the file or folder a Drive shortcut points to, marked with the shortcutId; other files are returned unchanged",
+        |ctx, args: ResolveShortcutArgs| async move {
+            let file = rest_request(
+                Service::Drive,
+                ctx.access_token(),
+                Method::GET,
+                files_url(Some(&args.file_id), "")?,
+                None,
+            )
+            .await?;
+            let Some(target_id) = shortcut_target(&file) else {
+                return json_response(&file);
+            };
+
+            let mut target = rest_request(
+                Service::Drive,
+                ctx.access_token(),
+                Method::GET,
+                files_url(Some(target_id), "")?,
+                None,
+            )
+            .await?;
+            target["shortcutId"] = file["id"].clone();
+            json_response(&target)
+        },
+    );
+
+    // Move a file between folders
+    tools.mutating_tool(
+        "move_file",
+        "Move a Drive file or folder into another folder",
+        |ctx, args: MoveFileArgs| async move {
+            let file_id = args.file_id.as_str();
+            let folder_id = args.folder_id.as_str();
+
+            let remove_parents = match args.remove_parents {
+                Some(parents) => parents,
+                None => {
+                    let file = rest_request(
+                        Service::Drive,
+                        ctx.access_token(),
+                        Method::GET,
+                        files_url(Some(file_id), "")?,
+                        None,
+                    )
+                    .await?;
+                    string_array(file.get("parents")).unwrap_or_default()
+                }
+            };
+
+            let mut url = files_url(Some(file_id), "")?;
+            url.query_pairs_mut().append_pair("addParents", folder_id);
+            let remove_parents = remove_parents
+                .into_iter()
+                .filter(|parent| parent != folder_id)
+                .collect::<Vec<_>>();
+            if !remove_parents.is_empty() {
+                url.query_pairs_mut()
+                    .append_pair("removeParents", &remove_parents.join(","));
+            }
+
+            let file = rest_request(
+                Service::Drive,
+                ctx.access_token(),
+                Method::PATCH,
+                url,
+                Some(json!({})),
+            )
+            .await?;
+            json_response(&file)
+        },
+    );
+
+    // Copy a file
+    tools.mutating_tool(
+        "copy_file",
+        "Copy a Drive file, optionally under a new name or into other folders",
+        |ctx, args: CopyFileArgs| async move {
+            let mut metadata = json!({});
+            if let Some(name) = args.name {
+                metadata["name"] = json!(name);
+            }
+            if let Some(parents) = args.parents {
+                metadata["parents"] = json!(parents);
+            }
+
+            let copy = rest_request(
+                Service::Drive,
+                ctx.access_token(),
+                Method::POST,
+                files_url(Some(&args.file_id), "/copy")?,
+                Some(metadata),
+            )
+            .await?;
+            json_response(&copy)
+        },
+    );
+
+    // Rename a file
+    tools.mutating_tool(
+        "rename_file",
+        "Rename a Drive file or folder",
+        |ctx, args: RenameFileArgs| async move {
+            let file = rest_request(
+                Service::Drive,
+                ctx.access_token(),
+                Method::PATCH,
+                files_url(Some(&args.file_id), "")?,
+                Some(json!({ "name": args.name })),
+            )
+            .await?;
+            json_response(&file)
+        },
+    );
+
+    // Trash or permanently delete a file
+    tools.mutating_tool(
+        "delete_file",
+        "Move a Drive file or folder to the trash, or delete it permanently with `permanent`",
+        |ctx, args: DeleteFileArgs| async move {
+            let file_id = args.file_id.as_str();
+
+            let url = files_url(Some(file_id), "")?;
+            if args.permanent {
+                rest_request(
+                    Service::Drive,
+                    ctx.access_token(),
+                    Method::DELETE,
+                    url,
+                    None,
+                )
+                .await?;
+                json_response(&json!({"id": file_id, "deleted": true}))
+            } else {
+                let file = rest_request(
+                    Service::Drive,
+                    ctx.access_token(),
+                    Method::PATCH,
+                    url,
+                    Some(json!({"trashed": true})),
+                )
+                .await?;
+                json_response(&file)
+            }
+        },
+    );
+
+    // List a file's revision history
+    tools.paged_tool(
+        "list_revisions",
+        "List the revisions of a Drive file, oldest first, with who made each change",
+        page_size.unwrap_or(100),
+        move |ctx, args: ListRevisionsArgs| async move {
+            let mut url = revisions_url(&args.file_id, None)?;
+            url.query_pairs_mut()
+                .append_pair(
+                    "pageSize",
+                    &args.page_size.or(page_size).unwrap_or(100).to_string(),
+                )
+                .append_pair(
+                    "fields",
+                    &format!("nextPageToken,revisions({})", REVISION_FIELDS),
+                );
+            if let Some(token) = &args.page_token {
+                url.query_pairs_mut().append_pair("pageToken", token);
+            }
+
+            let body =
+                rest_request(Service::Drive, ctx.access_token(), Method::GET, url, None).await?;
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&body)?,
+                }],
+                is_error: None,
+                meta: body
+                    .get("nextPageToken")
+                    .map(|token| json!({ "nextPageToken": token })),
+            })
+        },
+    );
+
+    // Get one revision, including export links for native files
+    tools.tool(
+        "get_revision",
+        "Get a revision of a Drive file; for Docs, Sheets and Slides this includes export links",
+        |ctx, args: RevisionArgs| async move {
+            let (file_id, revision_id) = (args.file_id.as_str(), args.revision_id.as_str());
+            let mut url = revisions_url(file_id, Some(revision_id))?;
+            url.query_pairs_mut()
+                .append_pair("fields", &format!("{},exportLinks", REVISION_FIELDS));
+
+            let revision =
+                rest_request(Service::Drive, ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&revision)
+        },
+    );
+
+    // Roll a binary file back to an earlier revision
+    tools.mutating_tool(
+        "restore_revision",
+        "Restore an uploaded (non-Google) file to an earlier revision by re-uploading its content as a new revision. Docs, Sheets and Slides cannot be restored through the API",
+        |ctx, args: RevisionArgs| async move {
+            let (file_id, revision_id) = (args.file_id.as_str(), args.revision_id.as_str());
+            let file = get_metadata(ctx.access_token(), file_id).await?;
+            if file.is_google_native() {
+                anyhow::bail!(
+                    "{} is a Google {} file; the Drive API cannot restore its revisions, use version history in the editor instead",
+                    file.name,
+                    file.mime_type.rsplit('.').next().unwrap_or("native")
+                );
+            }
+
+            let mut url = revisions_url(file_id, Some(revision_id))?;
+            url.query_pairs_mut().append_pair("fields", "mimeType");
+            let revision =
+                rest_request(Service::Drive, ctx.access_token(), Method::GET, url, None).await?;
+            let content =
+                download_revision(ctx.access_token(), file_id, revision_id, MAX_RESTORE_BYTES)
+                    .await?;
+
+            let restored = update_content(
+                ctx.access_token(),
+                file_id,
+                revision.get("mimeType").and_then(|v| v.as_str()),
+                content,
+            )
+            .await?;
+            json_response(&json!({ "file": restored, "restoredFrom": revision_id }))
+        },
+    );
+
+    // Poll the changes feed
+    tools.paged_tool(
+        "get_changes",
+        "List what changed in Drive since a page token. Call without page_token to get a starting token, then pass newStartPageToken back on the next poll",
+        page_size.unwrap_or(100),
+        move |ctx, args: GetChangesArgs| async move {
+            let drive_id = args.drive_id.as_deref();
+
+            let Some(page_token) = &args.page_token else {
+                // Nothing to compare against yet; hand back where the feed starts now
+                let url = changes_url("/startPageToken", drive_id)?;
+                let start =
+                    rest_request(Service::Drive, ctx.access_token(), Method::GET, url, None)
+                        .await?;
+                let token = start.get("startPageToken").cloned().unwrap_or(Value::Null);
+                return Ok(CallToolResponse {
+                    content: vec![ToolResponseContent::Text {
+                        text: serde_json::to_string(
+                            &json!({ "newStartPageToken": token, "changes": [] }),
+                        )?,
+                    }],
+                    is_error: None,
+                    meta: Some(json!({ "newStartPageToken": token })),
+                });
+            };
+
+            let mut url = changes_url("", drive_id)?;
+            url.query_pairs_mut()
+                .append_pair("pageToken", page_token)
+                .append_pair("includeItemsFromAllDrives", "true")
+                .append_pair("includeRemoved", &args.include_removed.to_string())
+                .append_pair(
+                    "pageSize",
+                    &args.page_size.or(page_size).unwrap_or(100).to_string(),
+                )
+                .append_pair("fields", CHANGE_LIST_FIELDS);
+
+            let body =
+                rest_request(Service::Drive, ctx.access_token(), Method::GET, url, None).await?;
+            // Either more pages follow, or the feed is drained and this is the next poll's token
+            let meta = ["nextPageToken", "newStartPageToken"]
+                .into_iter()
+                .find_map(|key| body.get(key).map(|token| json!({ key: token })));
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&body)?,
+                }],
+                is_error: None,
+                meta,
+            })
+        },
+    );
+
+    Ok(())
+}
+
+/// Quotes `value` as a Drive query string literal.
+pub(crate) fn quote_query(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Composes the Drive `q` parameter for `list_files`: the caller's query,
+/// narrowed to `mime_type` when both are given.
+pub(crate) fn list_query(args: &ListFilesArgs) -> String {
+    let mut clauses = Vec::new();
+    if let Some(query) = args
+        .query
+        .as_deref()
+        .filter(|query| !query.trim().is_empty())
+    {
+        clauses.push(format!("({})", query));
+    }
+    if let Some(mime_type) = args.mime_type.as_deref().filter(|mime| !mime.is_empty()) {
+        clauses.push(format!("mimeType = {}", quote_query(mime_type)));
+    }
+    clauses.join(" and ")
 }
 
-fn list_drive_resources() -> ResourcesListResponse {
+/// Composes the Drive `q` parameter for `search_files` from its filters.
+pub(crate) fn search_query(args: &SearchFilesArgs) -> Result<String> {
+    fn text(value: &Option<String>) -> Option<&str> {
+        value.as_deref().filter(|value| !value.is_empty())
+    }
+
+    let mut clauses = Vec::new();
+    if let Some(name) = text(&args.name_contains) {
+        clauses.push(format!("name contains {}", quote_query(name)));
+    }
+    if let Some(full_text) = text(&args.full_text) {
+        clauses.push(format!("fullText contains {}", quote_query(full_text)));
+    }
+    if let Some(mime_type) = text(&args.mime_type) {
+        clauses.push(format!("mimeType = {}", quote_query(mime_type)));
+    }
+    if let Some(parent_id) = text(&args.parent_id) {
+        clauses.push(format!("{} in parents", quote_query(parent_id)));
+    }
+    if let Some(owner) = text(&args.owner) {
+        clauses.push(format!("{} in owners", quote_query(owner)));
+    }
+    if let Some(starred) = args.starred {
+        clauses.push(format!("starred = {}", starred));
+    }
+    clauses.push(format!("trashed = {}", args.trashed));
+    if let Some(after) = text(&args.modified_after) {
+        let after = match chrono::NaiveDate::parse_from_str(after, "%Y-%m-%d") {
+            Ok(date) => format!("{}T00:00:00Z", date),
+            Err(_) => chrono::DateTime::parse_from_rfc3339(after)
+                .with_context(|| {
+                    format!(
+                        "invalid modified_after `{}`: expected YYYY-MM-DD or an RFC 3339 date-time",
+                        after
+                    )
+                })?
+                .with_timezone(&chrono::Utc)
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        };
+        clauses.push(format!("modifiedTime > {}", quote_query(&after)));
+    }
+    Ok(clauses.join(" and "))
+}
+
+/// URL of `files` or of one file (plus `suffix`, e.g. `/copy`), with shared
+/// drive support and the single-file field mask.
+fn files_url(file_id: Option<&str>, suffix: &str) -> Result<Url> {
+    let mut path = format!("{}drive/v3/files", drive_root_url());
+    if let Some(file_id) = file_id {
+        path.push('/');
+        path.push_str(&urlencoding::encode(file_id));
+    }
+    path.push_str(suffix);
+
+    let mut url = Url::parse(&path)?;
+    url.query_pairs_mut()
+        .append_pair("supportsAllDrives", "true")
+        .append_pair("fields", FILE_FIELDS);
+    Ok(url)
+}
+
+/// URL of a file's revisions, or of one revision. Unlike `files`, the
+/// revisions endpoints take no `supportsAllDrives` parameter.
+fn revisions_url(file_id: &str, revision_id: Option<&str>) -> Result<Url> {
+    let mut path = format!(
+        "{}drive/v3/files/{}/revisions",
+        drive_root_url(),
+        urlencoding::encode(file_id)
+    );
+    if let Some(revision_id) = revision_id {
+        path.push('/');
+        path.push_str(&urlencoding::encode(revision_id));
+    }
+    Ok(Url::parse(&path)?)
+}
+
+/// URL of the changes feed (plus `suffix`, e.g. `/startPageToken`), scoped to
+/// a shared drive when `drive_id` is given.
+fn changes_url(suffix: &str, drive_id: Option<&str>) -> Result<Url> {
+    let mut url = Url::parse(&format!("{}drive/v3/changes{}", drive_root_url(), suffix))?;
+    url.query_pairs_mut()
+        .append_pair("supportsAllDrives", "true");
+    if let Some(drive_id) = drive_id {
+        url.query_pairs_mut().append_pair("driveId", drive_id);
+    }
+    Ok(url)
+}
+
+/// Picks the upload source from the tool arguments; exactly one must be given.
+pub(crate) fn upload_source(args: &UploadFileArgs) -> Result<UploadSource> {
+    let mut sources = [
+        (
+            &args.content_base64,
+            UploadSource::Base64 as fn(String) -> UploadSource,
+        ),
+        (&args.path, UploadSource::Path),
+        (&args.url, UploadSource::Url),
+    ]
+    .into_iter()
+    .filter_map(|(value, source)| Some(source(value.clone()?)));
+
+    match (sources.next(), sources.next()) {
+        (Some(source), None) => Ok(source),
+        (None, _) => anyhow::bail!("one of content_base64, path or url required"),
+        (Some(_), Some(_)) => anyhow::bail!("only one of content_base64, path or url may be given"),
+    }
+}
+
+/// The target ID of a shortcut's metadata, or `None` for other files.
+pub(crate) fn shortcut_target(file: &Value) -> Option<&str> {
+    if file["mimeType"] != SHORTCUT_MIME_TYPE {
+        return None;
+    }
+    file["shortcutDetails"]["targetId"].as_str()
+}
+
+/// Adds `shortcutDetails` to the per-file fields of a listing mask such as
+/// `nextPageToken,files(id,name)`; masks without `files(...)` already
+/// return every file field.
+pub(crate) fn with_shortcut_details(fields: &str) -> String {
+    match fields.find("files(") {
+        Some(start) if !fields.contains("shortcutDetails") => {
+            let (head, tail) = fields.split_at(start + "files(".len());
+            format!("{}shortcutDetails,{}", head, tail)
+        }
+        _ => fields.to_string(),
+    }
+}
+
+/// The per-file part of a listing mask: `nextPageToken,files(id,owners(emailAddress))`
+/// gives `id,owners(emailAddress)`.
+pub(crate) fn file_fields(fields: &str) -> Option<&str> {
+    let start = fields.find("files(")? + "files(".len();
+    let mut depth = 0;
+    for (i, c) in fields[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(&fields[start..start + i]),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Replaces the shortcuts among listed `files` with their targets, read in
+/// one batch with the listing's own per-file fields.
+async fn resolve_shortcuts(access_token: &str, files: &mut [Value], fields: &str) -> Result<()> {
+    let file_fields = file_fields(fields).unwrap_or(FILE_FIELDS);
+    let requests = files
+        .iter()
+        .filter_map(shortcut_target)
+        .map(|target_id| BatchRequest {
+            method: "GET",
+            path: format!(
+                "/drive/v3/files/{}?supportsAllDrives=true&fields={}",
+                urlencoding::encode(target_id),
+                urlencoding::encode(file_fields)
+            ),
+            body: None,
+        })
+        .collect::<Vec<_>>();
+    if requests.is_empty() {
+        return Ok(());
+    }
+    let targets = execute_drive_batch(access_token, &requests).await?;
+    replace_shortcuts(files, targets);
+    Ok(())
+}
+
+/// Swaps each shortcut in `files` for its looked-up target, in order. A
+/// target that could not be read, e.g. for lack of access, leaves the
+/// shortcut in place with a `shortcutError`.
+pub(crate) fn replace_shortcuts(files: &mut [Value], targets: Vec<BatchResponse>) {
+    let shortcuts = files
+        .iter_mut()
+        .filter(|file| shortcut_target(file).is_some());
+    for (file, target) in shortcuts.zip(targets) {
+        if target.is_success() {
+            let shortcut_id = file["id"].clone();
+            *file = target.body;
+            file["shortcutId"] = shortcut_id;
+        } else {
+            file["shortcutError"] =
+                json!(InvokeError::from_google_json(&target.body, target.status, None).to_string());
+        }
+    }
+}
+
+fn string_array(value: Option<&Value>) -> Option<Vec<String>> {
+    value?
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str().map(String::from))
+        .collect()
+}
+
+/// Pairs each file ID with its batch outcome so partial failures are visible.
+fn batch_results(file_ids: &[String], responses: Vec<BatchResponse>) -> Value {
+    let items = file_ids
+        .iter()
+        .zip(responses)
+        .map(|(file_id, response)| {
+            if response.is_success() {
+                json!({"file_id": file_id, "ok": true, "result": response.body})
+            } else {
+                json!({
+                    "file_id": file_id,
+                    "ok": false,
+                    "status": response.status,
+                    "error": InvokeError::from_google_json(&response.body, response.status, None).to_string()
+                })
+            }
+        })
+        .collect::<Vec<_>>();
+    let failed = items.iter().filter(|i| i["ok"] == false).count();
+
+    json!({"succeeded": items.len() - failed, "failed": failed, "items": items})
+}
+
+pub(crate) fn list_drive_resources() -> ResourcesListResponse {
     let base = Url::parse("https://www.googleapis.com/drive/v3/").unwrap();
     ResourcesListResponse {
         resources: vec![Resource {
@@ -109,16 +1202,3 @@ fn list_drive_resources() -> ResourcesListResponse {
         meta: None,
     }
 }
-
-fn handle_result(result: Result<CallToolResponse>) -> Result<CallToolResponse> {
-    match result {
-        Ok(response) => Ok(response),
-        Err(e) => Ok(CallToolResponse {
-            content: vec![ToolResponseContent::Text {
-                text: format!("Error: {}", e),
-            }],
-            is_error: Some(true),
-            meta: None,
-        }),
-    }
-}
@@ -0,0 +1,572 @@
+use anyhow::Result;
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use super::{json_response, rest_request, NoArgs, ServerOptions, ToolRegistry};
+use crate::{
+    circuit::Service,
+    client::{drive_root_url, gmail_root_url},
+    export::{export_mime_type, file_name, get_metadata, open_download, read_limited},
+};
+
+const DEFAULT_MAX_RESULTS: u64 = 10;
+/// Gmail rejects messages whose attachments add up to more than this
+const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+/// Headers surfaced when reading messages; the rest are mostly transport noise
+const READ_HEADERS: &[&str] = &[
+    "From",
+    "To",
+    "Cc",
+    "Bcc",
+    "Subject",
+    "Date",
+    "Message-ID",
+    "In-Reply-To",
+    "References",
+];
+
+// Tool Arguments
+
+fn default_max_results() -> u64 {
+    DEFAULT_MAX_RESULTS
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListThreadsArgs {
+    /// Gmail search query
+    pub query: Option<String>,
+    /// Only threads with all of these labels, e.g. INBOX
+    #[serde(default)]
+    pub label_ids: Vec<String>,
+    #[serde(default = "default_max_results")]
+    pub max_results: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchMessagesArgs {
+    /// Gmail search query
+    pub query: String,
+    #[serde(default)]
+    pub label_ids: Vec<String>,
+    #[serde(default = "default_max_results")]
+    pub max_results: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadMessageArgs {
+    pub message_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadThreadArgs {
+    pub thread_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ComposeArgs {
+    /// Recipient addresses
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub cc: Vec<String>,
+    #[serde(default)]
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    /// Send body as HTML instead of plain text
+    #[serde(default)]
+    pub html: bool,
+    /// Thread to add a reply to
+    pub thread_id: Option<String>,
+    /// Message-ID header of the message being replied to
+    pub in_reply_to: Option<String>,
+    /// Drive files to attach or link
+    #[serde(default)]
+    pub drive_files: Vec<DriveFileArgs>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DriveFileMode {
+    /// Send the file's content as an attachment
+    #[default]
+    Attach,
+    /// Add a link to the file to the body; recipients need access to the file
+    Link,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DriveFileArgs {
+    pub file_id: String,
+    #[serde(default)]
+    pub mode: DriveFileMode,
+    /// Format Docs, Sheets and Slides are attached as (pdf, docx, xlsx, csv, ...);
+    /// defaults to their Office format
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ModifyLabelsArgs {
+    pub message_ids: Vec<String>,
+    #[serde(default)]
+    pub add_label_ids: Vec<String>,
+    #[serde(default)]
+    pub remove_label_ids: Vec<String>,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "gmail": {
+                    "version": "v1",
+                    "description": "Gmail API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_gmail_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    // List or search threads
+    tools.tool(
+        "list_threads",
+        "List Gmail threads, optionally filtered with Gmail search syntax (e.g. 'from:alice is:unread newer_than:7d')",
+        |ctx, args: ListThreadsArgs| async move {
+            let url = list_url(
+                "threads",
+                args.query.as_deref(),
+                &args.label_ids,
+                args.max_results,
+                args.page_token.as_deref(),
+            )?;
+            let body = gmail_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "search_messages",
+        "Search individual Gmail messages with Gmail search syntax (e.g. 'subject:invoice has:attachment')",
+        |ctx, args: SearchMessagesArgs| async move {
+            let url = list_url(
+                "messages",
+                Some(&args.query),
+                &args.label_ids,
+                args.max_results,
+                args.page_token.as_deref(),
+            )?;
+            let body = gmail_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "read_message",
+        "Read a Gmail message with its headers, decoded plain-text and HTML bodies, and attachment metadata",
+        |ctx, args: ReadMessageArgs| async move {
+            let url = user_url(&format!(
+                "messages/{}?format=full",
+                urlencoding::encode(&args.message_id)
+            ))?;
+            let message = gmail_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&decode_message(&message))
+        },
+    );
+
+    tools.tool(
+        "read_thread",
+        "Read every message of a Gmail thread, decoded like read_message",
+        |ctx, args: ReadThreadArgs| async move {
+            let url = user_url(&format!(
+                "threads/{}?format=full",
+                urlencoding::encode(&args.thread_id)
+            ))?;
+            let thread = gmail_request(ctx.access_token(), Method::GET, url, None).await?;
+            let messages = thread["messages"]
+                .as_array()
+                .map(|messages| messages.iter().map(decode_message).collect::<Vec<_>>())
+                .unwrap_or_default();
+            json_response(&json!({"id": thread["id"], "messages": messages}))
+        },
+    );
+
+    tools.tool(
+        "list_labels",
+        "List the mailbox's system and user labels with their IDs",
+        |ctx, _: NoArgs| async move {
+            let url = user_url("labels")?;
+            let body = gmail_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "send_email",
+        "Send an email from the authenticated Gmail account. Drive files can be attached (Docs, Sheets and Slides are exported, by default to Office formats) or linked in the body; attaching needs a Drive scope such as drive.readonly.",
+        |ctx, args: ComposeArgs| async move {
+            let message = compose_with_drive_files(ctx.access_token(), args).await?;
+            let url = user_url("messages/send")?;
+            let body = gmail_request(ctx.access_token(), Method::POST, url, Some(message)).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "create_draft",
+        "Save an email as a Gmail draft without sending it, with Drive files attached or linked like send_email",
+        |ctx, args: ComposeArgs| async move {
+            let message = compose_with_drive_files(ctx.access_token(), args).await?;
+            let url = user_url("drafts")?;
+            let body = gmail_request(
+                ctx.access_token(),
+                Method::POST,
+                url,
+                Some(json!({"message": message})),
+            )
+            .await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "modify_labels",
+        "Add or remove labels on messages, e.g. remove UNREAD to mark read or INBOX to archive",
+        |ctx, args: ModifyLabelsArgs| async move {
+            let request = json!({
+                "ids": args.message_ids,
+                "addLabelIds": args.add_label_ids,
+                "removeLabelIds": args.remove_label_ids,
+            });
+            let url = user_url("messages/batchModify")?;
+            gmail_request(ctx.access_token(), Method::POST, url, Some(request)).await?;
+            json_response(&json!({"modified": args.message_ids.len()}))
+        },
+    );
+
+    Ok(())
+}
+
+fn user_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!(
+        "{}gmail/v1/users/me/{}",
+        gmail_root_url(),
+        path
+    ))?)
+}
+
+fn list_url(
+    resource: &str,
+    q: Option<&str>,
+    label_ids: &[String],
+    max_results: u64,
+    page_token: Option<&str>,
+) -> Result<Url> {
+    let mut url = user_url(resource)?;
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("maxResults", &max_results.to_string());
+        if let Some(q) = q {
+            query.append_pair("q", q);
+        }
+        if let Some(token) = page_token {
+            query.append_pair("pageToken", token);
+        }
+        for label in label_ids {
+            query.append_pair("labelIds", label);
+        }
+    }
+    Ok(url)
+}
+
+async fn gmail_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::Gmail, access_token, method, url, body).await
+}
+
+/// Decodes Gmail's base64url payload data, which may or may not be padded.
+pub(crate) fn decode_body(data: &str) -> Option<String> {
+    let bytes = URL_SAFE_NO_PAD.decode(data.trim_end_matches('=')).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Flattens a `format=full` message into headers, text and HTML bodies and
+/// attachment metadata, walking nested multipart parts.
+pub(crate) fn decode_message(message: &Value) -> Value {
+    let payload = &message["payload"];
+
+    let mut headers = Map::new();
+    for header in payload["headers"].as_array().into_iter().flatten() {
+        let (Some(name), Some(value)) = (header["name"].as_str(), header["value"].as_str()) else {
+            continue;
+        };
+        if let Some(known) = READ_HEADERS.iter().find(|h| h.eq_ignore_ascii_case(name)) {
+            headers.insert(known.to_string(), json!(value));
+        }
+    }
+
+    let mut text = String::new();
+    let mut html = String::new();
+    let mut attachments = Vec::new();
+    let mut parts = vec![payload];
+    while let Some(part) = parts.pop() {
+        if let Some(children) = part["parts"].as_array() {
+            // Reverse so parts are visited in document order
+            parts.extend(children.iter().rev());
+            continue;
+        }
+
+        let mime_type = part["mimeType"].as_str().unwrap_or_default();
+        let filename = part["filename"].as_str().unwrap_or_default();
+        if !filename.is_empty() || part["body"]["attachmentId"].is_string() {
+            attachments.push(json!({
+                "filename": filename,
+                "mimeType": mime_type,
+                "size": part["body"]["size"],
+                "attachmentId": part["body"]["attachmentId"],
+            }));
+            continue;
+        }
+
+        let Some(body) = part["body"]["data"].as_str().and_then(decode_body) else {
+            continue;
+        };
+        match mime_type {
+            "text/plain" => text.push_str(&body),
+            "text/html" => html.push_str(&body),
+            _ => {}
+        }
+    }
+
+    json!({
+        "id": message["id"],
+        "threadId": message["threadId"],
+        "labelIds": message["labelIds"],
+        "snippet": message["snippet"],
+        "headers": headers,
+        "text": text,
+        "html": html,
+        "attachments": attachments,
+    })
+}
+
+/// Encodes a header value as an RFC 2047 encoded-word when it is not plain ASCII.
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(value))
+    }
+}
+
+/// A file attached to an outgoing message.
+#[derive(Debug)]
+pub(crate) struct Attachment {
+    pub filename: String,
+    pub mime_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Fetches the Drive files `args` asks for, attaching their content or
+/// linking them at the end of the body, then composes the message.
+async fn compose_with_drive_files(access_token: &str, mut args: ComposeArgs) -> Result<Value> {
+    let mut attachments = Vec::new();
+    let mut links = Vec::new();
+    let mut remaining = MAX_ATTACHMENT_BYTES;
+
+    for drive_file in std::mem::take(&mut args.drive_files) {
+        match drive_file.mode {
+            DriveFileMode::Attach => {
+                let file = get_metadata(access_token, &drive_file.file_id).await?;
+                let (response, format) =
+                    open_download(access_token, &file, drive_file.format.as_deref()).await?;
+                let Some(bytes) = read_limited(response, remaining).await? else {
+                    anyhow::bail!(
+                        "{} would take the attachments past Gmail's {} MB limit; link it instead",
+                        file.name,
+                        MAX_ATTACHMENT_BYTES / (1024 * 1024)
+                    );
+                };
+                remaining -= bytes.len();
+                let mime_type = match &format {
+                    Some(format) => export_mime_type(format).unwrap_or_default().to_string(),
+                    None => file.mime_type.clone(),
+                };
+                attachments.push(Attachment {
+                    filename: file_name(&file, format.as_deref()),
+                    mime_type,
+                    bytes,
+                });
+            }
+            DriveFileMode::Link => {
+                let mut url = Url::parse(&format!(
+                    "{}drive/v3/files/{}",
+                    drive_root_url(),
+                    urlencoding::encode(&drive_file.file_id)
+                ))?;
+                url.query_pairs_mut()
+                    .append_pair("fields", "name,webViewLink")
+                    .append_pair("supportsAllDrives", "true");
+                let file =
+                    rest_request(Service::Drive, access_token, Method::GET, url, None).await?;
+                links.push((
+                    file["name"].as_str().unwrap_or_default().to_string(),
+                    file["webViewLink"].as_str().unwrap_or_default().to_string(),
+                ));
+            }
+        }
+    }
+
+    args.body = append_links(&args.body, args.html, &links);
+    compose_message(&args, &attachments)
+}
+
+/// Appends `(name, url)` links to a message body, one per line.
+pub(crate) fn append_links(body: &str, html: bool, links: &[(String, String)]) -> String {
+    let mut body = body.to_string();
+    for (name, url) in links {
+        if html {
+            body.push_str(&format!(
+                "<p><a href=\"{}\">{}</a></p>",
+                escape_html(url),
+                escape_html(name)
+            ));
+        } else {
+            body.push_str(&format!("\n{}: {}", name, url));
+        }
+    }
+    body
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Appends `bytes` base64-encoded in 76-character lines.
+fn push_base64(raw: &mut String, bytes: &[u8]) -> Result<()> {
+    let encoded = STANDARD.encode(bytes);
+    for line in encoded.as_bytes().chunks(76) {
+        raw.push_str(std::str::from_utf8(line)?);
+        raw.push_str("\r\n");
+    }
+    Ok(())
+}
+
+/// Builds the RFC 2822 message for `send_email` and `create_draft`, returning
+/// the Gmail `Message` resource with the base64url `raw` field. With
+/// attachments the message is `multipart/mixed`, the body being its first part.
+pub(crate) fn compose_message(args: &ComposeArgs, attachments: &[Attachment]) -> Result<Value> {
+    if args.to.is_empty() {
+        anyhow::bail!("to required");
+    }
+
+    let mut headers = vec![("To", args.to.join(", "))];
+    for (addresses, header) in [(&args.cc, "Cc"), (&args.bcc, "Bcc")] {
+        if !addresses.is_empty() {
+            headers.push((header, addresses.join(", ")));
+        }
+    }
+    headers.push(("Subject", encode_header(&args.subject)));
+    if let Some(in_reply_to) = &args.in_reply_to {
+        headers.push(("In-Reply-To", in_reply_to.to_string()));
+        headers.push(("References", in_reply_to.to_string()));
+    }
+
+    let mut raw = String::new();
+    for (name, value) in &headers {
+        // A newline in a header value would let callers inject extra headers
+        if value.contains(['\r', '\n']) {
+            anyhow::bail!("{} must not contain line breaks", name);
+        }
+        raw.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    raw.push_str("MIME-Version: 1.0\r\n");
+    // Base64 never contains `_`, so the boundary cannot occur in any part
+    let boundary = format!("=_part_{:016x}", rand::random::<u64>());
+    if !attachments.is_empty() {
+        raw.push_str(&format!(
+            "Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n--{}\r\n",
+            boundary, boundary
+        ));
+    }
+    raw.push_str(&format!(
+        "Content-Type: text/{}; charset=\"UTF-8\"\r\n",
+        if args.html { "html" } else { "plain" }
+    ));
+    raw.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+    push_base64(&mut raw, args.body.as_bytes())?;
+
+    for attachment in attachments {
+        let filename = encode_header(&attachment.filename);
+        raw.push_str(&format!("--{}\r\n", boundary));
+        raw.push_str(&format!(
+            "Content-Type: {}; name=\"{}\"\r\n",
+            attachment.mime_type, filename
+        ));
+        raw.push_str(&format!(
+            "Content-Disposition: attachment; filename=\"{}\"\r\n",
+            filename
+        ));
+        raw.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+        push_base64(&mut raw, &attachment.bytes)?;
+    }
+    if !attachments.is_empty() {
+        raw.push_str(&format!("--{}--\r\n", boundary));
+    }
+
+    let mut message = json!({"raw": URL_SAFE_NO_PAD.encode(raw)});
+    if let Some(thread_id) = &args.thread_id {
+        message["threadId"] = json!(thread_id);
+    }
+    Ok(message)
+}
+
+pub(crate) fn list_gmail_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://gmail.googleapis.com/gmail/v1/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "gmail".to_string(),
+            description: Some("Gmail API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::Result;
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
+
+use super::{api_name, json_response, rest_request, ServerOptions, ToolRegistry};
+use crate::{circuit::Service, client::meet_root_url};
+
+const DEFAULT_MAX_CONFERENCES: u64 = 5;
+
+// Tool Arguments
+
+fn default_max_conferences() -> u64 {
+    DEFAULT_MAX_CONFERENCES
+}
+
+/// Who can join without knocking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AccessType {
+    /// Anyone with the link
+    Open,
+    /// The organisation's users and invited guests
+    Trusted,
+    /// Only invited guests
+    Restricted,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateMeetingSpaceArgs {
+    /// Defaults to the organisation's setting
+    pub access_type: Option<AccessType>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MeetingSpaceArgs {
+    /// Space name (`spaces/…`), meeting code (`abc-mnop-xyz`) or meeting link
+    pub space: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListMeetingArtifactsArgs {
+    /// Space name (`spaces/…`), meeting code (`abc-mnop-xyz`) or meeting link
+    pub space: String,
+    /// Most recent conferences to include
+    #[serde(default = "default_max_conferences")]
+    #[schemars(range(min = 1, max = 25))]
+    pub max_conferences: u64,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "meet": {
+                    "version": "v2",
+                    "description": "Google Meet API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_meet_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.mutating_tool(
+        "create_meeting_space",
+        "Create a Google Meet meeting space and return its join link and meeting code, ready to paste into an invite",
+        |ctx, args: CreateMeetingSpaceArgs| async move {
+            let mut body = json!({});
+            if let Some(access_type) = args.access_type {
+                body["config"] = json!({ "accessType": api_name(&access_type) });
+            }
+            let space = meet_request(
+                ctx.access_token(),
+                Method::POST,
+                meet_url("spaces")?,
+                Some(body),
+            )
+            .await?;
+            json_response(&space)
+        },
+    );
+
+    tools.tool(
+        "get_meeting_space",
+        "Look up a meeting space by name, meeting code or link: its join link, meeting code, access settings and any conference in progress",
+        |ctx, args: MeetingSpaceArgs| async move {
+            let name = space_name(&args.space)?;
+            ctx.span.record("resource", name.as_str());
+            let space =
+                meet_request(ctx.access_token(), Method::GET, meet_url(&name)?, None).await?;
+            json_response(&space)
+        },
+    );
+
+    tools.tool(
+        "list_meeting_artifacts",
+        "List the recent conferences held in a meeting space with their recordings (Drive files) and transcripts (Docs)",
+        |ctx, args: ListMeetingArtifactsArgs| async move {
+            let token = ctx.access_token();
+            let name = space_name(&args.space)?;
+            ctx.span.record("resource", name.as_str());
+            // Conference records are filtered by canonical space name, which a
+            // meeting code or link has to be resolved to first
+            let space = meet_request(token, Method::GET, meet_url(&name)?, None).await?;
+            let canonical = space["name"].as_str().unwrap_or(&name).to_string();
+
+            let mut url = meet_url("conferenceRecords")?;
+            url.query_pairs_mut()
+                .append_pair("filter", &format!("space.name = \"{}\"", canonical))
+                .append_pair("pageSize", &args.max_conferences.to_string());
+            let records = meet_request(token, Method::GET, url, None).await?;
+
+            let mut conferences = Vec::new();
+            for record in records["conferenceRecords"]
+                .as_array()
+                .into_iter()
+                .flatten()
+            {
+                let Some(record_name) = record["name"].as_str() else {
+                    continue;
+                };
+                let recordings = meet_request(
+                    token,
+                    Method::GET,
+                    meet_url(&format!("{}/recordings", record_name))?,
+                    None,
+                )
+                .await?;
+                let transcripts = meet_request(
+                    token,
+                    Method::GET,
+                    meet_url(&format!("{}/transcripts", record_name))?,
+                    None,
+                )
+                .await?;
+                conferences.push(conference_artifacts(record, &recordings, &transcripts));
+            }
+
+            json_response(&json!({
+                "space": canonical,
+                "meetingUri": space["meetingUri"],
+                "conferences": conferences,
+            }))
+        },
+    );
+
+    Ok(())
+}
+
+fn meet_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!("{}v2/{}", meet_root_url(), path))?)
+}
+
+async fn meet_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::Meet, access_token, method, url, body).await
+}
+
+/// The `spaces/…` path for a space name, meeting code or meeting link.
+/// `spaces.get` accepts a meeting code in place of the space's ID.
+pub(crate) fn space_name(space: &str) -> Result<String> {
+    let space = space.trim();
+    if let Some(id) = space.strip_prefix("spaces/") {
+        if !id.is_empty() && !id.contains('/') {
+            return Ok(space.to_string());
+        }
+    }
+    let link = if space.starts_with("meet.google.com/") {
+        format!("https://{}", space)
+    } else {
+        space.to_string()
+    };
+    let code = match Url::parse(&link) {
+        Ok(url) if url.host_str() == Some("meet.google.com") => url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default()
+            .to_string(),
+        Ok(_) => anyhow::bail!("`{}` is not a Google Meet link", space),
+        Err(_) => space.to_string(),
+    };
+    if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        anyhow::bail!(
+            "expected a space name (spaces/…), meeting code (abc-mnop-xyz) or meeting link, got `{}`",
+            space
+        );
+    }
+    Ok(format!("spaces/{}", code))
+}
+
+/// One conference with the artifacts that matter to a caller: where each
+/// recording and transcript lives and whether it is ready yet.
+pub(crate) fn conference_artifacts(
+    record: &Value,
+    recordings: &Value,
+    transcripts: &Value,
+) -> Value {
+    let recordings = recordings["recordings"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|recording| {
+            json!({
+                "name": recording["name"],
+                "state": recording["state"],
+                "driveFile": recording["driveDestination"]["file"],
+                "url": recording["driveDestination"]["exportUri"],
+            })
+        })
+        .collect::<Vec<_>>();
+    let transcripts = transcripts["transcripts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|transcript| {
+            json!({
+                "name": transcript["name"],
+                "state": transcript["state"],
+                "document": transcript["docsDestination"]["document"],
+                "url": transcript["docsDestination"]["exportUri"],
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "conferenceRecord": record["name"],
+        "startTime": record["startTime"],
+        "endTime": record["endTime"],
+        "recordings": recordings,
+        "transcripts": transcripts,
+    })
+}
+
+pub(crate) fn list_meet_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://meet.googleapis.com/v2/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "meet".to_string(),
+            description: Some("Google Meet API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
@@ -1,2 +1,1073 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use async_mcp::{
+    client::ClientBuilder,
+    protocol::RequestOptions,
+    server::{Server, ServerBuilder},
+    transport::{ClientInMemoryTransport, ServerInMemoryTransport, Transport},
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use reqwest::Method;
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, info, info_span, Instrument, Span};
+use url::Url;
+
+use crate::{
+    audit::{AuditLog, AuditRecord},
+    cache::ResponseCache,
+    circuit::{guarded, Service},
+    client::{
+        docs_hub, get_http_client, hub_client, sheets_hub, track_failures, DocsClient, HubClient,
+        RetryPolicy, SheetsClient, TransientFailure,
+    },
+    links,
+    metrics::Metrics,
+    payload,
+    quota::{self, QuotaTracker, QUOTA_WINDOW},
+    redact::redact,
+    upload::UploadPolicy,
+    InvokeError, TokenProvider,
+};
+
+use workspace::WorkspaceService;
+
+pub mod admin;
+pub mod apps_script;
+pub mod calendar;
+pub mod chat;
+pub mod classroom;
+pub mod docs;
 pub mod drive;
+pub mod gmail;
+pub mod meet;
+pub mod resources;
 pub mod sheets;
+pub mod slides;
+pub mod tasks;
+pub mod workspace;
+#[cfg(feature = "youtube")]
+pub mod youtube;
+
+/// Options shared by all server builders.
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    pub timeouts: Timeouts,
+    /// Only register tools that never modify Workspace data
+    pub read_only: bool,
+    /// Glob patterns of tools to expose; empty means all tools
+    pub allow_tools: Vec<String>,
+    /// Glob patterns of tools to hide, applied after `allow_tools`
+    pub deny_tools: Vec<String>,
+    /// Where mutating tool calls are recorded, if anywhere
+    pub audit_log: Option<Arc<AuditLog>>,
+    /// Spreadsheet used when neither the arguments nor the request meta name one
+    pub default_spreadsheet_id: Option<String>,
+    /// Apps Script project `run_script` calls when the arguments name none
+    pub default_script_id: Option<String>,
+    /// Google Cloud project BigQuery jobs run and bill in; `sheets_to_bigquery`
+    /// is only served when set
+    pub bigquery_project: Option<String>,
+    /// Uploads larger than this many bytes use a resumable session
+    /// (default `upload::DEFAULT_RESUMABLE_THRESHOLD`)
+    pub upload_resumable_threshold: Option<u64>,
+    /// Local directories and URLs `upload_file` may read from; only base64
+    /// content is accepted by default
+    pub uploads: UploadPolicy,
+    /// Directory `download_file` may write `out_dir` downloads under; they are
+    /// refused when unset
+    pub download_dir: Option<PathBuf>,
+    /// Supplies access tokens for calls whose meta carries none
+    pub token_provider: Option<Arc<TokenProvider>>,
+    /// Google account the server's credentials belong to, recorded as the
+    /// acting account of audited calls
+    pub account: Option<String>,
+    /// Prefix for registered tool names, e.g. `drive` for `drive.list_files`
+    pub namespace: Option<String>,
+    /// Retries of calls that Google rate-limited or failed transiently
+    pub retry: RetryPolicy,
+    /// Default page size of the Drive listing tools, replacing each tool's own
+    pub page_size: Option<u64>,
+    /// Caches the results of read-heavy tools when set
+    pub cache: Option<Arc<ResponseCache>>,
+}
+
+impl ServerOptions {
+    /// Whether the allow/deny lists expose `name`. Patterns may match either
+    /// the bare or the namespaced tool name.
+    pub fn is_tool_enabled(&self, name: &str) -> bool {
+        let qualified = self.qualified_name(name);
+        let matches = |p: &String| glob_match(p, name) || glob_match(p, &qualified);
+        let allowed = self.allow_tools.is_empty() || self.allow_tools.iter().any(matches);
+        allowed && !self.deny_tools.iter().any(matches)
+    }
+
+    /// The name a tool is advertised under.
+    pub fn qualified_name(&self, name: &str) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{}.{}", namespace, name),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// Matches `name` against a glob supporting `*` (any run) and `?` (one char).
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Deadlines applied to tool calls, with optional per-tool overrides.
+#[derive(Debug, Clone)]
+pub struct Timeouts {
+    pub default: Duration,
+    pub per_tool: HashMap<String, Duration>,
+}
+
+/// Built-in deadlines for tools that routinely outlast the global default:
+/// streamed range reads, batched Drive mutations and Apps Script runs, which
+/// Google lets take up to six minutes.
+const DEFAULT_TOOL_TIMEOUTS: &[(&str, u64)] = &[
+    ("read_values", 120),
+    ("trash_files", 120),
+    ("share_files", 120),
+    ("run_script", 360),
+];
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            default: Duration::from_secs(60),
+            per_tool: DEFAULT_TOOL_TIMEOUTS
+                .iter()
+                .map(|(name, secs)| (name.to_string(), Duration::from_secs(*secs)))
+                .collect(),
+        }
+    }
+}
+
+impl Timeouts {
+    /// The tool's deadline; namespaced names fall back to the bare tool's entry.
+    pub fn for_tool(&self, name: &str) -> Duration {
+        self.per_tool
+            .get(name)
+            .or_else(|| {
+                let (_, bare) = name.split_once('.')?;
+                self.per_tool.get(bare)
+            })
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// The deadline for one call: the tool's configured deadline, shortened to the
+    /// client's own timeout when the request carries one in `meta.timeout_ms`, so
+    /// we stop working on calls the client has already given up on.
+    pub fn for_request(&self, req: &CallToolRequest) -> Duration {
+        let configured = self.for_tool(&req.name);
+        match requested_timeout(req) {
+            Some(requested) => requested.min(configured),
+            None => configured,
+        }
+    }
+}
+
+fn requested_timeout(req: &CallToolRequest) -> Option<Duration> {
+    req.meta
+        .as_ref()
+        .and_then(|v| v.get("timeout_ms"))
+        .and_then(|v| v.as_u64())
+        .filter(|ms| *ms > 0)
+        .map(Duration::from_millis)
+}
+
+type InMemoryBuild =
+    fn(ServerInMemoryTransport, ServerOptions) -> Result<Server<ServerInMemoryTransport>>;
+
+/// Lists the tools a server registers under `options` by asking it over an
+/// in-memory transport, exactly as an MCP client would.
+pub async fn list_tools(build: InMemoryBuild, options: ServerOptions) -> Result<Vec<Tool>> {
+    let client_transport = ClientInMemoryTransport::new(move |transport| {
+        let options = options.clone();
+        tokio::spawn(async move {
+            match build(transport, options) {
+                Ok(server) => {
+                    if let Err(e) = server.listen().await {
+                        error!("In-memory server error: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to build server: {}", e),
+            }
+        })
+    });
+    client_transport.open().await?;
+
+    let client = ClientBuilder::new(client_transport.clone()).build();
+    let client_clone = client.clone();
+    tokio::spawn(async move { client_clone.start().await });
+
+    let response = client
+        .request("tools/list", Some(json!({})), RequestOptions::default())
+        .await?;
+    client_transport.close().await?;
+
+    Ok(serde_json::from_value(response["tools"].clone())?)
+}
+
+/// The tools the servers of `services` register under `options`.
+pub async fn list_service_tools(
+    services: &[WorkspaceService],
+    options: ServerOptions,
+) -> Result<Vec<Tool>> {
+    let mut tools = Vec::new();
+    for service in services {
+        let build: InMemoryBuild = match service {
+            WorkspaceService::Drive => drive::build_with_options,
+            WorkspaceService::Sheets => sheets::build_with_options,
+            WorkspaceService::Gmail => gmail::build_with_options,
+            WorkspaceService::Calendar => calendar::build_with_options,
+            WorkspaceService::Docs => docs::build_with_options,
+            WorkspaceService::Slides => slides::build_with_options,
+            WorkspaceService::Tasks => tasks::build_with_options,
+            WorkspaceService::AppsScript => apps_script::build_with_options,
+            WorkspaceService::Chat => chat::build_with_options,
+            WorkspaceService::Meet => meet::build_with_options,
+            WorkspaceService::Admin => admin::build_with_options,
+            WorkspaceService::Classroom => classroom::build_with_options,
+            #[cfg(feature = "youtube")]
+            WorkspaceService::YouTube => youtube::build_with_options,
+        };
+        tools.extend(list_tools(build, options.clone()).await?);
+    }
+    Ok(tools)
+}
+
+pub(crate) fn get_access_token(req: &CallToolRequest) -> Result<&str> {
+    req.meta
+        .as_ref()
+        .and_then(|v| v.get("access_token"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing or invalid access_token"))
+}
+
+/// Resolves the target spreadsheet from the `spreadsheet_id` argument, then
+/// the request meta, then the server's configured default.
+pub(crate) fn get_spreadsheet_id(
+    from_args: Option<&str>,
+    req: &CallToolRequest,
+    default: Option<&str>,
+) -> Result<String> {
+    let from_meta = || {
+        req.meta
+            .as_ref()
+            .and_then(|v| v.get("spreadsheet_id"))
+            .and_then(|v| v.as_str())
+    };
+
+    from_args
+        .or_else(from_meta)
+        .or(default)
+        .map(links::file_id)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "spreadsheet_id required in arguments or context (or start the server with --spreadsheet-id)"
+            )
+        })
+}
+
+/// Deserializes a call's arguments into the tool's argument struct. Unknown
+/// keys are ignored, so clients may send extras like `timeout_ms`.
+pub(crate) fn parse_args<A: DeserializeOwned>(req: &CallToolRequest) -> Result<A> {
+    let arguments = req.arguments.clone().unwrap_or_default();
+    serde_json::from_value(Value::Object(arguments.into_iter().collect()))
+        .map_err(|e| anyhow::anyhow!("invalid arguments: {}", e))
+}
+
+/// Arguments of a tool that takes none.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct NoArgs {}
+
+/// The `input_schema` of a tool taking `A`, generated from the struct
+/// `parse_args` fills so the advertised schema and the parsing cannot drift
+/// apart. Subschemas are inlined since not every client resolves `$ref`, and
+/// the struct's own doc comment is dropped in favor of the tool description.
+pub(crate) fn input_schema<A: JsonSchema>() -> Value {
+    let generator = SchemaSettings::draft07()
+        .with(|settings| {
+            settings.inline_subschemas = true;
+            settings.option_add_null_type = false;
+        })
+        .into_generator();
+    let mut schema =
+        serde_json::to_value(generator.into_root_schema_for::<A>()).unwrap_or_default();
+    if let Some(schema) = schema.as_object_mut() {
+        for key in ["$schema", "title", "description"] {
+            schema.remove(key);
+        }
+        // Some clients reject object schemas without `properties`
+        schema.entry("properties").or_insert_with(|| json!({}));
+    }
+    schema
+}
+
+/// [`input_schema`] for a listing tool, advertising `default_page_size` (the
+/// configured page size or the tool's own) as the `page_size` default.
+pub(crate) fn paged_input_schema<A: JsonSchema>(default_page_size: u64) -> Value {
+    let mut schema = input_schema::<A>();
+    schema["properties"]["page_size"]["default"] = json!(default_page_size);
+    schema
+}
+
+/// The name an enum argument goes by in Google's APIs, i.e. its serde name.
+pub(crate) fn api_name<E: Serialize>(value: &E) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+pub(crate) fn handle_result(result: Result<CallToolResponse>) -> Result<CallToolResponse> {
+    match result {
+        Ok(response) => Ok(response),
+        Err(e) => Ok(CallToolResponse {
+            content: vec![ToolResponseContent::Text {
+                text: format!("Error: {}", redact(&describe_error(&e))),
+            }],
+            is_error: Some(true),
+            meta: None,
+        }),
+    }
+}
+
+/// Wraps a JSON value as a tool's text response.
+pub(crate) fn json_response(value: &Value) -> Result<CallToolResponse> {
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: serde_json::to_string(value)?,
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// Makes a JSON REST call to a Google API that has no generated hub, through
+/// the shared HTTP client and the service's circuit breaker. Empty response
+/// bodies (e.g. from deletes) come back as `Value::Null`.
+pub(crate) async fn rest_request(
+    service: Service,
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    let client = get_http_client()?;
+    guarded(service, async {
+        payload::log_request(method.as_str(), url.as_str(), body.as_ref());
+        let mut request = client
+            .request(method, url.clone())
+            .bearer_auth(access_token);
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(InvokeError::from_response(response).await);
+        }
+
+        let status = response.status().as_u16();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+        let value = match text.trim() {
+            "" => Value::Null,
+            text => serde_json::from_str(text)?,
+        };
+        payload::log_response(url.as_str(), status, &value);
+        Ok(value)
+    })
+    .await
+}
+
+/// Renders hub errors through `InvokeError` so callers see Google's status and
+/// reason rather than a Debug dump of the raw response.
+fn describe_error(error: &anyhow::Error) -> String {
+    match error.downcast_ref::<google_sheets4::Error>() {
+        Some(hub_error) => InvokeError::from_hub_error(hub_error).to_string(),
+        None => error.to_string(),
+    }
+}
+
+/// Whether Google rejected the call's credentials, i.e. a fresh access token
+/// might succeed where this one failed.
+pub(crate) fn is_unauthenticated(error: &anyhow::Error) -> bool {
+    TransientFailure::classify(error) == Some(TransientFailure::Unauthenticated)
+}
+
+/// Returns `req` with `access_token` set in its meta.
+pub(crate) fn with_access_token(mut req: CallToolRequest, access_token: &str) -> CallToolRequest {
+    let mut meta = match req.meta.take() {
+        Some(Value::Object(meta)) => meta,
+        _ => serde_json::Map::new(),
+    };
+    meta.insert("access_token".to_string(), json!(access_token));
+    req.meta = Some(Value::Object(meta));
+    req
+}
+
+/// Runs `handler`, supplying an access token from `provider` when the client
+/// sent none, and repeats the call when a Google request inside it failed
+/// transiently: once with a refreshed token after a 401, and with backoff after
+/// rate limiting. Outages (5xx, dropped connections) are only retried for
+/// `idempotent` tools, since the failed request may already have been applied.
+async fn call_with_retries<F, Fut>(
+    handler: Arc<F>,
+    provider: Option<Arc<TokenProvider>>,
+    retry: RetryPolicy,
+    idempotent: bool,
+    req: CallToolRequest,
+) -> Result<CallToolResponse>
+where
+    F: Fn(CallToolRequest) -> Fut,
+    Fut: Future<Output = Result<CallToolResponse>>,
+{
+    let provider = provider.filter(|_| get_access_token(&req).is_err());
+    let mut token = match &provider {
+        Some(provider) => match provider.access_token().await {
+            Ok(token) => Some(token),
+            Err(e) => return handle_result(Err(e.into())),
+        },
+        None => None,
+    };
+    let mut refreshed = false;
+    let mut retries = 0;
+
+    loop {
+        let attempt = match &token {
+            Some(token) => with_access_token(req.clone(), token),
+            None => req.clone(),
+        };
+        let user = get_access_token(&attempt)
+            .map(token_digest)
+            .unwrap_or_default();
+        let (result, failure) = quota::as_user(user, track_failures(handler(attempt))).await;
+        let failed = match &result {
+            Ok(response) => response.is_error == Some(true),
+            Err(_) => true,
+        };
+        let Some((service, failure)) = failure.filter(|_| failed) else {
+            return result;
+        };
+
+        match failure {
+            TransientFailure::Unauthenticated => {
+                let (Some(provider), Some(rejected), false) = (&provider, &token, refreshed) else {
+                    return result;
+                };
+                info!("Access token rejected, refreshing and retrying");
+                provider.invalidate(rejected).await;
+                token = match provider.access_token().await {
+                    Ok(token) => Some(token),
+                    Err(e) => return handle_result(Err(e.into())),
+                };
+                refreshed = true;
+            }
+            TransientFailure::RateLimited { .. } | TransientFailure::Unavailable => {
+                let retry_after = match failure {
+                    TransientFailure::RateLimited { retry_after } => retry_after,
+                    _ if idempotent => None,
+                    _ => return result,
+                };
+                if retries >= retry.max_retries {
+                    return result;
+                }
+                let Some(delay) = retry.delay(retries, retry_after) else {
+                    return result;
+                };
+                info!(
+                    service = service.name(),
+                    attempt = retries + 1,
+                    delay_ms = delay.as_millis() as u64,
+                    "Transient {:?} failure, retrying",
+                    failure
+                );
+                Metrics::global().record_retry(service.name());
+                tokio::time::sleep(delay).await;
+                retries += 1;
+            }
+        }
+    }
+}
+
+fn response_len(response: &CallToolResponse) -> usize {
+    response
+        .content
+        .iter()
+        .map(|c| match c {
+            ToolResponseContent::Text { text } => text.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// A short random ID correlating one tool call's log lines.
+fn new_request_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// Registers a tool whose handler is bounded by its deadline, so a hung
+/// Google API connection surfaces as a timeout error instead of stalling the session.
+/// When the server has a token provider, calls without an access token get one.
+pub(crate) fn register_tool<T, F, Fut>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+    tool: Tool,
+    handler: F,
+) where
+    T: Transport,
+    F: Fn(CallToolRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<CallToolResponse>> + Send + 'static,
+{
+    register(server, options, tool, handler, true);
+}
+
+fn register<T, F, Fut>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+    mut tool: Tool,
+    handler: F,
+    idempotent: bool,
+) where
+    T: Transport,
+    F: Fn(CallToolRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<CallToolResponse>> + Send + 'static,
+{
+    if !options.is_tool_enabled(&tool.name) {
+        debug!("Tool {} excluded by allow/deny configuration", tool.name);
+        return;
+    }
+
+    tool.name = options.qualified_name(&tool.name);
+    let name = tool.name.clone();
+    let timeouts = options.timeouts.clone();
+    let token_provider = options.token_provider.clone();
+    let retry = options.retry.clone();
+    let handler = Arc::new(handler);
+
+    server.register_tool(tool, move |mut req: CallToolRequest| {
+        resolve_links(&mut req);
+        let name = name.clone();
+        let timeout = timeouts.for_request(&req);
+        let request_id = new_request_id();
+        let span = info_span!(
+            "tool_call",
+            tool = %name,
+            request_id = %request_id,
+            resource = target_resource(&req).as_deref().unwrap_or("-"),
+        );
+        if payload::is_enabled() {
+            let arguments = serde_json::to_string(&req.arguments).unwrap_or_default();
+            payload::log_tool_payload(&name, "arguments", &arguments);
+        }
+        let call = call_with_retries(
+            handler.clone(),
+            token_provider.clone(),
+            retry.clone(),
+            idempotent,
+            req,
+        );
+        Box::pin(
+            async move {
+                let started = Instant::now();
+                let result = match tokio::time::timeout(timeout, call).await {
+                    Ok(result) => result,
+                    Err(_) => handle_result(Err(anyhow::anyhow!(
+                        "{} timed out after {}s",
+                        name,
+                        timeout.as_secs_f64()
+                    ))),
+                };
+
+                let (is_error, bytes) = match &result {
+                    Ok(response) => (response.is_error.unwrap_or(false), response_len(response)),
+                    Err(_) => (true, 0),
+                };
+                if let Ok(response) = &result {
+                    for content in &response.content {
+                        if let ToolResponseContent::Text { text } = content {
+                            payload::log_tool_payload(&name, "result", text);
+                        }
+                    }
+                }
+                let elapsed = started.elapsed();
+                Metrics::global().record_call(&name, elapsed, is_error, bytes);
+                info!(
+                    duration_ms = elapsed.as_millis() as u64,
+                    is_error, bytes, "Tool call finished"
+                );
+
+                tag_errors(result, &request_id)
+            }
+            .instrument(span),
+        )
+    });
+}
+
+/// The spreadsheet or file a call targets, for log correlation.
+pub(crate) fn target_resource(req: &CallToolRequest) -> Option<String> {
+    const KEYS: &[&str] = &["spreadsheet_id", "file_id", "file_ids"];
+
+    let from_args = req
+        .arguments
+        .as_ref()
+        .and_then(|args| KEYS.iter().find_map(|key| args.get(*key)));
+    let from_meta = || {
+        req.meta
+            .as_ref()
+            .and_then(|meta| KEYS.iter().find_map(|key| meta.get(*key)))
+    };
+
+    match from_args.or_else(from_meta)? {
+        Value::String(id) => Some(id.clone()),
+        Value::Array(ids) => Some(
+            ids.iter()
+                .filter_map(|id| id.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        _ => None,
+    }
+}
+
+/// Swaps Google links in a call's ID arguments and `spreadsheet_id` meta for
+/// the IDs in them, before anything reads the call.
+pub(crate) fn resolve_links(req: &mut CallToolRequest) {
+    if let Some(arguments) = &mut req.arguments {
+        links::resolve_arguments(arguments);
+    }
+    if let Some(Value::String(id)) = req
+        .meta
+        .as_mut()
+        .and_then(|meta| meta.get_mut("spreadsheet_id"))
+    {
+        *id = links::file_id(id);
+    }
+}
+
+/// Appends the request ID to error output so a failed agent action can be
+/// matched to its log lines.
+pub(crate) fn tag_errors(
+    result: Result<CallToolResponse>,
+    request_id: &str,
+) -> Result<CallToolResponse> {
+    match result {
+        Ok(mut response) if response.is_error == Some(true) => {
+            for content in &mut response.content {
+                if let ToolResponseContent::Text { text } = content {
+                    text.push_str(&format!(" (request_id: {})", request_id));
+                }
+            }
+            Ok(response)
+        }
+        Ok(response) => Ok(response),
+        Err(e) => Err(anyhow::anyhow!("{} (request_id: {})", e, request_id)),
+    }
+}
+
+/// Registers a tool that modifies Workspace data. In read-only mode the tool is
+/// not registered at all, so it is neither advertised nor callable.
+pub(crate) fn register_mutating_tool<T, F, Fut>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+    tool: Tool,
+    handler: F,
+) where
+    T: Transport,
+    F: Fn(CallToolRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<CallToolResponse>> + Send + 'static,
+{
+    if options.read_only {
+        debug!("Read-only mode: skipping mutating tool {}", tool.name);
+        return;
+    }
+
+    let handler = invalidating_cache(options, handler);
+    let Some(audit_log) = options.audit_log.clone() else {
+        register(server, options, tool, handler, false);
+        return;
+    };
+
+    let name = tool.name.clone();
+    let account = options.account.clone();
+    let default_spreadsheet_id = options.default_spreadsheet_id.clone();
+    let audited = move |req: CallToolRequest| {
+        let arguments =
+            serde_json::to_value(req.arguments.clone().unwrap_or_default()).unwrap_or_default();
+        let mut record = AuditRecord::new(
+            &name,
+            &arguments,
+            &req.meta.clone().unwrap_or_default(),
+            account.as_deref(),
+            default_spreadsheet_id.as_deref(),
+        );
+        let audit_log = audit_log.clone();
+        let call = handler(req);
+
+        async move {
+            let result = call.await;
+            match &result {
+                Ok(response) if response.is_error != Some(true) => {
+                    record.outcome = "success".to_string();
+                }
+                Ok(response) => {
+                    record.outcome = "error".to_string();
+                    record.error = response.content.iter().find_map(|c| match c {
+                        ToolResponseContent::Text { text } => Some(text.clone()),
+                        _ => None,
+                    });
+                }
+                Err(e) => {
+                    record.outcome = "error".to_string();
+                    record.error = Some(e.to_string());
+                }
+            }
+            audit_log.record(&record);
+            result
+        }
+    };
+    register(server, options, tool, audited, false);
+}
+
+/// The response cache prefixes a write may make stale: Drive listings, and
+/// every spreadsheet or file the call names, or else the spreadsheet it
+/// falls back to.
+pub(crate) fn written_prefixes(
+    req: &CallToolRequest,
+    default_spreadsheet_id: Option<&str>,
+) -> Vec<String> {
+    const KEYS: &[&str] = &[
+        "spreadsheet_id",
+        "destination_spreadsheet_id",
+        "file_id",
+        "file_ids",
+    ];
+
+    let mut ids = Vec::new();
+    for value in KEYS
+        .iter()
+        .filter_map(|key| req.arguments.as_ref()?.get(*key))
+    {
+        match value {
+            Value::String(id) => ids.push(id.clone()),
+            Value::Array(list) => {
+                ids.extend(list.iter().filter_map(|id| id.as_str()).map(str::to_string))
+            }
+            _ => {}
+        }
+    }
+    if ids.is_empty() {
+        ids.extend(get_spreadsheet_id(None, req, default_spreadsheet_id).ok());
+    }
+
+    let mut prefixes = vec!["drive/".to_string()];
+    prefixes.extend(ids.iter().map(|id| format!("spreadsheet/{}/", id)));
+    prefixes
+}
+
+/// Wraps a mutating handler so that, with a response cache, what the call
+/// writes is invalidated both before it runs and once it has finished, so
+/// no read overlapping the write stays cached.
+fn invalidating_cache<F, Fut>(
+    options: &ServerOptions,
+    handler: F,
+) -> impl Fn(CallToolRequest) -> ToolFuture + Send + Sync + 'static
+where
+    F: Fn(CallToolRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<CallToolResponse>> + Send + 'static,
+{
+    let cache = options.cache.clone();
+    let default_spreadsheet_id = options.default_spreadsheet_id.clone();
+    move |req: CallToolRequest| -> ToolFuture {
+        let Some(cache) = cache.clone() else {
+            return Box::pin(handler(req));
+        };
+        let prefixes = written_prefixes(&req, default_spreadsheet_id.as_deref());
+        let invalidate = move || {
+            for prefix in &prefixes {
+                cache.invalidate_prefix(prefix);
+            }
+        };
+        invalidate();
+        let call = handler(req);
+        Box::pin(async move {
+            let result = call.await;
+            invalidate();
+            result
+        })
+    }
+}
+
+/// Everything a tool handler needs besides its arguments: the access token,
+/// the raw request, the server's options, the call's tracing span, and API
+/// hubs built on first use and shared by all of the handler's requests.
+pub(crate) struct ToolContext {
+    access_token: String,
+    pub req: CallToolRequest,
+    pub options: Arc<ServerOptions>,
+    /// The `tool_call` span the handler runs in
+    pub span: Span,
+    hub: HubClient,
+    sheets: OnceLock<SheetsClient>,
+    docs: OnceLock<DocsClient>,
+}
+
+impl ToolContext {
+    pub fn new(
+        access_token: String,
+        req: CallToolRequest,
+        options: Arc<ServerOptions>,
+    ) -> Result<Self> {
+        Ok(Self {
+            access_token,
+            req,
+            options,
+            span: Span::current(),
+            hub: hub_client()?,
+            sheets: OnceLock::new(),
+            docs: OnceLock::new(),
+        })
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn sheets(&self) -> &SheetsClient {
+        self.sheets
+            .get_or_init(|| sheets_hub(self.hub.clone(), &self.access_token))
+    }
+
+    pub fn docs(&self) -> &DocsClient {
+        self.docs
+            .get_or_init(|| docs_hub(self.hub.clone(), &self.access_token))
+    }
+
+    /// Serves `key` from the response cache while fresh, and otherwise runs
+    /// `fetch`, caching what it returns. Entries are per access token, so
+    /// callers never see each other's results.
+    pub async fn cached<Fut>(&self, key: &str, fetch: Fut) -> Result<Value>
+    where
+        Fut: Future<Output = Result<Value>>,
+    {
+        let Some(cache) = &self.options.cache else {
+            return fetch.await;
+        };
+        let key = format!("{}#{}", key, token_digest(&self.access_token));
+        if let Some(value) = cache.get(&key) {
+            debug!(key = %key, "Response cache hit");
+            return Ok(value);
+        }
+        let value = fetch.await?;
+        cache.insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// The target spreadsheet (see [`get_spreadsheet_id`]), recorded as the
+    /// span's `resource` since it may come from the server's default.
+    pub fn spreadsheet_id(&self, from_args: Option<&str>) -> Result<String> {
+        let spreadsheet_id = get_spreadsheet_id(
+            from_args,
+            &self.req,
+            self.options.default_spreadsheet_id.as_deref(),
+        )?;
+        self.span.record("resource", spreadsheet_id.as_str());
+        Ok(spreadsheet_id)
+    }
+}
+
+/// A short digest telling access tokens apart without keeping them in keys.
+fn token_digest(access_token: &str) -> String {
+    let digest = Sha256::digest(access_token.as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+type ToolFuture = Pin<Box<dyn Future<Output = Result<CallToolResponse>> + Send>>;
+
+/// Registers a server module's tools from typed handlers. Each handler gets
+/// its arguments parsed into `A`, whose schema becomes the tool's
+/// `input_schema`; a call without an access token fails, and handler errors
+/// become error responses, the same way for every tool.
+pub(crate) struct ToolRegistry<'a, T: Transport> {
+    server: &'a mut ServerBuilder<T>,
+    options: &'a ServerOptions,
+    shared: Arc<ServerOptions>,
+}
+
+impl<'a, T: Transport> ToolRegistry<'a, T> {
+    pub fn new(server: &'a mut ServerBuilder<T>, options: &'a ServerOptions) -> Self {
+        Self {
+            server,
+            options,
+            shared: Arc::new(options.clone()),
+        }
+    }
+
+    /// Registers a tool that only reads Workspace data.
+    pub fn tool<A, F, Fut>(&mut self, name: &str, description: &str, handler: F) -> &mut Self
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(ToolContext, A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResponse>> + Send + 'static,
+    {
+        let tool = new_tool(name, description, input_schema::<A>());
+        register_tool(
+            self.server,
+            self.options,
+            tool,
+            typed_handler(self.shared.clone(), handler),
+        );
+        self
+    }
+
+    /// Registers a listing tool, advertising `default_page_size` as the
+    /// default of its `page_size` argument.
+    pub fn paged_tool<A, F, Fut>(
+        &mut self,
+        name: &str,
+        description: &str,
+        default_page_size: u64,
+        handler: F,
+    ) -> &mut Self
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(ToolContext, A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResponse>> + Send + 'static,
+    {
+        let tool = new_tool(
+            name,
+            description,
+            paged_input_schema::<A>(default_page_size),
+        );
+        register_tool(
+            self.server,
+            self.options,
+            tool,
+            typed_handler(self.shared.clone(), handler),
+        );
+        self
+    }
+
+    /// Registers a tool that modifies Workspace data; see
+    /// [`register_mutating_tool`].
+    pub fn mutating_tool<A, F, Fut>(
+        &mut self,
+        name: &str,
+        description: &str,
+        handler: F,
+    ) -> &mut Self
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(ToolContext, A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResponse>> + Send + 'static,
+    {
+        let tool = new_tool(name, description, input_schema::<A>());
+        register_mutating_tool(
+            self.server,
+            self.options,
+            tool,
+            typed_handler(self.shared.clone(), handler),
+        );
+        self
+    }
+}
+
+/// Adapts a typed handler to the raw `CallToolRequest` signature.
+fn typed_handler<A, F, Fut>(
+    options: Arc<ServerOptions>,
+    handler: F,
+) -> impl Fn(CallToolRequest) -> ToolFuture + Send + Sync + 'static
+where
+    A: DeserializeOwned + Send + 'static,
+    F: Fn(ToolContext, A) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<CallToolResponse>> + Send + 'static,
+{
+    move |req: CallToolRequest| -> ToolFuture {
+        let access_token = match get_access_token(&req) {
+            Ok(access_token) => access_token.to_string(),
+            Err(e) => return Box::pin(async move { Err(e) }),
+        };
+        let args = match parse_args::<A>(&req) {
+            Ok(args) => args,
+            Err(e) => return Box::pin(async move { handle_result(Err(e)) }),
+        };
+        let ctx = match ToolContext::new(access_token, req, options.clone()) {
+            Ok(ctx) => ctx,
+            Err(e) => return Box::pin(async move { handle_result(Err(e)) }),
+        };
+        let response = handler(ctx, args);
+        Box::pin(async move { handle_result(response.await) })
+    }
+}
+
+/// Registers `get_quota_status`. Servers combining several services register
+/// it once, since the counters are shared by all of them.
+pub(crate) fn register_quota_tool<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) {
+    ToolRegistry::new(server, options).tool(
+        "get_quota_status",
+        "Show the current user's Google API usage per service over the last 100 seconds: calls made, the per-user limit, calls queued or delayed to stay under it, and calls Google rate-limited anyway. Check it before starting bulk jobs.",
+        |_ctx, _: NoArgs| async move {
+            json_response(&json!({
+                "window_secs": QUOTA_WINDOW.as_secs(),
+                "services": QuotaTracker::global().status(),
+            }))
+        },
+    );
+}
+
+fn new_tool(name: &str, description: &str, input_schema: Value) -> Tool {
+    Tool {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        input_schema,
+    }
+}
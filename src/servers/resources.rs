@@ -0,0 +1,636 @@
+//! MCP resources for individual spreadsheets (`gsheets://{id}`) and Drive
+//! files (`gdrive://{id}`). Listings come from the user's recently modified
+//! Drive files; reads go through Drive's download and export endpoints, with
+//! native files exported to a text format clients can show directly.
+//! Subscriptions are served by polling each watched file's Drive version.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use async_mcp::{
+    server::ServerBuilder,
+    transport::{JsonRpcMessage, JsonRpcNotification, Transport},
+    types::{Resource, ResourcesListResponse},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::{rest_request, sheets::spreadsheet_query};
+use crate::{
+    a1::Range,
+    cache::EtagCache,
+    circuit::Service,
+    client::{drive_root_url, sheets_root_url},
+    export::{
+        export_mime_type, get_metadata, open_download, read_limited, GOOGLE_APPS_MIME_PREFIX,
+    },
+    streaming::write_csv_row,
+    TokenProvider,
+};
+
+pub const SHEETS_SCHEME: &str = "gsheets";
+pub const DRIVE_SCHEME: &str = "gdrive";
+const SPREADSHEET_MIME_TYPE: &str = "application/vnd.google-apps.spreadsheet";
+const RESOURCE_LIST_FIELDS: &str = "nextPageToken,files(id,name,mimeType,modifiedTime)";
+const RESOURCE_PAGE_SIZE: u32 = 50;
+/// Resource contents travel inside a single JSON-RPC message
+const MAX_RESOURCE_BYTES: usize = 10 * 1024 * 1024;
+/// How often subscribed files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sends `notifications/resources/updated` for a resource URI.
+pub type ResourceNotifier =
+    Arc<dyn Fn(String) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Which files `resources/list` offers, and under which scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceScope {
+    /// Every Drive file, as `gdrive://` (spreadsheets too, unless `sheets` is set)
+    pub drive: bool,
+    /// Spreadsheets, as `gsheets://`
+    pub sheets: bool,
+}
+
+/// Params of `resources/list`. The access token travels in `_meta`, as it
+/// does for tool calls.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ListResourcesRequest {
+    pub cursor: Option<String>,
+    #[serde(rename = "_meta")]
+    pub meta: Option<Value>,
+}
+
+/// Params of `resources/read`, `resources/subscribe` and `resources/unsubscribe`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourceRequest {
+    pub uri: String,
+    #[serde(rename = "_meta", default)]
+    pub meta: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadResourceResponse {
+    pub contents: Vec<ResourceContents>,
+}
+
+/// One resource's content: `text` for textual MIME types, otherwise `blob`
+/// holding base64.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceContents {
+    pub uri: String,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplate {
+    pub uri_template: String,
+    pub name: String,
+    pub description: String,
+    pub mime_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplatesResponse {
+    pub resource_templates: Vec<ResourceTemplate>,
+}
+
+/// A parsed resource URI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceUri {
+    /// A spreadsheet, optionally narrowed to one sheet and an A1 range in it
+    Spreadsheet {
+        id: String,
+        sheet: Option<String>,
+        range: Option<String>,
+    },
+    File(String),
+}
+
+impl ResourceUri {
+    /// Parses `gsheets://{id}[/{sheet}[/{range}]]` or `gdrive://{id}`, with
+    /// percent-encoded segments.
+    pub fn parse(uri: &str) -> Result<Self> {
+        let (scheme, rest) = uri
+            .split_once("://")
+            .with_context(|| format!("invalid resource URI `{}`", uri))?;
+        let segments = rest
+            .trim_end_matches('/')
+            .split('/')
+            .map(|segment| urlencoding::decode(segment).map(|s| s.into_owned()))
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("invalid resource URI `{}`", uri))?;
+        let invalid = |expected: &str| {
+            anyhow::anyhow!(
+                "invalid resource URI `{}`: expected {}://{}",
+                uri,
+                scheme,
+                expected
+            )
+        };
+
+        match scheme {
+            SHEETS_SCHEME => {
+                let expected = "{spreadsheet_id}[/{sheet}[/{range}]]";
+                if segments.len() > 3 || segments.iter().any(String::is_empty) {
+                    return Err(invalid(expected));
+                }
+                let mut segments = segments.into_iter();
+                Ok(Self::Spreadsheet {
+                    id: segments.next().ok_or_else(|| invalid(expected))?,
+                    sheet: segments.next(),
+                    range: segments.next(),
+                })
+            }
+            DRIVE_SCHEME => match segments.as_slice() {
+                [id] if !id.is_empty() => Ok(Self::File(id.clone())),
+                _ => Err(invalid("{file_id}")),
+            },
+            other => anyhow::bail!(
+                "unsupported resource scheme `{}` (expected {} or {})",
+                other,
+                SHEETS_SCHEME,
+                DRIVE_SCHEME
+            ),
+        }
+    }
+
+    /// The Drive file the resource lives in.
+    pub fn file_id(&self) -> &str {
+        match self {
+            Self::Spreadsheet { id, .. } | Self::File(id) => id,
+        }
+    }
+}
+
+/// The `resources` server capability.
+pub(crate) fn resource_capabilities() -> Value {
+    json!({ "subscribe": true, "listChanged": false })
+}
+
+/// Registers the `resources/*` handlers. Listing falls back to `fallback`
+/// when `scope` is empty, or without an access token in the request or a
+/// token provider.
+pub(crate) fn with_resources<T: Transport>(
+    builder: ServerBuilder<T>,
+    scope: ResourceScope,
+    fallback: Vec<Resource>,
+    token_provider: Option<Arc<TokenProvider>>,
+    notifier: ResourceNotifier,
+) -> ServerBuilder<T> {
+    let read_provider = token_provider.clone();
+    let subscriptions = Arc::new(Subscriptions::new(notifier, token_provider.clone()));
+    let unsubscriptions = subscriptions.clone();
+    builder
+        .request_handler("resources/list", move |req: ListResourcesRequest| {
+            let fallback = fallback.clone();
+            let provider = token_provider.clone();
+            Box::pin(async move {
+                let access_token = match scope.drive || scope.sheets {
+                    true => access_token(req.meta.as_ref(), provider).await?,
+                    false => None,
+                };
+                let Some(access_token) = access_token else {
+                    debug!("Listing API resources only");
+                    return Ok(ResourcesListResponse {
+                        resources: fallback,
+                        next_cursor: None,
+                        meta: None,
+                    });
+                };
+                list_resources(&access_token, scope, req.cursor.as_deref()).await
+            })
+        })
+        .request_handler("resources/read", move |req: ResourceRequest| {
+            let provider = read_provider.clone();
+            Box::pin(async move {
+                let access_token = access_token(req.meta.as_ref(), provider)
+                    .await?
+                    .context("Missing or invalid access_token")?;
+                read_resource(&access_token, &req.uri).await
+            })
+        })
+        .request_handler(
+            "resources/templates/list",
+            move |_req: ListResourcesRequest| {
+                let response = ResourceTemplatesResponse {
+                    resource_templates: resource_templates(scope),
+                };
+                Box::pin(async move { Ok(response) })
+            },
+        )
+        .request_handler("resources/subscribe", move |req: ResourceRequest| {
+            let subscriptions = subscriptions.clone();
+            Box::pin(async move {
+                subscriptions.subscribe(req).await?;
+                Ok(json!({}))
+            })
+        })
+        .request_handler("resources/unsubscribe", move |req: ResourceRequest| {
+            unsubscriptions.unsubscribe(&req.uri);
+            Box::pin(async move { Ok(json!({})) })
+        })
+}
+
+/// Sends resource update notifications over a handle to the server's transport.
+pub(crate) fn transport_notifier<T: Transport + Clone>(transport: &T) -> ResourceNotifier {
+    let transport = transport.clone();
+    Arc::new(move |uri: String| {
+        let transport = transport.clone();
+        Box::pin(async move {
+            let notification = JsonRpcMessage::Notification(JsonRpcNotification {
+                method: "notifications/resources/updated".to_string(),
+                params: Some(json!({ "uri": uri })),
+                ..Default::default()
+            });
+            if let Err(e) = transport.send(&notification).await {
+                warn!("Could not send update for {}: {}", uri, e);
+            }
+        })
+    })
+}
+
+/// The URI templates clients can fill in to read resources that are not listed.
+pub(crate) fn resource_templates(scope: ResourceScope) -> Vec<ResourceTemplate> {
+    let mut templates = Vec::new();
+    if scope.sheets {
+        templates.push(ResourceTemplate {
+            uri_template: format!("{}://{{spreadsheet_id}}/{{sheet}}/{{range}}", SHEETS_SCHEME),
+            name: "Spreadsheet range".to_string(),
+            description: "Values of an A1 range, such as A1:D20, in one sheet as CSV".to_string(),
+            mime_type: "text/csv".to_string(),
+        });
+        templates.push(ResourceTemplate {
+            uri_template: format!("{}://{{spreadsheet_id}}/{{sheet}}", SHEETS_SCHEME),
+            name: "Sheet".to_string(),
+            description: "All values of one sheet as CSV".to_string(),
+            mime_type: "text/csv".to_string(),
+        });
+    }
+    if scope.drive {
+        templates.push(ResourceTemplate {
+            uri_template: format!("{}://{{file_id}}", DRIVE_SCHEME),
+            name: "Drive file".to_string(),
+            description: "A file's content; Docs, Sheets and Slides are exported as text"
+                .to_string(),
+            mime_type: "application/octet-stream".to_string(),
+        });
+    }
+    templates
+}
+
+async fn access_token(
+    meta: Option<&Value>,
+    provider: Option<Arc<TokenProvider>>,
+) -> Result<Option<String>> {
+    if let Some(token) = meta
+        .and_then(|meta| meta.get("access_token"))
+        .and_then(|v| v.as_str())
+    {
+        return Ok(Some(token.to_string()));
+    }
+    match provider {
+        Some(provider) => Ok(Some(provider.access_token().await?)),
+        None => Ok(None),
+    }
+}
+
+/// Lists the user's most recently modified files in `scope`, paging with
+/// Drive's page token as the cursor.
+pub(crate) async fn list_resources(
+    access_token: &str,
+    scope: ResourceScope,
+    cursor: Option<&str>,
+) -> Result<ResourcesListResponse> {
+    let query = if scope.drive {
+        "trashed=false and mimeType!='application/vnd.google-apps.folder'".to_string()
+    } else {
+        spreadsheet_query(None)
+    };
+    let mut url = Url::parse(&format!("{}drive/v3/files", drive_root_url()))?;
+    url.query_pairs_mut()
+        .append_pair("q", &query)
+        .append_pair("orderBy", "modifiedTime desc")
+        .append_pair("pageSize", &RESOURCE_PAGE_SIZE.to_string())
+        .append_pair("fields", RESOURCE_LIST_FIELDS)
+        .append_pair("supportsAllDrives", "true")
+        .append_pair("includeItemsFromAllDrives", "true");
+    if let Some(cursor) = cursor {
+        url.query_pairs_mut().append_pair("pageToken", cursor);
+    }
+
+    let body = EtagCache::global()
+        .get_json(Service::Drive, access_token, url.as_str())
+        .await?;
+    let resources = body
+        .get("files")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|file| file_resource(file, scope))
+        .collect();
+
+    Ok(ResourcesListResponse {
+        resources,
+        next_cursor: body
+            .get("nextPageToken")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        meta: None,
+    })
+}
+
+/// Describes a Drive file from a listing as a resource.
+pub(crate) fn file_resource(file: &Value, scope: ResourceScope) -> Option<Resource> {
+    let id = file.get("id")?.as_str()?;
+    let mime_type = file.get("mimeType")?.as_str()?;
+    let is_spreadsheet = mime_type == SPREADSHEET_MIME_TYPE;
+    let scheme = match (is_spreadsheet && scope.sheets, scope.drive) {
+        (true, _) => SHEETS_SCHEME,
+        (false, true) => DRIVE_SCHEME,
+        (false, false) => return None,
+    };
+
+    Some(Resource {
+        uri: Url::parse(&format!("{}://{}", scheme, id)).ok()?,
+        name: file
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or(id)
+            .to_string(),
+        description: file
+            .get("modifiedTime")
+            .and_then(|v| v.as_str())
+            .map(|time| format!("Last modified {}", time)),
+        mime_type: Some(
+            text_format(mime_type)
+                .and_then(export_mime_type)
+                .unwrap_or(mime_type)
+                .to_string(),
+        ),
+    })
+}
+
+/// The text format native files are read as, so clients get readable content
+/// rather than an office document. Other native files use the export default.
+pub(crate) fn text_format(mime_type: &str) -> Option<&'static str> {
+    match mime_type.strip_prefix(GOOGLE_APPS_MIME_PREFIX)? {
+        "document" => Some("md"),
+        "spreadsheet" => Some("csv"),
+        "presentation" => Some("txt"),
+        _ => None,
+    }
+}
+
+/// Reads a resource. Whole spreadsheets are exported as CSV, which Drive
+/// limits to the first sheet; name a sheet to read another one.
+pub(crate) async fn read_resource(access_token: &str, uri: &str) -> Result<ReadResourceResponse> {
+    let resource = ResourceUri::parse(uri)?;
+    if let ResourceUri::Spreadsheet {
+        id,
+        sheet: Some(sheet),
+        range,
+    } = &resource
+    {
+        let csv = read_sheet_csv(access_token, id, sheet, range.as_deref()).await?;
+        return Ok(ReadResourceResponse {
+            contents: vec![resource_contents(uri, "text/csv", csv.into_bytes())],
+        });
+    }
+
+    let file = get_metadata(access_token, resource.file_id()).await?;
+    let (response, format) =
+        open_download(access_token, &file, text_format(&file.mime_type)).await?;
+    let bytes = read_limited(response, MAX_RESOURCE_BYTES)
+        .await?
+        .with_context(|| format!("{} is larger than {} bytes", file.name, MAX_RESOURCE_BYTES))?;
+
+    let mime_type = match format.as_deref() {
+        Some(format) => export_mime_type(format).unwrap_or_default(),
+        None => file.mime_type.as_str(),
+    };
+    Ok(ReadResourceResponse {
+        contents: vec![resource_contents(uri, mime_type, bytes)],
+    })
+}
+
+async fn read_sheet_csv(
+    access_token: &str,
+    spreadsheet_id: &str,
+    sheet: &str,
+    range: Option<&str>,
+) -> Result<String> {
+    let range = match range {
+        Some(range) => Range::on_sheet(sheet, range)?,
+        None => Range::whole_sheet(sheet),
+    }
+    .to_string();
+    let url = Url::parse(&format!(
+        "{}v4/spreadsheets/{}/values/{}",
+        sheets_root_url(),
+        urlencoding::encode(spreadsheet_id),
+        urlencoding::encode(&range)
+    ))?;
+    let body = rest_request(Service::Sheets, access_token, Method::GET, url, None).await?;
+
+    let mut csv = String::new();
+    for row in body
+        .get("values")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        write_csv_row(
+            &mut csv,
+            row.as_array().map(Vec::as_slice).unwrap_or_default(),
+        );
+    }
+    Ok(csv)
+}
+
+/// Wraps downloaded bytes as text when the MIME type is textual and the bytes
+/// are valid UTF-8, and as a base64 blob otherwise.
+pub(crate) fn resource_contents(uri: &str, mime_type: &str, bytes: Vec<u8>) -> ResourceContents {
+    let textual = mime_type.starts_with("text/")
+        || mime_type.ends_with("+json")
+        || mime_type.ends_with("+xml")
+        || matches!(
+            mime_type,
+            "application/json" | "application/xml" | "application/javascript"
+        );
+    let (text, blob) = if textual {
+        match String::from_utf8(bytes) {
+            Ok(text) => (Some(text), None),
+            Err(e) => (None, Some(STANDARD.encode(e.into_bytes()))),
+        }
+    } else {
+        (None, Some(STANDARD.encode(&bytes)))
+    };
+    ResourceContents {
+        uri: uri.to_string(),
+        mime_type: mime_type.to_string(),
+        text,
+        blob,
+    }
+}
+
+/// Subscribed resources, polled for changes while any remain.
+struct Subscriptions {
+    state: Mutex<SubscriptionState>,
+    notifier: ResourceNotifier,
+    token_provider: Option<Arc<TokenProvider>>,
+}
+
+#[derive(Default)]
+struct SubscriptionState {
+    watched: HashMap<String, Watch>,
+    polling: bool,
+}
+
+struct Watch {
+    file_id: String,
+    /// Token from the subscribe request, used when there is no token provider
+    access_token: Option<String>,
+    version: Option<String>,
+}
+
+impl Subscriptions {
+    fn new(notifier: ResourceNotifier, token_provider: Option<Arc<TokenProvider>>) -> Self {
+        Self {
+            state: Mutex::default(),
+            notifier,
+            token_provider,
+        }
+    }
+
+    async fn subscribe(self: &Arc<Self>, req: ResourceRequest) -> Result<()> {
+        let file_id = ResourceUri::parse(&req.uri)?.file_id().to_string();
+        let access_token = req
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get("access_token"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let token = self
+            .token(access_token.as_deref())
+            .await?
+            .context("Missing or invalid access_token")?;
+        // The current version is the baseline; only later changes notify
+        let version = file_version(&token, &file_id).await?;
+
+        let start_polling = {
+            let mut state = self.state.lock().unwrap();
+            state.watched.insert(
+                req.uri,
+                Watch {
+                    file_id,
+                    access_token,
+                    version,
+                },
+            );
+            !std::mem::replace(&mut state.polling, true)
+        };
+        if start_polling {
+            tokio::spawn(self.clone().poll());
+        }
+        Ok(())
+    }
+
+    fn unsubscribe(&self, uri: &str) {
+        self.state.lock().unwrap().watched.remove(uri);
+    }
+
+    async fn token(&self, access_token: Option<&str>) -> Result<Option<String>> {
+        match (&self.token_provider, access_token) {
+            (Some(provider), _) => Ok(Some(provider.access_token().await?)),
+            (None, token) => Ok(token.map(str::to_string)),
+        }
+    }
+
+    async fn poll(self: Arc<Self>) {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let watched = {
+                let mut state = self.state.lock().unwrap();
+                if state.watched.is_empty() {
+                    state.polling = false;
+                    return;
+                }
+                state
+                    .watched
+                    .iter()
+                    .map(|(uri, watch)| {
+                        (
+                            uri.clone(),
+                            watch.file_id.clone(),
+                            watch.access_token.clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            for (uri, file_id, access_token) in watched {
+                let version = match self.token(access_token.as_deref()).await {
+                    Ok(Some(token)) => file_version(&token, &file_id).await,
+                    Ok(None) => continue,
+                    Err(e) => Err(e),
+                };
+                let version = match version {
+                    Ok(version) => version,
+                    Err(e) => {
+                        debug!("Could not check {} for changes: {}", uri, e);
+                        continue;
+                    }
+                };
+
+                let changed = {
+                    let mut state = self.state.lock().unwrap();
+                    match state.watched.get_mut(&uri) {
+                        Some(watch) if watch.version != version => {
+                            watch.version = version;
+                            true
+                        }
+                        _ => false,
+                    }
+                };
+                if changed {
+                    (self.notifier)(uri).await;
+                }
+            }
+        }
+    }
+}
+
+/// Drive's version number of a file, which increases with every change.
+async fn file_version(access_token: &str, file_id: &str) -> Result<Option<String>> {
+    let mut url = Url::parse(&format!(
+        "{}drive/v3/files/{}",
+        drive_root_url(),
+        urlencoding::encode(file_id)
+    ))?;
+    url.query_pairs_mut()
+        .append_pair("fields", "version")
+        .append_pair("supportsAllDrives", "true");
+    let file = rest_request(Service::Drive, access_token, Method::GET, url, None).await?;
+    Ok(file
+        .get("version")
+        .and_then(|v| v.as_str())
+        .map(str::to_string))
+}
@@ -1,389 +1,3437 @@
+use std::{collections::HashMap, fmt};
+
 use anyhow::{Context, Result};
 use async_mcp::{
     server::{Server, ServerBuilder},
     transport::Transport,
     types::{
-        CallToolRequest, CallToolResponse, ListRequest, Resource, ResourcesListResponse,
-        ServerCapabilities, Tool, ToolResponseContent,
+        CallToolResponse, Resource, ResourcesListResponse, ServerCapabilities, ToolResponseContent,
     },
 };
-use serde_json::json;
+use reqwest::Method;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use url::Url;
 
-use crate::client::get_sheets_client;
+use super::{
+    api_name,
+    drive::quote_query,
+    json_response, register_quota_tool,
+    resources::{resource_capabilities, transport_notifier, with_resources, ResourceScope},
+    rest_request, ServerOptions, ToolRegistry,
+};
+use crate::{
+    a1::{column_letters, parse_cell, parse_cell_position, quote_sheet, Range},
+    bigquery::{self, TableRef},
+    cache::EtagCache,
+    circuit::{guarded, Service},
+    client::{drive_root_url, sheets_root_url, with_quota_project, SheetsClient},
+    query::{infer_header, Filter, Query},
+    streaming::{markdown_table, stream_values, write_csv_row, write_tsv_row, RenderOptions},
+};
+
+// Default field masks; full Spreadsheet objects are mostly noise for agents
+const VALUE_RANGE_FIELDS: &str = "range,majorDimension,values";
+const SPREADSHEET_FIELDS: &str =
+    "spreadsheetId,spreadsheetUrl,properties.title,sheets.properties(sheetId,title)";
+const CSV_CHUNK_ROWS: usize = 1000;
+const SHEET_INFO_FIELDS: &str = "sheets(properties(sheetId,title,index,hidden,tabColorStyle,\
+    gridProperties(rowCount,columnCount,frozenRowCount,frozenColumnCount)),basicFilter.range)";
+const SPREADSHEET_MIME_TYPE: &str = "application/vnd.google-apps.spreadsheet";
+const DEFAULT_PIVOT_SHEET: &str = "Pivot Table";
+const SPREADSHEET_LIST_FIELDS: &str = "nextPageToken,files(id,name,modifiedTime,webViewLink)";
+
+// Tool Arguments
+
+/// Whether values are laid out by rows or by columns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MajorDimension {
+    #[default]
+    Rows,
+    Columns,
+}
+
+/// FORMULA returns formulas instead of their results; UNFORMATTED_VALUE returns raw numbers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ValueRenderOption {
+    #[default]
+    FormattedValue,
+    UnformattedValue,
+    Formula,
+}
+
+/// How dates are returned; ignored for FORMATTED_VALUE
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DateTimeRenderOption {
+    #[default]
+    SerialNumber,
+    FormattedString,
+}
+
+/// USER_ENTERED parses values as if typed in the UI, so formulas like =SUM(A1:A5) are evaluated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ValueInputOption {
+    #[default]
+    Raw,
+    UserEntered,
+}
+
+/// INSERT_ROWS inserts new rows; OVERWRITE writes into empty cells after the table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InsertDataOption {
+    #[default]
+    InsertRows,
+    Overwrite,
+}
+
+/// csv streams rows and is preferable for very large ranges
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Csv,
+    Tsv,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MergeType {
+    #[default]
+    MergeAll,
+    MergeColumns,
+    MergeRows,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HorizontalAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VerticalAlignment {
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NumberFormatType {
+    Text,
+    Number,
+    Percent,
+    Currency,
+    Date,
+    Time,
+    DateTime,
+    Scientific,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BorderStyle {
+    Dotted,
+    Dashed,
+    #[default]
+    Solid,
+    SolidMedium,
+    SolidThick,
+    Double,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BorderSide {
+    All,
+    Outer,
+    Inner,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    InnerHorizontal,
+    InnerVertical,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SummarizeFunction {
+    #[default]
+    Sum,
+    Counta,
+    Count,
+    Countunique,
+    Average,
+    Max,
+    Min,
+    Median,
+    Product,
+    Stdev,
+    Stdevp,
+    Var,
+    Varp,
+}
+
+/// DOCUMENT metadata is visible to anyone who can open the spreadsheet;
+/// PROJECT metadata only to the Google Cloud project that created it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MetadataVisibility {
+    #[default]
+    Document,
+    Project,
+}
+
+/// Column letter (e.g. 'C') or 0-based offset into the range
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Column {
+    Letter(String),
+    Offset(i64),
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Letter(letters) => write!(f, "{}", letters),
+            Self::Offset(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+/// The sheet a tool or operation applies to, by name or by ID.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct SheetTarget {
+    /// Sheet name
+    pub sheet: Option<String>,
+    /// Sheet ID, instead of sheet
+    pub sheet_id: Option<i32>,
+}
+
+impl SheetTarget {
+    /// Resolves the sheet ID, looking names up in `sheet_ids`.
+    pub fn id(&self, sheet_ids: &HashMap<String, i32>) -> Result<i32> {
+        if let Some(id) = self.sheet_id {
+            return Ok(id);
+        }
+        let name = self
+            .sheet
+            .as_deref()
+            .context("sheet or sheet_id required")?;
+        sheet_ids
+            .get(name)
+            .copied()
+            .with_context(|| format!("no sheet named `{}`", name))
+    }
+
+    /// Whether resolving the target needs the spreadsheet's sheet names.
+    fn by_name(&self) -> bool {
+        self.sheet_id.is_none() && self.sheet.is_some()
+    }
+}
+
+fn default_read_range() -> String {
+    "A1:ZZ".to_string()
+}
+
+fn default_append_range() -> String {
+    "A1".to_string()
+}
+
+fn default_value_range_fields() -> String {
+    VALUE_RANGE_FIELDS.to_string()
+}
+
+fn default_spreadsheet_fields() -> String {
+    SPREADSHEET_FIELDS.to_string()
+}
+
+fn default_sheet_title() -> String {
+    "Sheet1".to_string()
+}
+
+fn default_border_sides() -> Vec<BorderSide> {
+    vec![BorderSide::Outer]
+}
+
+fn default_border_color() -> String {
+    "#000000".to_string()
+}
+
+fn default_pivot_sheet() -> String {
+    DEFAULT_PIVOT_SHEET.to_string()
+}
+
+fn default_target_cell() -> String {
+    "A1".to_string()
+}
+
+fn default_order_by() -> String {
+    "modifiedTime desc".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Rows of cells that keep their JSON type: strings, numbers, booleans or null.
+fn cell_rows_schema(_: &mut SchemaGenerator) -> Schema {
+    serde_json::from_value(json!({
+        "type": "array",
+        "items": {"type": "array", "items": {"type": ["string", "number", "boolean", "null"]}}
+    }))
+    .unwrap_or(Schema::Bool(true))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadValuesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range to read (e.g. 'A1:B2')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+    #[serde(default)]
+    pub major_dimension: MajorDimension,
+    /// Field mask for the response
+    #[serde(default = "default_value_range_fields")]
+    pub fields: String,
+    #[serde(default)]
+    pub format: ReadFormat,
+    #[serde(default)]
+    pub value_render_option: ValueRenderOption,
+    #[serde(default)]
+    pub date_time_render_option: DateTimeRenderOption,
+    /// Rows of the range to skip, for paging through large sheets
+    pub row_offset: Option<u64>,
+    /// Maximum rows to return; the response meta reports totalRows and nextRowOffset
+    pub row_limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct QuerySheetArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range holding the table, header row first (e.g. 'A1:F')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+    /// SQL-like query without FROM, e.g. "SELECT Name, SUM(Total) AS total WHERE Region = 'EMEA'
+    /// GROUP BY Name ORDER BY total DESC LIMIT 10". Supports SELECT of columns, * and
+    /// COUNT/SUM/AVG/MIN/MAX (with AS); WHERE with =, !=, <, <=, >, >=, LIKE (case-insensitive,
+    /// % and _), IN (...), IS [NOT] NULL, AND, OR and NOT; GROUP BY; ORDER BY ... ASC|DESC;
+    /// LIMIT n OFFSET m. Name columns by header, in double quotes if they contain spaces, or by
+    /// letter (e.g. C); quote text in single quotes
+    pub query: String,
+    /// Whether the range's first row holds column names; inferred when omitted
+    pub header: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadTableArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range holding the table, starting at its header row (e.g. 'A1:F')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+    #[serde(default)]
+    pub value_render_option: ValueRenderOption,
+    #[serde(default)]
+    pub date_time_render_option: DateTimeRenderOption,
+    /// Records to skip, for paging through large tables
+    pub row_offset: Option<u64>,
+    /// Maximum records to return; the response reports totalRows and nextRowOffset
+    pub row_limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AppendRecordArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range holding the table, starting at its header row and first column (e.g. 'A1:F')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+    /// Cell values keyed by column name; names match headers case-insensitively
+    pub record: Map<String, Value>,
+    /// Add header cells for keys that match no column, instead of failing
+    #[serde(default)]
+    pub create_missing_columns: bool,
+    #[serde(default)]
+    pub value_input_option: ValueInputOption,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UpdateRowsWhereArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range holding the table, starting at its header row and first column (e.g. 'A1:F')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+    /// Condition picking the rows to update, written like a query_sheet WHERE clause,
+    /// e.g. "Email = 'x@y.com'" or "Status = 'open' AND Due < '2024-07-01'"
+    #[serde(rename = "where")]
+    pub condition: String,
+    /// New cell values keyed by column name; other columns keep their values
+    pub set: Map<String, Value>,
+    /// Add header cells for keys that match no column, instead of failing
+    #[serde(default)]
+    pub create_missing_columns: bool,
+    /// Fail without writing anything when more rows than this match
+    pub max_rows: Option<u64>,
+    #[serde(default)]
+    pub value_input_option: ValueInputOption,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetCellNotesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range to look for notes in (e.g. 'A1:F100')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetCellNoteArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Cell or range to annotate (e.g. 'B3' or 'B3:B10'); every cell gets the note
+    pub range: String,
+    /// Note text, e.g. "Imported from the CRM export on 2024-05-01"; empty removes the notes
+    pub note: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WriteValuesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range to write to (e.g. 'A1:B2')
+    pub range: String,
+    /// 2D array of values to write
+    #[schemars(schema_with = "cell_rows_schema")]
+    pub values: Vec<Vec<Value>>,
+    #[serde(default)]
+    pub major_dimension: MajorDimension,
+    #[serde(default)]
+    pub value_input_option: ValueInputOption,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AppendValuesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range used to find the table to append to (e.g. 'A1' or 'A:D')
+    #[serde(default = "default_append_range")]
+    pub range: String,
+    /// 2D array of rows to append
+    #[schemars(schema_with = "cell_rows_schema")]
+    pub values: Vec<Vec<Value>>,
+    #[serde(default)]
+    pub major_dimension: MajorDimension,
+    #[serde(default)]
+    pub insert_data_option: InsertDataOption,
+    #[serde(default)]
+    pub value_input_option: ValueInputOption,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NewSheet {
+    #[serde(default = "default_sheet_title")]
+    pub title: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateSpreadsheetArgs {
+    pub title: String,
+    #[serde(default)]
+    pub sheets: Vec<NewSheet>,
+    /// Field mask for the response
+    #[serde(default = "default_spreadsheet_fields")]
+    pub fields: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ClearValuesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    #[serde(default = "default_sheet_title")]
+    pub sheet: String,
+    /// Range to clear (e.g. 'A1:B2')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SpreadsheetArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+}
+
+/// One `batch_update` operation, selected by `type`.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchOperation {
+    /// Add a sheet, optionally with a row and column count
+    AddSheet {
+        /// Name of the new sheet
+        title: String,
+        rows: Option<u32>,
+        columns: Option<u32>,
+    },
+    /// Delete a sheet
+    DeleteSheet {
+        #[serde(flatten)]
+        target: SheetTarget,
+    },
+    /// Rename a sheet
+    RenameSheet {
+        #[serde(flatten)]
+        target: SheetTarget,
+        /// New name
+        title: String,
+    },
+    /// Set the pixel height of rows or width of columns
+    Resize {
+        #[serde(flatten)]
+        target: SheetTarget,
+        #[serde(default)]
+        dimension: MajorDimension,
+        /// First row/column, 0-based
+        start_index: u32,
+        /// End row/column, exclusive
+        end_index: u32,
+        pixel_size: u32,
+    },
+    /// Set the row and/or column count of a sheet
+    SetGridSize {
+        #[serde(flatten)]
+        target: SheetTarget,
+        rows: Option<u32>,
+        columns: Option<u32>,
+    },
+    /// Merge a range of cells
+    MergeCells {
+        #[serde(flatten)]
+        target: SheetTarget,
+        /// Range to merge (e.g. 'A1:C2')
+        range: String,
+        #[serde(default)]
+        merge_type: MergeType,
+    },
+}
+
+impl BatchOperation {
+    /// The existing sheet the operation applies to, if any.
+    fn target(&self) -> Option<&SheetTarget> {
+        match self {
+            Self::AddSheet { .. } => None,
+            Self::DeleteSheet { target }
+            | Self::RenameSheet { target, .. }
+            | Self::Resize { target, .. }
+            | Self::SetGridSize { target, .. }
+            | Self::MergeCells { target, .. } => Some(target),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BatchUpdateArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    pub operations: Vec<BatchOperation>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BatchReadValuesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Ranges in A1 notation including the sheet (e.g. ['Sheet1!A1:B2', 'Totals!C5'])
+    pub ranges: Vec<String>,
+    #[serde(default)]
+    pub major_dimension: MajorDimension,
+    #[serde(default)]
+    pub value_render_option: ValueRenderOption,
+    #[serde(default)]
+    pub date_time_render_option: DateTimeRenderOption,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ValueRangeData {
+    /// Range in A1 notation including the sheet (e.g. 'Sheet1!A1:B2')
+    pub range: String,
+    /// 2D array of values to write
+    #[schemars(schema_with = "cell_rows_schema")]
+    pub values: Vec<Vec<Value>>,
+    #[serde(default)]
+    pub major_dimension: MajorDimension,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BatchWriteValuesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    pub data: Vec<ValueRangeData>,
+    #[serde(default)]
+    pub value_input_option: ValueInputOption,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NumberFormat {
+    #[serde(rename = "type")]
+    pub kind: NumberFormatType,
+    /// e.g. '#,##0.00', '$#,##0', 'yyyy-mm-dd'
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct Borders {
+    #[serde(default = "default_border_sides")]
+    pub sides: Vec<BorderSide>,
+    #[serde(default)]
+    pub style: BorderStyle,
+    #[serde(default = "default_border_color")]
+    pub color: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FormatCellsArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Range to format (e.g. 'A1:D1')
+    pub range: String,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub font_size: Option<u32>,
+    pub font_family: Option<String>,
+    /// Hex color, e.g. '#1a73e8'
+    pub text_color: Option<String>,
+    /// Hex color, e.g. '#f1f3f4'
+    pub background_color: Option<String>,
+    pub number_format: Option<NumberFormat>,
+    pub horizontal_alignment: Option<HorizontalAlignment>,
+    pub vertical_alignment: Option<VerticalAlignment>,
+    /// Wrap text instead of overflowing into the next cell
+    pub wrap: Option<bool>,
+    pub borders: Option<Borders>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PivotGroup {
+    pub column: Column,
+    #[serde(default)]
+    pub sort_order: SortOrder,
+    #[serde(default = "default_true")]
+    pub show_totals: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PivotValue {
+    pub column: Column,
+    #[serde(default)]
+    pub function: SummarizeFunction,
+    /// Header for the aggregated column
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreatePivotTableArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Source data including the header row (e.g. 'A1:E200')
+    pub source_range: String,
+    /// Columns whose values become pivot rows
+    #[serde(default)]
+    pub rows: Vec<PivotGroup>,
+    /// Columns whose values become pivot columns
+    #[serde(default)]
+    pub columns: Vec<PivotGroup>,
+    pub values: Vec<PivotValue>,
+    #[serde(default = "default_pivot_sheet")]
+    pub target_sheet: String,
+    /// Top-left cell of the pivot table
+    #[serde(default = "default_target_cell")]
+    pub target_cell: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SortKey {
+    pub column: Column,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SortRangeArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Range to sort (e.g. 'A1:F200')
+    pub range: String,
+    /// Keep the first row of the range in place
+    #[serde(default)]
+    pub has_header: bool,
+    /// Sort keys, most significant first
+    pub sort_by: Vec<SortKey>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FilterCondition {
+    /// Condition type, e.g. NUMBER_GREATER, TEXT_CONTAINS, DATE_BEFORE, NOT_BLANK
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Values the condition compares against
+    #[serde(default)]
+    pub values: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FilterCriterion {
+    pub column: Column,
+    /// Rows with these values are hidden
+    pub hidden_values: Option<Vec<String>>,
+    pub condition: Option<FilterCondition>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetBasicFilterArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Range to filter, including the header row (e.g. 'A1:F200')
+    pub range: String,
+    #[serde(default)]
+    pub criteria: Vec<FilterCriterion>,
+    #[serde(default)]
+    pub sort_by: Vec<SortKey>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RemoveDuplicateRowsArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Range to deduplicate (e.g. 'A1:F200')
+    pub range: String,
+    /// Leave the first row of the range out of the comparison
+    #[serde(default)]
+    pub has_header: bool,
+    /// Columns whose values must all match for rows to count as duplicates;
+    /// every column of the range when empty
+    #[serde(default)]
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TrimWhitespaceArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Range to clean up (e.g. 'A1:F200')
+    pub range: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SheetArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FreezePanesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Rows to freeze at the top, e.g. 1 for a header row; 0 unfreezes them
+    pub rows: Option<u32>,
+    /// Columns to freeze at the left; 0 unfreezes them
+    pub columns: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetTabColorArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Tab color as #rrggbb; omit to remove the color
+    pub color: Option<String>,
+}
+
+/// Where developer metadata is attached: the whole spreadsheet when no sheet
+/// is given, a sheet, or rows or columns of a sheet.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct MetadataLocation {
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Rows of the sheet, numbered as shown (e.g. '5' or '5:7')
+    pub rows: Option<String>,
+    /// Columns of the sheet by letter (e.g. 'C' or 'C:E')
+    pub columns: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetMetadataArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub location: MetadataLocation,
+    /// Metadata key, e.g. 'order_id'
+    pub key: String,
+    /// Metadata value, e.g. a stable record ID
+    pub value: Option<String>,
+    #[serde(default)]
+    pub visibility: MetadataVisibility,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MetadataLookupArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Only metadata with this ID
+    pub metadata_id: Option<i32>,
+    /// Only metadata with this key
+    pub key: Option<String>,
+    /// Only metadata with this value
+    pub value: Option<String>,
+    #[serde(flatten)]
+    pub location: MetadataLocation,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DuplicateSheetArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    /// Name of the copy; defaults to 'Copy of <sheet>'
+    pub new_name: Option<String>,
+    /// 0-based tab position of the copy; defaults to after the source
+    pub insert_index: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CopySheetToSpreadsheetArgs {
+    /// Source spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    #[serde(flatten)]
+    pub target: SheetTarget,
+    pub destination_spreadsheet_id: String,
+    /// Name of the copy in the destination; defaults to 'Copy of <sheet>'
+    pub new_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CopyRangeBetweenSpreadsheetsArgs {
+    /// Source spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Source sheet name
+    pub sheet: String,
+    /// Range to copy (e.g. 'A1:D20')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+    pub destination_spreadsheet_id: String,
+    /// Sheet to paste into
+    pub destination_sheet: String,
+    /// Top-left cell of the pasted block
+    #[serde(default = "default_destination_cell")]
+    pub destination_cell: String,
+    /// Also copy formulas, formatting, validation and notes; otherwise only
+    /// the computed values are written
+    #[serde(default)]
+    pub with_formatting: bool,
+}
+
+fn default_destination_cell() -> String {
+    "A1".to_string()
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExportValuesArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range to read (e.g. 'A1:B2')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub value_render_option: ValueRenderOption,
+}
+
+/// How `sheets_to_bigquery` makes the sheet's data queryable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BigQueryMode {
+    /// A table reading the sheet live at query time
+    #[default]
+    External,
+    /// A native table holding a snapshot of the values
+    Load,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SheetsToBigQueryArgs {
+    /// Spreadsheet ID; defaults to the request context or the server's configured spreadsheet
+    pub spreadsheet_id: Option<String>,
+    /// Sheet name
+    pub sheet: String,
+    /// Range holding the table, starting at its header row (e.g. 'A1:F')
+    #[serde(default = "default_read_range")]
+    pub range: String,
+    /// Project of the dataset; defaults to the server's BigQuery project
+    pub project_id: Option<String>,
+    /// Existing dataset to create the table in
+    pub dataset_id: String,
+    pub table_id: String,
+    #[serde(default)]
+    pub mode: BigQueryMode,
+    /// Replace an existing external table, or a loaded table's rows, instead
+    /// of failing or appending
+    #[serde(default)]
+    pub replace: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListSpreadsheetsArgs {
+    /// Only spreadsheets whose name contains this text
+    pub name_contains: Option<String>,
+    pub page_size: Option<u64>,
+    /// nextPageToken from a previous call
+    pub page_token: Option<String>,
+    #[serde(default = "default_order_by")]
+    pub order_by: String,
+}
+
+pub fn build<T: Transport + Clone>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport + Clone>(
+    transport: T,
+    options: ServerOptions,
+) -> Result<Server<T>> {
+    let notifier = transport_notifier(&transport);
+    let server = Server::builder(transport).capabilities(ServerCapabilities {
+        tools: Some(json!({
+            "sheets": {
+                "version": "v4",
+                "description": "Google Sheets API operations"
+            }
+        })),
+        resources: Some(resource_capabilities()),
+        ..Default::default()
+    });
+    let mut server = with_resources(
+        server,
+        ResourceScope {
+            drive: false,
+            sheets: true,
+        },
+        list_sheets_resources().resources,
+        options.token_provider.clone(),
+        notifier,
+    );
+
+    register_tools(&mut server, &options)?;
+    register_quota_tool(&mut server, &options);
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+    let page_size = options.page_size;
+
+    tools.tool(
+        "read_values",
+        "Read values from a Google Sheet",
+        |ctx, args: ReadValuesArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+
+            let sheet = &args.sheet;
+            let mut user_range = args.range.clone();
+
+            // Paging narrows the range to the requested window of rows,
+            // bounded by the sheet's grid so the total is known
+            let mut meta = None;
+            if args.row_offset.is_some() || args.row_limit.is_some() {
+                let call = sheets
+                    .spreadsheets()
+                    .get(&spreadsheet_id)
+                    .add_ranges(&quote_sheet(sheet))
+                    .param("fields", "sheets.properties.gridProperties.rowCount");
+                let (_, spreadsheet) =
+                    guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+                let sheet_rows = spreadsheet
+                    .sheets
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find_map(|sheet| sheet.properties?.grid_properties?.row_count)
+                    .unwrap_or(0);
+
+                let window = row_window(
+                    &user_range,
+                    args.row_offset.unwrap_or(0),
+                    args.row_limit,
+                    sheet_rows.max(0) as u64,
+                )?;
+                meta = Some(window.meta);
+                match window.range {
+                    Some(range) => user_range = range,
+                    None => {
+                        return Ok(CallToolResponse {
+                            content: vec![ToolResponseContent::Text {
+                                text: json!({ "values": [] }).to_string(),
+                            }],
+                            is_error: None,
+                            meta,
+                        })
+                    }
+                }
+            }
+            let range = Range::on_sheet(sheet, &user_range)?.to_string();
+
+            let major_dimension = api_name(&args.major_dimension);
+            let value_render_option = api_name(&args.value_render_option);
+            let date_time_render_option = api_name(&args.date_time_render_option);
+            let render = RenderOptions {
+                value_render_option: Some(value_render_option.as_str()),
+                date_time_render_option: Some(date_time_render_option.as_str()),
+            };
+
+            // CSV output is streamed row by row so huge ranges never
+            // materialize as a full ValueRange
+            if args.format == ReadFormat::Csv {
+                let mut csv = String::new();
+                stream_values(
+                    ctx.access_token(),
+                    &spreadsheet_id,
+                    &range,
+                    &major_dimension,
+                    render,
+                    CSV_CHUNK_ROWS,
+                    |rows| {
+                        for row in rows {
+                            write_csv_row(&mut csv, row);
+                        }
+                        Ok(())
+                    },
+                )
+                .await?;
+
+                return Ok(CallToolResponse {
+                    content: vec![ToolResponseContent::Text { text: csv }],
+                    is_error: None,
+                    meta,
+                });
+            }
+
+            let key = format!(
+                "spreadsheet/{}/values/{}?{}&{}&{}&{}",
+                spreadsheet_id,
+                range,
+                major_dimension,
+                value_render_option,
+                date_time_render_option,
+                args.fields
+            );
+            // Large reads go through the gzip-enabled HTTP client rather than the hub
+            let mut url = values_url(&spreadsheet_id, Some(&range))?;
+            url.query_pairs_mut()
+                .append_pair("majorDimension", &major_dimension)
+                .append_pair("valueRenderOption", &value_render_option)
+                .append_pair("dateTimeRenderOption", &date_time_render_option)
+                .append_pair("fields", &args.fields);
+            let values = ctx
+                .cached(
+                    &key,
+                    rest_request(Service::Sheets, ctx.access_token(), Method::GET, url, None),
+                )
+                .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&values)?,
+                }],
+                is_error: None,
+                meta,
+            })
+        },
+    );
+
+    tools.tool(
+        "query_sheet",
+        "Filter, sort and summarise a sheet with a SQL-like query evaluated by the server, returning only the matching rows instead of the whole range",
+        |ctx, args: QuerySheetArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+            let first_column = first_column(&args.range);
+
+            // Raw numbers compare numerically, while dates read as they are shown
+            let render = RenderOptions {
+                value_render_option: Some("UNFORMATTED_VALUE"),
+                date_time_render_option: Some("FORMATTED_STRING"),
+            };
+            let mut query = None;
+            let mut header = false;
+            let mut matched = Vec::new();
+            let (mut scanned, mut matches) = (0, 0);
+            stream_values(
+                ctx.access_token(),
+                &spreadsheet_id,
+                &range,
+                "ROWS",
+                render,
+                CSV_CHUNK_ROWS,
+                |mut rows| {
+                    // Columns are named once the first rows are in
+                    let query = match &mut query {
+                        Some(query) => query,
+                        None => {
+                            let mut headers = Vec::new();
+                            header = args.header.unwrap_or_else(|| infer_header(rows));
+                            if let (true, Some((first, rest))) = (header, rows.split_first()) {
+                                headers = table_headers(first, first_column);
+                                rows = rest;
+                            }
+                            query.insert(Query::parse(&args.query, &headers, first_column)?)
+                        }
+                    };
+                    for row in rows {
+                        scanned += 1;
+                        if query.matches(row) {
+                            if query.keeps(matches) {
+                                matched.push(row.clone());
+                            }
+                            matches += 1;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .await?;
+
+            let Some(query) = query else {
+                return json_response(&json!({
+                    "columns": [],
+                    "rows": [],
+                    "header": false,
+                    "matchedRows": 0,
+                    "scannedRows": 0,
+                }));
+            };
+            let result = query.evaluate(&matched);
+            json_response(&json!({
+                "columns": result.columns,
+                "rows": result.rows,
+                "header": header,
+                "matchedRows": matches,
+                "scannedRows": scanned,
+            }))
+        },
+    );
+
+    tools.tool(
+        "read_table",
+        "Read a sheet as a table of records, each an object keyed by the column names in the header row",
+        |ctx, args: ReadTableArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+            let first_column = first_column(&args.range);
+            let value_render_option = api_name(&args.value_render_option);
+            let date_time_render_option = api_name(&args.date_time_render_option);
+            let render = RenderOptions {
+                value_render_option: Some(value_render_option.as_str()),
+                date_time_render_option: Some(date_time_render_option.as_str()),
+            };
+
+            let row_offset = args.row_offset.unwrap_or(0);
+            let row_limit = args.row_limit.unwrap_or(u64::MAX);
+            let mut headers = None;
+            let mut records = Vec::new();
+            let mut total_rows = 0;
+            stream_values(
+                ctx.access_token(),
+                &spreadsheet_id,
+                &range,
+                "ROWS",
+                render,
+                CSV_CHUNK_ROWS,
+                |mut rows| {
+                    let headers = match &mut headers {
+                        Some(headers) => headers,
+                        None => {
+                            let Some((first, rest)) = rows.split_first() else {
+                                return Ok(());
+                            };
+                            rows = rest;
+                            headers.insert(table_headers(first, first_column))
+                        }
+                    };
+                    for row in rows {
+                        if total_rows >= row_offset && total_rows - row_offset < row_limit {
+                            records.push(table_record(headers, row));
+                        }
+                        total_rows += 1;
+                    }
+                    Ok(())
+                },
+            )
+            .await?;
+
+            let next_row_offset = row_offset + records.len() as u64;
+            let mut body = json!({
+                "headers": headers.unwrap_or_default(),
+                "records": records,
+                "totalRows": total_rows,
+            });
+            if next_row_offset < total_rows {
+                body["nextRowOffset"] = json!(next_row_offset);
+            }
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "append_record",
+        "Append a row to a table from a JSON object keyed by column names, placing each value under its header; optionally adds missing columns",
+        |ctx, args: AppendRecordArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let first_column = first_column(&args.range);
+            let header_range =
+                Range::on_sheet(&args.sheet, &header_range(&args.range)?)?.to_string();
+            let call = sheets
+                .spreadsheets()
+                .values_get(&spreadsheet_id, &header_range)
+                .param("fields", "values");
+            let (_, header) = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+            let header = header
+                .values
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            let headers = table_headers(&header, first_column);
+
+            let (row, added) = record_row(&headers, &args.record, args.create_missing_columns)?;
+            add_table_columns(
+                sheets,
+                &spreadsheet_id,
+                &args.sheet,
+                &args.range,
+                headers.len(),
+                &added,
+            )
+            .await?;
+
+            let mut value_range = google_sheets4::api::ValueRange::default();
+            value_range.values = Some(vec![row]);
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+            let call = sheets
+                .spreadsheets()
+                .values_append(value_range, &spreadsheet_id, &range)
+                .value_input_option(&api_name(&args.value_input_option))
+                .insert_data_option("INSERT_ROWS");
+            let (_, result) = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            let mut body = serde_json::to_value(&result)?;
+            body["addedColumns"] = json!(added);
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "update_rows_where",
+        "Update chosen columns of every table row matching a condition, e.g. set Status where Email = 'x@y.com'. The rows are found and written by the server in one batch, so no need to read the sheet first.",
+        |ctx, args: UpdateRowsWhereArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+            let first_column = first_column(&args.range);
+            let header_row = header_row(&args.range)?;
+
+            // Match on raw numbers and displayed dates, as query_sheet does
+            let render = RenderOptions {
+                value_render_option: Some("UNFORMATTED_VALUE"),
+                date_time_render_option: Some("FORMATTED_STRING"),
+            };
+            let mut table = None;
+            let mut matched = Vec::new();
+            let mut scanned = 0;
+            stream_values(
+                ctx.access_token(),
+                &spreadsheet_id,
+                &range,
+                "ROWS",
+                render,
+                CSV_CHUNK_ROWS,
+                |mut rows| {
+                    // Check the condition and values against the header before scanning
+                    let (filter, ..) = match &mut table {
+                        Some(table) => table,
+                        None => {
+                            let Some((first, rest)) = rows.split_first() else {
+                                return Ok(());
+                            };
+                            rows = rest;
+                            let headers = table_headers(first, first_column);
+                            let filter = Filter::parse(&args.condition, &headers, first_column)?;
+                            let (cells, added) =
+                                record_row(&headers, &args.set, args.create_missing_columns)?;
+                            table.insert((filter, headers.len(), cells, added))
+                        }
+                    };
+                    for row in rows {
+                        scanned += 1;
+                        if filter.matches(row) {
+                            matched.push(header_row + scanned);
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .await?;
+
+            let Some((_, columns, cells, added)) = table else {
+                anyhow::bail!("{} has no header row", range);
+            };
+            if let Some(max_rows) = args.max_rows {
+                if matched.len() as u64 > max_rows {
+                    anyhow::bail!(
+                        "{} rows match, more than max_rows ({}); nothing was written",
+                        matched.len(),
+                        max_rows
+                    );
+                }
+            }
+            if matched.is_empty() {
+                return json_response(&json!({
+                    "matchedRows": 0,
+                    "scannedRows": scanned,
+                    "rows": [],
+                }));
+            }
+            add_table_columns(
+                sheets,
+                &spreadsheet_id,
+                &args.sheet,
+                &args.range,
+                columns,
+                &added,
+            )
+            .await?;
+
+            // Null cells are skipped, so each row is written from the table's first column
+            let data = matched
+                .iter()
+                .map(|row| ValueRangeData {
+                    range: Range::cell(first_column, *row as usize - 1)
+                        .with_sheet(&args.sheet)
+                        .to_string(),
+                    values: vec![cells.clone()],
+                    major_dimension: MajorDimension::Rows,
+                })
+                .collect::<Vec<_>>();
+            let request: google_sheets4::api::BatchUpdateValuesRequest =
+                serde_json::from_value(batch_write_request(&data, args.value_input_option))?;
+            let call = sheets
+                .spreadsheets()
+                .values_batch_update(request, &spreadsheet_id);
+            let (_, result) = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            json_response(&json!({
+                "matchedRows": matched.len(),
+                "scannedRows": scanned,
+                "rows": matched,
+                "updatedCells": result.total_updated_cells,
+                "addedColumns": added,
+            }))
+        },
+    );
+
+    tools.tool(
+        "get_cell_notes",
+        "List the notes attached to cells of a range, each with its A1 cell reference",
+        |ctx, args: GetCellNotesArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+            let call = sheets
+                .spreadsheets()
+                .get(&spreadsheet_id)
+                .add_ranges(&range)
+                .param(
+                    "fields",
+                    "sheets.data(startRow,startColumn,rowData.values.note)",
+                );
+            let (_, spreadsheet) =
+                guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            json_response(&json!({ "notes": cell_notes(&serde_json::to_value(&spreadsheet)?) }))
+        },
+    );
+
+    tools.mutating_tool(
+        "set_cell_note",
+        "Attach a note to cells, e.g. where a value came from, without changing the visible data; an empty note removes it",
+        |ctx, args: SetCellNoteArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let range = grid_range(args.target.id(&sheet_ids)?, &args.range)?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![json!({
+                    "repeatCell": {
+                        "range": range,
+                        "cell": { "note": args.note },
+                        "fields": "note",
+                    }
+                })],
+            )
+            .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "write_values",
+        "Write values to a Google Sheet; numbers and booleans keep their type",
+        |ctx, args: WriteValuesArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+
+            let mut value_range = google_sheets4::api::ValueRange::default();
+            value_range.major_dimension = Some(api_name(&args.major_dimension));
+            value_range.values = Some(to_cell_rows(&args.values));
+
+            let call = sheets
+                .spreadsheets()
+                .values_update(value_range, &spreadsheet_id, &range)
+                .value_input_option(&api_name(&args.value_input_option));
+            let result = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result.1)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "append_values",
+        "Append rows after the last row of a table in a Google Sheet, without computing where the table ends",
+        |ctx, args: AppendValuesArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+
+            let mut value_range = google_sheets4::api::ValueRange::default();
+            value_range.major_dimension = Some(api_name(&args.major_dimension));
+            value_range.values = Some(to_cell_rows(&args.values));
+
+            let call = sheets
+                .spreadsheets()
+                .values_append(value_range, &spreadsheet_id, &range)
+                .value_input_option(&api_name(&args.value_input_option))
+                .insert_data_option(&api_name(&args.insert_data_option));
+            let result = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result.1)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "create_spreadsheet",
+        "Create a new Google Sheet",
+        |ctx, args: CreateSpreadsheetArgs| async move {
+            let sheets = ctx.sheets();
+
+            let mut spreadsheet = google_sheets4::api::Spreadsheet::default();
+            spreadsheet.properties = Some(google_sheets4::api::SpreadsheetProperties {
+                title: Some(args.title.clone()),
+                ..Default::default()
+            });
+
+            // Add sheets if specified
+            if !args.sheets.is_empty() {
+                let sheets = args
+                    .sheets
+                    .iter()
+                    .map(|config| google_sheets4::api::Sheet {
+                        properties: Some(google_sheets4::api::SheetProperties {
+                            title: Some(config.title.clone()),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    })
+                    .collect();
+                spreadsheet.sheets = Some(sheets);
+            }
+
+            let call = sheets
+                .spreadsheets()
+                .create(spreadsheet)
+                .param("fields", args.fields.as_str());
+            let result = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result.1)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "clear_values",
+        "Clear values from a range in a Google Sheet",
+        |ctx, args: ClearValuesArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+
+            let clear_request = google_sheets4::api::ClearValuesRequest::default();
+            let call = sheets
+                .spreadsheets()
+                .values_clear(clear_request, &spreadsheet_id, &range);
+            let result = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result.1)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.tool(
+        "get_sheet_info",
+        "Get information about all sheets in a spreadsheet: title, sheetId (needed by tools that take sheet_id), tab index, maximum range (e.g. 'A1:Z1000'), frozen rows and columns, tab color, whether the sheet is hidden and whether it has a basic filter. This is useful for discovering what sheets exist and their dimensions.",
+        |ctx, args: SpreadsheetArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+
+            // Metadata is re-read often, so revalidate through the ETag cache
+            let url = format!(
+                "{}v4/spreadsheets/{}?fields={}",
+                sheets_root_url(),
+                urlencoding::encode(&spreadsheet_id),
+                urlencoding::encode(SHEET_INFO_FIELDS)
+            );
+            let key = format!("spreadsheet/{}/info", spreadsheet_id);
+            let body = ctx
+                .cached(
+                    &key,
+                    EtagCache::global().get_json(Service::Sheets, ctx.access_token(), &url),
+                )
+                .await?;
+            let sheet_info = body["sheets"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(sheet_info)
+                .collect::<Vec<_>>();
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&sheet_info)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "batch_update",
+        "Restructure a spreadsheet with a list of operations applied atomically: add_sheet, delete_sheet, rename_sheet, resize (row/column pixel size), set_grid_size (row/column count) and merge_cells. Sheets are referenced by name (`sheet`) or ID (`sheet_id`).",
+        |ctx, args: BatchUpdateArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            if args.operations.is_empty() {
+                anyhow::bail!("operations required");
+            }
+
+            // Only look up sheet IDs when an operation refers to a sheet by name
+            let sheet_ids = if args
+                .operations
+                .iter()
+                .any(|op| op.target().is_some_and(SheetTarget::by_name))
+            {
+                sheet_ids(ctx.sheets(), &spreadsheet_id).await?
+            } else {
+                HashMap::new()
+            };
+
+            let requests = args
+                .operations
+                .iter()
+                .enumerate()
+                .map(|(i, op)| {
+                    batch_operation(op, &sheet_ids).with_context(|| format!("operation {}", i))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let request: google_sheets4::api::BatchUpdateSpreadsheetRequest =
+                serde_json::from_value(json!({ "requests": requests }))?;
+
+            let call = sheets.spreadsheets().batch_update(request, &spreadsheet_id);
+            let result = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result.1)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.tool(
+        "batch_read_values",
+        "Read several ranges of a Google Sheet in one request",
+        |ctx, args: BatchReadValuesArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            if args.ranges.is_empty() {
+                anyhow::bail!("ranges required");
+            }
+
+            let mut url = values_url(&spreadsheet_id, None)?;
+            url.query_pairs_mut()
+                .append_pair("majorDimension", &api_name(&args.major_dimension))
+                .append_pair("valueRenderOption", &api_name(&args.value_render_option))
+                .append_pair(
+                    "dateTimeRenderOption",
+                    &api_name(&args.date_time_render_option),
+                );
+            for range in &args.ranges {
+                url.query_pairs_mut().append_pair("ranges", range);
+            }
+            let result =
+                rest_request(Service::Sheets, ctx.access_token(), Method::GET, url, None).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "batch_write_values",
+        "Write values to several ranges of a Google Sheet in one request",
+        |ctx, args: BatchWriteValuesArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            if args.data.is_empty() {
+                anyhow::bail!("data required");
+            }
+            let request: google_sheets4::api::BatchUpdateValuesRequest =
+                serde_json::from_value(batch_write_request(&args.data, args.value_input_option))?;
+
+            let call = sheets
+                .spreadsheets()
+                .values_batch_update(request, &spreadsheet_id);
+            let result = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result.1)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "format_cells",
+        "Format a range of cells: text style, colors, number/date/currency formats, alignment and borders. Only the given properties change.",
+        |ctx, args: FormatCellsArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                format_requests(&args, &sheet_ids)?,
+            )
+            .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "create_pivot_table",
+        "Summarise a table with a pivot table. Columns are given as letters of the source sheet (e.g. 'B') or 0-based offsets into the source range; the pivot is written to target_sheet, which is created if it does not exist.",
+        |ctx, args: CreatePivotTableArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = sheet_ids(ctx.sheets(), &spreadsheet_id).await?;
+            let (requests, target) = pivot_table_requests(&args, &sheet_ids)?;
+            apply_requests(ctx.sheets(), &spreadsheet_id, requests).await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&target)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "sort_range",
+        "Sort the rows of a range by one or more columns, each ascending or descending",
+        |ctx, args: SortRangeArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![sort_range_request(&args, &sheet_ids)?],
+            )
+            .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "set_basic_filter",
+        "Set the sheet's basic filter on a range, optionally hiding rows by value or condition and sorting",
+        |ctx, args: SetBasicFilterArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![basic_filter_request(&args, &sheet_ids)?],
+            )
+            .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "remove_duplicate_rows",
+        "Delete rows that repeat an earlier row in the range, comparing all or only the given columns; the first occurrence is kept and the reply reports duplicatesRemovedCount",
+        |ctx, args: RemoveDuplicateRowsArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![delete_duplicates_request(&args, &sheet_ids)?],
+            )
+            .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "trim_whitespace",
+        "Strip leading and trailing whitespace from every cell of a range and collapse runs of spaces inside text to one; the reply reports cellsChangedCount",
+        |ctx, args: TrimWhitespaceArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let range = grid_range(args.target.id(&sheet_ids)?, &args.range)?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![json!({"trimWhitespace": {"range": range}})],
+            )
+            .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "clear_basic_filter",
+        "Remove a sheet's basic filter, showing all rows again",
+        |ctx, args: SheetArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![json!({"clearBasicFilter": {"sheetId": args.target.id(&sheet_ids)?}})],
+            )
+            .await?;
 
-fn get_access_token(req: &CallToolRequest) -> Result<&str> {
-    req.meta
-        .as_ref()
-        .and_then(|v| v.get("access_token"))
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Missing or invalid access_token"))
-}
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
 
-pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
-    let mut server = Server::builder(transport)
-        .capabilities(ServerCapabilities {
-            tools: Some(json!({
-                "sheets": {
-                    "version": "v4",
-                    "description": "Google Sheets API operations"
-                }
-            })),
-            ..Default::default()
-        })
-        .request_handler("resources/list", |_req: ListRequest| {
-            Box::pin(async move { Ok(list_sheets_resources()) })
-        });
+    tools.mutating_tool(
+        "freeze_panes",
+        "Freeze the top rows and/or left columns of a sheet so headers stay visible while scrolling; 0 unfreezes",
+        |ctx, args: FreezePanesArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![freeze_panes_request(&args, &sheet_ids)?],
+            )
+            .await?;
 
-    register_tools(&mut server)?;
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
 
-    Ok(server.build())
-}
+    tools.mutating_tool(
+        "hide_sheet",
+        "Hide a sheet's tab, e.g. one holding helper data; at least one sheet must stay visible",
+        |ctx, args: SheetArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![hidden_request(args.target.id(&sheet_ids)?, true)],
+            )
+            .await?;
 
-fn register_tools<T: Transport>(server: &mut ServerBuilder<T>) -> Result<()> {
-    // Tool Definitions
-    let read_values_tool = Tool {
-        name: "read_values".to_string(),
-        description: Some("Read values from a Google Sheet".to_string()),
-        input_schema: json!({
-            "type": "object",
-            "properties": {
-                "sheet": {"type": "string", "description": "Sheet name"},
-                "range": {"type": "string", "description": "Range to read (e.g. 'A1:B2')", "default": "A1:ZZ"},
-                "major_dimension": {"type": "string", "enum": ["ROWS", "COLUMNS"], "default": "ROWS"}
-            },
-            "required": ["sheet"]
-        }),
-    };
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
 
-    let write_values_tool = Tool {
-        name: "write_values".to_string(),
-        description: Some("Write values to a Google Sheet".to_string()),
-        input_schema: json!({
-            "type": "object",
-            "properties": {
-                "sheet": {"type": "string", "description": "Sheet name"},
-                "range": {"type": "string", "description": "Range to write to (e.g. 'A1:B2')"},
-                "values": {
-                    "description": "2D array of values to write",
-                    "type": "array",
-                    "items": {
-                        "type": "array",
-                        "items": {
-                        "type": ["string", "number", "boolean", "null"],
-                        "description": "A single cell value"
+    tools.mutating_tool(
+        "show_sheet",
+        "Show a hidden sheet's tab again",
+        |ctx, args: SheetArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![hidden_request(args.target.id(&sheet_ids)?, false)],
+            )
+            .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "set_tab_color",
+        "Color a sheet's tab, or remove its color",
+        |ctx, args: SetTabColorArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![tab_color_request(&args, &sheet_ids)?],
+            )
+            .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&result)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "set_metadata",
+        "Tag the spreadsheet, a sheet, rows or columns with a developer metadata key and value. Tags move with their rows and columns when the sheet is sorted or rows are inserted, so they make stable record IDs where A1 references would shift.",
+        |ctx, args: SetMetadataArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids =
+                named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.location.target).await?;
+            let mut metadata = json!({
+                "metadataKey": args.key,
+                "location": metadata_location(&args.location, &sheet_ids)?
+                    .unwrap_or_else(|| json!({ "spreadsheet": true })),
+                "visibility": args.visibility,
+            });
+            if let Some(value) = &args.value {
+                metadata["metadataValue"] = json!(value);
+            }
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![json!({ "createDeveloperMetadata": { "developerMetadata": metadata } })],
+            )
+            .await?;
+
+            let created = serde_json::to_value(&result)?;
+            json_response(&metadata_summary(
+                &created["replies"][0]["createDeveloperMetadata"]["developerMetadata"],
+            ))
+        },
+    );
+
+    tools.tool(
+        "search_metadata",
+        "Find developer metadata by ID, key, value and/or location, returning each entry with the rows, columns or sheet it is attached to",
+        |ctx, args: MetadataLookupArgs| async move {
+            let sheets = ctx.sheets();
+
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(sheets, &spreadsheet_id, &args.location.target).await?;
+            let lookup = metadata_lookup(&args, &sheet_ids)?;
+            let request: google_sheets4::api::SearchDeveloperMetadataRequest =
+                serde_json::from_value(json!({
+                    "dataFilters": [{ "developerMetadataLookup": lookup }]
+                }))?;
+            let call = sheets
+                .spreadsheets()
+                .developer_metadata_search(request, &spreadsheet_id);
+            let (_, result) = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            let metadata = result
+                .matched_developer_metadata
+                .unwrap_or_default()
+                .iter()
+                .map(|matched| {
+                    Ok(metadata_summary(&serde_json::to_value(
+                        &matched.developer_metadata,
+                    )?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            json_response(&json!({ "metadata": metadata }))
+        },
+    );
+
+    tools.mutating_tool(
+        "delete_metadata",
+        "Delete developer metadata matching an ID, key, value and/or location; the rows and columns it was attached to are kept",
+        |ctx, args: MetadataLookupArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids =
+                named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.location.target).await?;
+            let filter = json!({ "developerMetadataLookup": metadata_lookup(&args, &sheet_ids)? });
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![json!({ "deleteDeveloperMetadata": { "dataFilter": filter } })],
+            )
+            .await?;
+
+            let reply = serde_json::to_value(&result)?;
+            let deleted = reply["replies"][0]["deleteDeveloperMetadata"]
+                ["deletedDeveloperMetadata"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(metadata_summary)
+                .collect::<Vec<_>>();
+            json_response(&json!({ "deleted": deleted }))
+        },
+    );
+
+    tools.mutating_tool(
+        "duplicate_sheet",
+        "Duplicate a sheet, e.g. a template, within the same spreadsheet",
+        |ctx, args: DuplicateSheetArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let result = apply_requests(
+                ctx.sheets(),
+                &spreadsheet_id,
+                vec![duplicate_sheet_request(&args, &sheet_ids)?],
+            )
+            .await?;
+            let properties = result
+                .replies
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .and_then(|reply| reply.duplicate_sheet)
+                .and_then(|reply| reply.properties);
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&properties)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "copy_sheet_to_spreadsheet",
+        "Copy a sheet, with its data and formatting, into another spreadsheet",
+        |ctx, args: CopySheetToSpreadsheetArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let destination = &args.destination_spreadsheet_id;
+            let sheet_ids = named_sheet_ids(ctx.sheets(), &spreadsheet_id, &args.target).await?;
+            let sheet_id = args.target.id(&sheet_ids)?;
+
+            let sheets = ctx.sheets();
+            let request = google_sheets4::api::CopySheetToAnotherSpreadsheetRequest {
+                destination_spreadsheet_id: Some(destination.clone()),
+            };
+            let call = sheets
+                .spreadsheets()
+                .sheets_copy_to(request, &spreadsheet_id, sheet_id);
+            let (_, mut properties) =
+                guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+
+            // The copy is always named "Copy of ..."; rename it in the destination
+            if let Some(new_name) = &args.new_name {
+                let copied_id = properties.sheet_id.context("copied sheet has no ID")?;
+                apply_requests(
+                    ctx.sheets(),
+                    destination,
+                    vec![json!({
+                        "updateSheetProperties": {
+                            "properties": {"sheetId": copied_id, "title": new_name},
+                            "fields": "title",
+                        }
+                    })],
+                )
+                .await?;
+                properties.title = Some(new_name.clone());
+            }
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&properties)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    tools.mutating_tool(
+        "copy_range_between_spreadsheets",
+        "Copy a range from one spreadsheet into another without passing the data through the conversation. Writes values only unless with_formatting is set.",
+        |ctx, args: CopyRangeBetweenSpreadsheetsArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let destination = &args.destination_spreadsheet_id;
+            let sheets = ctx.sheets();
+            let source = Range::on_sheet(&args.sheet, &args.range)?;
+            let range = source.to_string();
+
+            // The values give the block's real size; open-ended ranges stop
+            // at the last row and column holding data. Numbers keep their full
+            // precision, while dates come back as shown so that writing them
+            // USER_ENTERED makes dates again rather than day serial numbers
+            let call = sheets
+                .spreadsheets()
+                .values_get(&spreadsheet_id, &range)
+                .major_dimension("ROWS")
+                .value_render_option("UNFORMATTED_VALUE")
+                .date_time_render_option("FORMATTED_STRING")
+                .param("fields", "values");
+            let (_, values) = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+            let values = values.values.unwrap_or_default();
+            let rows = values.len();
+            let columns = values.iter().map(Vec::len).max().unwrap_or(0);
+            if rows == 0 || columns == 0 {
+                return json_response(&json!({ "source": range, "rows": 0, "columns": 0 }));
+            }
+            let (column, row) = parse_cell_position(&args.destination_cell)?;
+            let block = Range::block(column, row, rows, columns);
+            let pasted = block
+                .clone()
+                .with_sheet(&args.destination_sheet)
+                .to_string();
+
+            if args.with_formatting {
+                let source_ids = sheet_ids(sheets, &spreadsheet_id).await?;
+                let source_id = *source_ids
+                    .get(&args.sheet)
+                    .with_context(|| format!("no sheet named `{}`", args.sheet))?;
+                let destination_ids = sheet_ids(sheets, destination).await?;
+                let destination_id = *destination_ids
+                    .get(&args.destination_sheet)
+                    .with_context(|| format!("no sheet named `{}`", args.destination_sheet))?;
+
+                // copyPaste only works within a spreadsheet, so the source
+                // sheet is copied over, pasted from and then deleted
+                let request = google_sheets4::api::CopySheetToAnotherSpreadsheetRequest {
+                    destination_spreadsheet_id: Some(destination.clone()),
+                };
+                let call =
+                    sheets
+                        .spreadsheets()
+                        .sheets_copy_to(request, &spreadsheet_id, source_id);
+                let (_, copied) =
+                    guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+                let copied_id = copied.sheet_id.context("copied sheet has no ID")?;
+                let copied_block =
+                    Range::block(source.first_column(), source.first_row(), rows, columns);
+                let delete = json!({ "deleteSheet": { "sheetId": copied_id } });
+                let requests = vec![
+                    json!({
+                        "copyPaste": {
+                            "source": copied_block.to_grid_range(copied_id),
+                            "destination": block.to_grid_range(destination_id),
+                            "pasteType": "PASTE_NORMAL",
+                        }
+                    }),
+                    delete.clone(),
+                ];
+                if let Err(e) = apply_requests(sheets, destination, requests).await {
+                    // Don't leave the intermediate copy behind
+                    apply_requests(sheets, destination, vec![delete]).await.ok();
+                    return Err(e);
+                }
+            } else {
+                let mut value_range = google_sheets4::api::ValueRange::default();
+                value_range.values = Some(to_cell_rows(&values));
+                let call = sheets
+                    .spreadsheets()
+                    .values_update(value_range, destination, &pasted)
+                    .value_input_option("USER_ENTERED");
+                guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+            }
+
+            json_response(&json!({
+                "source": range,
+                "destination": pasted,
+                "rows": rows,
+                "columns": columns,
+                "withFormatting": args.with_formatting,
+            }))
+        },
+    );
+
+    tools.tool(
+        "export_values",
+        "Read a range and return it as a Markdown table, CSV or TSV text instead of JSON. Markdown treats the first row as the header.",
+        |ctx, args: ExportValuesArgs| async move {
+            let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+            let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+            let value_render_option = api_name(&args.value_render_option);
+            let render = RenderOptions {
+                value_render_option: Some(value_render_option.as_str()),
+                date_time_render_option: None,
+            };
+
+            // Delimited formats are written as rows arrive; a Markdown
+            // table needs every row to know its width
+            let mut text = String::new();
+            let mut table = Vec::new();
+            stream_values(
+                ctx.access_token(),
+                &spreadsheet_id,
+                &range,
+                "ROWS",
+                render,
+                CSV_CHUNK_ROWS,
+                |rows| {
+                    for row in rows {
+                        match args.format {
+                            ExportFormat::Csv => write_csv_row(&mut text, row),
+                            ExportFormat::Tsv => write_tsv_row(&mut text, row),
+                            ExportFormat::Markdown => table.push(row.clone()),
                         }
                     }
+                    Ok(())
                 },
-                "major_dimension": {"type": "string", "enum": ["ROWS", "COLUMNS"], "default": "ROWS"}
-            },
-            "required": ["values", "range", "sheet"]
-        }),
-    };
+            )
+            .await?;
+            if args.format == ExportFormat::Markdown {
+                text = markdown_table(&table);
+            }
 
-    let create_spreadsheet_tool = Tool {
-        name: "create_spreadsheet".to_string(),
-        description: Some("Create a new Google Sheet".to_string()),
-        input_schema: json!({
-            "type": "object",
-            "properties": {
-                "title": {"type": "string"},
-                "sheets": {
-                    "type": "array",
-                    "items": {
-                        "type": "object",
-                        "properties": {
-                            "title": {"type": "string"}
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text { text }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    // Only served with a project to run BigQuery jobs in, so Sheets setups
+    // without BigQuery access keep passing the startup scope check
+    if options.bigquery_project.is_some() {
+        tools.mutating_tool(
+            "sheets_to_bigquery",
+            "Make a sheet range queryable with SQL in BigQuery, as an external table reading the sheet live or as a native table loaded with its values. Column names and types come from the header row and the values.",
+            |ctx, args: SheetsToBigQueryArgs| async move {
+                let spreadsheet_id = ctx.spreadsheet_id(args.spreadsheet_id.as_deref())?;
+                let job_project = ctx
+                    .options
+                    .bigquery_project
+                    .clone()
+                    .context("no BigQuery project configured")?;
+                let table = TableRef {
+                    project_id: args
+                        .project_id
+                        .clone()
+                        .unwrap_or_else(|| job_project.clone()),
+                    dataset_id: args.dataset_id.clone(),
+                    table_id: args.table_id.clone(),
+                };
+                let range = Range::on_sheet(&args.sheet, &args.range)?.to_string();
+
+                // Dates come back as text, so they become STRING columns
+                // rather than day serial numbers
+                let render = RenderOptions {
+                    value_render_option: Some("UNFORMATTED_VALUE"),
+                    date_time_render_option: Some("FORMATTED_STRING"),
+                };
+                let mut rows = Vec::new();
+                stream_values(
+                    ctx.access_token(),
+                    &spreadsheet_id,
+                    &range,
+                    "ROWS",
+                    render,
+                    CSV_CHUNK_ROWS,
+                    |chunk| {
+                        rows.extend_from_slice(chunk);
+                        Ok(())
+                    },
+                )
+                .await?;
+                let Some((header, rows)) = rows.split_first() else {
+                    anyhow::bail!("{} is empty; it must start with a header row", range);
+                };
+                let schema = bigquery::infer_schema(&bigquery::field_names(header), rows);
+
+                let table_name = format!(
+                    "{}.{}.{}",
+                    table.project_id, table.dataset_id, table.table_id
+                );
+                let mut body = json!({
+                    "table": table_name,
+                    "mode": api_name(&args.mode),
+                    "schema": schema,
+                });
+                match args.mode {
+                    BigQueryMode::External => {
+                        if args.replace {
+                            bigquery::delete_table(ctx.access_token(), &table).await?;
                         }
+                        let definition =
+                            bigquery::external_table(&table, &spreadsheet_id, &range, &schema);
+                        bigquery::create_table(ctx.access_token(), &table, &definition).await?;
+                    }
+                    BigQueryMode::Load => {
+                        let job = bigquery::load_rows(
+                            ctx.access_token(),
+                            &job_project,
+                            &table,
+                            &schema,
+                            bigquery::ndjson_rows(&schema, rows),
+                            args.replace,
+                        )
+                        .await?;
+                        body["jobId"] = job["jobReference"]["jobId"].clone();
+                        body["outputRows"] = job["statistics"]["load"]["outputRows"].clone();
                     }
                 }
+                body["query"] = json!(format!("SELECT * FROM `{}` LIMIT 100", table_name));
+                json_response(&body)
             },
-            "required": ["title"]
-        }),
-    };
+        );
+    }
 
-    let clear_values_tool = Tool {
-        name: "clear_values".to_string(),
-        description: Some("Clear values from a range in a Google Sheet".to_string()),
-        input_schema: json!({
-            "type": "object",
-            "properties": {
-                "sheet": {"type": "string", "description": "Sheet name", "default": "Sheet1"},
-                "range": {"type": "string", "description": "Range to clear (e.g. 'A1:B2')", "default": "A1:ZZ"}
-            },
-            "required": ["sheet", "range"]
-        }),
-    };
+    tools.paged_tool(
+        "list_spreadsheets",
+        "Find spreadsheets in Google Drive by name, most recently modified first",
+        page_size.unwrap_or(25),
+        move |ctx, args: ListSpreadsheetsArgs| async move {
+            let mut url = Url::parse(&format!("{}drive/v3/files", drive_root_url()))?;
+            {
+                let mut query = url.query_pairs_mut();
+                query
+                    .append_pair("q", &spreadsheet_query(args.name_contains.as_deref()))
+                    .append_pair(
+                        "pageSize",
+                        &args.page_size.or(page_size).unwrap_or(25).to_string(),
+                    )
+                    .append_pair("orderBy", &args.order_by)
+                    .append_pair("fields", SPREADSHEET_LIST_FIELDS)
+                    .append_pair("supportsAllDrives", "true")
+                    .append_pair("includeItemsFromAllDrives", "true");
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
 
-    let get_sheet_info_tool = Tool {
-        name: "get_sheet_info".to_string(),
-        description: Some("Get information about all sheets in a spreadsheet, including their titles and maximum ranges (e.g. 'A1:Z1000'). This is useful for discovering what sheets exist and their dimensions.".to_string()),
-        input_schema: json!({
-            "type": "object",
-            "properties": {},
-            "required": []
-        }),
+            let body = EtagCache::global()
+                .get_json(Service::Drive, ctx.access_token(), url.as_str())
+                .await?;
+
+            Ok(CallToolResponse {
+                content: vec![ToolResponseContent::Text {
+                    text: serde_json::to_string(&body)?,
+                }],
+                is_error: None,
+                meta: None,
+            })
+        },
+    );
+
+    Ok(())
+}
+
+/// Drive query matching spreadsheets not in the trash, optionally by name.
+pub(crate) fn spreadsheet_query(name_contains: Option<&str>) -> String {
+    let mut query = format!("mimeType='{}' and trashed=false", SPREADSHEET_MIME_TYPE);
+    if let Some(name) = name_contains.filter(|name| !name.is_empty()) {
+        query.push_str(&format!(" and name contains {}", quote_query(name)));
+    }
+    query
+}
+
+/// Maps each sheet's title to its ID.
+async fn sheet_ids(sheets: &SheetsClient, spreadsheet_id: &str) -> Result<HashMap<String, i32>> {
+    let call = sheets
+        .spreadsheets()
+        .get(spreadsheet_id)
+        .param("fields", "sheets.properties(sheetId,title)");
+    let (_, spreadsheet) = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+    Ok(spreadsheet
+        .sheets
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|sheet| {
+            let props = sheet.properties?;
+            Some((props.title?, props.sheet_id?))
+        })
+        .collect())
+}
+
+/// Like `sheet_ids`, but skips the lookup unless `target` names its sheet.
+async fn named_sheet_ids(
+    sheets: &SheetsClient,
+    spreadsheet_id: &str,
+    target: &SheetTarget,
+) -> Result<HashMap<String, i32>> {
+    if target.by_name() {
+        sheet_ids(sheets, spreadsheet_id).await
+    } else {
+        Ok(HashMap::new())
+    }
+}
+
+/// Applies `requests` to the spreadsheet in one atomic batchUpdate.
+async fn apply_requests(
+    sheets: &SheetsClient,
+    spreadsheet_id: &str,
+    requests: Vec<Value>,
+) -> Result<google_sheets4::api::BatchUpdateSpreadsheetResponse> {
+    let request: google_sheets4::api::BatchUpdateSpreadsheetRequest =
+        serde_json::from_value(json!({ "requests": requests }))?;
+    let call = sheets.spreadsheets().batch_update(request, spreadsheet_id);
+    let (_, response) = guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+    Ok(response)
+}
+
+/// Writes `added` header cells after a table's `existing` columns.
+async fn add_table_columns(
+    sheets: &SheetsClient,
+    spreadsheet_id: &str,
+    sheet: &str,
+    range: &str,
+    existing: usize,
+    added: &[String],
+) -> Result<()> {
+    if added.is_empty() {
+        return Ok(());
+    }
+    let table = Range::parse_cells(range)?;
+    let range = Range::cell(table.first_column() + existing, table.first_row())
+        .with_sheet(sheet)
+        .to_string();
+    let mut value_range = google_sheets4::api::ValueRange::default();
+    value_range.values = Some(vec![added.iter().map(|name| json!(name)).collect()]);
+    let call = sheets
+        .spreadsheets()
+        .values_update(value_range, spreadsheet_id, &range)
+        .value_input_option("RAW");
+    guarded(Service::Sheets, with_quota_project!(call).doit()).await?;
+    Ok(())
+}
+
+/// URL of the values of one range of a spreadsheet, or of its `values:batchGet`
+/// endpoint when `range` is `None`.
+pub(crate) fn values_url(spreadsheet_id: &str, range: Option<&str>) -> Result<Url> {
+    let endpoint = match range {
+        Some(range) => format!("values/{}", urlencoding::encode(range)),
+        None => "values:batchGet".to_string(),
     };
+    Ok(Url::parse(&format!(
+        "{}v4/spreadsheets/{}/{}",
+        sheets_root_url(),
+        urlencoding::encode(spreadsheet_id),
+        endpoint
+    ))?)
+}
 
-    // Tool Implementations
-    server.register_tool(read_values_tool, move |req: CallToolRequest| {
-        Box::pin(async move {
-            let access_token = get_access_token(&req)?;
-            let args = req.arguments.clone().unwrap_or_default();
-            let context = req.meta.clone().unwrap_or_default();
+/// The rows of a range selected by `row_offset`/`row_limit`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct RowWindow {
+    /// The narrowed A1 range, or `None` when the offset is past the last row
+    pub range: Option<String>,
+    /// `rowOffset`, `rowCount`, `totalRows` and, while rows remain, `nextRowOffset`
+    pub meta: Value,
+}
 
-            let result = async {
-                let sheets = get_sheets_client(access_token);
+/// Narrows `range` to `row_limit` rows starting `row_offset` rows into it.
+/// Open-ended ranges such as `A1:ZZ` end at the sheet's last row.
+pub(crate) fn row_window(
+    range: &str,
+    row_offset: u64,
+    row_limit: Option<u64>,
+    sheet_rows: u64,
+) -> Result<RowWindow> {
+    let range = Range::parse_cells(range)?;
+    let start_row = range.first_row() as u64 + 1;
+    let end_row = range
+        .end_row
+        .map_or(sheet_rows, |row| row as u64 + 1)
+        .min(sheet_rows);
+    let total_rows = (end_row + 1).saturating_sub(start_row);
 
-                let spreadsheet_id = context
-                    .get("spreadsheet_id")
-                    .and_then(|v| v.as_str())
-                    .context("spreadsheet_id required in context")?;
+    let first = start_row + row_offset;
+    let last = match row_limit {
+        Some(limit) => end_row.min((first + limit).saturating_sub(1)),
+        None => end_row,
+    };
+    if row_limit == Some(0) || first > last {
+        return Ok(RowWindow {
+            range: None,
+            meta: json!({ "rowOffset": row_offset, "rowCount": 0, "totalRows": total_rows }),
+        });
+    }
 
-                let sheet = args["sheet"].as_str().context("sheet name required")?;
-                let user_range = args["range"].as_str().unwrap_or("A1:ZZ");
-                let range = format!("{}!{}", sheet, user_range);
+    let row_count = last - first + 1;
+    let mut meta = json!({
+        "rowOffset": row_offset,
+        "rowCount": row_count,
+        "totalRows": total_rows,
+    });
+    if row_offset + row_count < total_rows {
+        meta["nextRowOffset"] = json!(row_offset + row_count);
+    }
+    let window = Range {
+        start_row: Some(first as usize - 1),
+        end_row: Some(last as usize - 1),
+        ..range
+    };
+    Ok(RowWindow {
+        range: Some(window.cells()),
+        meta,
+    })
+}
 
-                let major_dimension = args
-                    .get("major_dimension")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("ROWS");
+/// The sheet index of the first column of an A1 range such as `C2:F`, or 0
+/// when the range names no column.
+pub(crate) fn first_column(range: &str) -> usize {
+    Range::parse_cells(range).map_or(0, |range| range.first_column())
+}
 
-                let result = sheets
-                    .spreadsheets()
-                    .values_get(spreadsheet_id, &range)
-                    .major_dimension(major_dimension)
-                    .doit()
-                    .await?;
-
-                Ok(CallToolResponse {
-                    content: vec![ToolResponseContent::Text {
-                        text: serde_json::to_string(&result.1)?,
-                    }],
-                    is_error: None,
-                    meta: None,
-                })
+/// The 1-based sheet row of a table's header, the first row of its range.
+pub(crate) fn header_row(range: &str) -> Result<u64> {
+    Ok(Range::parse_cells(range)?.first_row() as u64 + 1)
+}
+
+/// The first row of a table's range, where its header sits: `A1:ZZ` gives `A1:ZZ1`.
+pub(crate) fn header_range(range: &str) -> Result<String> {
+    let range = Range::parse_cells(range)?;
+    let row = range.first_row();
+    let header = Range {
+        start_row: Some(row),
+        end_row: Some(row),
+        ..range
+    };
+    Ok(header.cells())
+}
+
+/// Column names from a table's header row. Blank cells are named by their
+/// column letter and repeated names get a `_2`, `_3`, ... suffix, so every
+/// column has its own key.
+pub(crate) fn table_headers(row: &[Value], first_column: usize) -> Vec<String> {
+    let mut headers: Vec<String> = Vec::with_capacity(row.len());
+    for (i, cell) in row.iter().enumerate() {
+        let name = match cell {
+            Value::String(name) => name.trim().to_string(),
+            Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        let name = if name.is_empty() {
+            column_letters(first_column + i)
+        } else {
+            name
+        };
+        let mut unique = name.clone();
+        for n in 2.. {
+            if !headers.iter().any(|header| same_column(header, &unique)) {
+                break;
             }
-            .await;
+            unique = format!("{}_{}", name, n);
+        }
+        headers.push(unique);
+    }
+    headers
+}
+
+fn same_column(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
 
-            handle_result(result)
+/// A row as an object keyed by `headers`, with empty cells as null. Cells
+/// right of the last header are left out.
+pub(crate) fn table_record(headers: &[String], row: &[Value]) -> Value {
+    let record = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            let cell = match row.get(i) {
+                None => Value::Null,
+                Some(Value::String(text)) if text.is_empty() => Value::Null,
+                Some(cell) => cell.clone(),
+            };
+            (header.clone(), cell)
         })
-    });
+        .collect::<Map<_, _>>();
+    Value::Object(record)
+}
+
+/// Lays `record` out as a row under `headers`, matching keys to column names
+/// case-insensitively. Keys that match no column fail unless `add_columns`,
+/// which returns them as new headers to write after the existing ones.
+pub(crate) fn record_row(
+    headers: &[String],
+    record: &Map<String, Value>,
+    add_columns: bool,
+) -> Result<(Vec<Value>, Vec<String>)> {
+    if record.is_empty() {
+        anyhow::bail!("no values to write");
+    }
+    let mut row = vec![Value::Null; headers.len()];
+    let mut added = Vec::<String>::new();
+    let mut unknown = Vec::new();
+    for (key, value) in record {
+        let column = headers
+            .iter()
+            .chain(&added)
+            .position(|header| same_column(header, key));
+        let column = match column {
+            Some(column) => column,
+            None if add_columns => {
+                added.push(key.trim().to_string());
+                row.push(Value::Null);
+                row.len() - 1
+            }
+            None => {
+                unknown.push(format!("`{}`", key));
+                continue;
+            }
+        };
+        row[column] = value.clone();
+    }
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "no column for {} (columns: {}); set create_missing_columns to add them",
+            unknown.join(", "),
+            headers.join(", ")
+        );
+    }
 
-    server.register_tool(write_values_tool, move |req: CallToolRequest| {
-        Box::pin(async move {
-            let access_token = get_access_token(&req)?;
-            let args = req.arguments.clone().unwrap_or_default();
-            let context = req.meta.clone().unwrap_or_default();
-
-            let result = async {
-                let sheets = get_sheets_client(access_token);
-
-                let spreadsheet_id = context
-                    .get("spreadsheet_id")
-                    .and_then(|v| v.as_str())
-                    .context("spreadsheet_id required in context")?;
-
-                let sheet = args["sheet"].as_str().context("sheet name required")?;
-                let user_range = args["range"].as_str().context("range is required")?;
-                let range = format!("{}!{}", sheet, user_range);
-
-                let values = args
-                    .get("values")
-                    .and_then(|v| v.as_array())
-                    .context("values required")?;
-                let major_dimension = args
-                    .get("major_dimension")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("ROWS");
+    // Null cells are left untouched, so trailing ones need not be sent
+    while row.last().is_some_and(Value::is_null) {
+        row.pop();
+    }
+    let row = to_cell_rows(&[row]).into_iter().next().unwrap_or_default();
+    Ok((row, added))
+}
 
-                let mut value_range = google_sheets4::api::ValueRange::default();
-                value_range.major_dimension = Some(major_dimension.to_string());
-                value_range.values = Some(
-                    values
-                        .iter()
-                        .map(|row| {
-                            row.as_array()
-                                .unwrap_or(&vec![])
-                                .iter()
-                                .map(|v| v.as_str().unwrap_or_default().to_string().into())
-                                .collect::<Vec<serde_json::Value>>()
-                        })
-                        .collect(),
-                );
+/// Converts an A1 range like `A1:C3`, or an open one like `A:C`, into a
+/// GridRange on `sheet_id`.
+fn grid_range(sheet_id: i32, range: &str) -> Result<Value> {
+    Ok(Range::parse_cells(range)?.to_grid_range(sheet_id))
+}
 
-                let result = sheets
-                    .spreadsheets()
-                    .values_update(value_range, spreadsheet_id, &range)
-                    .value_input_option("RAW")
-                    .doit()
-                    .await?;
-
-                Ok(CallToolResponse {
-                    content: vec![ToolResponseContent::Text {
-                        text: serde_json::to_string(&result.1)?,
-                    }],
-                    is_error: None,
-                    meta: None,
-                })
+/// Translates one high-level `batch_update` operation into a Sheets API request.
+pub(crate) fn batch_operation(
+    op: &BatchOperation,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Value> {
+    let request = match op {
+        BatchOperation::AddSheet {
+            title,
+            rows,
+            columns,
+        } => {
+            let mut properties = json!({ "title": title });
+            for (count, field) in [(rows, "rowCount"), (columns, "columnCount")] {
+                if let Some(count) = count {
+                    properties["gridProperties"][field] = json!(count);
+                }
+            }
+            json!({ "addSheet": { "properties": properties } })
+        }
+        BatchOperation::DeleteSheet { target } => json!({
+            "deleteSheet": { "sheetId": target.id(sheet_ids)? }
+        }),
+        BatchOperation::RenameSheet { target, title } => json!({
+            "updateSheetProperties": {
+                "properties": {
+                    "sheetId": target.id(sheet_ids)?,
+                    "title": title,
+                },
+                "fields": "title",
             }
-            .await;
+        }),
+        BatchOperation::Resize {
+            target,
+            dimension,
+            start_index,
+            end_index,
+            pixel_size,
+        } => {
+            if end_index <= start_index {
+                anyhow::bail!("end_index must be greater than start_index");
+            }
+            json!({
+                "updateDimensionProperties": {
+                    "range": {
+                        "sheetId": target.id(sheet_ids)?,
+                        "dimension": dimension,
+                        "startIndex": start_index,
+                        "endIndex": end_index,
+                    },
+                    "properties": { "pixelSize": pixel_size },
+                    "fields": "pixelSize",
+                }
+            })
+        }
+        BatchOperation::SetGridSize {
+            target,
+            rows,
+            columns,
+        } => {
+            let mut grid = serde_json::Map::new();
+            let mut fields = Vec::new();
+            for (count, field) in [(rows, "rowCount"), (columns, "columnCount")] {
+                if let Some(count) = count {
+                    grid.insert(field.to_string(), json!(count));
+                    fields.push(format!("gridProperties.{}", field));
+                }
+            }
+            if fields.is_empty() {
+                anyhow::bail!("rows or columns required");
+            }
+            json!({
+                "updateSheetProperties": {
+                    "properties": {
+                        "sheetId": target.id(sheet_ids)?,
+                        "gridProperties": grid,
+                    },
+                    "fields": fields.join(","),
+                }
+            })
+        }
+        BatchOperation::MergeCells {
+            target,
+            range,
+            merge_type,
+        } => json!({
+            "mergeCells": {
+                "range": grid_range(target.id(sheet_ids)?, range)?,
+                "mergeType": merge_type,
+            }
+        }),
+    };
+    Ok(request)
+}
 
-            handle_result(result)
-        })
-    });
+/// Summarises one entry of a spreadsheet's `sheets` for `get_sheet_info`;
+/// sheets without a grid, such as chart sheets, are skipped.
+pub(crate) fn sheet_info(sheet: &Value) -> Option<Value> {
+    let props = &sheet["properties"];
+    let grid = props.get("gridProperties")?;
+    let count = |field: &str| grid[field].as_u64().unwrap_or(0);
 
-    server.register_tool(create_spreadsheet_tool, move |req: CallToolRequest| {
-        Box::pin(async move {
-            let access_token = get_access_token(&req)?;
-            let args = req.arguments.clone().unwrap_or_default();
-            let result = async {
-                let sheets = get_sheets_client(access_token);
+    // Grid sizes are at least one cell, but default to a fresh sheet's
+    let columns = grid["columnCount"].as_u64().unwrap_or(26).max(1);
+    let rows = grid["rowCount"].as_u64().unwrap_or(1000).max(1);
+    let max_range = format!("A1:{}{}", column_letters(columns as usize - 1), rows);
 
-                let title = args["title"].as_str().context("title required")?;
+    let mut info = json!({
+        "sheetId": props["sheetId"].as_i64().unwrap_or(0),
+        "title": props["title"].as_str()?,
+        "index": props["index"].as_u64().unwrap_or(0),
+        "maxRange": max_range,
+        "rowCount": rows,
+        "columnCount": columns,
+        "frozenRowCount": count("frozenRowCount"),
+        "frozenColumnCount": count("frozenColumnCount"),
+        "hidden": props["hidden"].as_bool().unwrap_or(false),
+        "hasBasicFilter": sheet.get("basicFilter").is_some(),
+    });
+    if let Some(color) = format_color_style(&props["tabColorStyle"]) {
+        info["tabColor"] = json!(color);
+    }
+    Some(info)
+}
 
-                let mut spreadsheet = google_sheets4::api::Spreadsheet::default();
-                spreadsheet.properties = Some(google_sheets4::api::SpreadsheetProperties {
-                    title: Some(title.to_string()),
-                    ..Default::default()
-                });
+/// Renders a Sheets `ColorStyle` as `#rrggbb`, or as its theme color name
+/// such as `ACCENT1`.
+fn format_color_style(style: &Value) -> Option<String> {
+    if let Some(theme) = style["themeColor"].as_str() {
+        return Some(theme.to_string());
+    }
+    let color = style.get("rgbColor")?;
+    // Channels that are 0 are left out of the response
+    let channel = |name: &str| (color[name].as_f64().unwrap_or(0.0) * 255.0).round() as u8;
+    Some(format!(
+        "#{:02x}{:02x}{:02x}",
+        channel("red"),
+        channel("green"),
+        channel("blue")
+    ))
+}
 
-                // Add sheets if specified
-                if let Some(sheet_configs) = args["sheets"].as_array() {
-                    let sheets = sheet_configs
-                        .iter()
-                        .map(|config| {
-                            let title = config["title"].as_str().unwrap_or("Sheet1").to_string();
-                            google_sheets4::api::Sheet {
-                                properties: Some(google_sheets4::api::SheetProperties {
-                                    title: Some(title),
-                                    ..Default::default()
-                                }),
-                                ..Default::default()
-                            }
-                        })
-                        .collect();
-                    spreadsheet.sheets = Some(sheets);
+/// Collects the notes in a spreadsheet fetched with grid data, as
+/// `{cell, note}` objects with A1 cell references.
+pub(crate) fn cell_notes(spreadsheet: &Value) -> Vec<Value> {
+    let mut notes = Vec::new();
+    let grids = spreadsheet["sheets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|sheet| sheet["data"].as_array().into_iter().flatten());
+    for grid in grids {
+        let start_row = grid["startRow"].as_u64().unwrap_or(0);
+        let start_column = grid["startColumn"].as_u64().unwrap_or(0);
+        let rows = grid["rowData"].as_array().into_iter().flatten();
+        for (i, row) in rows.enumerate() {
+            let cells = row["values"].as_array().into_iter().flatten();
+            for (j, cell) in cells.enumerate() {
+                if let Some(note) = cell["note"].as_str() {
+                    notes.push(json!({
+                        "cell": Range::cell(start_column as usize + j, start_row as usize + i)
+                            .cells(),
+                        "note": note,
+                    }));
                 }
+            }
+        }
+    }
+    notes
+}
 
-                let result = sheets.spreadsheets().create(spreadsheet).doit().await?;
+/// Parses a `#rrggbb` hex color into a Sheets `Color`.
+fn parse_color(color: &str) -> Result<Value> {
+    let hex = color.trim().trim_start_matches('#');
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .map(|c| f64::from(c) / 255.0)
+    };
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(red), Some(green), Some(blue)) => {
+            Ok(json!({"red": red, "green": green, "blue": blue}))
+        }
+        _ => anyhow::bail!("invalid color `{}`: expected #rrggbb", color),
+    }
+}
 
-                Ok(CallToolResponse {
-                    content: vec![ToolResponseContent::Text {
-                        text: serde_json::to_string(&result.1)?,
-                    }],
-                    is_error: None,
-                    meta: None,
-                })
+/// Compiles the `format_cells` arguments into a `repeatCell` request for the
+/// cell format and an `updateBorders` request for borders. The `repeatCell`
+/// field mask lists only the properties given, so other formatting is kept.
+pub(crate) fn format_requests(
+    args: &FormatCellsArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Vec<Value>> {
+    let range = grid_range(args.target.id(sheet_ids)?, &args.range)?;
+
+    let mut format = json!({});
+    let mut fields = Vec::new();
+    for (value, field) in [
+        (args.bold, "bold"),
+        (args.italic, "italic"),
+        (args.underline, "underline"),
+        (args.strikethrough, "strikethrough"),
+    ] {
+        if let Some(value) = value {
+            format["textFormat"][field] = json!(value);
+            fields.push(format!("textFormat.{}", field));
+        }
+    }
+    if let Some(size) = args.font_size {
+        format["textFormat"]["fontSize"] = json!(size);
+        fields.push("textFormat.fontSize".to_string());
+    }
+    if let Some(family) = &args.font_family {
+        format["textFormat"]["fontFamily"] = json!(family);
+        fields.push("textFormat.fontFamily".to_string());
+    }
+    if let Some(color) = &args.text_color {
+        format["textFormat"]["foregroundColor"] = parse_color(color)?;
+        fields.push("textFormat.foregroundColor".to_string());
+    }
+    if let Some(color) = &args.background_color {
+        format["backgroundColor"] = parse_color(color)?;
+        fields.push("backgroundColor".to_string());
+    }
+    if let Some(number_format) = &args.number_format {
+        format["numberFormat"] = json!({ "type": number_format.kind });
+        if let Some(pattern) = &number_format.pattern {
+            format["numberFormat"]["pattern"] = json!(pattern);
+        }
+        fields.push("numberFormat".to_string());
+    }
+    if let Some(alignment) = args.horizontal_alignment {
+        format["horizontalAlignment"] = json!(alignment);
+        fields.push("horizontalAlignment".to_string());
+    }
+    if let Some(alignment) = args.vertical_alignment {
+        format["verticalAlignment"] = json!(alignment);
+        fields.push("verticalAlignment".to_string());
+    }
+    if let Some(wrap) = args.wrap {
+        format["wrapStrategy"] = json!(if wrap { "WRAP" } else { "OVERFLOW_CELL" });
+        fields.push("wrapStrategy".to_string());
+    }
+
+    let mut requests = Vec::new();
+    if !fields.is_empty() {
+        requests.push(json!({
+            "repeatCell": {
+                "range": range,
+                "cell": { "userEnteredFormat": format },
+                "fields": fields
+                    .iter()
+                    .map(|field| format!("userEnteredFormat.{}", field))
+                    .collect::<Vec<_>>()
+                    .join(","),
             }
-            .await;
+        }));
+    }
 
-            handle_result(result)
-        })
-    });
+    if let Some(borders) = &args.borders {
+        let border = json!({
+            "style": borders.style,
+            "color": parse_color(&borders.color)?,
+        });
 
-    server.register_tool(clear_values_tool, move |req: CallToolRequest| {
-        Box::pin(async move {
-            let access_token = get_access_token(&req)?;
-            let args = req.arguments.clone().unwrap_or_default();
-            let context = req.meta.clone().unwrap_or_default();
-
-            let result = async {
-                let sheets = get_sheets_client(access_token);
-
-                let spreadsheet_id = context
-                    .get("spreadsheet_id")
-                    .and_then(|v| v.as_str())
-                    .context("spreadsheet_id required in context")?;
-
-                let sheet = args
-                    .get("sheet")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Sheet1");
-                let user_range = args
-                    .get("range")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("A1:ZZ");
-                let range = format!("{}!{}", sheet, user_range);
-
-                let clear_request = google_sheets4::api::ClearValuesRequest::default();
-                let result = sheets
-                    .spreadsheets()
-                    .values_clear(clear_request, spreadsheet_id, &range)
-                    .doit()
-                    .await?;
-
-                Ok(CallToolResponse {
-                    content: vec![ToolResponseContent::Text {
-                        text: serde_json::to_string(&result.1)?,
-                    }],
-                    is_error: None,
-                    meta: None,
-                })
+        let mut request = json!({ "range": range });
+        for side in &borders.sides {
+            let fields: &[&str] = match side {
+                BorderSide::All => &[
+                    "top",
+                    "bottom",
+                    "left",
+                    "right",
+                    "innerHorizontal",
+                    "innerVertical",
+                ],
+                BorderSide::Outer => &["top", "bottom", "left", "right"],
+                BorderSide::Inner => &["innerHorizontal", "innerVertical"],
+                BorderSide::Top => &["top"],
+                BorderSide::Bottom => &["bottom"],
+                BorderSide::Left => &["left"],
+                BorderSide::Right => &["right"],
+                BorderSide::InnerHorizontal => &["innerHorizontal"],
+                BorderSide::InnerVertical => &["innerVertical"],
+            };
+            for field in fields {
+                request[*field] = border.clone();
             }
-            .await;
+        }
+        requests.push(json!({ "updateBorders": request }));
+    }
 
-            handle_result(result)
+    if requests.is_empty() {
+        anyhow::bail!("no formatting given");
+    }
+    Ok(requests)
+}
+
+/// Resolves a column, given as a sheet column letter or as an offset into
+/// `range`, to its offset from the range's first column.
+fn range_column_offset(column: &Column, range: &Value) -> Result<i64> {
+    let start = range["startColumnIndex"].as_i64().unwrap_or(0);
+    // Ranges of whole rows run to the sheet's last column
+    let width = range["endColumnIndex"]
+        .as_i64()
+        .map_or(i64::MAX, |end| end - start);
+    let offset = match column {
+        Column::Offset(offset) => *offset,
+        Column::Letter(letters) => match parse_cell(letters)? {
+            (Some(index), None) => index as i64 - start,
+            _ => anyhow::bail!("invalid column `{}`", letters),
+        },
+    };
+    if !(0..width).contains(&offset) {
+        anyhow::bail!("column {} is outside the range", column);
+    }
+    Ok(offset)
+}
+
+fn pivot_groups(groups: &[PivotGroup], source: &Value) -> Result<Vec<Value>> {
+    groups
+        .iter()
+        .map(|group| {
+            Ok(json!({
+                "sourceColumnOffset": range_column_offset(&group.column, source)?,
+                "sortOrder": group.sort_order,
+                "showTotals": group.show_totals,
+            }))
         })
-    });
+        .collect()
+}
+
+/// Builds the `duplicateSheet` request for `duplicate_sheet`.
+pub(crate) fn duplicate_sheet_request(
+    args: &DuplicateSheetArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Value> {
+    let mut request = json!({ "sourceSheetId": args.target.id(sheet_ids)? });
+    if let Some(name) = &args.new_name {
+        request["newSheetName"] = json!(name);
+    }
+    if let Some(index) = args.insert_index {
+        request["insertSheetIndex"] = json!(index);
+    }
+    Ok(json!({ "duplicateSheet": request }))
+}
+
+/// Converts `sort_by` keys into Sheets `SortSpec`s on columns of `range`.
+fn sort_specs(keys: &[SortKey], range: &Value) -> Result<Vec<Value>> {
+    let start = range["startColumnIndex"].as_i64().unwrap_or(0);
+    keys.iter()
+        .map(|key| {
+            Ok(json!({
+                "dimensionIndex": start + range_column_offset(&key.column, range)?,
+                "sortOrder": key.order,
+            }))
+        })
+        .collect()
+}
 
-    server.register_tool(get_sheet_info_tool, move |req: CallToolRequest| {
-        Box::pin(async move {
-            let access_token = get_access_token(&req)?;
-            let context = req.meta.clone().unwrap_or_default();
+/// Builds the `sortRange` request for `sort_range`.
+pub(crate) fn sort_range_request(
+    args: &SortRangeArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Value> {
+    let mut range = grid_range(args.target.id(sheet_ids)?, &args.range)?;
+    let sort_specs = sort_specs(&args.sort_by, &range)?;
+    if sort_specs.is_empty() {
+        anyhow::bail!("sort_by required");
+    }
+    if args.has_header {
+        range["startRowIndex"] = json!(range["startRowIndex"].as_i64().unwrap_or(0) + 1);
+    }
+    Ok(json!({ "sortRange": { "range": range, "sortSpecs": sort_specs } }))
+}
 
-            let result = async {
-                let sheets = get_sheets_client(access_token);
+/// Builds the `updateSheetProperties` request for `freeze_panes`, setting
+/// only the counts given.
+pub(crate) fn freeze_panes_request(
+    args: &FreezePanesArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Value> {
+    let mut grid = json!({});
+    let mut fields = Vec::new();
+    for (count, field) in [
+        (args.rows, "frozenRowCount"),
+        (args.columns, "frozenColumnCount"),
+    ] {
+        if let Some(count) = count {
+            grid[field] = json!(count);
+            fields.push(format!("gridProperties.{}", field));
+        }
+    }
+    if fields.is_empty() {
+        anyhow::bail!("rows or columns required");
+    }
+    Ok(json!({
+        "updateSheetProperties": {
+            "properties": {
+                "sheetId": args.target.id(sheet_ids)?,
+                "gridProperties": grid,
+            },
+            "fields": fields.join(","),
+        }
+    }))
+}
 
-                let spreadsheet_id = context
-                    .get("spreadsheet_id")
-                    .and_then(|v| v.as_str())
-                    .context("spreadsheet_id required in context")?;
+/// Builds the `updateSheetProperties` request for `hide_sheet` and `show_sheet`.
+pub(crate) fn hidden_request(sheet_id: i32, hidden: bool) -> Value {
+    json!({
+        "updateSheetProperties": {
+            "properties": { "sheetId": sheet_id, "hidden": hidden },
+            "fields": "hidden",
+        }
+    })
+}
 
-                let result = sheets.spreadsheets().get(spreadsheet_id).doit().await?;
+/// Builds the `updateSheetProperties` request for `set_tab_color`; without a
+/// color the field is cleared.
+pub(crate) fn tab_color_request(
+    args: &SetTabColorArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Value> {
+    let mut properties = json!({ "sheetId": args.target.id(sheet_ids)? });
+    if let Some(color) = &args.color {
+        properties["tabColorStyle"] = json!({ "rgbColor": parse_color(color)? });
+    }
+    Ok(json!({
+        "updateSheetProperties": {
+            "properties": properties,
+            "fields": "tabColorStyle",
+        }
+    }))
+}
 
-                let spreadsheet = result.1;
+/// Converts a `MetadataLocation` into a Sheets `DeveloperMetadataLocation`,
+/// or `None` when it names no sheet and so means the whole spreadsheet.
+pub(crate) fn metadata_location(
+    location: &MetadataLocation,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Option<Value>> {
+    let spans = [(&location.rows, "ROWS"), (&location.columns, "COLUMNS")];
+    let mut spans = spans
+        .into_iter()
+        .filter_map(|(span, dimension)| Some((span.as_deref()?, dimension)));
+    let span = spans.next();
+    if spans.next().is_some() {
+        anyhow::bail!("give rows or columns, not both");
+    }
+    if location.target.sheet.is_none() && location.target.sheet_id.is_none() {
+        if span.is_some() {
+            anyhow::bail!("sheet or sheet_id required for rows and columns");
+        }
+        return Ok(None);
+    }
 
-                // Extract sheet information
-                let sheet_info = spreadsheet
-                    .sheets
-                    .unwrap_or_default()
-                    .into_iter()
-                    .filter_map(|sheet| {
-                        let props = sheet.properties?;
-                        let title = props.title?;
-                        let grid_props = props.grid_properties?;
-
-                        // Calculate the maximum range based on grid properties
-                        let max_col = grid_props.column_count.unwrap_or(26) as u8;
-                        let max_row = grid_props.row_count.unwrap_or(1000);
-                        let max_range = format!("A1:{}{}", (b'A' + max_col - 1) as char, max_row);
-
-                        Some(serde_json::json!({
-                            "title": title,
-                            "maxRange": max_range,
-                        }))
+    let sheet_id = location.target.id(sheet_ids)?;
+    let Some((span, dimension)) = span else {
+        return Ok(Some(json!({ "sheetId": sheet_id })));
+    };
+    let (start, end) = dimension_span(span, dimension)?;
+    Ok(Some(json!({
+        "dimensionRange": {
+            "sheetId": sheet_id,
+            "dimension": dimension,
+            "startIndex": start,
+            "endIndex": end,
+        }
+    })))
+}
+
+/// Parses rows like `5:7` or columns like `C:E` into a 0-based, end-exclusive
+/// index span.
+fn dimension_span(span: &str, dimension: &str) -> Result<(u64, u64)> {
+    let (first, last) = span.split_once(':').unwrap_or((span, span));
+    let index = |end: &str| -> Option<u64> {
+        match (dimension, parse_cell(end).ok()?) {
+            ("ROWS", (None, Some(row))) => Some(row as u64),
+            ("COLUMNS", (Some(column), None)) => Some(column as u64),
+            _ => None,
+        }
+    };
+    match (index(first), index(last)) {
+        (Some(first), Some(last)) => Ok((first.min(last), first.max(last) + 1)),
+        _ => anyhow::bail!("invalid {} `{}`", dimension.to_lowercase(), span),
+    }
+}
+
+/// Builds the `DeveloperMetadataLookup` shared by `search_metadata` and
+/// `delete_metadata`. Metadata on rows or columns within a given sheet matches too.
+pub(crate) fn metadata_lookup(
+    args: &MetadataLookupArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Value> {
+    let mut lookup = json!({});
+    if let Some(id) = args.metadata_id {
+        lookup["metadataId"] = json!(id);
+    }
+    if let Some(key) = &args.key {
+        lookup["metadataKey"] = json!(key);
+    }
+    if let Some(value) = &args.value {
+        lookup["metadataValue"] = json!(value);
+    }
+    if let Some(location) = metadata_location(&args.location, sheet_ids)? {
+        lookup["metadataLocation"] = location;
+        lookup["locationMatchingStrategy"] = json!("INTERSECTING_LOCATION");
+    }
+    if lookup.as_object().is_some_and(|lookup| lookup.is_empty()) {
+        anyhow::bail!("metadata_id, key, value or a location required");
+    }
+    Ok(lookup)
+}
+
+/// Summarises a Sheets `DeveloperMetadata`, with row and column locations
+/// written back as `5:7` or `C:E`.
+pub(crate) fn metadata_summary(metadata: &Value) -> Value {
+    let location = &metadata["location"];
+    let range = &location["dimensionRange"];
+    let place = match location["locationType"].as_str() {
+        Some(kind @ ("ROW" | "COLUMN")) => {
+            // Index 0, like the first sheet's ID, is left out by the API
+            let start = range["startIndex"].as_u64().unwrap_or(0);
+            let end = range["endIndex"]
+                .as_u64()
+                .unwrap_or(start + 1)
+                .max(start + 1);
+            let name = |index: u64| match kind {
+                "COLUMN" => column_letters(index as usize),
+                _ => (index + 1).to_string(),
+            };
+            let span = if end - start == 1 {
+                name(start)
+            } else {
+                format!("{}:{}", name(start), name(end - 1))
+            };
+            let field = if kind == "COLUMN" { "columns" } else { "rows" };
+            json!({ "sheetId": range["sheetId"].as_i64().unwrap_or(0), field: span })
+        }
+        Some("SPREADSHEET") => json!({ "spreadsheet": true }),
+        _ => json!({ "sheetId": location["sheetId"].as_i64().unwrap_or(0) }),
+    };
+    json!({
+        "metadataId": metadata["metadataId"],
+        "key": metadata["metadataKey"],
+        "value": metadata["metadataValue"],
+        "visibility": metadata["visibility"],
+        "location": place,
+    })
+}
+
+/// Builds the `deleteDuplicates` request for `remove_duplicate_rows`.
+pub(crate) fn delete_duplicates_request(
+    args: &RemoveDuplicateRowsArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Value> {
+    let mut range = grid_range(args.target.id(sheet_ids)?, &args.range)?;
+    let start = range["startColumnIndex"].as_i64().unwrap_or(0);
+    let comparison_columns = args
+        .columns
+        .iter()
+        .map(|column| {
+            let index = start + range_column_offset(column, &range)?;
+            Ok(json!({
+                "sheetId": range["sheetId"],
+                "dimension": "COLUMNS",
+                "startIndex": index,
+                "endIndex": index + 1,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if args.has_header {
+        range["startRowIndex"] = json!(range["startRowIndex"].as_i64().unwrap_or(0) + 1);
+    }
+
+    let mut request = json!({ "range": range });
+    if !comparison_columns.is_empty() {
+        request["comparisonColumns"] = json!(comparison_columns);
+    }
+    Ok(json!({ "deleteDuplicates": request }))
+}
+
+/// Builds the `setBasicFilter` request for `set_basic_filter`.
+pub(crate) fn basic_filter_request(
+    args: &SetBasicFilterArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<Value> {
+    let range = grid_range(args.target.id(sheet_ids)?, &args.range)?;
+    let start = range["startColumnIndex"].as_i64().unwrap_or(0);
+
+    let filter_specs = args
+        .criteria
+        .iter()
+        .map(|criterion| {
+            let mut criteria = json!({});
+            if let Some(hidden) = &criterion.hidden_values {
+                criteria["hiddenValues"] = json!(hidden);
+            }
+            if let Some(condition) = &criterion.condition {
+                let values = condition
+                    .values
+                    .iter()
+                    .map(|value| match value {
+                        Value::String(text) => json!({ "userEnteredValue": text }),
+                        other => json!({ "userEnteredValue": other.to_string() }),
                     })
                     .collect::<Vec<_>>();
+                criteria["condition"] = json!({ "type": condition.kind, "values": values });
+            }
+            Ok(json!({
+                "columnIndex": start + range_column_offset(&criterion.column, &range)?,
+                "filterCriteria": criteria,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-                Ok(CallToolResponse {
-                    content: vec![ToolResponseContent::Text {
-                        text: serde_json::to_string(&sheet_info)?,
-                    }],
-                    is_error: None,
-                    meta: None,
-                })
+    Ok(json!({
+        "setBasicFilter": {
+            "filter": {
+                "range": range,
+                "filterSpecs": filter_specs,
+                "sortSpecs": sort_specs(&args.sort_by, &range)?,
             }
-            .await;
+        }
+    }))
+}
 
-            handle_result(result)
+/// Builds the requests for `create_pivot_table`: an `addSheet` when the
+/// target sheet does not exist yet, then an `updateCells` writing the pivot
+/// table at the target cell. Also returns where the pivot table was placed.
+pub(crate) fn pivot_table_requests(
+    args: &CreatePivotTableArgs,
+    sheet_ids: &HashMap<String, i32>,
+) -> Result<(Vec<Value>, Value)> {
+    let source = grid_range(args.target.id(sheet_ids)?, &args.source_range)?;
+
+    if args.values.is_empty() {
+        anyhow::bail!("values required");
+    }
+    let values = args
+        .values
+        .iter()
+        .map(|value| {
+            let mut pivot_value = json!({
+                "sourceColumnOffset": range_column_offset(&value.column, &source)?,
+                "summarizeFunction": value.function,
+            });
+            if let Some(name) = &value.name {
+                pivot_value["name"] = json!(name);
+            }
+            Ok(pivot_value)
         })
+        .collect::<Result<Vec<_>>>()?;
+
+    let pivot_table = json!({
+        "source": source,
+        "rows": pivot_groups(&args.rows, &source)?,
+        "columns": pivot_groups(&args.columns, &source)?,
+        "values": values,
     });
 
-    Ok(())
+    let mut requests = Vec::new();
+    let target_sheet = args.target_sheet.as_str();
+    let target_sheet_id = match sheet_ids.get(target_sheet) {
+        Some(id) => *id,
+        None => {
+            let id = (1..)
+                .find(|id| !sheet_ids.values().any(|existing| existing == id))
+                .unwrap_or_default();
+            requests.push(json!({
+                "addSheet": { "properties": { "sheetId": id, "title": target_sheet } }
+            }));
+            id
+        }
+    };
+    let target_cell = args.target_cell.as_str();
+    let (column, row) = parse_cell_position(target_cell)?;
+    requests.push(json!({
+        "updateCells": {
+            "rows": [{ "values": [{ "pivotTable": pivot_table }] }],
+            "start": { "sheetId": target_sheet_id, "rowIndex": row, "columnIndex": column },
+            "fields": "pivotTable",
+        }
+    }));
+
+    Ok((
+        requests,
+        json!({ "sheet": target_sheet, "sheetId": target_sheet_id, "cell": target_cell }),
+    ))
+}
+
+/// Converts rows of cells from tool arguments into cell values for a ValueRange.
+/// Strings, numbers and booleans keep their type; nulls are kept so the API skips
+/// those cells, and nested arrays or objects are written as their JSON text.
+pub(crate) fn to_cell_rows(values: &[Vec<Value>]) -> Vec<Vec<Value>> {
+    values
+        .iter()
+        .map(|cells| {
+            cells
+                .iter()
+                .map(|cell| match cell {
+                    Value::Array(_) | Value::Object(_) => Value::String(cell.to_string()),
+                    scalar => scalar.clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Builds a `values.batchUpdate` body from the tool's `data` entries.
+pub(crate) fn batch_write_request(
+    data: &[ValueRangeData],
+    value_input_option: ValueInputOption,
+) -> Value {
+    let data = data
+        .iter()
+        .map(|entry| {
+            json!({
+                "range": entry.range,
+                "majorDimension": entry.major_dimension,
+                "values": to_cell_rows(&entry.values),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "valueInputOption": value_input_option, "data": data })
 }
 
-fn list_sheets_resources() -> ResourcesListResponse {
+pub(crate) fn list_sheets_resources() -> ResourcesListResponse {
     let base = Url::parse("https://sheets.googleapis.com/v4/").unwrap();
     ResourcesListResponse {
         resources: vec![Resource {
@@ -396,16 +3444,3 @@ fn list_sheets_resources() -> ResourcesListResponse {
         meta: None,
     }
 }
-
-fn handle_result(result: Result<CallToolResponse>) -> Result<CallToolResponse> {
-    match result {
-        Ok(response) => Ok(response),
-        Err(e) => Ok(CallToolResponse {
-            content: vec![ToolResponseContent::Text {
-                text: format!("Error: {}", e),
-            }],
-            is_error: Some(true),
-            meta: None,
-        }),
-    }
-}
@@ -0,0 +1,375 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use super::{json_response, rest_request, ServerOptions, ToolRegistry};
+use crate::{circuit::Service, client::slides_root_url};
+
+/// Enough of a presentation to describe each slide without its styling
+const PRESENTATION_FIELDS: &str = "presentationId,title,slides(objectId,slideProperties(layoutObjectId),pageElements(objectId,shape(placeholder(type),text(textElements(textRun(content))))))";
+
+// Tool Arguments
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PredefinedLayout {
+    #[default]
+    Blank,
+    CaptionOnly,
+    Title,
+    TitleAndBody,
+    TitleAndTwoColumns,
+    TitleOnly,
+    SectionHeader,
+    SectionTitleAndDescription,
+    OneColumnText,
+    MainPoint,
+    BigNumber,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreatePresentationArgs {
+    /// Presentation title
+    pub title: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PresentationArgs {
+    pub presentation_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AddSlideArgs {
+    pub presentation_id: String,
+    #[serde(default)]
+    pub layout: PredefinedLayout,
+    /// Object ID of a layout in the presentation, instead of layout
+    pub layout_id: Option<String>,
+    /// 0-based position; defaults to the end
+    pub insertion_index: Option<u64>,
+    /// Object ID for the new slide; generated if omitted
+    pub object_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReplacePlaceholdersArgs {
+    pub presentation_id: String,
+    /// Map of text to find to its replacement, e.g. {"{{quarter}}": "Q3"}
+    #[schemars(with = "BTreeMap<String, String>")]
+    pub replacements: BTreeMap<String, Value>,
+    #[serde(default = "default_true")]
+    pub match_case: bool,
+    /// Limit the replacement to these slides
+    pub slide_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct InsertImageArgs {
+    pub presentation_id: String,
+    /// Object ID of the slide
+    pub slide_id: String,
+    /// Publicly accessible PNG, JPEG or GIF under 50 MB
+    pub image_url: String,
+    /// Left edge in points
+    #[serde(default)]
+    pub x: f64,
+    /// Top edge in points
+    #[serde(default)]
+    pub y: f64,
+    /// Width in points; the aspect ratio is kept
+    pub width: Option<f64>,
+    /// Height in points; the aspect ratio is kept
+    pub height: Option<f64>,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "slides": {
+                    "version": "v1",
+                    "description": "Google Slides API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_slides_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.mutating_tool(
+        "create_presentation",
+        "Create an empty Google Slides presentation",
+        |ctx, args: CreatePresentationArgs| async move {
+            let presentation = slides_request(
+                ctx.access_token(),
+                Method::POST,
+                slides_url("presentations")?,
+                Some(json!({ "title": args.title })),
+            )
+            .await?;
+            json_response(&json!({
+                "presentationId": presentation["presentationId"],
+                "title": presentation["title"],
+                "slides": slide_summaries(&presentation),
+            }))
+        },
+    );
+
+    tools.tool(
+        "list_slides",
+        "List the slides of a presentation with their object IDs, layouts, placeholders and text",
+        |ctx, args: PresentationArgs| async move {
+            let mut url = presentation_url(&args.presentation_id, "")?;
+            url.query_pairs_mut()
+                .append_pair("fields", PRESENTATION_FIELDS);
+            let presentation = slides_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&json!({
+                "presentationId": presentation["presentationId"],
+                "title": presentation["title"],
+                "slides": slide_summaries(&presentation),
+            }))
+        },
+    );
+
+    tools.mutating_tool(
+        "add_slide",
+        "Insert a slide based on a predefined layout (e.g. TITLE_AND_BODY) or one of the presentation's layouts",
+        |ctx, args: AddSlideArgs| async move {
+            let requests = vec![create_slide_request(&args)];
+            let response =
+                batch_update(ctx.access_token(), &args.presentation_id, requests).await?;
+            json_response(&response["replies"][0]["createSlide"])
+        },
+    );
+
+    tools.mutating_tool(
+        "replace_placeholders",
+        "Replace every occurrence of placeholder text such as {{name}} across the presentation or selected slides",
+        |ctx, args: ReplacePlaceholdersArgs| async move {
+            let requests = replace_text_requests(&args)?;
+            let finds = requests
+                .iter()
+                .map(|r| r["replaceAllText"]["containsText"]["text"].clone())
+                .collect::<Vec<_>>();
+            let response = batch_update(ctx.access_token(), &args.presentation_id, requests).await?;
+
+            let replaced = finds
+                .into_iter()
+                .zip(response["replies"].as_array().into_iter().flatten())
+                .map(|(find, reply)| {
+                    json!({
+                        "text": find,
+                        "occurrencesChanged": reply["replaceAllText"]["occurrencesChanged"].as_u64().unwrap_or(0),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json_response(&json!({ "replaced": replaced }))
+        },
+    );
+
+    tools.mutating_tool(
+        "insert_image",
+        "Place an image from a public URL on a slide, positioned and sized in points",
+        |ctx, args: InsertImageArgs| async move {
+            let requests = vec![create_image_request(&args)];
+            let response =
+                batch_update(ctx.access_token(), &args.presentation_id, requests).await?;
+            json_response(&response["replies"][0]["createImage"])
+        },
+    );
+
+    Ok(())
+}
+
+fn slides_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!("{}v1/{}", slides_root_url(), path))?)
+}
+
+/// URL of the presentation `presentation_id`, plus `suffix` (e.g.
+/// `:batchUpdate`).
+fn presentation_url(presentation_id: &str, suffix: &str) -> Result<Url> {
+    slides_url(&format!(
+        "presentations/{}{}",
+        urlencoding::encode(presentation_id),
+        suffix
+    ))
+}
+
+async fn slides_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::Slides, access_token, method, url, body).await
+}
+
+async fn batch_update(
+    access_token: &str,
+    presentation_id: &str,
+    requests: Vec<Value>,
+) -> Result<Value> {
+    slides_request(
+        access_token,
+        Method::POST,
+        presentation_url(presentation_id, ":batchUpdate")?,
+        Some(json!({ "requests": requests })),
+    )
+    .await
+}
+
+/// Condenses a presentation into one entry per slide: its object ID, layout,
+/// and the placeholder type and text of each shape.
+pub(crate) fn slide_summaries(presentation: &Value) -> Vec<Value> {
+    presentation["slides"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, slide)| {
+            let elements = slide["pageElements"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|element| element["shape"].is_object())
+                .map(|element| {
+                    let text = element["shape"]["text"]["textElements"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|e| e["textRun"]["content"].as_str())
+                        .collect::<String>();
+                    let mut summary = json!({"objectId": element["objectId"], "text": text});
+                    if let Some(placeholder) = element["shape"]["placeholder"]["type"].as_str() {
+                        summary["placeholder"] = json!(placeholder);
+                    }
+                    summary
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "index": index,
+                "objectId": slide["objectId"],
+                "layoutId": slide["slideProperties"]["layoutObjectId"],
+                "elements": elements,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn create_slide_request(args: &AddSlideArgs) -> Value {
+    let layout = match &args.layout_id {
+        Some(layout_id) => json!({ "layoutId": layout_id }),
+        None => json!({ "predefinedLayout": args.layout }),
+    };
+
+    let mut request = json!({ "slideLayoutReference": layout });
+    if let Some(index) = args.insertion_index {
+        request["insertionIndex"] = json!(index);
+    }
+    if let Some(object_id) = &args.object_id {
+        request["objectId"] = json!(object_id);
+    }
+    json!({ "createSlide": request })
+}
+
+pub(crate) fn replace_text_requests(args: &ReplacePlaceholdersArgs) -> Result<Vec<Value>> {
+    if args.replacements.is_empty() {
+        anyhow::bail!("replacements required");
+    }
+
+    Ok(args
+        .replacements
+        .iter()
+        .map(|(find, replace)| {
+            let replace = match replace {
+                Value::String(text) => text.clone(),
+                Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            let mut request = json!({
+                "containsText": {"text": find, "matchCase": args.match_case},
+                "replaceText": replace,
+            });
+            if let Some(slide_ids) = &args.slide_ids {
+                request["pageObjectIds"] = json!(slide_ids);
+            }
+            json!({ "replaceAllText": request })
+        })
+        .collect())
+}
+
+pub(crate) fn create_image_request(args: &InsertImageArgs) -> Value {
+    let mut properties = json!({
+        "pageObjectId": args.slide_id,
+        "transform": {
+            "scaleX": 1,
+            "scaleY": 1,
+            "translateX": args.x,
+            "translateY": args.y,
+            "unit": "PT",
+        },
+    });
+    let mut size = Map::new();
+    for (key, magnitude) in [("width", args.width), ("height", args.height)] {
+        if let Some(magnitude) = magnitude {
+            size.insert(
+                key.to_string(),
+                json!({"magnitude": magnitude, "unit": "PT"}),
+            );
+        }
+    }
+    if !size.is_empty() {
+        properties["size"] = Value::Object(size);
+    }
+
+    json!({
+        "createImage": {
+            "url": args.image_url,
+            "elementProperties": properties,
+        }
+    })
+}
+
+pub(crate) fn list_slides_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://slides.googleapis.com/v1/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "slides".to_string(),
+            description: Some("Google Slides API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
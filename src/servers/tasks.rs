@@ -0,0 +1,303 @@
+use anyhow::{Context, Result};
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use reqwest::Method;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+use super::{json_response, rest_request, NoArgs, ServerOptions, ToolRegistry};
+use crate::{circuit::Service, client::tasks_root_url};
+
+const DEFAULT_TASK_LIST: &str = "@default";
+const DEFAULT_MAX_RESULTS: u64 = 100;
+
+// Tool Arguments
+
+fn default_task_list() -> String {
+    DEFAULT_TASK_LIST.to_string()
+}
+
+fn default_max_results() -> u64 {
+    DEFAULT_MAX_RESULTS
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A date string that may also be null.
+fn nullable_date_schema(_: &mut SchemaGenerator) -> Schema {
+    serde_json::from_value(json!({"type": ["string", "null"]})).unwrap_or(Schema::Bool(true))
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListTasksArgs {
+    #[serde(default = "default_task_list")]
+    pub task_list_id: String,
+    #[serde(default = "default_true")]
+    pub show_completed: bool,
+    /// Earliest due date, YYYY-MM-DD or RFC 3339
+    pub due_min: Option<String>,
+    /// Latest due date, YYYY-MM-DD or RFC 3339
+    pub due_max: Option<String>,
+    #[serde(default = "default_max_results")]
+    pub max_results: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateTaskArgs {
+    #[serde(default = "default_task_list")]
+    pub task_list_id: String,
+    pub title: String,
+    pub notes: Option<String>,
+    /// YYYY-MM-DD; Google Tasks ignores the time of day
+    pub due: Option<String>,
+    /// Task ID to nest the new task under
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CompleteTaskArgs {
+    #[serde(default = "default_task_list")]
+    pub task_list_id: String,
+    pub task_id: String,
+    #[serde(default = "default_true")]
+    pub completed: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetTaskDueDateArgs {
+    #[serde(default = "default_task_list")]
+    pub task_list_id: String,
+    pub task_id: String,
+    /// YYYY-MM-DD, or null to clear the due date
+    #[schemars(schema_with = "nullable_date_schema")]
+    pub due: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TaskArgs {
+    #[serde(default = "default_task_list")]
+    pub task_list_id: String,
+    pub task_id: String,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "tasks": {
+                    "version": "v1",
+                    "description": "Google Tasks API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_tasks_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.tool(
+        "list_task_lists",
+        "List the user's task lists with their IDs",
+        |ctx, _: NoArgs| async move {
+            let mut url = tasks_url("users/@me/lists")?;
+            url.query_pairs_mut().append_pair("maxResults", "100");
+            let body = tasks_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.tool(
+        "list_tasks",
+        "List the tasks in a task list, optionally limited to a due date range",
+        |ctx, args: ListTasksArgs| async move {
+            let mut url = task_url(&args.task_list_id, None)?;
+            {
+                let mut query = url.query_pairs_mut();
+                let show_completed = args.show_completed.to_string();
+                query
+                    .append_pair("showCompleted", &show_completed)
+                    .append_pair("showHidden", &show_completed)
+                    .append_pair("maxResults", &args.max_results.to_string());
+                for (value, param) in [(&args.due_min, "dueMin"), (&args.due_max, "dueMax")] {
+                    if let Some(value) = value {
+                        query.append_pair(param, &due_timestamp(value)?);
+                    }
+                }
+                if let Some(token) = &args.page_token {
+                    query.append_pair("pageToken", token);
+                }
+            }
+            let body = tasks_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "create_task",
+        "Create a task, optionally with notes, a due date, or under a parent task",
+        |ctx, args: CreateTaskArgs| async move {
+            let mut url = task_url(&args.task_list_id, None)?;
+            if let Some(parent) = &args.parent {
+                url.query_pairs_mut().append_pair("parent", parent);
+            }
+            let body = tasks_request(
+                ctx.access_token(),
+                Method::POST,
+                url,
+                Some(task_body(&args)?),
+            )
+            .await?;
+            json_response(&body)
+        },
+    );
+
+    tools.mutating_tool(
+        "complete_task",
+        "Mark a task as completed, or reopen it with completed: false",
+        |ctx, args: CompleteTaskArgs| async move {
+            // Reopening has to clear the completion time as well
+            let body = if args.completed {
+                json!({"status": "completed"})
+            } else {
+                json!({"status": "needsAction", "completed": null})
+            };
+            let task = tasks_request(
+                ctx.access_token(),
+                Method::PATCH,
+                task_url(&args.task_list_id, Some(&args.task_id))?,
+                Some(body),
+            )
+            .await?;
+            json_response(&task)
+        },
+    );
+
+    tools.mutating_tool(
+        "set_task_due_date",
+        "Set or clear a task's due date",
+        |ctx, args: SetTaskDueDateArgs| async move {
+            let due = match &args.due {
+                Some(due) => json!(due_timestamp(due)?),
+                None => Value::Null,
+            };
+            let task = tasks_request(
+                ctx.access_token(),
+                Method::PATCH,
+                task_url(&args.task_list_id, Some(&args.task_id))?,
+                Some(json!({ "due": due })),
+            )
+            .await?;
+            json_response(&task)
+        },
+    );
+
+    tools.mutating_tool(
+        "delete_task",
+        "Delete a task and any subtasks",
+        |ctx, args: TaskArgs| async move {
+            tasks_request(
+                ctx.access_token(),
+                Method::DELETE,
+                task_url(&args.task_list_id, Some(&args.task_id))?,
+                None,
+            )
+            .await?;
+            json_response(&json!({ "deleted": args.task_id }))
+        },
+    );
+
+    Ok(())
+}
+
+fn tasks_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!(
+        "{}tasks/v1/{}",
+        tasks_root_url(),
+        path
+    ))?)
+}
+
+/// URL of a task list's tasks collection, or of one task in it.
+fn task_url(task_list_id: &str, task_id: Option<&str>) -> Result<Url> {
+    let mut path = format!("lists/{}/tasks", urlencoding::encode(task_list_id));
+    if let Some(task_id) = task_id {
+        path.push('/');
+        path.push_str(&urlencoding::encode(task_id));
+    }
+    tasks_url(&path)
+}
+
+async fn tasks_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::Tasks, access_token, method, url, body).await
+}
+
+/// Converts a due date to the RFC 3339 timestamp the Tasks API expects.
+/// Only the date is stored, so `YYYY-MM-DD` is taken as midnight UTC.
+pub(crate) fn due_timestamp(value: &str) -> Result<String> {
+    if chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() {
+        return Ok(format!("{}T00:00:00.000Z", value));
+    }
+    let due = chrono::DateTime::parse_from_rfc3339(value).with_context(|| {
+        format!(
+            "invalid due date `{}`: expected YYYY-MM-DD or an RFC 3339 date-time",
+            value
+        )
+    })?;
+    Ok(due
+        .with_timezone(&chrono::Utc)
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+}
+
+/// Builds the body of a new task from the tool's arguments.
+pub(crate) fn task_body(args: &CreateTaskArgs) -> Result<Value> {
+    let mut body = json!({ "title": args.title });
+    if let Some(notes) = &args.notes {
+        body["notes"] = json!(notes);
+    }
+    if let Some(due) = &args.due {
+        body["due"] = json!(due_timestamp(due)?);
+    }
+    Ok(body)
+}
+
+pub(crate) fn list_tasks_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://tasks.googleapis.com/tasks/v1/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "tasks".to_string(),
+            description: Some("Google Tasks API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
@@ -0,0 +1,272 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::Result;
+use async_mcp::{
+    server::Server,
+    transport::Transport,
+    types::{ResourcesListResponse, ServerCapabilities},
+};
+use serde::Deserialize;
+use serde_json::{json, Map};
+
+#[cfg(feature = "youtube")]
+use super::youtube;
+use super::{
+    admin, apps_script, calendar, chat, classroom, docs, drive, gmail, meet, register_quota_tool,
+    resources::{resource_capabilities, transport_notifier, with_resources, ResourceScope},
+    sheets, slides, tasks, ServerOptions,
+};
+
+/// A Google service whose tools can be served from a combined server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceService {
+    Drive,
+    Sheets,
+    Gmail,
+    Calendar,
+    Docs,
+    Slides,
+    Tasks,
+    #[serde(rename = "apps_script")]
+    AppsScript,
+    Chat,
+    Meet,
+    Admin,
+    Classroom,
+    #[cfg(feature = "youtube")]
+    YouTube,
+}
+
+impl WorkspaceService {
+    /// The services served by default. Apps Script, Chat, Meet, Admin and
+    /// Classroom are opt-in: `run_script` needs a deployed script and
+    /// whatever scopes that script uses, the Chat API only answers projects
+    /// with a Chat app configured, Meet's and Classroom's scopes are ones
+    /// existing logins lack, and the Directory API only answers Workspace
+    /// administrators. YouTube, in builds with the `youtube` feature, is
+    /// opt-in too: it acts on a channel rather than a Workspace account.
+    pub const ALL: &'static [WorkspaceService] = &[
+        WorkspaceService::Drive,
+        WorkspaceService::Sheets,
+        WorkspaceService::Gmail,
+        WorkspaceService::Calendar,
+        WorkspaceService::Docs,
+        WorkspaceService::Slides,
+        WorkspaceService::Tasks,
+    ];
+
+    /// The OAuth scopes requested for the service's full set of tools.
+    pub fn oauth_scopes(self) -> &'static [&'static str] {
+        match self {
+            WorkspaceService::Drive => &["https://www.googleapis.com/auth/drive"],
+            WorkspaceService::Sheets => &["https://www.googleapis.com/auth/spreadsheets"],
+            WorkspaceService::Gmail => &["https://www.googleapis.com/auth/gmail.modify"],
+            WorkspaceService::Calendar => &["https://www.googleapis.com/auth/calendar"],
+            WorkspaceService::Docs => &["https://www.googleapis.com/auth/documents"],
+            WorkspaceService::Slides => &["https://www.googleapis.com/auth/presentations"],
+            WorkspaceService::Tasks => &["https://www.googleapis.com/auth/tasks"],
+            // Covers spreadsheet macros; scripts touching other services need
+            // their scopes configured under `[scopes] apps_script`
+            WorkspaceService::AppsScript => &["https://www.googleapis.com/auth/spreadsheets"],
+            // No single Chat scope covers both listing spaces and messaging
+            WorkspaceService::Chat => &[
+                "https://www.googleapis.com/auth/chat.spaces.readonly",
+                "https://www.googleapis.com/auth/chat.messages",
+            ],
+            WorkspaceService::Meet => &[
+                "https://www.googleapis.com/auth/meetings.space.created",
+                "https://www.googleapis.com/auth/meetings.space.readonly",
+            ],
+            WorkspaceService::Admin => &[
+                "https://www.googleapis.com/auth/admin.directory.user.readonly",
+                "https://www.googleapis.com/auth/admin.directory.group.readonly",
+                "https://www.googleapis.com/auth/admin.directory.group.member",
+            ],
+            WorkspaceService::Classroom => &[
+                "https://www.googleapis.com/auth/classroom.courses.readonly",
+                "https://www.googleapis.com/auth/classroom.coursework.students",
+                "https://www.googleapis.com/auth/classroom.announcements",
+            ],
+            #[cfg(feature = "youtube")]
+            WorkspaceService::YouTube => &[
+                "https://www.googleapis.com/auth/youtube",
+                "https://www.googleapis.com/auth/yt-analytics.readonly",
+            ],
+        }
+    }
+}
+
+impl fmt::Display for WorkspaceService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkspaceService::Drive => write!(f, "drive"),
+            WorkspaceService::Sheets => write!(f, "sheets"),
+            WorkspaceService::Gmail => write!(f, "gmail"),
+            WorkspaceService::Calendar => write!(f, "calendar"),
+            WorkspaceService::Docs => write!(f, "docs"),
+            WorkspaceService::Slides => write!(f, "slides"),
+            WorkspaceService::Tasks => write!(f, "tasks"),
+            WorkspaceService::AppsScript => write!(f, "apps_script"),
+            WorkspaceService::Chat => write!(f, "chat"),
+            WorkspaceService::Meet => write!(f, "meet"),
+            WorkspaceService::Admin => write!(f, "admin"),
+            WorkspaceService::Classroom => write!(f, "classroom"),
+            #[cfg(feature = "youtube")]
+            WorkspaceService::YouTube => write!(f, "youtube"),
+        }
+    }
+}
+
+impl FromStr for WorkspaceService {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "drive" => Ok(Self::Drive),
+            "sheets" => Ok(Self::Sheets),
+            "gmail" => Ok(Self::Gmail),
+            "calendar" => Ok(Self::Calendar),
+            "docs" => Ok(Self::Docs),
+            "slides" => Ok(Self::Slides),
+            "tasks" => Ok(Self::Tasks),
+            "apps_script" | "appsscript" => Ok(Self::AppsScript),
+            "chat" => Ok(Self::Chat),
+            "meet" => Ok(Self::Meet),
+            "admin" => Ok(Self::Admin),
+            "classroom" => Ok(Self::Classroom),
+            #[cfg(feature = "youtube")]
+            "youtube" => Ok(Self::YouTube),
+            other => Err(format!(
+                "unknown service `{}` (expected drive|sheets|gmail|calendar|docs|slides|tasks|apps_script|chat|meet|admin|classroom)",
+                other
+            )),
+        }
+    }
+}
+
+/// Builds one server exposing the tools of every selected service.
+pub fn build_with_options<T: Transport + Clone>(
+    transport: T,
+    services: &[WorkspaceService],
+    options: ServerOptions,
+) -> Result<Server<T>> {
+    build(transport, services, options, false)
+}
+
+/// Like `build_with_options`, but prefixes each tool with its service
+/// (`drive.list_files`, `sheets.read_values`, ...) so names stay unambiguous
+/// however many services are combined.
+pub fn build_namespaced<T: Transport + Clone>(
+    transport: T,
+    services: &[WorkspaceService],
+    options: ServerOptions,
+) -> Result<Server<T>> {
+    build(transport, services, options, true)
+}
+
+fn build<T: Transport + Clone>(
+    transport: T,
+    services: &[WorkspaceService],
+    options: ServerOptions,
+    namespaced: bool,
+) -> Result<Server<T>> {
+    let mut capabilities = Map::new();
+    for service in services {
+        let (version, description) = match service {
+            WorkspaceService::Drive => ("v3", "Google Drive API operations"),
+            WorkspaceService::Sheets => ("v4", "Google Sheets API operations"),
+            WorkspaceService::Gmail => ("v1", "Gmail API operations"),
+            WorkspaceService::Calendar => ("v3", "Google Calendar API operations"),
+            WorkspaceService::Docs => ("v1", "Google Docs API operations"),
+            WorkspaceService::Slides => ("v1", "Google Slides API operations"),
+            WorkspaceService::Tasks => ("v1", "Google Tasks API operations"),
+            WorkspaceService::AppsScript => ("v1", "Google Apps Script API operations"),
+            WorkspaceService::Chat => ("v1", "Google Chat API operations"),
+            WorkspaceService::Meet => ("v2", "Google Meet API operations"),
+            WorkspaceService::Admin => {
+                ("directory_v1", "Google Admin SDK Directory API operations")
+            }
+            WorkspaceService::Classroom => ("v1", "Google Classroom API operations"),
+            #[cfg(feature = "youtube")]
+            WorkspaceService::YouTube => ("v3", "YouTube Data and Analytics API operations"),
+        };
+        capabilities.insert(
+            service.to_string(),
+            json!({"version": version, "description": description}),
+        );
+    }
+
+    let notifier = transport_notifier(&transport);
+    let server = Server::builder(transport).capabilities(ServerCapabilities {
+        tools: Some(capabilities.into()),
+        resources: Some(resource_capabilities()),
+        ..Default::default()
+    });
+    let mut server = with_resources(
+        server,
+        ResourceScope {
+            drive: services.contains(&WorkspaceService::Drive),
+            sheets: services.contains(&WorkspaceService::Sheets),
+        },
+        list_resources(services).resources,
+        options.token_provider.clone(),
+        notifier,
+    );
+
+    for service in services {
+        let options = ServerOptions {
+            namespace: namespaced.then(|| service.to_string()),
+            ..options.clone()
+        };
+        match service {
+            WorkspaceService::Drive => drive::register_tools(&mut server, &options)?,
+            WorkspaceService::Sheets => sheets::register_tools(&mut server, &options)?,
+            WorkspaceService::Gmail => gmail::register_tools(&mut server, &options)?,
+            WorkspaceService::Calendar => calendar::register_tools(&mut server, &options)?,
+            WorkspaceService::Docs => docs::register_tools(&mut server, &options)?,
+            WorkspaceService::Slides => slides::register_tools(&mut server, &options)?,
+            WorkspaceService::Tasks => tasks::register_tools(&mut server, &options)?,
+            WorkspaceService::AppsScript => apps_script::register_tools(&mut server, &options)?,
+            WorkspaceService::Chat => chat::register_tools(&mut server, &options)?,
+            WorkspaceService::Meet => meet::register_tools(&mut server, &options)?,
+            WorkspaceService::Admin => admin::register_tools(&mut server, &options)?,
+            WorkspaceService::Classroom => classroom::register_tools(&mut server, &options)?,
+            #[cfg(feature = "youtube")]
+            WorkspaceService::YouTube => youtube::register_tools(&mut server, &options)?,
+        }
+    }
+    if services.contains(&WorkspaceService::Sheets) || services.contains(&WorkspaceService::Drive) {
+        register_quota_tool(&mut server, &options);
+    }
+
+    Ok(server.build())
+}
+
+fn list_resources(services: &[WorkspaceService]) -> ResourcesListResponse {
+    let resources = services
+        .iter()
+        .flat_map(|service| match service {
+            WorkspaceService::Drive => drive::list_drive_resources().resources,
+            WorkspaceService::Sheets => sheets::list_sheets_resources().resources,
+            WorkspaceService::Gmail => gmail::list_gmail_resources().resources,
+            WorkspaceService::Calendar => calendar::list_calendar_resources().resources,
+            WorkspaceService::Docs => docs::list_docs_resources().resources,
+            WorkspaceService::Slides => slides::list_slides_resources().resources,
+            WorkspaceService::Tasks => tasks::list_tasks_resources().resources,
+            WorkspaceService::AppsScript => apps_script::list_apps_script_resources().resources,
+            WorkspaceService::Chat => chat::list_chat_resources().resources,
+            WorkspaceService::Meet => meet::list_meet_resources().resources,
+            WorkspaceService::Admin => admin::list_admin_resources().resources,
+            WorkspaceService::Classroom => classroom::list_classroom_resources().resources,
+            #[cfg(feature = "youtube")]
+            WorkspaceService::YouTube => youtube::list_youtube_resources().resources,
+        })
+        .collect();
+
+    ResourcesListResponse {
+        resources,
+        next_cursor: None,
+        meta: None,
+    }
+}
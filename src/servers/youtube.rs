@@ -0,0 +1,422 @@
+use anyhow::{Context, Result};
+use async_mcp::{
+    server::{Server, ServerBuilder},
+    transport::Transport,
+    types::{ListRequest, Resource, ResourcesListResponse, ServerCapabilities},
+};
+use reqwest::Method;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use super::{api_name, json_response, rest_request, ServerOptions, ToolRegistry};
+use crate::{
+    circuit::Service,
+    client::{youtube_analytics_root_url, youtube_root_url},
+};
+
+const DEFAULT_MAX_RESULTS: u64 = 25;
+const DEFAULT_REPORT_DAYS: i64 = 28;
+const ANALYTICS_METRICS: &str = "views,estimatedMinutesWatched,averageViewDuration,likes,comments,subscribersGained,subscribersLost";
+/// `snippet` fields a caller may set; `videos.update` clears any left out
+const WRITABLE_SNIPPET: &[&str] = &[
+    "title",
+    "description",
+    "tags",
+    "categoryId",
+    "defaultLanguage",
+    "defaultAudioLanguage",
+];
+/// `status` fields a caller may set; `videos.update` clears any left out
+const WRITABLE_STATUS: &[&str] = &[
+    "privacyStatus",
+    "embeddable",
+    "license",
+    "publicStatsViewable",
+    "publishAt",
+    "selfDeclaredMadeForKids",
+];
+
+// Tool Arguments
+
+fn default_max_results() -> u64 {
+    DEFAULT_MAX_RESULTS
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyStatus {
+    Public,
+    Unlisted,
+    Private,
+}
+
+/// How to break an analytics report down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportDimension {
+    Day,
+    Video,
+    Country,
+    InsightTrafficSourceType,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListChannelVideosArgs {
+    /// Defaults to the signed-in user's channel
+    pub channel_id: Option<String>,
+    #[serde(default = "default_max_results")]
+    #[schemars(range(min = 1, max = 50))]
+    pub max_results: u64,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetVideoArgs {
+    pub video_id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UpdateVideoMetadataArgs {
+    pub video_id: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Replaces the video's tags
+    pub tags: Option<Vec<String>>,
+    /// Numeric video category, e.g. `27` for Education
+    pub category_id: Option<String>,
+    pub privacy_status: Option<PrivacyStatus>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetChannelAnalyticsArgs {
+    /// First day of the report, `YYYY-MM-DD`; defaults to 28 days before `end_date`
+    pub start_date: Option<String>,
+    /// Last day of the report, `YYYY-MM-DD`; defaults to today
+    pub end_date: Option<String>,
+    /// Only this video's numbers
+    pub video_id: Option<String>,
+    /// Break the totals down by day, video (top videos by views), country or traffic source
+    pub group_by: Option<ReportDimension>,
+    #[serde(default = "default_max_results")]
+    #[schemars(range(min = 1, max = 200))]
+    pub max_results: u64,
+}
+
+pub fn build<T: Transport>(transport: T) -> Result<Server<T>> {
+    build_with_options(transport, ServerOptions::default())
+}
+
+pub fn build_with_options<T: Transport>(transport: T, options: ServerOptions) -> Result<Server<T>> {
+    let mut server = Server::builder(transport)
+        .capabilities(ServerCapabilities {
+            tools: Some(json!({
+                "youtube": {
+                    "version": "v3",
+                    "description": "YouTube Data and Analytics API operations"
+                }
+            })),
+            ..Default::default()
+        })
+        .request_handler("resources/list", |_req: ListRequest| {
+            Box::pin(async move { Ok(list_youtube_resources()) })
+        });
+
+    register_tools(&mut server, &options)?;
+
+    Ok(server.build())
+}
+
+pub(crate) fn register_tools<T: Transport>(
+    server: &mut ServerBuilder<T>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let mut tools = ToolRegistry::new(server, options);
+
+    tools.tool(
+        "list_channel_videos",
+        "List a channel's uploads, newest first, with their privacy status and view, like and comment counts",
+        |ctx, args: ListChannelVideosArgs| async move {
+            let token = ctx.access_token();
+            let mut url = youtube_url("channels")?;
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("part", "contentDetails");
+                match &args.channel_id {
+                    Some(channel_id) => query.append_pair("id", channel_id),
+                    None => query.append_pair("mine", "true"),
+                };
+            }
+            let channels = youtube_request(token, Method::GET, url, None).await?;
+            let uploads = channels["items"][0]["contentDetails"]["relatedPlaylists"]["uploads"]
+                .as_str()
+                .context("channel not found, or the signed-in user has no channel")?
+                .to_string();
+            ctx.span.record("resource", uploads.as_str());
+
+            let mut url = youtube_url("playlistItems")?;
+            {
+                let mut query = url.query_pairs_mut();
+                query
+                    .append_pair("part", "contentDetails")
+                    .append_pair("playlistId", &uploads)
+                    .append_pair("maxResults", &args.max_results.to_string());
+                if let Some(page_token) = &args.page_token {
+                    query.append_pair("pageToken", page_token);
+                }
+            }
+            let items = youtube_request(token, Method::GET, url, None).await?;
+            let ids = items["items"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|item| item["contentDetails"]["videoId"].as_str())
+                .collect::<Vec<_>>();
+
+            let mut videos = Vec::new();
+            if !ids.is_empty() {
+                let mut url = youtube_url("videos")?;
+                url.query_pairs_mut()
+                    .append_pair("part", "snippet,statistics,status")
+                    .append_pair("id", &ids.join(","));
+                let body = youtube_request(token, Method::GET, url, None).await?;
+                videos = body["items"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(video_summary)
+                    .collect();
+            }
+
+            json_response(&json!({
+                "videos": videos,
+                "nextPageToken": items["nextPageToken"],
+            }))
+        },
+    );
+
+    tools.tool(
+        "get_video",
+        "Get a video's metadata (title, description, tags, category), privacy status, duration and statistics",
+        |ctx, args: GetVideoArgs| async move {
+            ctx.span.record("resource", args.video_id.as_str());
+            let video = fetch_video(
+                ctx.access_token(),
+                &args.video_id,
+                "snippet,contentDetails,statistics,status",
+            )
+            .await?;
+            json_response(&video)
+        },
+    );
+
+    tools.mutating_tool(
+        "update_video_metadata",
+        "Update a video's title, description, tags, category or privacy status, leaving the fields not given as they are",
+        |ctx, args: UpdateVideoMetadataArgs| async move {
+            let token = ctx.access_token();
+            ctx.span.record("resource", args.video_id.as_str());
+            let current = fetch_video(token, &args.video_id, "snippet,status").await?;
+            let (parts, body) = video_update(&current, &args)?;
+            let mut url = youtube_url("videos")?;
+            url.query_pairs_mut().append_pair("part", &parts);
+            let video = youtube_request(token, Method::PUT, url, Some(body)).await?;
+            json_response(&video)
+        },
+    );
+
+    tools.tool(
+        "get_channel_analytics",
+        "Summarise the signed-in user's channel, or one video, over a date range: views, watch time, average view duration, likes, comments and subscribers, optionally by day, video, country or traffic source",
+        |ctx, args: GetChannelAnalyticsArgs| async move {
+            let (start_date, end_date) =
+                report_window(args.start_date.as_deref(), args.end_date.as_deref())?;
+            let mut url = Url::parse(&format!("{}v2/reports", youtube_analytics_root_url()))?;
+            {
+                let mut query = url.query_pairs_mut();
+                query
+                    .append_pair("ids", "channel==MINE")
+                    .append_pair("startDate", &start_date)
+                    .append_pair("endDate", &end_date)
+                    .append_pair("metrics", ANALYTICS_METRICS);
+                if let Some(video_id) = &args.video_id {
+                    ctx.span.record("resource", video_id.as_str());
+                    query.append_pair("filters", &format!("video=={}", video_id));
+                }
+                if let Some(dimension) = &args.group_by {
+                    query.append_pair("dimensions", &api_name(dimension));
+                    if *dimension == ReportDimension::Day {
+                        query.append_pair("sort", "day");
+                    } else {
+                        query
+                            .append_pair("sort", "-views")
+                            .append_pair("maxResults", &args.max_results.to_string());
+                    }
+                }
+            }
+            let report = youtube_request(ctx.access_token(), Method::GET, url, None).await?;
+            json_response(&json!({
+                "startDate": start_date,
+                "endDate": end_date,
+                "rows": report_rows(&report),
+            }))
+        },
+    );
+
+    Ok(())
+}
+
+fn youtube_url(path: &str) -> Result<Url> {
+    Ok(Url::parse(&format!(
+        "{}youtube/v3/{}",
+        youtube_root_url(),
+        path
+    ))?)
+}
+
+async fn youtube_request(
+    access_token: &str,
+    method: Method,
+    url: Url,
+    body: Option<Value>,
+) -> Result<Value> {
+    rest_request(Service::YouTube, access_token, method, url, body).await
+}
+
+async fn fetch_video(access_token: &str, video_id: &str, parts: &str) -> Result<Value> {
+    let mut url = youtube_url("videos")?;
+    url.query_pairs_mut()
+        .append_pair("part", parts)
+        .append_pair("id", video_id);
+    let body = youtube_request(access_token, Method::GET, url, None).await?;
+    match body["items"].get(0) {
+        Some(video) => Ok(video.clone()),
+        None => anyhow::bail!("video `{}` not found", video_id),
+    }
+}
+
+/// The parts of a `videos` resource a channel owner scans a list for.
+pub(crate) fn video_summary(video: &Value) -> Value {
+    json!({
+        "id": video["id"],
+        "title": video["snippet"]["title"],
+        "publishedAt": video["snippet"]["publishedAt"],
+        "privacyStatus": video["status"]["privacyStatus"],
+        "views": video["statistics"]["viewCount"],
+        "likes": video["statistics"]["likeCount"],
+        "comments": video["statistics"]["commentCount"],
+    })
+}
+
+fn writable(resource: &Value, fields: &[&str]) -> Map<String, Value> {
+    fields
+        .iter()
+        .filter_map(|field| Some((field.to_string(), resource.get(*field)?.clone())))
+        .collect()
+}
+
+/// The `part` list and body of a `videos.update` applying `args` to the
+/// `current` video. Each part is sent whole, so its other writable fields are
+/// copied over from the current video.
+pub(crate) fn video_update(
+    current: &Value,
+    args: &UpdateVideoMetadataArgs,
+) -> Result<(String, Value)> {
+    let mut parts = Vec::new();
+    let mut body = json!({ "id": args.video_id });
+
+    if args.title.is_some()
+        || args.description.is_some()
+        || args.tags.is_some()
+        || args.category_id.is_some()
+    {
+        let mut snippet = writable(&current["snippet"], WRITABLE_SNIPPET);
+        if let Some(title) = &args.title {
+            snippet.insert("title".to_string(), json!(title));
+        }
+        if let Some(description) = &args.description {
+            snippet.insert("description".to_string(), json!(description));
+        }
+        if let Some(tags) = &args.tags {
+            snippet.insert("tags".to_string(), json!(tags));
+        }
+        if let Some(category_id) = &args.category_id {
+            snippet.insert("categoryId".to_string(), json!(category_id));
+        }
+        parts.push("snippet");
+        body["snippet"] = Value::Object(snippet);
+    }
+    if let Some(privacy_status) = &args.privacy_status {
+        let mut status = writable(&current["status"], WRITABLE_STATUS);
+        status.insert("privacyStatus".to_string(), json!(api_name(privacy_status)));
+        parts.push("status");
+        body["status"] = Value::Object(status);
+    }
+
+    if parts.is_empty() {
+        anyhow::bail!(
+            "nothing to update: pass a title, description, tags, category_id or privacy_status"
+        );
+    }
+    Ok((parts.join(","), body))
+}
+
+/// The report's date range, defaulting to the 28 days up to today.
+pub(crate) fn report_window(start: Option<&str>, end: Option<&str>) -> Result<(String, String)> {
+    let parse = |value: &str| {
+        chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .with_context(|| format!("invalid date `{}`: expected YYYY-MM-DD", value))
+    };
+    let end = match end {
+        Some(end) => parse(end)?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let start = match start {
+        Some(start) => parse(start)?,
+        None => end - chrono::Duration::days(DEFAULT_REPORT_DAYS),
+    };
+    if start > end {
+        anyhow::bail!("start_date {} is after end_date {}", start, end);
+    }
+    Ok((start.to_string(), end.to_string()))
+}
+
+/// Turns an Analytics report's positional rows into objects keyed by column.
+pub(crate) fn report_rows(report: &Value) -> Vec<Value> {
+    let columns = report["columnHeaders"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|header| header["name"].as_str().unwrap_or_default())
+        .collect::<Vec<_>>();
+    report["rows"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|row| {
+            let cells = row.as_array().map(Vec::as_slice).unwrap_or_default();
+            Value::Object(
+                columns
+                    .iter()
+                    .zip(cells)
+                    .map(|(column, cell)| (column.to_string(), cell.clone()))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn list_youtube_resources() -> ResourcesListResponse {
+    let base = Url::parse("https://youtube.googleapis.com/youtube/v3/").unwrap();
+    ResourcesListResponse {
+        resources: vec![Resource {
+            uri: base,
+            name: "youtube".to_string(),
+            description: Some("YouTube Data API".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }],
+        next_cursor: None,
+        meta: None,
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::{
+    circuit::{guarded, Service},
+    client::{get_http_client, sheets_root_url},
+    payload, InvokeError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    SeekingValues,
+    AfterValuesKey,
+    InValues,
+    Done,
+}
+
+/// Incrementally extracts rows from a `ValueRange` JSON body (`{"values": [[...], ...]}`)
+/// without materializing the whole document.
+#[derive(Debug)]
+pub struct RowStreamParser {
+    state: State,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    key: Vec<u8>,
+    last_key: Vec<u8>,
+    row: Vec<u8>,
+    row_active: bool,
+}
+
+impl Default for RowStreamParser {
+    fn default() -> Self {
+        Self {
+            state: State::SeekingValues,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            key: Vec::new(),
+            last_key: Vec::new(),
+            row: Vec::new(),
+            row_active: false,
+        }
+    }
+}
+
+impl RowStreamParser {
+    /// Feeds the next chunk of the body, appending any completed rows to `rows`.
+    pub fn feed(&mut self, chunk: &[u8], rows: &mut Vec<Vec<Value>>) -> Result<()> {
+        for &b in chunk {
+            if self.row_active {
+                self.row.push(b);
+            }
+
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                    if !self.row_active && self.depth == 1 {
+                        self.last_key = std::mem::take(&mut self.key);
+                    }
+                    continue;
+                }
+                if !self.row_active && self.depth == 1 {
+                    self.key.push(b);
+                }
+                continue;
+            }
+
+            match b {
+                b'"' => {
+                    self.in_string = true;
+                    self.key.clear();
+                }
+                b'{' | b'[' => {
+                    self.depth += 1;
+                    match (self.state, self.depth, b) {
+                        (State::AfterValuesKey, 2, b'[') => self.state = State::InValues,
+                        (State::InValues, 3, b'[') => {
+                            self.row_active = true;
+                            self.row.clear();
+                            self.row.push(b);
+                        }
+                        _ => {}
+                    }
+                }
+                b'}' | b']' => {
+                    if self.row_active && self.depth == 3 {
+                        self.row_active = false;
+                        rows.push(serde_json::from_slice(&self.row)?);
+                    } else if self.state == State::InValues && self.depth == 2 {
+                        self.state = State::Done;
+                    }
+                    self.depth = self.depth.saturating_sub(1);
+                }
+                b':' if self.depth == 1
+                    && self.state == State::SeekingValues
+                    && self.last_key == b"values" =>
+                {
+                    self.state = State::AfterValuesKey;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How the Sheets API renders values it returns; `None` leaves the API default
+/// (formatted values, dates as serial numbers).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions<'a> {
+    /// FORMATTED_VALUE, UNFORMATTED_VALUE or FORMULA
+    pub value_render_option: Option<&'a str>,
+    /// SERIAL_NUMBER or FORMATTED_STRING
+    pub date_time_render_option: Option<&'a str>,
+}
+
+/// Streams a range from the Sheets API, invoking `on_rows` with batches of at most
+/// `chunk_rows` rows as they arrive. Returns the number of rows read.
+pub async fn stream_values(
+    access_token: &str,
+    spreadsheet_id: &str,
+    range: &str,
+    major_dimension: &str,
+    render: RenderOptions<'_>,
+    chunk_rows: usize,
+    mut on_rows: impl FnMut(&[Vec<Value>]) -> Result<()>,
+) -> Result<usize> {
+    let mut url = format!(
+        "{}v4/spreadsheets/{}/values/{}?majorDimension={}",
+        sheets_root_url(),
+        urlencoding::encode(spreadsheet_id),
+        urlencoding::encode(range),
+        urlencoding::encode(major_dimension)
+    );
+    if let Some(option) = render.value_render_option {
+        url.push_str(&format!(
+            "&valueRenderOption={}",
+            urlencoding::encode(option)
+        ));
+    }
+    if let Some(option) = render.date_time_render_option {
+        url.push_str(&format!(
+            "&dateTimeRenderOption={}",
+            urlencoding::encode(option)
+        ));
+    }
+    let client = get_http_client()?;
+    let mut response = guarded(Service::Sheets, async {
+        payload::log_request("GET", &url, None);
+        let response = client
+            .get(url)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(InvokeError::from_response(response).await);
+        }
+        Ok(response)
+    })
+    .await?;
+
+    let mut parser = RowStreamParser::default();
+    let mut rows = Vec::new();
+    let mut total = 0;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| InvokeError::Transport(e.to_string()))?
+    {
+        parser.feed(&chunk, &mut rows)?;
+        if rows.len() >= chunk_rows {
+            total += rows.len();
+            on_rows(&rows)?;
+            rows.clear();
+        }
+    }
+    if !rows.is_empty() {
+        total += rows.len();
+        on_rows(&rows)?;
+    }
+
+    Ok(total)
+}
+
+fn cell_text(cell: &Value) -> String {
+    match cell {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Appends one CSV record for `row`, quoting cells that need it.
+pub fn write_csv_row(out: &mut String, row: &[Value]) {
+    for (i, cell) in row.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let text = cell_text(cell);
+        if text.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&text.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(&text);
+        }
+    }
+    out.push('\n');
+}
+
+/// Appends one TSV record for `row`. TSV has no quoting, so tabs and line
+/// breaks inside cells become spaces.
+pub fn write_tsv_row(out: &mut String, row: &[Value]) {
+    for (i, cell) in row.iter().enumerate() {
+        if i > 0 {
+            out.push('\t');
+        }
+        out.push_str(&cell_text(cell).replace(['\t', '\n', '\r'], " "));
+    }
+    out.push('\n');
+}
+
+/// Renders `rows` as a Markdown table with the first row as its header.
+/// Short rows are padded so every row has the same number of columns.
+pub fn markdown_table(rows: &[Vec<Value>]) -> String {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    let mut write_row = |row: &[Value]| {
+        out.push('|');
+        for i in 0..width {
+            let text = row.get(i).map(cell_text).unwrap_or_default();
+            out.push(' ');
+            out.push_str(
+                &text
+                    .replace('|', "\\|")
+                    .replace("\r\n", "<br>")
+                    .replace('\n', "<br>"),
+            );
+            out.push_str(" |");
+        }
+        out.push('\n');
+    };
+    write_row(&rows[0]);
+    write_row(&vec![Value::String("---".to_string()); width]);
+    for row in &rows[1..] {
+        write_row(row);
+    }
+    out
+}
@@ -0,0 +1,114 @@
+use serde_json::json;
+
+use crate::a1::{column_index, column_letters, parse_cell, quote_sheet, Range};
+
+#[test]
+fn test_column_letters() {
+    assert_eq!(column_letters(0), "A");
+    assert_eq!(column_letters(25), "Z");
+    assert_eq!(column_letters(26), "AA");
+    assert_eq!(column_letters(701), "ZZ");
+    assert_eq!(column_index("AA"), Some(26));
+    assert_eq!(column_index("ZZ"), Some(701));
+    assert_eq!(column_index("Total"), None);
+}
+
+#[test]
+fn test_parse_range() {
+    let range = Range::parse("Data!B2:D10").unwrap();
+    assert_eq!(
+        range,
+        Range {
+            sheet: Some("Data".to_string()),
+            start_column: Some(1),
+            start_row: Some(1),
+            end_column: Some(3),
+            end_row: Some(9),
+        }
+    );
+
+    // Quoted names may hold spaces, `!` and doubled quotes
+    let range = Range::parse("'Q1 '' Results!'!a:c").unwrap();
+    assert_eq!(range.sheet.as_deref(), Some("Q1 ' Results!"));
+    assert_eq!(range.cells(), "A:C");
+
+    // Open-ended ranges and reversed corners
+    assert_eq!(Range::parse("A2:F").unwrap().end_row, None);
+    assert_eq!(Range::parse("2:5").unwrap().start_column, None);
+    assert_eq!(Range::parse("C3:A1").unwrap().cells(), "A1:C3");
+    assert_eq!(Range::parse("B7").unwrap().cells(), "B7");
+
+    for invalid in [
+        "", "A", "5", "A0", "1A:B2", "A:C5", "A2:5", "AAAA1", "'Data!A1", "!A1",
+    ] {
+        assert!(Range::parse(invalid).is_err(), "{}", invalid);
+    }
+    let error = Range::parse("A1:B2:C3").unwrap_err();
+    assert!(format!("{:#}", error).contains("expected A1 notation"));
+    assert!(Range::parse_cells("Data!A1").is_err());
+}
+
+#[test]
+fn test_format_range() {
+    let range = |sheet: &str| Range::on_sheet(sheet, "A1:B2").unwrap().to_string();
+    assert_eq!(range("Data"), "Data!A1:B2");
+    assert_eq!(range("Sheet_1"), "Sheet_1!A1:B2");
+    assert_eq!(range("Q1 ' Results"), "'Q1 '' Results'!A1:B2");
+    // Names that read as a cell or an R1C1 reference are quoted too
+    assert_eq!(range("Q1"), "'Q1'!A1:B2");
+    assert_eq!(range("R1C1"), "'R1C1'!A1:B2");
+    assert_eq!(range("2024"), "'2024'!A1:B2");
+
+    assert_eq!(Range::whole_sheet("My Sheet").to_string(), "'My Sheet'");
+    assert_eq!(Range::block(2, 9, 3, 2).to_string(), "C10:D12");
+    assert_eq!(quote_sheet("Orders"), "Orders");
+
+    // What is written parses back to the same range
+    let range = Range::on_sheet("It's 'quoted'", "C:E").unwrap();
+    assert_eq!(Range::parse(&range.to_string()).unwrap(), range);
+}
+
+#[test]
+fn test_grid_range() {
+    assert_eq!(
+        Range::parse("B2:D10").unwrap().to_grid_range(7),
+        json!({
+            "sheetId": 7,
+            "startRowIndex": 1,
+            "endRowIndex": 10,
+            "startColumnIndex": 1,
+            "endColumnIndex": 4,
+        })
+    );
+    assert_eq!(
+        Range::parse("A:C").unwrap().to_grid_range(0),
+        json!({"sheetId": 0, "startColumnIndex": 0, "endColumnIndex": 3})
+    );
+    assert_eq!(
+        Range::parse("A2:F").unwrap().to_grid_range(0),
+        json!({"sheetId": 0, "startRowIndex": 1, "startColumnIndex": 0, "endColumnIndex": 6})
+    );
+
+    for cells in ["B2:D10", "A:C", "2:5", "A2:F"] {
+        let range = Range::parse(cells).unwrap();
+        assert_eq!(
+            Range::from_grid_range(&range.to_grid_range(3), None),
+            range,
+            "{}",
+            cells
+        );
+    }
+    assert_eq!(
+        Range::from_grid_range(&json!({"sheetId": 3}), Some("Data")).to_string(),
+        "Data"
+    );
+}
+
+#[test]
+fn test_parse_cell() {
+    assert_eq!(parse_cell("b3").unwrap(), (Some(1), Some(2)));
+    assert_eq!(parse_cell("AB").unwrap(), (Some(27), None));
+    assert_eq!(parse_cell("12").unwrap(), (None, Some(11)));
+    assert!(parse_cell("B0").is_err());
+    assert!(parse_cell("B+3").is_err());
+}
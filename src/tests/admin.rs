@@ -0,0 +1,33 @@
+use crate::servers::{
+    admin::{directory_owner, AddGroupMemberArgs, GroupRole, ListGroupMembersArgs},
+    api_name,
+};
+
+#[test]
+fn test_directory_owner() {
+    assert_eq!(directory_owner(None), ("customer", "my_customer"));
+    assert_eq!(
+        directory_owner(Some("example.com")),
+        ("domain", "example.com")
+    );
+}
+
+#[test]
+fn test_group_member_args() {
+    let args: AddGroupMemberArgs = serde_json::from_value(serde_json::json!({
+        "group_key": "eng@example.com",
+        "email": "jane@example.com",
+    }))
+    .unwrap();
+    assert_eq!(args.role, GroupRole::Member);
+    assert_eq!(api_name(&GroupRole::Manager), "MANAGER");
+
+    let args: ListGroupMembersArgs = serde_json::from_value(serde_json::json!({
+        "group_key": "eng@example.com",
+        "roles": ["OWNER", "MANAGER"],
+    }))
+    .unwrap();
+    assert_eq!(args.roles, vec![GroupRole::Owner, GroupRole::Manager]);
+    assert_eq!(args.max_results, 100);
+    assert!(!args.include_derived_membership);
+}
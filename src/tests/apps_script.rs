@@ -0,0 +1,51 @@
+use serde_json::json;
+
+use crate::servers::apps_script::script_result;
+
+#[test]
+fn test_script_result() {
+    let operation = json!({
+        "done": true,
+        "response": {
+            "@type": "type.googleapis.com/google.apps.script.v1.ExecutionResponse",
+            "result": {"total": 42, "rows": [1, 2]}
+        }
+    });
+    assert_eq!(
+        script_result(&operation).unwrap(),
+        json!({"total": 42, "rows": [1, 2]})
+    );
+    // Functions that return nothing have no result
+    assert!(script_result(&json!({"done": true, "response": {}}))
+        .unwrap()
+        .is_null());
+}
+
+#[test]
+fn test_script_result_error() {
+    let operation = json!({
+        "done": true,
+        "error": {
+            "code": 3,
+            "message": "ScriptError",
+            "details": [{
+                "errorMessage": "Sheet Archive not found",
+                "errorType": "TypeError",
+                "scriptStackTraceElements": [
+                    {"function": "archiveRows", "lineNumber": 12},
+                    {"function": "main", "lineNumber": 3}
+                ]
+            }]
+        }
+    });
+    assert_eq!(
+        script_result(&operation).unwrap_err().to_string(),
+        "script failed with TypeError: Sheet Archive not found at archiveRows (line 12) < main (line 3)"
+    );
+
+    let operation = json!({"error": {"code": 3, "message": "Script function not found: nope"}});
+    assert_eq!(
+        script_result(&operation).unwrap_err().to_string(),
+        "script failed with Script function not found: nope"
+    );
+}
@@ -0,0 +1,50 @@
+use serde_json::json;
+
+use crate::audit::AuditRecord;
+
+#[test]
+fn test_audit_record_account() {
+    let meta = json!({"account": "client@example.com"});
+
+    // The server's configured account wins over what the client reports
+    let record = AuditRecord::new(
+        "write_values",
+        &json!({}),
+        &meta,
+        Some("me@example.com"),
+        None,
+    );
+    assert_eq!(record.account.as_deref(), Some("me@example.com"));
+
+    let record = AuditRecord::new("write_values", &json!({}), &meta, None, None);
+    assert_eq!(record.account.as_deref(), Some("client@example.com"));
+}
+
+#[test]
+fn test_audit_record_target() {
+    let target = |arguments: serde_json::Value, meta: serde_json::Value| {
+        AuditRecord::new("tool", &arguments, &meta, None, Some("default")).target
+    };
+
+    assert_eq!(
+        target(json!({"spreadsheet_id": "abc"}), json!({})).as_deref(),
+        Some("abc")
+    );
+    assert_eq!(
+        target(json!({"file_ids": ["a", "b"]}), json!({})).as_deref(),
+        Some("a,b")
+    );
+    assert_eq!(
+        target(json!({"destination_spreadsheet_id": "xyz"}), json!({})).as_deref(),
+        Some("xyz")
+    );
+    assert_eq!(
+        target(json!({}), json!({"spreadsheet_id": "meta"})).as_deref(),
+        Some("meta")
+    );
+    // Calls bound to the default spreadsheet name none themselves
+    assert_eq!(
+        target(json!({"range": "A1"}), json!({})).as_deref(),
+        Some("default")
+    );
+}
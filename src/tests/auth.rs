@@ -0,0 +1,24 @@
+use crate::auth::{parse_callback, pkce_challenge};
+
+#[test]
+fn test_pkce_challenge() {
+    // Example from RFC 7636 appendix B
+    assert_eq!(
+        pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+        "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+    );
+}
+
+#[test]
+fn test_parse_callback() {
+    assert_eq!(
+        parse_callback("GET /?state=s1&code=4%2Fabc&scope=email HTTP/1.1", "s1").unwrap(),
+        Some("4/abc".to_string())
+    );
+    assert_eq!(
+        parse_callback("GET /favicon.ico HTTP/1.1", "s1").unwrap(),
+        None
+    );
+    assert!(parse_callback("GET /?state=other&code=abc HTTP/1.1", "s1").is_err());
+    assert!(parse_callback("GET /?error=access_denied&state=s1 HTTP/1.1", "s1").is_err());
+}
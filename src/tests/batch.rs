@@ -0,0 +1,55 @@
+use crate::batch::{encode_batch, parse_batch_response, BatchRequest};
+use serde_json::json;
+
+#[test]
+fn test_encode_batch() {
+    let requests = vec![
+        BatchRequest {
+            method: "PATCH",
+            path: "/drive/v3/files/abc".to_string(),
+            body: Some(json!({"trashed": true})),
+        },
+        BatchRequest {
+            method: "DELETE",
+            path: "/drive/v3/files/def".to_string(),
+            body: None,
+        },
+    ];
+
+    let body = encode_batch("b1", &requests);
+    assert!(body.starts_with("--b1\r\nContent-Type: application/http\r\nContent-ID: <item0>"));
+    assert!(body.contains("PATCH /drive/v3/files/abc\r\nContent-Type: application/json"));
+    assert!(body.contains("{\"trashed\":true}"));
+    assert!(body.contains("Content-ID: <item1>\r\n\r\nDELETE /drive/v3/files/def\r\n"));
+    assert!(body.ends_with("--b1--\r\n"));
+}
+
+#[test]
+fn test_parse_batch_response() {
+    let body = "--batch_xyz\r\n\
+Content-Type: application/http\r\n\
+Content-ID: <response-item1>\r\n\
+\r\n\
+HTTP/1.1 404 Not Found\r\n\
+Content-Type: application/json; charset=UTF-8\r\n\
+\r\n\
+{\"error\": {\"code\": 404, \"message\": \"File not found: def.\"}}\r\n\
+--batch_xyz\r\n\
+Content-Type: application/http\r\n\
+Content-ID: <response-item0>\r\n\
+\r\n\
+HTTP/1.1 200 OK\r\n\
+Content-Type: application/json; charset=UTF-8\r\n\
+\r\n\
+{\"id\": \"abc\", \"trashed\": true}\r\n\
+--batch_xyz--\r\n";
+
+    let mut parts = parse_batch_response("multipart/mixed; boundary=batch_xyz", body).unwrap();
+    parts.sort_by_key(|(index, _)| *index);
+
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].0, 0);
+    assert!(parts[0].1.is_success());
+    assert_eq!(parts[0].1.body["id"], "abc");
+    assert_eq!(parts[1].1.status, 404);
+}
@@ -0,0 +1,88 @@
+use serde_json::json;
+
+use crate::bigquery::{external_table, field_names, infer_schema, ndjson_rows, TableRef};
+
+#[test]
+fn test_field_names() {
+    let headers = [
+        json!("Order ID"),
+        json!("order-id"),
+        json!(""),
+        json!("2024 revenue ($)"),
+        json!(null),
+        json!(42),
+    ];
+    assert_eq!(
+        field_names(&headers),
+        vec![
+            "Order_ID",
+            "order_id_2",
+            "column_3",
+            "_2024_revenue",
+            "column_5",
+            "_42"
+        ]
+    );
+}
+
+#[test]
+fn test_infer_schema() {
+    let names = field_names(&[json!("id"), json!("price"), json!("paid"), json!("note")]);
+    let rows = vec![
+        vec![json!(1), json!(2.5), json!(true), json!("a")],
+        vec![json!(2), json!(3), json!(false)],
+        vec![json!(3), json!(""), json!(true), json!(7)],
+    ];
+    let types = infer_schema(&names, &rows)
+        .iter()
+        .map(|field| field["type"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(types, ["INTEGER", "FLOAT", "BOOLEAN", "STRING"]);
+
+    // Columns with no values at all are left as text
+    let schema = infer_schema(&["empty".to_string()], &[vec![json!("")]]);
+    assert_eq!(
+        schema,
+        vec![json!({"name": "empty", "type": "STRING", "mode": "NULLABLE"})]
+    );
+}
+
+#[test]
+fn test_ndjson_rows() {
+    let names = field_names(&[json!("id"), json!("note")]);
+    let rows = vec![
+        vec![json!(1), json!(5)],
+        vec![json!(""), json!("")],
+        vec![json!(2.0)],
+        vec![json!(3), json!("x")],
+    ];
+    let schema = infer_schema(&names, &rows);
+    assert_eq!(
+        ndjson_rows(&schema, &rows),
+        "{\"id\":1,\"note\":\"5\"}\n{\"id\":2}\n{\"id\":3,\"note\":\"x\"}\n"
+    );
+}
+
+#[test]
+fn test_external_table() {
+    let table = TableRef {
+        project_id: "proj".to_string(),
+        dataset_id: "sales".to_string(),
+        table_id: "orders".to_string(),
+    };
+    let schema = vec![json!({"name": "id", "type": "INTEGER", "mode": "NULLABLE"})];
+    let definition = external_table(&table, "sheet-1", "Orders!A1:F", &schema);
+
+    assert_eq!(
+        definition["tableReference"],
+        json!({"projectId": "proj", "datasetId": "sales", "tableId": "orders"})
+    );
+    let config = &definition["externalDataConfiguration"];
+    assert_eq!(config["sourceFormat"], "GOOGLE_SHEETS");
+    assert_eq!(
+        config["sourceUris"],
+        json!(["https://docs.google.com/spreadsheets/d/sheet-1"])
+    );
+    assert_eq!(config["googleSheetsOptions"]["range"], "Orders!A1:F");
+    assert_eq!(config["schema"]["fields"], json!(schema));
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use async_mcp::types::CallToolRequest;
+use serde_json::json;
+
+use crate::{
+    cache::{EtagCache, ResponseCache},
+    servers::written_prefixes,
+};
+
+#[test]
+fn test_response_cache_expires() {
+    let cache = ResponseCache::new(Duration::from_millis(50));
+    cache.insert(
+        "spreadsheet/abc/info".to_string(),
+        json!({"title": "Budget"}),
+    );
+    assert_eq!(
+        cache.get("spreadsheet/abc/info"),
+        Some(json!({"title": "Budget"}))
+    );
+
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(cache.get("spreadsheet/abc/info"), None);
+}
+
+#[test]
+fn test_response_cache_invalidates_prefix() {
+    let cache = ResponseCache::new(Duration::from_secs(60));
+    cache.insert("spreadsheet/abc/info".to_string(), json!(1));
+    cache.insert("spreadsheet/abc/values/Sheet1!A1:B2".to_string(), json!(2));
+    cache.insert("spreadsheet/abcd/info".to_string(), json!(3));
+
+    cache.invalidate_prefix("spreadsheet/abc/");
+    assert_eq!(cache.get("spreadsheet/abc/info"), None);
+    assert_eq!(cache.get("spreadsheet/abc/values/Sheet1!A1:B2"), None);
+    assert_eq!(cache.get("spreadsheet/abcd/info"), Some(json!(3)));
+}
+
+#[test]
+fn test_etag_cache_evicts_least_recently_used() {
+    let cache = EtagCache::new(2);
+    cache.store("https://a", "\"1\"".to_string(), json!("a"));
+    std::thread::sleep(Duration::from_millis(2));
+    cache.store("https://b", "\"2\"".to_string(), json!("b"));
+    std::thread::sleep(Duration::from_millis(2));
+
+    // Reading `a` makes `b` the least recently used
+    assert_eq!(cache.lookup("https://a").unwrap().body, json!("a"));
+    std::thread::sleep(Duration::from_millis(2));
+    cache.store("https://c", "\"3\"".to_string(), json!("c"));
+    assert!(cache.lookup("https://b").is_none());
+    assert_eq!(cache.lookup("https://a").unwrap().etag, "\"1\"");
+    assert_eq!(cache.lookup("https://c").unwrap().body, json!("c"));
+}
+
+#[test]
+fn test_written_prefixes() {
+    let request = |arguments: serde_json::Value, meta: Option<serde_json::Value>| CallToolRequest {
+        name: "write_values".to_string(),
+        arguments: serde_json::from_value(arguments).unwrap(),
+        meta,
+    };
+
+    assert_eq!(
+        written_prefixes(&request(json!({"spreadsheet_id": "abc"}), None), None),
+        ["drive/", "spreadsheet/abc/"]
+    );
+    assert_eq!(
+        written_prefixes(
+            &request(
+                json!({"spreadsheet_id": "abc", "destination_spreadsheet_id": "xyz"}),
+                None
+            ),
+            None
+        ),
+        ["drive/", "spreadsheet/abc/", "spreadsheet/xyz/"]
+    );
+    assert_eq!(
+        written_prefixes(&request(json!({"file_ids": ["a", "b"]}), None), None),
+        ["drive/", "spreadsheet/a/", "spreadsheet/b/"]
+    );
+    // Without IDs in the arguments, the fallback spreadsheet is invalidated
+    assert_eq!(
+        written_prefixes(&request(json!({}), None), Some("default")),
+        ["drive/", "spreadsheet/default/"]
+    );
+    assert_eq!(
+        written_prefixes(&request(json!({}), None), None),
+        ["drive/"]
+    );
+}
@@ -0,0 +1,128 @@
+use serde_json::{json, Value};
+
+use crate::{
+    servers::calendar::{
+        event_body, event_time, respond_as_self, FindMeetingSlotsArgs, ResponseStatus, SlotSearch,
+    },
+    tests::args,
+};
+
+#[test]
+fn test_event_time() {
+    assert_eq!(
+        event_time("2024-05-01", None).unwrap(),
+        json!({"date": "2024-05-01"})
+    );
+    assert_eq!(
+        event_time("2024-05-01T09:00:00+02:00", None).unwrap(),
+        json!({"dateTime": "2024-05-01T09:00:00+02:00"})
+    );
+    assert_eq!(
+        event_time("2024-05-01T09:00:00", Some("Europe/Berlin")).unwrap(),
+        json!({"dateTime": "2024-05-01T09:00:00", "timeZone": "Europe/Berlin"})
+    );
+    assert!(event_time("tomorrow at 9", None).is_err());
+}
+
+#[test]
+fn test_event_body_only_includes_given_fields() {
+    let body = event_body(&args(json!({
+        "event_id": "e1",
+        "summary": "Standup",
+        "attendees": ["bob@example.com"]
+    })))
+    .unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "summary": "Standup",
+            "attendees": [{"email": "bob@example.com"}]
+        })
+    );
+
+    assert_eq!(event_body(&args(json!({}))).unwrap(), json!({}));
+}
+
+#[test]
+fn test_respond_as_self() {
+    let event = json!({
+        "attendees": [
+            {"email": "alice@example.com", "responseStatus": "accepted"},
+            {"email": "me@example.com", "self": true, "responseStatus": "needsAction"}
+        ]
+    });
+
+    let attendees = respond_as_self(&event, ResponseStatus::Declined).unwrap();
+    assert_eq!(attendees[0]["responseStatus"], "accepted");
+    assert_eq!(attendees[1]["responseStatus"], "declined");
+
+    assert!(serde_json::from_value::<ResponseStatus>(json!("maybe")).is_err());
+    assert!(respond_as_self(&json!({"attendees": []}), ResponseStatus::Accepted).is_err());
+}
+
+fn slot_search(value: Value) -> anyhow::Result<SlotSearch> {
+    SlotSearch::parse(&serde_json::from_value::<FindMeetingSlotsArgs>(value).unwrap())
+}
+
+#[test]
+fn test_find_meeting_slots() {
+    // Friday 2024-05-03 and, skipping the weekend, Monday 2024-05-06
+    let search = slot_search(json!({
+        "attendees": ["bob@example.com"],
+        "optional_attendees": ["carol@example.com", "dave@example.org"],
+        "duration_minutes": 60,
+        "date_from": "2024-05-03",
+        "date_to": "2024-05-06",
+        "utc_offset": "+02:00",
+        "max_results": 3
+    }))
+    .unwrap();
+    let (time_min, time_max) = search.range();
+    assert_eq!(time_min.to_rfc3339(), "2024-05-03T09:00:00+02:00");
+    assert_eq!(time_max.to_rfc3339(), "2024-05-06T17:00:00+02:00");
+
+    let free_busy = json!({"calendars": {
+        "primary": {"busy": [{"start": "2024-05-03T07:00:00Z", "end": "2024-05-03T09:00:00Z"}]},
+        "bob@example.com": {"busy": [{"start": "2024-05-03T12:00:00Z", "end": "2024-05-03T15:00:00Z"}]},
+        "carol@example.com": {"busy": [{"start": "2024-05-06T07:00:00Z", "end": "2024-05-06T08:00:00Z"}]},
+        "dave@example.org": {"errors": [{"domain": "global", "reason": "notFound"}]}
+    }});
+    let ranked = search.rank(&free_busy, "2024-05-01T00:00:00Z".parse().unwrap());
+
+    // Friday's only gap is 11:00-14:00; its middle slot keeps an hour free on both sides
+    assert_eq!(ranked["slots"][0]["start"], "2024-05-03T12:00:00+02:00");
+    assert_eq!(ranked["slots"][0]["end"], "2024-05-03T13:00:00+02:00");
+    assert_eq!(
+        ranked["slots"][0]["optionalAttendeesFree"],
+        json!(["carol@example.com"])
+    );
+    // Carol is busy early on Monday
+    assert_eq!(ranked["slots"][1]["start"], "2024-05-06T10:00:00+02:00");
+    assert_eq!(ranked["slots"][2]["start"], "2024-05-06T10:30:00+02:00");
+    assert_eq!(ranked["uncheckedCalendars"], json!(["dave@example.org"]));
+
+    // Slots already past are skipped
+    let ranked = search.rank(&free_busy, "2024-05-06T10:00:00Z".parse().unwrap());
+    assert_eq!(ranked["slots"][0]["start"], "2024-05-06T12:00:00+02:00");
+}
+
+#[test]
+fn test_find_meeting_slots_validates_window() {
+    let base = json!({"duration_minutes": 30, "date_from": "2024-05-06", "date_to": "2024-05-07"});
+    let with = |field: &str, value: Value| {
+        let mut args = base.clone();
+        args[field] = value;
+        slot_search(args)
+    };
+
+    assert!(slot_search(base.clone()).is_ok());
+    assert!(with("date_to", json!("2024-05-01")).is_err());
+    assert!(with("date_to", json!("2024-07-01")).is_err());
+    assert!(with("work_end", json!("08:00")).is_err());
+    assert!(with("utc_offset", json!("CET")).is_err());
+    assert!(with("duration_minutes", json!(0)).is_err());
+    // A weekend-only window needs include_weekends
+    let weekend =
+        json!({"duration_minutes": 30, "date_from": "2024-05-04", "date_to": "2024-05-05"});
+    assert!(slot_search(weekend).is_err());
+}
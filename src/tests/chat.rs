@@ -0,0 +1,45 @@
+use serde_json::json;
+
+use crate::{
+    servers::chat::{message_body, message_filter, space_name},
+    tests::args,
+};
+
+#[test]
+fn test_space_name() {
+    assert_eq!(space_name("AAAAb1c2"), "spaces/AAAAb1c2");
+    assert_eq!(space_name(" spaces/AAAAb1c2/ "), "spaces/AAAAb1c2");
+}
+
+#[test]
+fn test_message_filter() {
+    assert_eq!(message_filter(None, None), None);
+    assert_eq!(
+        message_filter(Some("2024-05-01T00:00:00Z"), Some("spaces/A/threads/T")).as_deref(),
+        Some("createTime > \"2024-05-01T00:00:00Z\" AND thread.name = spaces/A/threads/T")
+    );
+}
+
+#[test]
+fn test_message_body() {
+    assert_eq!(
+        message_body(&args(
+            json!({"space": "A", "text": "Import done", "thread": "job-42"})
+        ))
+        .unwrap(),
+        json!({"text": "Import done", "thread": {"threadKey": "job-42"}})
+    );
+    assert_eq!(
+        message_body(&args(json!({
+            "space": "A",
+            "cards": [{"header": {"title": "Nightly sync"}}],
+            "thread": "spaces/A/threads/T"
+        })))
+        .unwrap(),
+        json!({
+            "cardsV2": [{"cardId": "card-1", "card": {"header": {"title": "Nightly sync"}}}],
+            "thread": {"name": "spaces/A/threads/T"}
+        })
+    );
+    assert!(message_body(&args(json!({"space": "A", "text": ""}))).is_err());
+}
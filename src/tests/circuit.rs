@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use crate::{
+    circuit::{is_outage, trips_breaker, CircuitBreaker, Service},
+    InvokeError,
+};
+
+#[test]
+fn test_circuit_breaker_opens_and_resets() {
+    let breaker = CircuitBreaker::new(Service::Sheets, 3, Duration::from_secs(60));
+
+    breaker.record_failure();
+    breaker.record_failure();
+    assert!(breaker.check().is_ok());
+
+    breaker.record_failure();
+    match breaker.check() {
+        Err(InvokeError::ServiceUnavailable {
+            service,
+            retry_after,
+        }) => {
+            assert_eq!(service, "Google Sheets API");
+            assert!(retry_after > 0 && retry_after <= 60);
+        }
+        other => panic!("expected open breaker, got {:?}", other),
+    }
+
+    breaker.record_success();
+    assert!(breaker.check().is_ok());
+}
+
+#[test]
+fn test_circuit_breaker_half_open_after_cooldown() {
+    let breaker = CircuitBreaker::new(Service::Drive, 1, Duration::from_millis(10));
+    breaker.record_failure();
+    assert!(breaker.check().is_err());
+
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(breaker.check().is_ok());
+    // Only the one trial call goes through while it is in flight
+    assert!(breaker.check().is_err());
+
+    // A failing trial call reopens the breaker immediately
+    breaker.record_failure();
+    assert!(breaker.check().is_err());
+
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(breaker.check().is_ok());
+    breaker.record_success();
+    assert!(breaker.check().is_ok());
+    assert!(breaker.check().is_ok());
+}
+
+#[test]
+fn test_circuit_breaker_abandoned_probe() {
+    let breaker = CircuitBreaker::new(Service::Docs, 1, Duration::from_millis(10));
+    breaker.record_failure();
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(breaker.check().is_ok());
+    assert!(breaker.check().is_err());
+
+    // A trial call that never reports back is replaced after a cooldown
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(breaker.check().is_ok());
+}
+
+#[test]
+fn test_outage_classification() {
+    let outage = |error: InvokeError| is_outage(&error.into());
+    assert!(outage(InvokeError::Transport(
+        "connection reset".to_string()
+    )));
+    assert!(outage(InvokeError::Api {
+        code: 503,
+        status: "UNAVAILABLE".to_string(),
+        message: "backend error".to_string(),
+        reason: None,
+        domain: None,
+        help_links: Vec::new(),
+    }));
+    // A body that fails to decode says nothing about the service's health
+    assert!(!outage(InvokeError::GoogleApi(
+        "expected value at line 1 column 1".to_string()
+    )));
+
+    let rate_limited = InvokeError::RateLimited {
+        retry_after: None,
+        message: "Quota exceeded".to_string(),
+    };
+    assert!(trips_breaker(&rate_limited.into()));
+    let not_found = InvokeError::NotFound {
+        resource: "file".to_string(),
+    };
+    assert!(!trips_breaker(&not_found.into()));
+}
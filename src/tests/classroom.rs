@@ -0,0 +1,52 @@
+use serde_json::json;
+
+use crate::servers::classroom::{assignment_body, due_fields, materials, CreateAssignmentArgs};
+
+#[test]
+fn test_due_fields() {
+    assert_eq!(
+        due_fields("2026-10-30T17:30:00-07:00").unwrap(),
+        (
+            json!({"year": 2026, "month": 10, "day": 31}),
+            json!({"hours": 0, "minutes": 30})
+        )
+    );
+    assert!(due_fields("2026-10-30").is_err());
+}
+
+#[test]
+fn test_materials() {
+    assert_eq!(
+        materials(
+            &["https://example.com".to_string()],
+            &["abc123".to_string()]
+        ),
+        vec![
+            json!({"link": {"url": "https://example.com"}}),
+            json!({"driveFile": {"driveFile": {"id": "abc123"}, "shareMode": "VIEW"}}),
+        ]
+    );
+}
+
+#[test]
+fn test_assignment_body() {
+    let args: CreateAssignmentArgs = serde_json::from_value(json!({
+        "course_id": "123",
+        "title": "Essay",
+        "max_points": 20,
+        "due": "2026-11-02T09:00:00Z",
+        "draft": true,
+    }))
+    .unwrap();
+    assert_eq!(
+        assignment_body(&args).unwrap(),
+        json!({
+            "title": "Essay",
+            "workType": "ASSIGNMENT",
+            "state": "DRAFT",
+            "maxPoints": 20.0,
+            "dueDate": {"year": 2026, "month": 11, "day": 2},
+            "dueTime": {"hours": 9, "minutes": 0},
+        })
+    );
+}
@@ -0,0 +1,232 @@
+use crate::{
+    config::{parse_client_secrets, save_credentials, Config, Credentials, TransportKind},
+    servers::workspace::WorkspaceService,
+    token_store::TokenStoreKind,
+};
+
+#[test]
+fn test_parse_config() {
+    let config = Config::parse(
+        r#"
+        [credentials]
+        client_id = "id.apps.googleusercontent.com"
+        refresh_token = "1//refresh"
+
+        [sheets]
+        spreadsheet_id = "abc123"
+
+        [apps_script]
+        script_id = "script-1"
+
+        [tools]
+        read_only = true
+        deny = ["*clear*"]
+        timeout_secs = 30
+        timeouts = { read_values = 300 }
+
+        [logging]
+        level = "info"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.credentials.client_id.as_deref(),
+        Some("id.apps.googleusercontent.com")
+    );
+    assert_eq!(config.credentials.client_secret, None);
+    assert_eq!(config.sheets.spreadsheet_id.as_deref(), Some("abc123"));
+    assert_eq!(config.apps_script.script_id.as_deref(), Some("script-1"));
+    assert_eq!(config.tools.read_only, Some(true));
+    assert_eq!(config.tools.deny, vec!["*clear*"]);
+    assert!(config.tools.allow.is_empty());
+    assert_eq!(config.tools.timeout_secs, Some(30));
+    assert_eq!(config.tools.timeouts.get("read_values"), Some(&300));
+    assert_eq!(config.logging.level.as_deref(), Some("info"));
+}
+
+#[test]
+fn test_empty_config_and_unknown_keys() {
+    let config = Config::parse("").unwrap();
+    assert!(config.credentials.client_id.is_none());
+    assert!(config.tools.read_only.is_none());
+
+    assert!(Config::parse("[tools]\nreadonly = true").is_err());
+}
+
+#[test]
+fn test_select_profile() {
+    let config = Config::parse(
+        r#"
+        [credentials]
+        refresh_token = "default"
+
+        [profiles.work]
+        account = "me@work.example"
+        refresh_token = "work"
+
+        [profiles.personal]
+        account = "me@gmail.com"
+        refresh_token = "personal"
+        "#,
+    )
+    .unwrap();
+
+    let (name, credentials) = config.credentials_for(None, None).unwrap();
+    assert_eq!(name, None);
+    assert_eq!(credentials.refresh_token.as_deref(), Some("default"));
+
+    let (name, credentials) = config.credentials_for(Some("work"), None).unwrap();
+    assert_eq!(name, Some("work"));
+    assert_eq!(credentials.refresh_token.as_deref(), Some("work"));
+
+    let (name, _) = config.credentials_for(None, Some("ME@gmail.com")).unwrap();
+    assert_eq!(name, Some("personal"));
+
+    assert!(config.credentials_for(Some("missing"), None).is_err());
+    assert!(config
+        .credentials_for(None, Some("nobody@example.com"))
+        .is_err());
+}
+
+#[test]
+fn test_parse_log_rotation() {
+    use crate::logging::LogRotation;
+
+    let config = Config::parse("[logging]\nfile = \"server.log\"\nrotation = \"50MB\"").unwrap();
+    assert_eq!(config.logging.rotation, Some(LogRotation::Size(50 << 20)));
+    assert_eq!("hourly".parse(), Ok(LogRotation::Hourly));
+    assert!("weekly".parse::<LogRotation>().is_err());
+}
+
+#[test]
+fn test_save_credentials_keeps_other_settings() {
+    let dir = std::env::temp_dir().join(format!("mcp-google-config-{}", std::process::id()));
+    let path = dir.join("config.toml");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        &path,
+        "[sheets]\nspreadsheet_id = \"abc123\"\n\n[credentials]\nclient_id = \"old\"\n",
+    )
+    .unwrap();
+
+    let credentials = Credentials {
+        account: Some("me@example.com".to_string()),
+        client_id: Some("id".to_string()),
+        client_secret: Some("secret".to_string()),
+        refresh_token: Some("1//refresh".to_string()),
+        ..Default::default()
+    };
+    save_credentials(&path, None, &credentials).unwrap();
+    save_credentials(&path, Some("work"), &credentials).unwrap();
+    // Moving the token to a token store drops it from the file
+    save_credentials(
+        &path,
+        Some("work"),
+        &Credentials {
+            refresh_token: None,
+            ..credentials.clone()
+        },
+    )
+    .unwrap();
+
+    let config = Config::load(Some(&path)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(config.sheets.spreadsheet_id.as_deref(), Some("abc123"));
+    assert_eq!(config.credentials.client_id.as_deref(), Some("id"));
+    assert_eq!(
+        config.credentials.refresh_token.as_deref(),
+        Some("1//refresh")
+    );
+    let (profile, work) = config
+        .credentials_for(None, Some("me@example.com"))
+        .unwrap();
+    assert_eq!(profile, Some("work"));
+    assert_eq!(work.client_secret.as_deref(), Some("secret"));
+    assert!(work.refresh_token.is_none());
+}
+
+#[test]
+fn test_transport_page_size_and_scopes() {
+    let config = Config::parse(
+        r#"
+        [tools]
+        page_size = 50
+        cache_ttl_secs = 30
+
+        [transport]
+        kind = "sse"
+        port = 8080
+
+        [scopes]
+        sheets = ["spreadsheets.readonly"]
+        drive = []
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.tools.page_size, Some(50));
+    assert_eq!(config.tools.cache_ttl_secs, Some(30));
+    assert_eq!(config.transport.kind, Some(TransportKind::Sse));
+    assert_eq!(config.transport.port, Some(8080));
+    assert_eq!(config.transport.host, None);
+    assert_eq!(
+        config.scopes_for(WorkspaceService::Sheets),
+        vec!["https://www.googleapis.com/auth/spreadsheets.readonly"]
+    );
+    assert_eq!(
+        config.scopes_for(WorkspaceService::Drive),
+        vec!["https://www.googleapis.com/auth/drive"]
+    );
+    assert_eq!(
+        config.scopes_for(WorkspaceService::Chat),
+        vec![
+            "https://www.googleapis.com/auth/chat.spaces.readonly",
+            "https://www.googleapis.com/auth/chat.messages"
+        ]
+    );
+    assert!(Config::parse("[transport]\nkind = \"http\"").is_err());
+}
+
+#[test]
+fn test_parse_json_config() {
+    let config = Config::parse_json(
+        r#"{"sheets": {"spreadsheet_id": "abc123"}, "tools": {"allow": ["read_*"]}}"#,
+    )
+    .unwrap();
+    assert_eq!(config.sheets.spreadsheet_id.as_deref(), Some("abc123"));
+    assert_eq!(config.tools.allow, vec!["read_*"]);
+    assert!(Config::parse_json(r#"{"tool": {}}"#).is_err());
+}
+
+#[test]
+fn test_token_store_config() {
+    let config = Config::parse(
+        r#"
+        [tokens]
+        store = "file"
+        file = "/tmp/tokens.enc"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.tokens.store, Some(TokenStoreKind::File));
+    assert_eq!(
+        config.tokens.file.as_deref(),
+        Some(std::path::Path::new("/tmp/tokens.enc"))
+    );
+    assert!(Config::parse("[tokens]\nstore = \"vault\"").is_err());
+}
+
+#[test]
+fn test_parse_client_secrets() {
+    let (id, secret) = parse_client_secrets(
+        r#"{"installed": {"client_id": "id.apps.googleusercontent.com", "client_secret": "shh", "redirect_uris": ["http://localhost"]}}"#,
+    )
+    .unwrap();
+    assert_eq!(id, "id.apps.googleusercontent.com");
+    assert_eq!(secret, "shh");
+
+    assert!(parse_client_secrets(r#"{"web": {"client_id": "id"}}"#).is_err());
+    assert!(parse_client_secrets(r#"{"type": "service_account"}"#).is_err());
+}
@@ -0,0 +1,101 @@
+use serde_json::{json, Value};
+
+use crate::{
+    servers::docs::{
+        blocks_to_text, document_blocks, format_requests, insert_text_request, FormatTextArgs,
+    },
+    tests::args,
+};
+
+fn paragraph(text: &str, style: &str) -> Value {
+    json!({
+        "elements": [{"textRun": {"content": text}}],
+        "paragraphStyle": {"namedStyleType": style}
+    })
+}
+
+#[test]
+fn test_document_blocks() {
+    let document = json!({
+        "body": {
+            "content": [
+                {"endIndex": 1, "sectionBreak": {}},
+                {"startIndex": 1, "endIndex": 8, "paragraph": paragraph("Agenda\n", "HEADING_1")},
+                {"startIndex": 8, "endIndex": 14, "paragraph": {
+                    "elements": [{"textRun": {"content": "Intro"}}, {"textRun": {"content": "\n"}}],
+                    "paragraphStyle": {"namedStyleType": "NORMAL_TEXT"},
+                    "bullet": {"listId": "l1"}
+                }},
+                {"startIndex": 14, "endIndex": 30, "table": {
+                    "tableRows": [
+                        {"tableCells": [
+                            {"content": [{"paragraph": paragraph("Owner\n", "NORMAL_TEXT")}]},
+                            {"content": [{"paragraph": paragraph("Due\n", "NORMAL_TEXT")}]}
+                        ]}
+                    ]
+                }}
+            ]
+        }
+    });
+
+    let blocks = document_blocks(&document);
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0]["type"], "heading");
+    assert_eq!(blocks[0]["text"], "Agenda");
+    assert_eq!(blocks[0]["startIndex"], 1);
+    assert_eq!(blocks[1]["type"], "list_item");
+    assert_eq!(blocks[2]["rows"], json!([["Owner", "Due"]]));
+
+    assert_eq!(blocks_to_text(&blocks), "Agenda\n- Intro\nOwner\tDue\n");
+}
+
+#[test]
+fn test_insert_text_request() {
+    assert_eq!(
+        insert_text_request("hi", Some(5)),
+        json!({"insertText": {"text": "hi", "location": {"index": 5}}})
+    );
+    assert_eq!(
+        insert_text_request("hi", None),
+        json!({"insertText": {"text": "hi", "endOfSegmentLocation": {}}})
+    );
+}
+
+#[test]
+fn test_format_requests() {
+    let requests = format_requests(&args(json!({
+        "document_id": "d",
+        "start_index": 1,
+        "end_index": 8,
+        "bold": true,
+        "font_size": 14,
+        "paragraph_style": "HEADING_2"
+    })))
+    .unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["updateTextStyle"]["fields"], "bold,fontSize");
+    assert_eq!(
+        requests[0]["updateTextStyle"]["textStyle"]["fontSize"],
+        json!({"magnitude": 14.0, "unit": "PT"})
+    );
+    assert_eq!(
+        requests[1]["updateParagraphStyle"]["paragraphStyle"]["namedStyleType"],
+        "HEADING_2"
+    );
+
+    assert!(format_requests(&args(
+        json!({"document_id": "d", "start_index": 1, "end_index": 8})
+    ))
+    .is_err());
+    assert!(format_requests(&args(
+        json!({"document_id": "d", "start_index": 8, "end_index": 1, "bold": true})
+    ))
+    .is_err());
+    assert!(serde_json::from_value::<FormatTextArgs>(json!({
+        "document_id": "d",
+        "start_index": 1,
+        "end_index": 8,
+        "paragraph_style": "HEADING_9"
+    }))
+    .is_err());
+}
@@ -0,0 +1,59 @@
+use crate::{
+    config::Credentials,
+    doctor::{check_scopes, Status},
+    servers::workspace::WorkspaceService,
+};
+
+#[test]
+fn test_check_scopes() {
+    let granted = vec![
+        "openid".to_string(),
+        "https://www.googleapis.com/auth/drive.file".to_string(),
+    ];
+
+    let drive = check_scopes(WorkspaceService::Drive, &granted);
+    assert_eq!(drive.status, Status::Ok);
+    assert!(drive.detail.contains("drive.file"));
+
+    let sheets = check_scopes(WorkspaceService::Sheets, &granted);
+    assert_eq!(sheets.status, Status::Fail);
+    assert!(sheets
+        .fix
+        .unwrap()
+        .contains("https://www.googleapis.com/auth/spreadsheets"));
+}
+
+#[test]
+fn test_doctor_env_credentials() {
+    // `doctor` and `export` refresh with the credentials servers use, which
+    // may come from the environment alone
+    let env = |var: &str| match var {
+        "GOOGLE_CLIENT_ID" => Some("env-id".to_string()),
+        "GOOGLE_CLIENT_SECRET" => Some("env-secret".to_string()),
+        "GOOGLE_REFRESH_TOKEN" => Some("env-refresh".to_string()),
+        _ => None,
+    };
+    assert_eq!(
+        Credentials::default().refresh_credentials_with(env),
+        Some((
+            "env-id".to_string(),
+            "env-secret".to_string(),
+            "env-refresh".to_string()
+        ))
+    );
+
+    // Configured values win over the environment
+    let configured = Credentials {
+        client_id: Some("config-id".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        configured.refresh_credentials_with(env).unwrap().0,
+        "config-id"
+    );
+
+    assert_eq!(
+        Credentials::default().refresh_credentials_with(|_| None),
+        None
+    );
+}
@@ -59,7 +59,7 @@ async fn test_list_spreadsheets() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
 
     let access_token = env::var("GOOGLE_ACCESS_TOKEN").unwrap();
-    let drive = get_drive_client(&access_token);
+    let drive = get_drive_client(&access_token)?;
 
     // Add more detailed query parameters and debug output
     let result = drive
@@ -88,3 +88,114 @@ async fn test_list_spreadsheets() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_search_query() {
+    use crate::servers::drive::{search_query, SearchFilesArgs};
+
+    let args: SearchFilesArgs = serde_json::from_value(json!({
+        "name_contains": "Bob's report",
+        "mime_type": "application/pdf",
+        "parent_id": "folder-1",
+        "starred": true,
+        "modified_after": "2024-03-01"
+    }))
+    .unwrap();
+    assert_eq!(
+        search_query(&args).unwrap(),
+        "name contains 'Bob\\'s report' and mimeType = 'application/pdf' and 'folder-1' in parents \
+         and starred = true and trashed = false and modifiedTime > '2024-03-01T00:00:00Z'"
+    );
+
+    let args: SearchFilesArgs =
+        serde_json::from_value(json!({"full_text": "C:\\temp", "trashed": true})).unwrap();
+    assert_eq!(
+        search_query(&args).unwrap(),
+        "fullText contains 'C:\\\\temp' and trashed = true"
+    );
+
+    let args: SearchFilesArgs =
+        serde_json::from_value(json!({"modified_after": "last week"})).unwrap();
+    assert!(search_query(&args).is_err());
+}
+
+#[test]
+fn test_list_query() {
+    use crate::servers::drive::{list_query, ListFilesArgs};
+
+    let args: ListFilesArgs = serde_json::from_value(json!({
+        "query": "name contains 'budget' or starred = true",
+        "mime_type": "application/vnd.google-apps.spreadsheet"
+    }))
+    .unwrap();
+    assert_eq!(
+        list_query(&args),
+        "(name contains 'budget' or starred = true) and \
+         mimeType = 'application/vnd.google-apps.spreadsheet'"
+    );
+
+    let args: ListFilesArgs = serde_json::from_value(json!({"mime_type": "x' or 'a'='a"})).unwrap();
+    assert_eq!(list_query(&args), "mimeType = 'x\\' or \\'a\\'=\\'a'");
+
+    let args: ListFilesArgs = serde_json::from_value(json!({"query": " "})).unwrap();
+    assert_eq!(list_query(&args), "");
+}
+
+#[test]
+fn test_shortcut_fields() {
+    use crate::servers::drive::{file_fields, with_shortcut_details};
+
+    assert_eq!(
+        with_shortcut_details("nextPageToken,files(id,name)"),
+        "nextPageToken,files(shortcutDetails,id,name)"
+    );
+    assert_eq!(
+        with_shortcut_details("files(id,shortcutDetails)"),
+        "files(id,shortcutDetails)"
+    );
+    assert_eq!(with_shortcut_details("*"), "*");
+
+    assert_eq!(
+        file_fields("files(id,owners(emailAddress)),nextPageToken"),
+        Some("id,owners(emailAddress)")
+    );
+    assert_eq!(file_fields("nextPageToken,files"), None);
+}
+
+#[test]
+fn test_replace_shortcuts() {
+    use crate::{batch::BatchResponse, servers::drive::replace_shortcuts};
+
+    let shortcut = |id: &str, target: &str| {
+        json!({
+            "id": id,
+            "mimeType": "application/vnd.google-apps.shortcut",
+            "shortcutDetails": {"targetId": target}
+        })
+    };
+    let mut files = vec![
+        shortcut("s1", "t1"),
+        json!({"id": "f1", "mimeType": "application/pdf"}),
+        shortcut("s2", "t2"),
+    ];
+    replace_shortcuts(
+        &mut files,
+        vec![
+            BatchResponse {
+                status: 200,
+                body: json!({"id": "t1", "name": "Budget"}),
+            },
+            BatchResponse {
+                status: 404,
+                body: json!({"error": {"code": 404, "message": "File not found: t2."}}),
+            },
+        ],
+    );
+    assert_eq!(
+        files[0],
+        json!({"id": "t1", "name": "Budget", "shortcutId": "s1"})
+    );
+    assert_eq!(files[1]["id"], "f1");
+    assert_eq!(files[2]["id"], "s2");
+    assert!(files[2]["shortcutError"].is_string());
+}
@@ -0,0 +1,55 @@
+use crate::InvokeError;
+use serde_json::json;
+
+#[test]
+fn test_rate_limit_error_with_retry_info() {
+    let body = json!({
+        "error": {
+            "code": 429,
+            "message": "Quota exceeded for quota metric 'Read requests'",
+            "status": "RESOURCE_EXHAUSTED",
+            "details": [
+                {"@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "30s"}
+            ]
+        }
+    });
+
+    match InvokeError::from_google_json(&body, 429, None) {
+        InvokeError::RateLimited { retry_after, .. } => assert_eq!(retry_after, Some(30)),
+        other => panic!("expected RateLimited, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_not_found_and_generic_errors() {
+    let not_found = json!({
+        "error": {"code": 404, "message": "Requested entity was not found.", "status": "NOT_FOUND"}
+    });
+    assert!(matches!(
+        InvokeError::from_google_json(&not_found, 404, None),
+        InvokeError::NotFound { .. }
+    ));
+
+    let bad_range = json!({
+        "error": {
+            "code": 400,
+            "message": "Unable to parse range: Sheet9!A1",
+            "status": "INVALID_ARGUMENT",
+            "errors": [{"reason": "badRequest", "domain": "global"}]
+        }
+    });
+    let error = InvokeError::from_google_json(&bad_range, 400, None);
+    assert_eq!(
+        error.to_string(),
+        "Google API error 400 (INVALID_ARGUMENT): Unable to parse range: Sheet9!A1 [badRequest]"
+    );
+}
+
+#[test]
+fn test_oauth_error_body() {
+    let body = json!({"error": "invalid_grant", "error_description": "Token has been expired or revoked."});
+    assert!(matches!(
+        InvokeError::from_google_json(&body, 400, None),
+        InvokeError::Unauthenticated { .. }
+    ));
+}
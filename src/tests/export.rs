@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use crate::export::{
+    default_export_format, export_mime_type, open_output, resolve_out_dir, sanitize_file_name,
+};
+
+#[test]
+fn test_export_formats() {
+    assert_eq!(
+        default_export_format("application/vnd.google-apps.spreadsheet"),
+        "xlsx"
+    );
+    assert_eq!(
+        default_export_format("application/vnd.google-apps.drawing"),
+        "pdf"
+    );
+    assert_eq!(export_mime_type("CSV"), Some("text/csv"));
+    assert_eq!(export_mime_type("exe"), None);
+}
+
+#[test]
+fn test_sanitize_file_name() {
+    assert_eq!(sanitize_file_name("Q3/Q4: Budget?"), "Q3_Q4_ Budget_");
+    assert_eq!(sanitize_file_name(".."), "untitled");
+}
+
+#[test]
+fn test_resolve_out_dir() {
+    let root = std::env::temp_dir().join(format!("mcp-google-downloads-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("exports")).unwrap();
+    let root = root.canonicalize().unwrap();
+
+    assert!(resolve_out_dir(Path::new("exports"), None).is_err());
+    assert_eq!(
+        resolve_out_dir(Path::new("exports/new"), Some(&root)).unwrap(),
+        root.join("exports/new")
+    );
+    assert!(resolve_out_dir(Path::new("../escape"), Some(&root)).is_err());
+    assert!(resolve_out_dir(Path::new("exports/../../escape"), Some(&root)).is_err());
+    assert!(resolve_out_dir(&std::env::temp_dir(), Some(&root)).is_err());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_open_output_keeps_existing_files() {
+    let path = std::env::temp_dir().join(format!("mcp-google-export-{}.txt", std::process::id()));
+    std::fs::write(&path, "kept").unwrap();
+
+    assert!(open_output(&path, false).await.is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "kept");
+    drop(open_output(&path, true).await.unwrap());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+    std::fs::remove_file(&path).unwrap();
+}
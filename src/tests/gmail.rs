@@ -0,0 +1,151 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::json;
+
+use crate::{
+    servers::gmail::{append_links, compose_message, decode_body, decode_message, Attachment},
+    tests::args,
+};
+
+fn encode(text: &str) -> String {
+    URL_SAFE_NO_PAD.encode(text)
+}
+
+#[test]
+fn test_decode_body_accepts_padding() {
+    assert_eq!(decode_body(&encode("héllo?>")).as_deref(), Some("héllo?>"));
+    assert_eq!(decode_body("aGk=").as_deref(), Some("hi"));
+    assert_eq!(decode_body("!!"), None);
+}
+
+#[test]
+fn test_decode_multipart_message() {
+    let message = json!({
+        "id": "m1",
+        "threadId": "t1",
+        "payload": {
+            "mimeType": "multipart/mixed",
+            "headers": [
+                {"name": "Subject", "value": "Report"},
+                {"name": "from", "value": "alice@example.com"},
+                {"name": "X-Mailer", "value": "noise"}
+            ],
+            "parts": [
+                {
+                    "mimeType": "multipart/alternative",
+                    "parts": [
+                        {"mimeType": "text/plain", "body": {"data": encode("plain body")}},
+                        {"mimeType": "text/html", "body": {"data": encode("<p>html body</p>")}}
+                    ]
+                },
+                {
+                    "mimeType": "application/pdf",
+                    "filename": "report.pdf",
+                    "body": {"attachmentId": "att1", "size": 1024}
+                }
+            ]
+        }
+    });
+
+    let decoded = decode_message(&message);
+    assert_eq!(decoded["headers"]["Subject"], "Report");
+    assert_eq!(decoded["headers"]["From"], "alice@example.com");
+    assert!(decoded["headers"].get("X-Mailer").is_none());
+    assert_eq!(decoded["text"], "plain body");
+    assert_eq!(decoded["html"], "<p>html body</p>");
+    assert_eq!(decoded["attachments"][0]["filename"], "report.pdf");
+    assert_eq!(decoded["attachments"][0]["attachmentId"], "att1");
+}
+
+#[test]
+fn test_compose_message() {
+    let message = compose_message(
+        &args(json!({
+            "to": ["bob@example.com", "carol@example.com"],
+            "subject": "Café menu",
+            "body": "See you there",
+            "thread_id": "t1",
+            "in_reply_to": "<abc@mail.example.com>"
+        })),
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(message["threadId"], "t1");
+    let raw = URL_SAFE_NO_PAD
+        .decode(message["raw"].as_str().unwrap())
+        .unwrap();
+    let raw = String::from_utf8(raw).unwrap();
+    assert!(raw.contains("To: bob@example.com, carol@example.com\r\n"));
+    assert!(raw.contains("Subject: =?UTF-8?B?Q2Fmw6kgbWVudQ==?=\r\n"));
+    assert!(raw.contains("In-Reply-To: <abc@mail.example.com>\r\n"));
+    assert!(raw.contains("Content-Type: text/plain; charset=\"UTF-8\"\r\n"));
+    assert!(raw.ends_with("U2VlIHlvdSB0aGVyZQ==\r\n"));
+}
+
+#[test]
+fn test_compose_rejects_header_injection() {
+    let result = compose_message(
+        &args(json!({
+            "to": ["bob@example.com"],
+            "subject": "Hi\r\nBcc: eve@example.com",
+            "body": "x"
+        })),
+        &[],
+    );
+    assert!(result.is_err());
+
+    assert!(compose_message(&args(json!({"to": [], "subject": "s", "body": "b"})), &[]).is_err());
+}
+
+#[test]
+fn test_compose_message_with_attachments() {
+    let attachments = [Attachment {
+        filename: "Q3 report.pdf".to_string(),
+        mime_type: "application/pdf".to_string(),
+        bytes: b"%PDF-1.7".to_vec(),
+    }];
+    let message = compose_message(
+        &args(json!({
+            "to": ["bob@example.com"],
+            "cc": ["carol@example.com"],
+            "subject": "Report",
+            "body": "<p>Attached</p>",
+            "html": true
+        })),
+        &attachments,
+    )
+    .unwrap();
+
+    let raw = URL_SAFE_NO_PAD
+        .decode(message["raw"].as_str().unwrap())
+        .unwrap();
+    let raw = String::from_utf8(raw).unwrap();
+    let boundary = raw
+        .split("boundary=\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap();
+    assert!(raw.contains("Cc: carol@example.com\r\n"));
+    assert!(raw.contains("Content-Type: text/html; charset=\"UTF-8\"\r\n"));
+    assert!(raw.contains("Content-Type: application/pdf; name=\"Q3 report.pdf\"\r\n"));
+    assert!(raw.contains("Content-Disposition: attachment; filename=\"Q3 report.pdf\"\r\n"));
+    assert!(raw.contains("JVBERi0xLjc=\r\n"));
+    assert_eq!(raw.matches(&format!("--{}\r\n", boundary)).count(), 2);
+    assert!(raw.ends_with(&format!("--{}--\r\n", boundary)));
+}
+
+#[test]
+fn test_append_links() {
+    let links = [(
+        "Q3 <draft>".to_string(),
+        "https://docs.google.com/d/1?a=1&b=2".to_string(),
+    )];
+    assert_eq!(
+        append_links("See:", false, &links),
+        "See:\nQ3 <draft>: https://docs.google.com/d/1?a=1&b=2"
+    );
+    assert_eq!(
+        append_links("<p>See:</p>", true, &links),
+        "<p>See:</p><p><a href=\"https://docs.google.com/d/1?a=1&amp;b=2\">Q3 &lt;draft&gt;</a></p>"
+    );
+}
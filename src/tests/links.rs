@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::links::{file_id, resolve_arguments, sheet_gid};
+
+#[test]
+fn test_file_id() {
+    let id = "1BxiMVs0XRA5nFMdKvBdBZjgmUUqptlbs74OgvE2upms";
+    for link in [
+        format!("https://docs.google.com/spreadsheets/d/{}/edit#gid=0", id),
+        format!(
+            "https://docs.google.com/spreadsheets/u/1/d/{}/edit?usp=sharing",
+            id
+        ),
+        format!("https://docs.google.com/document/d/{}", id),
+        format!("https://drive.google.com/file/d/{}/view", id),
+        format!("https://drive.google.com/open?id={}", id),
+        format!("https://drive.google.com/drive/u/0/folders/{}", id),
+        format!("  {}  ", id),
+    ] {
+        assert_eq!(file_id(&link), id, "{}", link);
+    }
+
+    // Anything else is passed through for the API to reject
+    let published = "https://docs.google.com/spreadsheets/d/e/2PACX-1vQ/pubhtml";
+    assert_eq!(file_id(published), published);
+    let other = format!("https://example.com/d/{}", id);
+    assert_eq!(file_id(&other), other);
+}
+
+#[test]
+fn test_sheet_gid() {
+    assert_eq!(
+        sheet_gid("https://docs.google.com/spreadsheets/d/abc/edit#gid=1234"),
+        Some(1234)
+    );
+    assert_eq!(
+        sheet_gid("https://docs.google.com/spreadsheets/d/abc/edit?gid=5#gid=5"),
+        Some(5)
+    );
+    assert_eq!(
+        sheet_gid("https://docs.google.com/spreadsheets/d/abc/edit"),
+        None
+    );
+    assert_eq!(sheet_gid("abc"), None);
+}
+
+#[test]
+fn test_resolve_arguments() {
+    let mut arguments = HashMap::from([
+        (
+            "spreadsheet_id".to_string(),
+            json!("https://docs.google.com/spreadsheets/d/abc/edit#gid=7"),
+        ),
+        (
+            "file_ids".to_string(),
+            json!(["https://drive.google.com/file/d/f1/view", "f2"]),
+        ),
+        ("range".to_string(), json!("A1:B2")),
+    ]);
+    resolve_arguments(&mut arguments);
+    assert_eq!(arguments["spreadsheet_id"], "abc");
+    assert_eq!(arguments["file_ids"], json!(["f1", "f2"]));
+    assert_eq!(arguments["sheet_id"], 7);
+    assert_eq!(arguments["range"], "A1:B2");
+
+    // A sheet named in the call wins over the link's gid
+    let mut arguments = HashMap::from([
+        (
+            "spreadsheet_id".to_string(),
+            json!("https://docs.google.com/spreadsheets/d/abc/edit#gid=7"),
+        ),
+        ("sheet".to_string(), json!("Data")),
+    ]);
+    resolve_arguments(&mut arguments);
+    assert!(!arguments.contains_key("sheet_id"));
+}
@@ -0,0 +1,194 @@
+use serde_json::{json, Value};
+
+use crate::markdown::{from_document, parse, requests, Block, Inline, Style};
+
+fn run(content: &str, style: Value) -> Value {
+    json!({"textRun": {"content": content, "textStyle": style}})
+}
+
+fn paragraph(elements: Vec<Value>, extra: Value) -> Value {
+    let mut paragraph =
+        json!({"elements": elements, "paragraphStyle": {"namedStyleType": "NORMAL_TEXT"}});
+    if let Value::Object(extra) = extra {
+        paragraph.as_object_mut().unwrap().extend(extra);
+    }
+    json!({ "paragraph": paragraph })
+}
+
+fn item(text: &str, list_id: &str, level: u64) -> Value {
+    paragraph(
+        vec![run(text, json!({}))],
+        json!({"bullet": {"listId": list_id, "nestingLevel": level}}),
+    )
+}
+
+#[test]
+fn test_inline() {
+    let inline = Inline::parse("A **bold _and_ italic** [link](https://x.y \"t\") `a*b*` \\*x\\*");
+    assert_eq!(inline.text, "A bold and italic link a*b* *x*");
+    assert_eq!(
+        inline.styles,
+        vec![
+            (7, 10, Style::Italic),
+            (2, 17, Style::Bold),
+            (18, 22, Style::Link("https://x.y".to_string())),
+            (23, 27, Style::Code),
+        ]
+    );
+
+    // Underscores inside words, and lone markers, stay literal
+    let inline = Inline::parse("snake_case_name * 2 ~~gone~~");
+    assert_eq!(inline.text, "snake_case_name * 2 gone");
+    assert_eq!(inline.styles, vec![(20, 24, Style::Strikethrough)]);
+
+    // Offsets count UTF-16 units
+    let inline = Inline::parse("😀 *hi*");
+    assert_eq!(inline.styles, vec![(3, 5, Style::Italic)]);
+}
+
+#[test]
+fn test_parse_blocks() {
+    let blocks = parse(
+        "# Title #\n\nSome\ntext\n\n- one\n  - nested\n- two\n1. first\n\n> quoted\n> more\n\n---\n```rust\nlet x = 1;\n```\n| A | B |\n|---|:-:|\n| 1 | a\\|b |\n\nSub\n---\n",
+    );
+    let inline = Inline::parse;
+    assert_eq!(
+        blocks,
+        vec![
+            Block::Heading {
+                level: 1,
+                text: inline("Title")
+            },
+            Block::Paragraph(inline("Some text")),
+            Block::ListItem {
+                ordered: false,
+                depth: 0,
+                text: inline("one")
+            },
+            Block::ListItem {
+                ordered: false,
+                depth: 1,
+                text: inline("nested")
+            },
+            Block::ListItem {
+                ordered: false,
+                depth: 0,
+                text: inline("two")
+            },
+            Block::ListItem {
+                ordered: true,
+                depth: 0,
+                text: inline("first")
+            },
+            Block::Quote(inline("quoted more")),
+            Block::Code("let x = 1;".to_string()),
+            Block::Table(vec![
+                vec![inline("A"), inline("B")],
+                vec![inline("1"), inline("a|b")],
+            ]),
+            Block::Heading {
+                level: 2,
+                text: inline("Sub")
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_requests() {
+    let requests = requests(&parse("## Hi\n\n- **a**\n  - b\n\nEnd"));
+    assert_eq!(
+        requests,
+        vec![
+            json!({"insertText": {"text": "Hi\n", "location": {"index": 1}}}),
+            json!({"updateParagraphStyle": {
+                "range": {"startIndex": 1, "endIndex": 4},
+                "paragraphStyle": {"namedStyleType": "HEADING_2"},
+                "fields": "namedStyleType",
+            }}),
+            json!({"insertText": {"text": "a\n\tb\n", "location": {"index": 4}}}),
+            json!({"updateTextStyle": {
+                "range": {"startIndex": 4, "endIndex": 5},
+                "textStyle": {"bold": true},
+                "fields": "bold",
+            }}),
+            json!({"createParagraphBullets": {
+                "range": {"startIndex": 4, "endIndex": 9},
+                "bulletPreset": "BULLET_DISC_CIRCLE_SQUARE",
+            }}),
+            // The nesting tab is gone once the bullets exist
+            json!({"insertText": {"text": "End\n", "location": {"index": 8}}}),
+        ]
+    );
+}
+
+#[test]
+fn test_table_requests() {
+    let requests = requests(&parse("| A | B |\n|---|---|\n| 1 |   |\n\nAfter"));
+    assert_eq!(
+        requests,
+        vec![
+            json!({"insertTable": {"rows": 2, "columns": 2, "location": {"index": 1}}}),
+            json!({"insertText": {"text": "1", "location": {"index": 10}}}),
+            json!({"insertText": {"text": "B", "location": {"index": 7}}}),
+            json!({"updateTextStyle": {
+                "range": {"startIndex": 7, "endIndex": 8},
+                "textStyle": {"bold": true},
+                "fields": "bold",
+            }}),
+            json!({"insertText": {"text": "A", "location": {"index": 5}}}),
+            json!({"updateTextStyle": {
+                "range": {"startIndex": 5, "endIndex": 6},
+                "textStyle": {"bold": true},
+                "fields": "bold",
+            }}),
+            // 1 newline + table start + 2 rows of 5 + 3 characters of text
+            json!({"insertText": {"text": "After\n", "location": {"index": 16}}}),
+        ]
+    );
+}
+
+#[test]
+fn test_from_document() {
+    let code = json!({"weightedFontFamily": {"fontFamily": "Roboto Mono"}});
+    let cell = |text: &str| json!({"content": [paragraph(vec![run(text, json!({}))], json!({}))]});
+    let document = json!({
+        "lists": {
+            "bullets": {"listProperties": {"nestingLevels": [{"glyphSymbol": "●"}, {"glyphSymbol": "○"}]}},
+            "numbers": {"listProperties": {"nestingLevels": [{"glyphType": "DECIMAL"}]}},
+        },
+        "body": {"content": [
+            {"sectionBreak": {}},
+            paragraph(
+                vec![run("Plan\n", json!({}))],
+                json!({"paragraphStyle": {"namedStyleType": "HEADING_1"}}),
+            ),
+            paragraph(
+                vec![
+                    run("Ship ", json!({})),
+                    run("fast ", json!({"bold": true})),
+                    run("docs", json!({"link": {"url": "https://x.y"}})),
+                    run(" 2*3\n", json!({})),
+                ],
+                json!({}),
+            ),
+            item("One\n", "bullets", 0),
+            item("Sub\n", "bullets", 1),
+            item("A\n", "numbers", 0),
+            item("B\n", "numbers", 0),
+            paragraph(vec![run("let x = 1;\n", code.clone())], json!({})),
+            paragraph(vec![run("let y = 2;\n", code)], json!({})),
+            {"table": {"tableRows": [
+                {"tableCells": [cell("Name\n"), cell("Qty\n")]},
+                {"tableCells": [cell("a|b\n"), cell("\n")]},
+            ]}},
+            paragraph(vec![run("\n", json!({}))], json!({})),
+        ]}
+    });
+
+    assert_eq!(
+        from_document(&document),
+        "# Plan\n\nShip **fast** [docs](https://x.y) 2\\*3\n\n- One\n    - Sub\n\n1. A\n2. B\n\n```\nlet x = 1;\nlet y = 2;\n```\n\n| Name | Qty |\n| --- | --- |\n| a\\|b |  |\n"
+    );
+    assert_eq!(from_document(&json!({})), "");
+}
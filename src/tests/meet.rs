@@ -0,0 +1,53 @@
+use serde_json::json;
+
+use crate::servers::meet::{conference_artifacts, space_name};
+
+#[test]
+fn test_space_name() {
+    assert_eq!(
+        space_name("spaces/jQCFfuBOdN5z").unwrap(),
+        "spaces/jQCFfuBOdN5z"
+    );
+    assert_eq!(space_name(" abc-mnop-xyz ").unwrap(), "spaces/abc-mnop-xyz");
+    assert_eq!(
+        space_name("https://meet.google.com/abc-mnop-xyz?authuser=1").unwrap(),
+        "spaces/abc-mnop-xyz"
+    );
+    assert_eq!(
+        space_name("meet.google.com/abc-mnop-xyz").unwrap(),
+        "spaces/abc-mnop-xyz"
+    );
+    assert!(space_name("https://zoom.us/j/123").is_err());
+    assert!(space_name("spaces/").is_err());
+    assert!(space_name("").is_err());
+}
+
+#[test]
+fn test_conference_artifacts() {
+    let record = json!({
+        "name": "conferenceRecords/c1",
+        "startTime": "2024-05-01T09:00:00Z",
+        "endTime": "2024-05-01T09:45:00Z",
+        "space": "spaces/s1"
+    });
+    let recordings = json!({"recordings": [{
+        "name": "conferenceRecords/c1/recordings/r1",
+        "state": "FILE_GENERATED",
+        "driveDestination": {"file": "f1", "exportUri": "https://drive.google.com/file/d/f1"}
+    }]});
+    assert_eq!(
+        conference_artifacts(&record, &recordings, &json!({})),
+        json!({
+            "conferenceRecord": "conferenceRecords/c1",
+            "startTime": "2024-05-01T09:00:00Z",
+            "endTime": "2024-05-01T09:45:00Z",
+            "recordings": [{
+                "name": "conferenceRecords/c1/recordings/r1",
+                "state": "FILE_GENERATED",
+                "driveFile": "f1",
+                "url": "https://drive.google.com/file/d/f1"
+            }],
+            "transcripts": []
+        })
+    );
+}
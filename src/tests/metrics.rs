@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use crate::metrics::Metrics;
+
+#[test]
+fn test_metrics_render() {
+    let metrics = Metrics::default();
+    metrics.record_call("read_values", Duration::from_millis(80), false, 120);
+    metrics.record_call("read_values", Duration::from_secs(3), true, 40);
+    metrics.record_retry("sheets");
+
+    let rendered = metrics.render();
+    assert!(rendered.contains("mcp_tool_calls_total{tool=\"read_values\"} 2"));
+    assert!(rendered.contains("mcp_tool_errors_total{tool=\"read_values\"} 1"));
+    assert!(rendered.contains("mcp_tool_response_bytes_total{tool=\"read_values\"} 160"));
+    assert!(
+        rendered.contains("mcp_tool_duration_seconds_bucket{tool=\"read_values\",le=\"0.1\"} 1")
+    );
+    assert!(rendered.contains("mcp_tool_duration_seconds_bucket{tool=\"read_values\",le=\"5\"} 2"));
+    assert!(rendered.contains("mcp_google_api_retries_total{service=\"sheets\"} 1"));
+}
@@ -0,0 +1,216 @@
+use crate::{
+    mock::{start_mock_google, MOCK_ACCESS_TOKEN, MOCK_SPREADSHEET_ID},
+    servers::{drive, sheets},
+};
+use async_mcp::{
+    client::Client,
+    protocol::RequestOptions,
+    transport::{ClientInMemoryTransport, ServerInMemoryTransport, Transport},
+    types::CallToolRequest,
+};
+use serde_json::{json, Value};
+use std::{collections::HashMap, time::Duration};
+
+async fn sheets_client() -> anyhow::Result<Client<ClientInMemoryTransport>> {
+    start_mock_google().await;
+    let transport = ClientInMemoryTransport::new(|t: ServerInMemoryTransport| {
+        tokio::spawn(async move { sheets::build(t).unwrap().listen().await.unwrap() })
+    });
+    transport.open().await?;
+
+    let client = async_mcp::client::ClientBuilder::new(transport).build();
+    let client_clone = client.clone();
+    tokio::spawn(async move { client_clone.start().await });
+    Ok(client)
+}
+
+async fn drive_client() -> anyhow::Result<Client<ClientInMemoryTransport>> {
+    start_mock_google().await;
+    let transport = ClientInMemoryTransport::new(|t: ServerInMemoryTransport| {
+        tokio::spawn(async move { drive::build(t).unwrap().listen().await.unwrap() })
+    });
+    transport.open().await?;
+
+    let client = async_mcp::client::ClientBuilder::new(transport).build();
+    let client_clone = client.clone();
+    tokio::spawn(async move { client_clone.start().await });
+    Ok(client)
+}
+
+async fn call_tool(
+    client: &Client<ClientInMemoryTransport>,
+    name: &str,
+    arguments: Value,
+) -> anyhow::Result<Value> {
+    let params = CallToolRequest {
+        name: name.to_string(),
+        arguments: Some(serde_json::from_value::<HashMap<String, Value>>(arguments)?),
+        meta: Some(json!({
+            "access_token": MOCK_ACCESS_TOKEN,
+            "spreadsheet_id": MOCK_SPREADSHEET_ID,
+        })),
+    };
+    let response = client
+        .request(
+            "tools/call",
+            Some(serde_json::to_value(&params)?),
+            RequestOptions::default().timeout(Duration::from_secs(5)),
+        )
+        .await?;
+    assert_ne!(
+        response["isError"],
+        json!(true),
+        "{} failed: {}",
+        name,
+        response
+    );
+
+    let text = response["content"][0]["text"].as_str().unwrap_or_default();
+    Ok(serde_json::from_str(text)?)
+}
+
+#[tokio::test]
+async fn test_mock_sheets_tools() -> anyhow::Result<()> {
+    let client = sheets_client().await?;
+
+    let info = call_tool(&client, "get_sheet_info", json!({})).await?;
+    assert_eq!(info[0]["title"], "Sheet1");
+    assert_eq!(info[0]["maxRange"], "A1:Z1000");
+    assert_eq!(info[1]["title"], "Data");
+    assert_eq!(info[1]["sheetId"], 1);
+    assert_eq!(info[1]["maxRange"], "A1:AD100");
+    assert_eq!(info[1]["frozenRowCount"], 1);
+
+    let values = call_tool(&client, "read_values", json!({"sheet": "Sheet1"})).await?;
+    assert_eq!(values["values"][1][0], "Ada");
+
+    let write = call_tool(
+        &client,
+        "write_values",
+        json!({"sheet": "Sheet1", "range": "A1", "values": [["1"]]}),
+    )
+    .await?;
+    assert_eq!(write["updatedCells"], 1);
+
+    let append = call_tool(
+        &client,
+        "append_values",
+        json!({"sheet": "Sheet1", "values": [["Grace", "grace@example.com", "99"]]}),
+    )
+    .await?;
+    assert_eq!(append["updates"]["updatedRange"], "Sheet1!A4:C4");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_copy_range_keeps_dates() -> anyhow::Result<()> {
+    let client = sheets_client().await?;
+
+    let copied = call_tool(
+        &client,
+        "copy_range_between_spreadsheets",
+        json!({
+            "sheet": "Sheet1",
+            "range": "A1:C3",
+            "destination_spreadsheet_id": "mock-copy-target",
+            "destination_sheet": "Sheet1"
+        }),
+    )
+    .await?;
+    assert_eq!(copied["destination"], "Sheet1!A1:C3");
+    assert_eq!(copied["rows"], 3);
+
+    // Dates are read as shown and written for Sheets to parse, so they are
+    // not pasted as day serial numbers
+    let requests = start_mock_google()
+        .await
+        .received_requests()
+        .await
+        .unwrap_or_default();
+    let queries = |method: &str, prefix: &str| -> Vec<HashMap<String, String>> {
+        requests
+            .iter()
+            .filter(|request| {
+                request.method.as_str() == method && request.url.path().starts_with(prefix)
+            })
+            .map(|request| request.url.query_pairs().into_owned().collect())
+            .collect()
+    };
+    let read = queries("GET", "/v4/spreadsheets/mock-spreadsheet/values/")
+        .into_iter()
+        .find(|query| query.get("fields").map(String::as_str) == Some("values"))
+        .expect("source values read");
+    assert_eq!(read["valueRenderOption"], "UNFORMATTED_VALUE");
+    assert_eq!(read["dateTimeRenderOption"], "FORMATTED_STRING");
+    let write = queries("PUT", "/v4/spreadsheets/mock-copy-target/values/")
+        .pop()
+        .expect("destination values written");
+    assert_eq!(write["valueInputOption"], "USER_ENTERED");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_drive_tools() -> anyhow::Result<()> {
+    let client = drive_client().await?;
+
+    let files = call_tool(&client, "list_files", json!({"page_size": 5})).await?;
+    assert_eq!(files["files"][0]["id"], MOCK_SPREADSHEET_ID);
+    assert_eq!(files["nextPageToken"], "mock-page-2");
+
+    let files = call_tool(
+        &client,
+        "list_files",
+        json!({"page_size": 5, "page_token": "mock-page-2"}),
+    )
+    .await?;
+    assert_eq!(files["files"][0]["id"], "mock-file");
+    assert!(files["nextPageToken"].is_null());
+
+    let trashed = call_tool(&client, "trash_files", json!({"file_ids": ["a", "b"]})).await?;
+    assert_eq!(trashed["succeeded"], 2);
+    assert_eq!(trashed["items"][1]["result"]["id"], "b");
+
+    let folder = call_tool(&client, "create_folder", json!({"name": "Archive"})).await?;
+    assert_eq!(folder["mimeType"], "application/vnd.google-apps.folder");
+
+    let moved = call_tool(
+        &client,
+        "move_file",
+        json!({"file_id": "mock-file", "folder_id": "mock-folder"}),
+    )
+    .await?;
+    assert_eq!(moved["parents"], json!(["mock-folder"]));
+
+    let start = call_tool(&client, "get_changes", json!({})).await?;
+    assert_eq!(start["newStartPageToken"], "41");
+    let changes = call_tool(&client, "get_changes", json!({"page_token": "41"})).await?;
+    assert_eq!(changes["changes"][0]["fileId"], "mock-file");
+    assert_eq!(changes["newStartPageToken"], "42");
+
+    let meta = json!({"_meta": {"access_token": MOCK_ACCESS_TOKEN}});
+    let resources = client
+        .request(
+            "resources/list",
+            Some(meta.clone()),
+            RequestOptions::default(),
+        )
+        .await?;
+    assert_eq!(
+        resources["resources"][0]["uri"],
+        "gdrive://mock-spreadsheet"
+    );
+    assert_eq!(resources["resources"][0]["mimeType"], "text/csv");
+    assert_eq!(resources["nextCursor"], "mock-page-2");
+
+    let mut read = meta;
+    read["uri"] = json!("gdrive://mock-file");
+    let contents = client
+        .request("resources/read", Some(read), RequestOptions::default())
+        .await?;
+    assert_eq!(contents["contents"][0]["mimeType"], "text/plain");
+    assert!(contents["contents"][0]["text"].is_string());
+
+    Ok(())
+}
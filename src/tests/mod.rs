@@ -1,2 +1,50 @@
+pub mod a1;
+pub mod admin;
+pub mod apps_script;
+pub mod audit;
+pub mod auth;
+pub mod batch;
+pub mod bigquery;
+pub mod cache;
+pub mod calendar;
+pub mod chat;
+pub mod circuit;
+pub mod classroom;
+pub mod config;
+pub mod docs;
+pub mod doctor;
 pub mod drive;
+pub mod error;
+pub mod export;
+pub mod gmail;
+pub mod links;
+pub mod markdown;
+pub mod meet;
+pub mod metrics;
+#[cfg(feature = "mock-google")]
+pub mod mock;
+pub mod options;
+pub mod payload;
+pub mod query;
+pub mod quota;
+pub mod redact;
+pub mod relay;
+pub mod resources;
+pub mod retry;
+pub mod scopes;
 pub mod sheets;
+pub mod slides;
+pub mod streaming;
+pub mod tasks;
+pub mod token_store;
+pub mod upload;
+#[cfg(feature = "youtube")]
+pub mod youtube;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Parses tool arguments the way the tool handlers do.
+pub(crate) fn args<A: DeserializeOwned>(value: Value) -> A {
+    serde_json::from_value(value).unwrap()
+}
@@ -0,0 +1,302 @@
+use std::{sync::Arc, time::Duration};
+
+use async_mcp::types::{CallToolRequest, CallToolResponse, ToolResponseContent};
+use serde_json::json;
+
+use crate::{
+    servers::{
+        get_access_token, get_spreadsheet_id, glob_match, is_unauthenticated, list_tools, sheets,
+        tag_errors, target_resource, with_access_token,
+        workspace::{self, WorkspaceService},
+        ServerOptions, Timeouts, ToolContext,
+    },
+    InvokeError,
+};
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*", "read_values"));
+    assert!(glob_match("read_*", "read_values"));
+    assert!(glob_match("*clear*", "clear_values"));
+    assert!(glob_match("get_sheet_inf?", "get_sheet_info"));
+    assert!(!glob_match("read_*", "write_values"));
+    assert!(!glob_match("*delete", "delete_file_now"));
+}
+
+#[test]
+fn test_allow_and_deny_tools() {
+    let options = ServerOptions {
+        allow_tools: vec!["*_values".to_string(), "get_sheet_info".to_string()],
+        deny_tools: vec!["clear_*".to_string()],
+        ..Default::default()
+    };
+
+    assert!(options.is_tool_enabled("read_values"));
+    assert!(options.is_tool_enabled("get_sheet_info"));
+    assert!(!options.is_tool_enabled("clear_values"));
+    assert!(!options.is_tool_enabled("create_spreadsheet"));
+}
+
+#[test]
+fn test_request_timeout_caps_tool_deadline() {
+    let timeouts = Timeouts::default();
+    let request = |name: &str, meta: Option<serde_json::Value>| CallToolRequest {
+        name: name.to_string(),
+        arguments: None,
+        meta,
+    };
+
+    assert_eq!(
+        timeouts.for_request(&request("get_sheet_info", None)),
+        Duration::from_secs(60)
+    );
+    assert_eq!(
+        timeouts.for_request(&request("read_values", None)),
+        Duration::from_secs(120)
+    );
+    assert_eq!(
+        timeouts.for_request(&request("read_values", Some(json!({ "timeout_ms": 5000 })))),
+        Duration::from_secs(5)
+    );
+    // A client timeout never extends the configured deadline
+    assert_eq!(
+        timeouts.for_request(&request(
+            "get_sheet_info",
+            Some(json!({ "timeout_ms": 600000 }))
+        )),
+        Duration::from_secs(60)
+    );
+}
+
+#[tokio::test]
+async fn test_list_tools_honours_read_only() -> anyhow::Result<()> {
+    let options = ServerOptions {
+        read_only: true,
+        ..Default::default()
+    };
+    let tools = list_tools(sheets::build_with_options, options).await?;
+    let names = tools.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+
+    assert!(names.contains(&"read_values"));
+    assert!(names.contains(&"get_sheet_info"));
+    assert!(!names.contains(&"write_values"));
+    assert!(!names.contains(&"clear_values"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_workspace_server_combines_services() -> anyhow::Result<()> {
+    let tools = list_tools(
+        |transport, options| {
+            workspace::build_with_options(transport, WorkspaceService::ALL, options)
+        },
+        ServerOptions::default(),
+    )
+    .await?;
+    let names = tools.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+
+    assert!(names.contains(&"list_files"));
+    assert!(names.contains(&"read_values"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tool_schemas_are_objects() -> anyhow::Result<()> {
+    let tools = list_tools(
+        |transport, options| {
+            workspace::build_with_options(transport, WorkspaceService::ALL, options)
+        },
+        ServerOptions::default(),
+    )
+    .await?;
+    for tool in &tools {
+        assert_eq!(tool.input_schema["type"], "object", "{}", tool.name);
+        // Tools without arguments still advertise an empty property list
+        assert!(
+            tool.input_schema["properties"].is_object(),
+            "{} has no properties",
+            tool.name
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_workspace_namespaces_tools() -> anyhow::Result<()> {
+    let options = ServerOptions {
+        deny_tools: vec!["drive.trash_*".to_string(), "clear_values".to_string()],
+        ..Default::default()
+    };
+    let tools = list_tools(
+        |transport, options| {
+            workspace::build_namespaced(
+                transport,
+                &[WorkspaceService::Drive, WorkspaceService::Sheets],
+                options,
+            )
+        },
+        options,
+    )
+    .await?;
+    let names = tools.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+
+    assert!(names.contains(&"drive.list_files"));
+    assert!(names.contains(&"sheets.read_values"));
+    assert!(!names.contains(&"list_files"));
+    assert!(!names.contains(&"drive.trash_files"));
+    assert!(!names.contains(&"sheets.clear_values"));
+    Ok(())
+}
+
+#[test]
+fn test_namespaced_tool_timeouts() {
+    let timeouts = Timeouts::default();
+    assert_eq!(
+        timeouts.for_tool("sheets.read_values"),
+        timeouts.for_tool("read_values")
+    );
+    assert_eq!(timeouts.for_tool("drive.list_files"), timeouts.default);
+}
+
+#[test]
+fn test_parse_workspace_service() {
+    assert_eq!("Sheets".parse(), Ok(WorkspaceService::Sheets));
+    assert_eq!(" drive".parse(), Ok(WorkspaceService::Drive));
+    assert_eq!("gmail".parse(), Ok(WorkspaceService::Gmail));
+    assert_eq!("Calendar".parse(), Ok(WorkspaceService::Calendar));
+    assert_eq!("docs".parse(), Ok(WorkspaceService::Docs));
+    assert_eq!("slides".parse(), Ok(WorkspaceService::Slides));
+    assert_eq!("tasks".parse(), Ok(WorkspaceService::Tasks));
+    assert_eq!("apps_script".parse(), Ok(WorkspaceService::AppsScript));
+    assert_eq!("chat".parse(), Ok(WorkspaceService::Chat));
+    assert_eq!("meet".parse(), Ok(WorkspaceService::Meet));
+    assert_eq!("admin".parse(), Ok(WorkspaceService::Admin));
+    assert_eq!("classroom".parse(), Ok(WorkspaceService::Classroom));
+    #[cfg(feature = "youtube")]
+    assert_eq!("youtube".parse(), Ok(WorkspaceService::YouTube));
+    assert!("forms".parse::<WorkspaceService>().is_err());
+}
+
+#[test]
+fn test_target_resource() {
+    let mut request = CallToolRequest {
+        name: "read_values".to_string(),
+        arguments: None,
+        meta: Some(json!({ "spreadsheet_id": "sheet-1" })),
+    };
+    assert_eq!(target_resource(&request).as_deref(), Some("sheet-1"));
+
+    request.arguments = Some([("file_ids".to_string(), json!(["a", "b"]))].into());
+    assert_eq!(target_resource(&request).as_deref(), Some("a,b"));
+
+    request.arguments = None;
+    request.meta = None;
+    assert_eq!(target_resource(&request), None);
+}
+
+#[test]
+fn test_errors_carry_request_id() {
+    let response = CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: "Error: not found".to_string(),
+        }],
+        is_error: Some(true),
+        meta: None,
+    };
+    let tagged = tag_errors(Ok(response), "abc123").unwrap();
+    match &tagged.content[0] {
+        ToolResponseContent::Text { text } => {
+            assert_eq!(text, "Error: not found (request_id: abc123)")
+        }
+        _ => panic!("expected text content"),
+    }
+
+    let failed = tag_errors(Err(anyhow::anyhow!("Missing access_token")), "abc123");
+    assert!(failed
+        .unwrap_err()
+        .to_string()
+        .ends_with("(request_id: abc123)"));
+}
+
+#[test]
+fn test_spreadsheet_id_fallback() {
+    let mut request = CallToolRequest {
+        name: "read_values".to_string(),
+        arguments: None,
+        meta: Some(json!({ "spreadsheet_id": "from-meta" })),
+    };
+    assert_eq!(
+        get_spreadsheet_id(Some("from-args"), &request, Some("default")).unwrap(),
+        "from-args"
+    );
+    assert_eq!(
+        get_spreadsheet_id(None, &request, Some("default")).unwrap(),
+        "from-meta"
+    );
+
+    request.meta = None;
+    assert_eq!(
+        get_spreadsheet_id(None, &request, Some("default")).unwrap(),
+        "default"
+    );
+    assert_eq!(
+        get_spreadsheet_id(
+            Some("https://docs.google.com/spreadsheets/d/from-link/edit#gid=0"),
+            &request,
+            None
+        )
+        .unwrap(),
+        "from-link"
+    );
+    assert!(get_spreadsheet_id(None, &request, None).is_err());
+}
+
+#[tokio::test]
+async fn test_tool_context() {
+    let options = ServerOptions {
+        default_spreadsheet_id: Some("default".to_string()),
+        ..Default::default()
+    };
+    let request = CallToolRequest {
+        name: "read_values".to_string(),
+        arguments: None,
+        meta: None,
+    };
+    let ctx = ToolContext::new("ya29.token".to_string(), request, Arc::new(options)).unwrap();
+
+    assert_eq!(ctx.access_token(), "ya29.token");
+    assert_eq!(ctx.spreadsheet_id(None).unwrap(), "default");
+    assert_eq!(ctx.spreadsheet_id(Some("sheet-1")).unwrap(), "sheet-1");
+    // Hubs are built once and reused for the rest of the call
+    assert!(std::ptr::eq(ctx.sheets(), ctx.sheets()));
+}
+
+#[test]
+fn test_with_access_token_keeps_meta() {
+    let request = CallToolRequest {
+        name: "list_files".to_string(),
+        arguments: None,
+        meta: Some(json!({"spreadsheet_id": "sheet1"})),
+    };
+    let request = with_access_token(request, "ya29.fresh");
+    assert_eq!(get_access_token(&request).unwrap(), "ya29.fresh");
+    assert_eq!(request.meta.unwrap()["spreadsheet_id"], "sheet1");
+}
+
+#[test]
+fn test_is_unauthenticated() {
+    let expired = InvokeError::from_google_json(
+        &json!({"error": {"code": 401, "message": "Invalid Credentials", "status": "UNAUTHENTICATED"}}),
+        401,
+        None,
+    );
+    assert!(is_unauthenticated(&expired.into()));
+
+    let forbidden = InvokeError::from_google_json(
+        &json!({"error": {"code": 403, "message": "Forbidden", "status": "PERMISSION_DENIED"}}),
+        403,
+        None,
+    );
+    assert!(!is_unauthenticated(&forbidden.into()));
+    assert!(!is_unauthenticated(&anyhow::anyhow!("timed out")));
+}
@@ -0,0 +1,37 @@
+use serde_json::json;
+
+use crate::payload::{mask_fields, truncate, PayloadLogOptions};
+
+#[test]
+fn test_mask_fields_keeps_allowed_fields() {
+    let options = PayloadLogOptions::default();
+    let body = json!({
+        "range": "Sheet1!A1:B2",
+        "values": [["secret", 42]],
+        "properties": {"title": "Budget", "locale": "en_US"}
+    });
+
+    assert_eq!(
+        mask_fields(&body, &options),
+        json!({
+            "range": "Sheet1!A1:B2",
+            "values": [["***", "***"]],
+            "properties": {"title": "Budget", "locale": "***"}
+        })
+    );
+
+    let options = PayloadLogOptions {
+        extra_fields: vec!["values".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        mask_fields(&body, &options)["values"],
+        json!([["secret", 42]])
+    );
+}
+
+#[test]
+fn test_truncate_respects_char_boundaries() {
+    assert_eq!(truncate("short", 10), "short");
+    assert_eq!(truncate("héllo", 2), "h… (5 bytes truncated)");
+}
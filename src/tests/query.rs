@@ -0,0 +1,178 @@
+use serde_json::{json, Value};
+
+use crate::query::{infer_header, Filter, Query};
+
+fn table() -> Vec<Vec<Value>> {
+    serde_json::from_value(json!([
+        ["Name", "Region", "Total", "Paid"],
+        ["Acme", "EMEA", 120, true],
+        ["Globex", "AMER", 80.5, false],
+        ["Initech", "EMEA", 45, true],
+        ["Hooli", "APAC", "", false],
+        ["Umbrella", "AMER", 300],
+    ]))
+    .unwrap()
+}
+
+fn run(sql: &str) -> Value {
+    let table = table();
+    let headers = table[0]
+        .iter()
+        .map(|cell| cell.as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    let query = Query::parse(sql, &headers, 0).unwrap();
+    let rows = table[1..]
+        .iter()
+        .filter(|row| query.matches(row))
+        .cloned()
+        .collect::<Vec<_>>();
+    serde_json::to_value(query.evaluate(&rows)).unwrap()
+}
+
+#[test]
+fn test_query_filter_and_order() {
+    assert_eq!(
+        run("SELECT Name, Total WHERE Region = 'EMEA' OR Total > 100 ORDER BY Total DESC"),
+        json!({
+            "columns": ["Name", "Total"],
+            "rows": [["Umbrella", 300], ["Acme", 120], ["Initech", 45]]
+        })
+    );
+}
+
+#[test]
+fn test_query_predicates() {
+    assert_eq!(
+        run("select name where name like '%E%' and not region in ('EMEA', 'APAC') order by name")
+            ["rows"],
+        json!([["Globex"], ["Umbrella"]])
+    );
+    assert_eq!(
+        run("SELECT A, C AS amount WHERE Total IS NULL"),
+        json!({"columns": ["Name", "amount"], "rows": [["Hooli", ""]]})
+    );
+    assert_eq!(
+        run("SELECT Name WHERE Paid = TRUE AND Total >= 45 LIMIT 1 OFFSET 1")["rows"],
+        json!([["Initech"]])
+    );
+    assert_eq!(
+        run("SELECT * WHERE \"Total\" < 50")["rows"],
+        json!([["Initech", "EMEA", 45, true]])
+    );
+}
+
+#[test]
+fn test_query_aggregates() {
+    assert_eq!(
+        run("SELECT Region, COUNT(*) AS n, SUM(Total), MAX(Name) GROUP BY Region ORDER BY n DESC, Region"),
+        json!({
+            "columns": ["Region", "n", "SUM(Total)", "MAX(Name)"],
+            "rows": [
+                ["AMER", 2, 380.5, "Umbrella"],
+                ["EMEA", 2, 165, "Initech"],
+                ["APAC", 1, null, "Hooli"]
+            ]
+        })
+    );
+    assert_eq!(
+        run("SELECT COUNT(Total), AVG(Total), MIN(Total) WHERE Paid = TRUE")["rows"],
+        json!([[2, 82.5, 45]])
+    );
+}
+
+#[test]
+fn test_query_errors() {
+    let headers = vec!["Name".to_string(), "Total".to_string()];
+    let error = |sql: &str| Query::parse(sql, &headers, 0).unwrap_err().to_string();
+    assert_eq!(
+        error("SELECT Price"),
+        "unknown column `Price` (columns: Name, Total)"
+    );
+    assert_eq!(
+        error("SELECT Name, SUM(Total)"),
+        "column `Name` must appear in GROUP BY or inside an aggregate"
+    );
+    assert_eq!(
+        error("SELECT Name WHERE Total"),
+        "expected a comparison at the end of the query"
+    );
+    assert_eq!(
+        error("SELECT Name LIMIT 2.5"),
+        "LIMIT takes a whole number of rows, not `2.5`"
+    );
+    assert_eq!(
+        error("SELECT Name FROM Sheet1"),
+        "unexpected `FROM` after the query"
+    );
+    assert!(Query::parse("SELECT Name WHERE Name = 'open", &headers, 0).is_err());
+}
+
+#[test]
+fn test_query_without_header() {
+    // Columns are named by their sheet letters, starting at the range's first column
+    let query = Query::parse("SELECT D, C WHERE C > 1", &[], 2).unwrap();
+    let rows = vec![vec![json!(1), json!("a")], vec![json!(2), json!("b")]];
+    let matched = rows
+        .into_iter()
+        .filter(|row| query.matches(row))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        serde_json::to_value(query.evaluate(&matched)).unwrap(),
+        json!({"columns": ["D", "C"], "rows": [["b", 2]]})
+    );
+    assert!(Query::parse("SELECT A", &[], 2).is_err());
+}
+
+#[test]
+fn test_query_keeps() {
+    let headers = vec!["Name".to_string()];
+    let query = Query::parse("SELECT Name LIMIT 2 OFFSET 1", &headers, 0).unwrap();
+    assert!(query.keeps(2));
+    assert!(!query.keeps(3));
+    let query = Query::parse("SELECT Name ORDER BY Name LIMIT 2", &headers, 0).unwrap();
+    assert!(query.keeps(10));
+}
+
+#[test]
+fn test_filter() {
+    let table = table();
+    let headers = vec![
+        "Name".to_string(),
+        "Region".to_string(),
+        "Total".to_string(),
+    ];
+    let matched = |condition: &str| {
+        let filter = Filter::parse(condition, &headers, 0).unwrap();
+        table[1..]
+            .iter()
+            .filter(|row| filter.matches(row))
+            .map(|row| row[0].clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        matched("region == 'EMEA'"),
+        vec![json!("Acme"), json!("Initech")]
+    );
+    assert_eq!(
+        matched("Region = 'AMER' AND C > 100"),
+        vec![json!("Umbrella")]
+    );
+    assert_eq!(
+        Filter::parse("Name = 'Acme' LIMIT 1", &headers, 0)
+            .unwrap_err()
+            .to_string(),
+        "unexpected `LIMIT` after the condition"
+    );
+    assert!(Filter::parse("Email = 'x@y.com'", &headers, 0).is_err());
+}
+
+#[test]
+fn test_infer_header() {
+    let table = table();
+    assert!(infer_header(&table));
+    assert!(!infer_header(&table[1..]));
+    assert!(!infer_header(&[vec![json!("Name"), json!("name")]]));
+    assert!(!infer_header(&[vec![json!("Name"), json!("")]]));
+    assert!(!infer_header(&[vec![json!("2024"), json!("2025")]]));
+    assert!(!infer_header(&[]));
+}
@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    circuit::Service,
+    quota::{QuotaLimits, QuotaTracker},
+};
+
+fn tracker(sheets: u32) -> QuotaTracker {
+    QuotaTracker::new(
+        QuotaLimits {
+            sheets: Some(sheets),
+            drive: None,
+        },
+        Duration::from_secs(100),
+    )
+}
+
+#[test]
+fn test_quota_delays_calls_over_the_limit() {
+    let quota = tracker(2);
+    let start = Instant::now();
+
+    assert_eq!(quota.reserve("alice", Service::Sheets, start), None);
+    let second = start + Duration::from_secs(10);
+    assert_eq!(quota.reserve("alice", Service::Sheets, second), None);
+
+    // The third call waits for the first to leave the window, the fourth for the second
+    let now = start + Duration::from_secs(20);
+    assert_eq!(
+        quota.reserve("alice", Service::Sheets, now),
+        Some(Duration::from_secs(80))
+    );
+    assert_eq!(
+        quota.reserve("alice", Service::Sheets, now),
+        Some(Duration::from_secs(90))
+    );
+
+    let status = quota.status_of("alice", now);
+    assert_eq!(status["sheets"].calls, 4);
+    assert_eq!(status["sheets"].queued, 2);
+    assert_eq!(status["sheets"].throttled, 2);
+    assert_eq!(status["sheets"].throttle_wait_ms, 170_000);
+}
+
+#[test]
+fn test_quota_is_per_user_and_service() {
+    let quota = tracker(1);
+    let now = Instant::now();
+
+    assert_eq!(quota.reserve("alice", Service::Sheets, now), None);
+    assert_eq!(quota.reserve("bob", Service::Sheets, now), None);
+    // Drive is untracked here, so it never waits
+    for _ in 0..10 {
+        assert_eq!(quota.reserve("alice", Service::Drive, now), None);
+    }
+    assert!(quota.reserve("alice", Service::Sheets, now).is_some());
+
+    // Calls leave the window after 100 seconds
+    let later = now + Duration::from_secs(201);
+    assert_eq!(quota.reserve("bob", Service::Sheets, later), None);
+
+    let status = quota.status_of("bob", later);
+    assert_eq!(status["sheets"].calls, 1);
+    assert_eq!(status["sheets"].limit, Some(1));
+    assert_eq!(status["drive"].calls, 0);
+    assert_eq!(status["drive"].limit, None);
+}
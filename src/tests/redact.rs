@@ -0,0 +1,24 @@
+use crate::redact::redact;
+
+#[test]
+fn test_redact_tokens() {
+    let payload = r#"{"client_id":"abc.apps.googleusercontent.com","client_secret":"GOCSPX-s3cr3t","refresh_token":"1//0gLx-abc","grant_type":"refresh_token"}"#;
+    let redacted = redact(payload);
+    assert!(!redacted.contains("s3cr3t"));
+    assert!(!redacted.contains("0gLx-abc"));
+    assert!(redacted.contains("abc.apps.googleusercontent.com"));
+    assert!(redacted.contains(r#""grant_type":"refresh_token""#));
+
+    assert_eq!(
+        redact("Authorization: Bearer ya29.a0AfH6SM-xyz"),
+        "Authorization: Bearer ***"
+    );
+    assert_eq!(
+        redact("token ya29.a0AfH6SM-xyz expired"),
+        "token ya29.*** expired"
+    );
+    assert_eq!(
+        redact("https://x?access_token=abc123&alt=json"),
+        "https://x?access_token=***&alt=json"
+    );
+}
@@ -0,0 +1,34 @@
+use std::net::SocketAddr;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::relay::relay;
+
+#[tokio::test]
+async fn test_relay_forwards_both_directions() -> anyhow::Result<()> {
+    let upstream = TcpListener::bind("127.0.0.1:0").await?;
+    let target: SocketAddr = upstream.local_addr()?;
+    tokio::spawn(async move {
+        let (mut socket, _) = upstream.accept().await.unwrap();
+        let mut buf = [0u8; 4];
+        socket.read_exact(&mut buf).await.unwrap();
+        socket
+            .write_all(&buf.map(|b| b.to_ascii_uppercase()))
+            .await
+            .unwrap();
+    });
+
+    let front = TcpListener::bind("127.0.0.1:0").await?;
+    let front_addr = front.local_addr()?;
+    tokio::spawn(relay(front, target));
+
+    let mut client = TcpStream::connect(front_addr).await?;
+    client.write_all(b"ping").await?;
+    let mut reply = [0u8; 4];
+    client.read_exact(&mut reply).await?;
+    assert_eq!(&reply, b"PING");
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use serde_json::json;
+
+use crate::servers::resources::{
+    file_resource, resource_contents, resource_templates, ResourceScope, ResourceUri, DRIVE_SCHEME,
+};
+
+#[test]
+fn test_parse_resource_uri() {
+    assert_eq!(
+        ResourceUri::parse("gsheets://1AbC-d_e").unwrap(),
+        ResourceUri::Spreadsheet {
+            id: "1AbC-d_e".to_string(),
+            sheet: None,
+            range: None
+        }
+    );
+    assert_eq!(
+        ResourceUri::parse("gsheets://s1/Q1%20Sales/A1:D20").unwrap(),
+        ResourceUri::Spreadsheet {
+            id: "s1".to_string(),
+            sheet: Some("Q1 Sales".to_string()),
+            range: Some("A1:D20".to_string())
+        }
+    );
+    assert_eq!(
+        ResourceUri::parse("gdrive://file1/").unwrap(),
+        ResourceUri::File("file1".to_string())
+    );
+    assert_eq!(
+        ResourceUri::parse("gsheets://s1/Data").unwrap().file_id(),
+        "s1"
+    );
+    assert!(ResourceUri::parse("gdrive://").is_err());
+    assert!(ResourceUri::parse("gdrive://file1/extra").is_err());
+    assert!(ResourceUri::parse("gsheets://s1//A1").is_err());
+    assert!(ResourceUri::parse("gsheets://s1/Data/A1/more").is_err());
+    assert!(ResourceUri::parse("gmail://msg1").is_err());
+    assert!(ResourceUri::parse("file1").is_err());
+}
+
+#[test]
+fn test_file_resource_scopes() {
+    let spreadsheet = json!({
+        "id": "s1",
+        "name": "Budget",
+        "mimeType": "application/vnd.google-apps.spreadsheet",
+        "modifiedTime": "2024-01-01T00:00:00.000Z"
+    });
+    let pdf = json!({"id": "f1", "name": "scan.pdf", "mimeType": "application/pdf"});
+    let both = ResourceScope {
+        drive: true,
+        sheets: true,
+    };
+    let drive_only = ResourceScope {
+        drive: true,
+        sheets: false,
+    };
+    let sheets_only = ResourceScope {
+        drive: false,
+        sheets: true,
+    };
+
+    let resource = file_resource(&spreadsheet, both).unwrap();
+    assert_eq!(resource.uri.as_str(), "gsheets://s1");
+    assert_eq!(resource.name, "Budget");
+    assert_eq!(resource.mime_type.as_deref(), Some("text/csv"));
+    assert_eq!(
+        resource.description.as_deref(),
+        Some("Last modified 2024-01-01T00:00:00.000Z")
+    );
+
+    let resource = file_resource(&spreadsheet, drive_only).unwrap();
+    assert_eq!(resource.uri.scheme(), DRIVE_SCHEME);
+    assert_eq!(
+        file_resource(&pdf, both).unwrap().mime_type.as_deref(),
+        Some("application/pdf")
+    );
+    assert!(file_resource(&pdf, sheets_only).is_none());
+}
+
+#[test]
+fn test_resource_contents() {
+    let text = resource_contents("gdrive://f1", "text/plain", b"hello".to_vec());
+    assert_eq!(text.text.as_deref(), Some("hello"));
+    assert_eq!(text.blob, None);
+
+    let binary = resource_contents("gdrive://f2", "application/pdf", vec![0x25, 0x50]);
+    assert_eq!(binary.text, None);
+    assert_eq!(binary.blob.as_deref(), Some("JVA="));
+
+    // Mislabelled binary content still round-trips as a blob
+    let invalid = resource_contents("gdrive://f3", "text/plain", vec![0xff, 0xfe]);
+    assert_eq!(invalid.blob.as_deref(), Some("//4="));
+}
+
+#[test]
+fn test_resource_templates() {
+    let templates = resource_templates(ResourceScope {
+        drive: false,
+        sheets: true,
+    });
+    let uris = templates
+        .iter()
+        .map(|t| t.uri_template.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        uris,
+        [
+            "gsheets://{spreadsheet_id}/{sheet}/{range}",
+            "gsheets://{spreadsheet_id}/{sheet}"
+        ]
+    );
+    assert!(resource_templates(ResourceScope {
+        drive: false,
+        sheets: false
+    })
+    .is_empty());
+}
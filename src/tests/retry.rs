@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::{
+    client::{RetryPolicy, TransientFailure},
+    InvokeError,
+};
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy {
+        max_retries: 5,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+    };
+
+    for attempt in 0..6 {
+        let ceiling = Duration::from_millis(100 * 2u64.pow(attempt)).min(Duration::from_secs(1));
+        let delay = policy.delay(attempt, None).unwrap();
+        assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?}", delay);
+    }
+
+    assert_eq!(
+        policy.delay(0, Some(Duration::from_millis(700))),
+        Some(Duration::from_millis(700))
+    );
+    assert_eq!(policy.delay(0, Some(Duration::from_secs(60))), None);
+}
+
+#[test]
+fn test_classify_transient_failures() {
+    let classify = |body: serde_json::Value, status: u16, retry_after: Option<u64>| {
+        TransientFailure::classify(
+            &InvokeError::from_google_json(&body, status, retry_after).into(),
+        )
+    };
+
+    assert_eq!(
+        classify(
+            json!({"error": {"code": 429, "message": "Quota exceeded", "status": "RESOURCE_EXHAUSTED"}}),
+            429,
+            Some(3)
+        ),
+        Some(TransientFailure::RateLimited {
+            retry_after: Some(Duration::from_secs(3))
+        })
+    );
+    assert_eq!(
+        classify(
+            json!({"error": {"code": 403, "message": "Rate Limit Exceeded", "errors": [{"reason": "userRateLimitExceeded"}]}}),
+            403,
+            None
+        ),
+        Some(TransientFailure::RateLimited { retry_after: None })
+    );
+    assert_eq!(
+        classify(
+            json!({"error": {"code": 503, "message": "Backend Error", "status": "UNAVAILABLE"}}),
+            503,
+            None
+        ),
+        Some(TransientFailure::Unavailable)
+    );
+    assert_eq!(
+        classify(
+            json!({"error": {"code": 400, "message": "Unable to parse range", "status": "INVALID_ARGUMENT"}}),
+            400,
+            None
+        ),
+        None
+    );
+
+    let breaker_open = InvokeError::ServiceUnavailable {
+        service: "Google Sheets API".to_string(),
+        retry_after: 30,
+    };
+    assert_eq!(TransientFailure::classify(&breaker_open.into()), None);
+}
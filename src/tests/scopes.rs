@@ -0,0 +1,72 @@
+use crate::{
+    scopes::{expand_scope, missing_scopes, needs_no_scopes, required_scopes, tool_scopes},
+    servers::{list_service_tools, workspace::WorkspaceService, ServerOptions},
+};
+
+const AUTH: &str = "https://www.googleapis.com/auth/";
+
+fn scopes(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| expand_scope(name)).collect()
+}
+
+#[test]
+fn test_required_scopes() {
+    assert_eq!(
+        required_scopes(["read_values", "get_sheet_info"]),
+        scopes(&["spreadsheets.readonly"])
+    );
+    // A tool that writes needs the full scope, which also covers the readers
+    assert_eq!(
+        required_scopes(["read_values", "write_values", "list_spreadsheets"]),
+        scopes(&["drive.metadata.readonly", "spreadsheets"])
+    );
+    // The full Drive scope covers every Sheets and Drive tool
+    assert_eq!(
+        required_scopes(["read_values", "write_values", "upload_file", "trash_files"]),
+        scopes(&["drive"])
+    );
+    assert_eq!(
+        required_scopes(["gmail.send_email", "gmail.read_message"]),
+        scopes(&["gmail.readonly", "gmail.send"])
+    );
+    assert!(required_scopes(["no_such_tool"]).is_empty());
+}
+
+#[test]
+fn test_missing_scopes() {
+    let granted = scopes(&["spreadsheets.readonly", "drive.metadata.readonly"]);
+    let missing = missing_scopes(
+        ["read_values", "sheets.write_values", "list_files"],
+        &granted,
+    );
+    assert_eq!(missing.keys().collect::<Vec<_>>(), [&"sheets.write_values"]);
+    assert_eq!(
+        missing["sheets.write_values"].minimal,
+        format!("{}spreadsheets", AUTH)
+    );
+
+    assert!(missing_scopes(["write_values"], &scopes(&["drive"])).is_empty());
+    assert_eq!(
+        tool_scopes("drive.upload_file").unwrap().minimal,
+        format!("{}drive.file", AUTH)
+    );
+}
+
+#[tokio::test]
+async fn test_every_tool_has_scopes() -> anyhow::Result<()> {
+    // A BigQuery project brings in `sheets_to_bigquery`
+    let options = ServerOptions {
+        bigquery_project: Some("analytics".to_string()),
+        ..Default::default()
+    };
+    let tools = list_service_tools(WorkspaceService::ALL, options).await?;
+    assert!(tools.iter().any(|tool| tool.name == "sheets_to_bigquery"));
+    for tool in &tools {
+        assert!(
+            tool_scopes(&tool.name).is_some() || needs_no_scopes(&tool.name),
+            "{} has no scope requirement",
+            tool.name
+        );
+    }
+    Ok(())
+}
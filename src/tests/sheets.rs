@@ -1,6 +1,7 @@
 use crate::{
     client::{get_drive_client, get_sheets_client},
     servers::sheets,
+    tests::args,
 };
 use async_mcp::{
     protocol::RequestOptions,
@@ -9,7 +10,7 @@ use async_mcp::{
 };
 use dotenv::dotenv;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::{collections::HashMap, env, time::Duration};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -89,7 +90,7 @@ async fn test_google_sheets() -> Result<(), Box<dyn std::error::Error>> {
     // let access_token = token_response.access_token;
 
     let access_token = env::var("GOOGLE_ACCESS_TOKEN").unwrap();
-    let sheets = get_sheets_client(&access_token);
+    let sheets = get_sheets_client(&access_token)?;
 
     let spreadsheet_id = env::var("TEST_SPREADSHEET_ID").unwrap();
 
@@ -125,8 +126,8 @@ async fn test_list_spreadsheet_details() -> Result<(), Box<dyn std::error::Error
     dotenv().ok();
     let access_token = env::var("GOOGLE_ACCESS_TOKEN").unwrap();
 
-    let drive = get_drive_client(&access_token);
-    let sheets = get_sheets_client(&access_token);
+    let drive = get_drive_client(&access_token)?;
+    let sheets = get_sheets_client(&access_token)?;
 
     let result = drive
         .files()
@@ -295,3 +296,768 @@ async fn test_sheet_operations() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_batch_operations() {
+    use crate::servers::sheets::{batch_operation, BatchOperation};
+
+    let sheet_ids = HashMap::from([("Data".to_string(), 42)]);
+
+    assert_eq!(
+        batch_operation(
+            &args(json!({"type": "add_sheet", "title": "Summary", "rows": 10})),
+            &sheet_ids
+        )
+        .unwrap(),
+        json!({"addSheet": {"properties": {"title": "Summary", "gridProperties": {"rowCount": 10}}}})
+    );
+    assert_eq!(
+        batch_operation(
+            &args(json!({"type": "rename_sheet", "sheet": "Data", "title": "Raw"})),
+            &sheet_ids
+        )
+        .unwrap(),
+        json!({"updateSheetProperties": {"properties": {"sheetId": 42, "title": "Raw"}, "fields": "title"}})
+    );
+    assert_eq!(
+        batch_operation(
+            &args(json!({"type": "merge_cells", "sheet_id": 7, "range": "B2:AA3"})),
+            &sheet_ids
+        )
+        .unwrap(),
+        json!({"mergeCells": {
+            "range": {"sheetId": 7, "startRowIndex": 1, "endRowIndex": 3, "startColumnIndex": 1, "endColumnIndex": 27},
+            "mergeType": "MERGE_ALL"
+        }})
+    );
+    assert_eq!(
+        batch_operation(
+            &args(json!({"type": "set_grid_size", "sheet": "Data", "columns": 5})),
+            &sheet_ids
+        )
+        .unwrap()["updateSheetProperties"]["fields"],
+        "gridProperties.columnCount"
+    );
+
+    assert!(batch_operation(
+        &args(json!({"type": "delete_sheet", "sheet": "Missing"})),
+        &sheet_ids
+    )
+    .is_err());
+    assert!(batch_operation(
+        &args(json!({"type": "merge_cells", "sheet_id": 1, "range": "1A:B2"})),
+        &sheet_ids
+    )
+    .is_err());
+    assert!(batch_operation(&args(json!({"type": "resize", "sheet_id": 1, "start_index": 3, "end_index": 3, "pixel_size": 20})), &sheet_ids).is_err());
+
+    // Unknown operations and missing fields are rejected while parsing
+    assert!(serde_json::from_value::<BatchOperation>(json!({"type": "sort"})).is_err());
+    assert!(serde_json::from_value::<BatchOperation>(json!({"type": "rename_sheet"})).is_err());
+}
+
+#[test]
+fn test_cell_rows_keep_types() {
+    use crate::servers::sheets::{to_cell_rows, WriteValuesArgs};
+
+    let rows = to_cell_rows(&args::<Vec<Vec<Value>>>(json!([
+        ["total", 42, 1.5, true, null, "=SUM(A1:A5)"],
+        [["nested"], {"a": 1}]
+    ])));
+    assert_eq!(
+        rows[0],
+        vec![
+            json!("total"),
+            json!(42),
+            json!(1.5),
+            json!(true),
+            json!(null),
+            json!("=SUM(A1:A5)")
+        ]
+    );
+    assert_eq!(rows[1], vec![json!("[\"nested\"]"), json!("{\"a\":1}")]);
+
+    // Rows that are not arrays are rejected while parsing
+    assert!(serde_json::from_value::<WriteValuesArgs>(
+        json!({"sheet": "Data", "range": "A1", "values": ["not a row"]})
+    )
+    .is_err());
+}
+
+#[test]
+fn test_batch_write_request() {
+    use crate::servers::sheets::{batch_write_request, ValueInputOption, ValueRangeData};
+
+    let request = batch_write_request(
+        &args::<Vec<ValueRangeData>>(json!([
+            {"range": "Data!A1:B1", "values": [["a", 1]]},
+            {"range": "Totals!C5", "values": [["=SUM(Data!B:B)"]], "major_dimension": "COLUMNS"}
+        ])),
+        ValueInputOption::UserEntered,
+    );
+    assert_eq!(
+        request,
+        json!({
+            "valueInputOption": "USER_ENTERED",
+            "data": [
+                {"range": "Data!A1:B1", "majorDimension": "ROWS", "values": [["a", 1]]},
+                {"range": "Totals!C5", "majorDimension": "COLUMNS", "values": [["=SUM(Data!B:B)"]]}
+            ]
+        })
+    );
+
+    assert!(serde_json::from_value::<ValueRangeData>(json!({"values": [[1]]})).is_err());
+}
+
+#[test]
+fn test_spreadsheet_query() {
+    use crate::servers::sheets::spreadsheet_query;
+
+    assert_eq!(
+        spreadsheet_query(None),
+        "mimeType='application/vnd.google-apps.spreadsheet' and trashed=false"
+    );
+    assert_eq!(
+        spreadsheet_query(Some("Q3 'final'")),
+        "mimeType='application/vnd.google-apps.spreadsheet' and trashed=false and name contains 'Q3 \\'final\\''"
+    );
+    assert_eq!(spreadsheet_query(Some("")), spreadsheet_query(None));
+}
+
+#[test]
+fn test_format_requests() {
+    use crate::servers::sheets::{format_requests, FormatCellsArgs};
+
+    let sheet_ids = HashMap::from([("Report".to_string(), 7)]);
+    let requests = format_requests(
+        &args(json!({
+            "sheet": "Report",
+            "range": "A1:C1",
+            "bold": true,
+            "background_color": "#ff0000",
+            "number_format": {"type": "CURRENCY", "pattern": "$#,##0.00"},
+            "borders": {"sides": ["bottom"], "style": "SOLID_MEDIUM"}
+        })),
+        &sheet_ids,
+    )
+    .unwrap();
+    let range = json!({
+        "sheetId": 7,
+        "startRowIndex": 0,
+        "endRowIndex": 1,
+        "startColumnIndex": 0,
+        "endColumnIndex": 3
+    });
+    assert_eq!(
+        requests,
+        vec![
+            json!({"repeatCell": {
+                "range": range,
+                "cell": {"userEnteredFormat": {
+                    "textFormat": {"bold": true},
+                    "backgroundColor": {"red": 1.0, "green": 0.0, "blue": 0.0},
+                    "numberFormat": {"type": "CURRENCY", "pattern": "$#,##0.00"}
+                }},
+                "fields": "userEnteredFormat.textFormat.bold,userEnteredFormat.backgroundColor,userEnteredFormat.numberFormat"
+            }}),
+            json!({"updateBorders": {
+                "range": range,
+                "bottom": {"style": "SOLID_MEDIUM", "color": {"red": 0.0, "green": 0.0, "blue": 0.0}}
+            }}),
+        ]
+    );
+
+    assert!(format_requests(&args(json!({"sheet_id": 0, "range": "A1"})), &sheet_ids).is_err());
+    assert!(format_requests(
+        &args(json!({"sheet_id": 0, "range": "A1", "background_color": "red"})),
+        &sheet_ids
+    )
+    .is_err());
+    assert!(serde_json::from_value::<FormatCellsArgs>(
+        json!({"range": "A1", "horizontal_alignment": "JUSTIFY"})
+    )
+    .is_err());
+}
+
+#[test]
+fn test_pivot_table_requests() {
+    use crate::servers::sheets::pivot_table_requests;
+
+    let sheet_ids = HashMap::from([("Sales".to_string(), 0), ("Summary".to_string(), 1)]);
+    let (requests, target) = pivot_table_requests(
+        &args(json!({
+            "sheet": "Sales",
+            "source_range": "B1:E50",
+            "rows": [{"column": "C", "sort_order": "DESCENDING"}],
+            "values": [{"column": 3, "function": "SUM", "name": "Revenue"}]
+        })),
+        &sheet_ids,
+    )
+    .unwrap();
+
+    // The default target sheet does not exist yet, so it is added first
+    assert_eq!(
+        requests[0],
+        json!({"addSheet": {"properties": {"sheetId": 2, "title": "Pivot Table"}}})
+    );
+    assert_eq!(
+        target,
+        json!({"sheet": "Pivot Table", "sheetId": 2, "cell": "A1"})
+    );
+    let update = &requests[1]["updateCells"];
+    assert_eq!(
+        update["start"],
+        json!({"sheetId": 2, "rowIndex": 0, "columnIndex": 0})
+    );
+    assert_eq!(
+        update["rows"][0]["values"][0]["pivotTable"],
+        json!({
+            "source": {
+                "sheetId": 0,
+                "startRowIndex": 0,
+                "endRowIndex": 50,
+                "startColumnIndex": 1,
+                "endColumnIndex": 5
+            },
+            "rows": [{"sourceColumnOffset": 1, "sortOrder": "DESCENDING", "showTotals": true}],
+            "columns": [],
+            "values": [{"sourceColumnOffset": 3, "summarizeFunction": "SUM", "name": "Revenue"}]
+        })
+    );
+
+    let (requests, _) = pivot_table_requests(
+        &args(json!({
+            "sheet_id": 0,
+            "source_range": "A1:B10",
+            "values": [{"column": "B"}],
+            "target_sheet": "Summary",
+            "target_cell": "D3"
+        })),
+        &sheet_ids,
+    )
+    .unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0]["updateCells"]["start"],
+        json!({"sheetId": 1, "rowIndex": 2, "columnIndex": 3})
+    );
+
+    // Column F lies outside B1:E50
+    assert!(pivot_table_requests(
+        &args(json!({"sheet_id": 0, "source_range": "B1:E50", "values": [{"column": "F"}]})),
+        &sheet_ids
+    )
+    .is_err());
+}
+
+#[test]
+fn test_sheet_info() {
+    use crate::servers::sheets::sheet_info;
+
+    let info = sheet_info(&json!({
+        "properties": {
+            "sheetId": 912,
+            "title": "Orders",
+            "index": 2,
+            "hidden": true,
+            "tabColorStyle": {"rgbColor": {"red": 1, "blue": 0.5}},
+            "gridProperties": {"rowCount": 500, "columnCount": 53, "frozenRowCount": 1}
+        },
+        "basicFilter": {"range": {"sheetId": 912}}
+    }))
+    .unwrap();
+    assert_eq!(
+        info,
+        json!({
+            "sheetId": 912,
+            "title": "Orders",
+            "index": 2,
+            "maxRange": "A1:BA500",
+            "rowCount": 500,
+            "columnCount": 53,
+            "frozenRowCount": 1,
+            "frozenColumnCount": 0,
+            "hidden": true,
+            "hasBasicFilter": true,
+            "tabColor": "#ff0080"
+        })
+    );
+
+    // The first sheet's ID and index are 0, which the API leaves out
+    let info = sheet_info(&json!({"properties": {
+        "title": "Sheet1",
+        "tabColorStyle": {"themeColor": "ACCENT2"},
+        "gridProperties": {"rowCount": 1000, "columnCount": 26}
+    }}))
+    .unwrap();
+    assert_eq!(
+        (
+            &info["sheetId"],
+            &info["index"],
+            &info["maxRange"],
+            &info["tabColor"]
+        ),
+        (&json!(0), &json!(0), &json!("A1:Z1000"), &json!("ACCENT2"))
+    );
+    assert_eq!(info["hasBasicFilter"], false);
+
+    // Chart sheets have no grid
+    assert!(sheet_info(&json!({"properties": {"title": "Chart", "sheetId": 3}})).is_none());
+}
+
+#[test]
+fn test_sheet_property_requests() {
+    use crate::servers::sheets::{freeze_panes_request, hidden_request, tab_color_request};
+
+    let sheet_ids = HashMap::from([("Report".to_string(), 4)]);
+    assert_eq!(
+        freeze_panes_request(&args(json!({"sheet": "Report", "rows": 1})), &sheet_ids).unwrap(),
+        json!({"updateSheetProperties": {
+            "properties": {"sheetId": 4, "gridProperties": {"frozenRowCount": 1}},
+            "fields": "gridProperties.frozenRowCount"
+        }})
+    );
+    assert_eq!(
+        freeze_panes_request(
+            &args(json!({"sheet_id": 4, "rows": 0, "columns": 2})),
+            &sheet_ids
+        )
+        .unwrap()["updateSheetProperties"]["fields"],
+        "gridProperties.frozenRowCount,gridProperties.frozenColumnCount"
+    );
+    assert!(freeze_panes_request(&args(json!({"sheet": "Report"})), &sheet_ids).is_err());
+
+    assert_eq!(
+        hidden_request(4, true),
+        json!({"updateSheetProperties": {
+            "properties": {"sheetId": 4, "hidden": true},
+            "fields": "hidden"
+        }})
+    );
+
+    assert_eq!(
+        tab_color_request(
+            &args(json!({"sheet": "Report", "color": "#ff0000"})),
+            &sheet_ids
+        )
+        .unwrap(),
+        json!({"updateSheetProperties": {
+            "properties": {
+                "sheetId": 4,
+                "tabColorStyle": {"rgbColor": {"red": 1.0, "green": 0.0, "blue": 0.0}}
+            },
+            "fields": "tabColorStyle"
+        }})
+    );
+    // Without a color the tab's color is cleared
+    assert_eq!(
+        tab_color_request(&args(json!({"sheet": "Report"})), &sheet_ids).unwrap()
+            ["updateSheetProperties"]["properties"],
+        json!({"sheetId": 4})
+    );
+    assert!(tab_color_request(
+        &args(json!({"sheet": "Report", "color": "red"})),
+        &sheet_ids
+    )
+    .is_err());
+}
+
+#[test]
+fn test_cell_notes() {
+    use crate::servers::sheets::cell_notes;
+
+    let spreadsheet = json!({"sheets": [{"data": [{
+        "startRow": 2,
+        "startColumn": 26,
+        "rowData": [
+            {"values": [{"note": "from CRM"}, {}]},
+            {},
+            {"values": [null, {"note": "checked"}]}
+        ]
+    }]}]});
+    assert_eq!(
+        cell_notes(&spreadsheet),
+        vec![
+            json!({"cell": "AA3", "note": "from CRM"}),
+            json!({"cell": "AB5", "note": "checked"})
+        ]
+    );
+    assert!(cell_notes(&json!({"sheets": [{"data": [{}]}]})).is_empty());
+}
+
+#[test]
+fn test_metadata_location() {
+    use crate::servers::sheets::{metadata_location, MetadataLocation};
+
+    let sheet_ids = HashMap::from([("Orders".to_string(), 9)]);
+    let location = |value: Value| metadata_location(&args::<MetadataLocation>(value), &sheet_ids);
+    assert_eq!(location(json!({})).unwrap(), None);
+    assert_eq!(
+        location(json!({"sheet": "Orders"})).unwrap(),
+        Some(json!({"sheetId": 9}))
+    );
+    assert_eq!(
+        location(json!({"sheet": "Orders", "rows": "7:5"})).unwrap(),
+        Some(json!({"dimensionRange": {
+            "sheetId": 9, "dimension": "ROWS", "startIndex": 4, "endIndex": 7
+        }}))
+    );
+    assert_eq!(
+        location(json!({"sheet_id": 2, "columns": "ab"})).unwrap(),
+        Some(json!({"dimensionRange": {
+            "sheetId": 2, "dimension": "COLUMNS", "startIndex": 27, "endIndex": 28
+        }}))
+    );
+    assert!(location(json!({"rows": "5"})).is_err());
+    assert!(location(json!({"sheet": "Orders", "rows": "C"})).is_err());
+    assert!(location(json!({"sheet": "Orders", "rows": "5", "columns": "C"})).is_err());
+}
+
+#[test]
+fn test_metadata_lookup_and_summary() {
+    use crate::servers::sheets::{metadata_lookup, metadata_summary};
+
+    let lookup = metadata_lookup(
+        &args(json!({"key": "order_id", "sheet_id": 9})),
+        &HashMap::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        lookup,
+        json!({
+            "metadataKey": "order_id",
+            "metadataLocation": {"sheetId": 9},
+            "locationMatchingStrategy": "INTERSECTING_LOCATION"
+        })
+    );
+    assert!(metadata_lookup(&args(json!({})), &HashMap::new()).is_err());
+
+    let summary = metadata_summary(&json!({
+        "metadataId": 17,
+        "metadataKey": "order_id",
+        "metadataValue": "A-1001",
+        "visibility": "DOCUMENT",
+        "location": {
+            "locationType": "ROW",
+            "dimensionRange": {"sheetId": 9, "dimension": "ROWS", "endIndex": 1}
+        }
+    }));
+    assert_eq!(
+        summary,
+        json!({
+            "metadataId": 17,
+            "key": "order_id",
+            "value": "A-1001",
+            "visibility": "DOCUMENT",
+            "location": {"sheetId": 9, "rows": "1"}
+        })
+    );
+    let location =
+        |location: Value| metadata_summary(&json!({"location": location}))["location"].clone();
+    assert_eq!(
+        location(json!({
+            "locationType": "COLUMN",
+            "dimensionRange": {"dimension": "COLUMNS", "startIndex": 2, "endIndex": 5}
+        })),
+        json!({"sheetId": 0, "columns": "C:E"})
+    );
+    assert_eq!(
+        location(json!({"locationType": "SPREADSHEET", "spreadsheet": true})),
+        json!({"spreadsheet": true})
+    );
+    assert_eq!(
+        location(json!({"locationType": "SHEET"})),
+        json!({"sheetId": 0})
+    );
+}
+
+#[test]
+fn test_sort_range_request() {
+    use crate::servers::sheets::sort_range_request;
+
+    let request = sort_range_request(
+        &args(json!({
+            "sheet_id": 3,
+            "range": "B1:F20",
+            "has_header": true,
+            "sort_by": [{"column": "D", "order": "DESCENDING"}, {"column": 0}]
+        })),
+        &HashMap::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        request,
+        json!({"sortRange": {
+            "range": {
+                "sheetId": 3,
+                "startRowIndex": 1,
+                "endRowIndex": 20,
+                "startColumnIndex": 1,
+                "endColumnIndex": 6
+            },
+            "sortSpecs": [
+                {"dimensionIndex": 3, "sortOrder": "DESCENDING"},
+                {"dimensionIndex": 1, "sortOrder": "ASCENDING"}
+            ]
+        }})
+    );
+
+    assert!(sort_range_request(
+        &args(json!({"sheet_id": 3, "range": "B1:F20", "sort_by": []})),
+        &HashMap::new()
+    )
+    .is_err());
+}
+
+#[test]
+fn test_delete_duplicates_request() {
+    use crate::servers::sheets::delete_duplicates_request;
+
+    let sheet_ids = HashMap::from([("Contacts".to_string(), 7)]);
+    let request = delete_duplicates_request(
+        &args(json!({
+            "sheet": "Contacts",
+            "range": "B1:E50",
+            "has_header": true,
+            "columns": ["C", 3]
+        })),
+        &sheet_ids,
+    )
+    .unwrap();
+    assert_eq!(
+        request,
+        json!({"deleteDuplicates": {
+            "range": {
+                "sheetId": 7,
+                "startRowIndex": 1,
+                "endRowIndex": 50,
+                "startColumnIndex": 1,
+                "endColumnIndex": 5
+            },
+            "comparisonColumns": [
+                {"sheetId": 7, "dimension": "COLUMNS", "startIndex": 2, "endIndex": 3},
+                {"sheetId": 7, "dimension": "COLUMNS", "startIndex": 4, "endIndex": 5}
+            ]
+        }})
+    );
+
+    // Without columns every column is compared
+    let request = delete_duplicates_request(
+        &args(json!({"sheet_id": 7, "range": "A1:C10"})),
+        &HashMap::new(),
+    )
+    .unwrap();
+    assert!(request["deleteDuplicates"]
+        .get("comparisonColumns")
+        .is_none());
+
+    assert!(delete_duplicates_request(
+        &args(json!({"sheet_id": 7, "range": "A1:C10", "columns": ["F"]})),
+        &HashMap::new()
+    )
+    .is_err());
+}
+
+#[test]
+fn test_basic_filter_request() {
+    use crate::servers::sheets::basic_filter_request;
+
+    let sheet_ids = HashMap::from([("Orders".to_string(), 5)]);
+    let request = basic_filter_request(
+        &args(json!({
+            "sheet": "Orders",
+            "range": "A1:C100",
+            "criteria": [
+                {"column": "B", "hidden_values": ["cancelled"]},
+                {"column": 2, "condition": {"type": "NUMBER_GREATER", "values": [100]}}
+            ]
+        })),
+        &sheet_ids,
+    )
+    .unwrap();
+    let filter = &request["setBasicFilter"]["filter"];
+    assert_eq!(filter["range"]["sheetId"], 5);
+    assert_eq!(
+        filter["filterSpecs"],
+        json!([
+            {"columnIndex": 1, "filterCriteria": {"hiddenValues": ["cancelled"]}},
+            {"columnIndex": 2, "filterCriteria": {"condition": {
+                "type": "NUMBER_GREATER",
+                "values": [{"userEnteredValue": "100"}]
+            }}}
+        ])
+    );
+    assert_eq!(filter["sortSpecs"], json!([]));
+}
+
+#[test]
+fn test_duplicate_sheet_request() {
+    use crate::servers::sheets::duplicate_sheet_request;
+
+    let sheet_ids = HashMap::from([("Template".to_string(), 42)]);
+    assert_eq!(
+        duplicate_sheet_request(
+            &args(json!({"sheet": "Template", "new_name": "March", "insert_index": 0})),
+            &sheet_ids
+        )
+        .unwrap(),
+        json!({"duplicateSheet": {
+            "sourceSheetId": 42,
+            "newSheetName": "March",
+            "insertSheetIndex": 0
+        }})
+    );
+    assert!(duplicate_sheet_request(&args(json!({"sheet": "Missing"})), &sheet_ids).is_err());
+}
+
+#[test]
+fn test_values_url() {
+    use crate::servers::sheets::values_url;
+
+    let url = values_url("abc", Some("'Q1 Sales'!A1:B2")).unwrap();
+    assert!(url
+        .path()
+        .ends_with("/v4/spreadsheets/abc/values/%27Q1%20Sales%27%21A1%3AB2"));
+    let url = values_url("abc", None).unwrap();
+    assert!(url.path().ends_with("/v4/spreadsheets/abc/values:batchGet"));
+}
+
+#[test]
+fn test_row_window() {
+    use crate::servers::sheets::row_window;
+
+    let window = row_window("A1:ZZ", 0, Some(100), 250).unwrap();
+    assert_eq!(window.range.as_deref(), Some("A1:ZZ100"));
+    assert_eq!(
+        window.meta,
+        json!({"rowOffset": 0, "rowCount": 100, "totalRows": 250, "nextRowOffset": 100})
+    );
+
+    let window = row_window("A1:ZZ", 200, Some(100), 250).unwrap();
+    assert_eq!(window.range.as_deref(), Some("A201:ZZ250"));
+    assert_eq!(
+        window.meta,
+        json!({"rowOffset": 200, "rowCount": 50, "totalRows": 250})
+    );
+
+    // Explicit bounds win over the grid size; whole-column ranges work too
+    let window = row_window("B10:D19", 5, None, 1000).unwrap();
+    assert_eq!(window.range.as_deref(), Some("B15:D19"));
+    assert_eq!(window.meta["totalRows"], 10);
+    assert_eq!(
+        row_window("C:C", 1, Some(2), 1000)
+            .unwrap()
+            .range
+            .as_deref(),
+        Some("C2:C3")
+    );
+
+    let window = row_window("A1:ZZ", 300, Some(100), 250).unwrap();
+    assert_eq!(window.range, None);
+    assert_eq!(window.meta["rowCount"], 0);
+
+    assert!(row_window("A0:B2", 0, None, 10).is_err());
+}
+
+#[test]
+fn test_table_headers() {
+    use crate::servers::sheets::{
+        first_column, header_range, header_row, table_headers, table_record,
+    };
+
+    assert_eq!(first_column("A1:ZZ"), 0);
+    assert_eq!(first_column("C2:F"), 2);
+    assert_eq!(first_column("Data"), 0);
+    assert_eq!(header_range("A1:ZZ").unwrap(), "A1:ZZ1");
+    assert_eq!(header_range("C3:F").unwrap(), "C3:F3");
+    assert_eq!(header_row("C3:F").unwrap(), 3);
+    assert_eq!(header_row("A:F").unwrap(), 1);
+
+    // Blank cells take their column letter and repeats get a suffix
+    let headers = table_headers(&[json!(" Name "), json!(""), json!("name"), json!(2024)], 1);
+    assert_eq!(headers, ["Name", "C", "name_2", "2024"]);
+
+    assert_eq!(
+        table_record(&headers, &[json!("Acme"), json!(""), json!(3)]),
+        json!({"Name": "Acme", "C": null, "name_2": 3, "2024": null})
+    );
+}
+
+#[test]
+fn test_record_row() {
+    use crate::servers::sheets::record_row;
+
+    let headers = ["Name".to_string(), "Email".to_string(), "Total".to_string()];
+    let record = |value: Value| value.as_object().unwrap().clone();
+
+    let (row, added) = record_row(
+        &headers,
+        &record(json!({"total": 5, "NAME": "Acme"})),
+        false,
+    )
+    .unwrap();
+    assert_eq!(row, [json!("Acme"), Value::Null, json!(5)]);
+    assert!(added.is_empty());
+
+    let error = record_row(
+        &headers,
+        &record(json!({"Name": "Acme", "Phone": "1"})),
+        false,
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "no column for `Phone` (columns: Name, Email, Total); set create_missing_columns to add them"
+    );
+
+    let (row, added) = record_row(
+        &headers,
+        &record(json!({"Name": "Acme", "Tags": ["a", "b"]})),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        row,
+        [
+            json!("Acme"),
+            Value::Null,
+            Value::Null,
+            json!("[\"a\",\"b\"]")
+        ]
+    );
+    assert_eq!(added, ["Tags"]);
+
+    // A sheet without a header gets one from the record's keys
+    let (row, added) = record_row(&[], &record(json!({"Name": "Acme"})), true).unwrap();
+    assert_eq!(
+        (row, added),
+        (vec![json!("Acme")], vec!["Name".to_string()])
+    );
+    assert!(record_row(&headers, &Default::default(), true).is_err());
+}
+
+#[test]
+fn test_argument_schemas() {
+    use crate::servers::{
+        input_schema,
+        sheets::{BatchUpdateArgs, ReadValuesArgs},
+    };
+
+    let schema = input_schema::<ReadValuesArgs>();
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["required"], json!(["sheet"]));
+    assert_eq!(schema["properties"]["range"]["default"], "A1:ZZ");
+    assert_eq!(
+        schema["properties"]["major_dimension"]["enum"],
+        json!(["ROWS", "COLUMNS"])
+    );
+    assert!(schema.get("$schema").is_none());
+
+    // Each operation type lists its own fields, inlined for clients without $ref support
+    let schema = input_schema::<BatchUpdateArgs>();
+    let operations = schema["properties"]["operations"]["items"]["oneOf"]
+        .as_array()
+        .unwrap();
+    assert_eq!(operations.len(), 6);
+    assert!(!schema.to_string().contains("$ref"));
+}
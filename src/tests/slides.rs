@@ -0,0 +1,120 @@
+use serde_json::json;
+
+use crate::{
+    servers::slides::{
+        create_image_request, create_slide_request, replace_text_requests, slide_summaries,
+        AddSlideArgs, InsertImageArgs,
+    },
+    tests::args,
+};
+
+#[test]
+fn test_create_slide_request() {
+    assert_eq!(
+        create_slide_request(&args(
+            json!({"presentation_id": "p", "layout": "TITLE_AND_BODY", "insertion_index": 1})
+        )),
+        json!({"createSlide": {
+            "slideLayoutReference": {"predefinedLayout": "TITLE_AND_BODY"},
+            "insertionIndex": 1
+        }})
+    );
+    assert_eq!(
+        create_slide_request(&args(
+            json!({"presentation_id": "p", "layout_id": "p1", "object_id": "s9"})
+        )),
+        json!({"createSlide": {
+            "slideLayoutReference": {"layoutId": "p1"},
+            "objectId": "s9"
+        }})
+    );
+    assert!(serde_json::from_value::<AddSlideArgs>(
+        json!({"presentation_id": "p", "layout": "FANCY"})
+    )
+    .is_err());
+}
+
+#[test]
+fn test_replace_text_requests() {
+    let requests = replace_text_requests(&args(json!({
+        "presentation_id": "p",
+        "replacements": {"{{name}}": "Ada", "{{year}}": 2024},
+        "match_case": false,
+        "slide_ids": ["s1"]
+    })))
+    .unwrap();
+    assert_eq!(
+        requests,
+        vec![
+            json!({"replaceAllText": {
+                "containsText": {"text": "{{name}}", "matchCase": false},
+                "replaceText": "Ada",
+                "pageObjectIds": ["s1"]
+            }}),
+            json!({"replaceAllText": {
+                "containsText": {"text": "{{year}}", "matchCase": false},
+                "replaceText": "2024",
+                "pageObjectIds": ["s1"]
+            }}),
+        ]
+    );
+
+    assert!(
+        replace_text_requests(&args(json!({"presentation_id": "p", "replacements": {}}))).is_err()
+    );
+}
+
+#[test]
+fn test_create_image_request() {
+    let request = create_image_request(&args(json!({
+        "presentation_id": "p",
+        "slide_id": "s1",
+        "image_url": "https://example.com/logo.png",
+        "x": 36,
+        "width": 120
+    })));
+    let properties = &request["createImage"]["elementProperties"];
+    assert_eq!(properties["pageObjectId"], "s1");
+    assert_eq!(properties["transform"]["translateX"], 36.0);
+    assert_eq!(properties["transform"]["translateY"], 0.0);
+    assert_eq!(
+        properties["size"],
+        json!({"width": {"magnitude": 120.0, "unit": "PT"}})
+    );
+
+    assert!(serde_json::from_value::<InsertImageArgs>(
+        json!({"presentation_id": "p", "slide_id": "s1"})
+    )
+    .is_err());
+}
+
+#[test]
+fn test_slide_summaries() {
+    let presentation = json!({
+        "slides": [{
+            "objectId": "s1",
+            "slideProperties": {"layoutObjectId": "l1"},
+            "pageElements": [
+                {"objectId": "t1", "shape": {
+                    "placeholder": {"type": "TITLE"},
+                    "text": {"textElements": [
+                        {"textRun": {"content": "Quarterly "}},
+                        {"paragraphMarker": {}},
+                        {"textRun": {"content": "review\n"}}
+                    ]}
+                }},
+                {"objectId": "img1", "image": {}}
+            ]
+        }]
+    });
+
+    assert_eq!(
+        slide_summaries(&presentation),
+        vec![json!({
+            "index": 0,
+            "objectId": "s1",
+            "layoutId": "l1",
+            "elements": [{"objectId": "t1", "placeholder": "TITLE", "text": "Quarterly review\n"}]
+        })]
+    );
+}
@@ -0,0 +1,58 @@
+use crate::streaming::{markdown_table, write_csv_row, write_tsv_row, RowStreamParser};
+use serde_json::{json, Value};
+
+#[test]
+fn test_row_stream_parser_across_chunks() {
+    let body = r#"{
+  "range": "Sheet1!A1:C3",
+  "majorDimension": "ROWS",
+  "values": [
+    ["name", "note", "score"],
+    ["Ada", "likes [brackets] and \"quotes\"", 42],
+    ["Linus", "values: {}", true]
+  ]
+}"#;
+
+    // Feed in awkward 7-byte chunks to cross token boundaries
+    let mut parser = RowStreamParser::default();
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+    for chunk in body.as_bytes().chunks(7) {
+        parser.feed(chunk, &mut rows).unwrap();
+    }
+
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0], vec![json!("name"), json!("note"), json!("score")]);
+    assert_eq!(rows[1][1], json!("likes [brackets] and \"quotes\""));
+    assert_eq!(rows[2][2], json!(true));
+}
+
+#[test]
+fn test_write_csv_row() {
+    let mut out = String::new();
+    write_csv_row(
+        &mut out,
+        &[json!("a,b"), json!("say \"hi\""), json!(3), Value::Null],
+    );
+    assert_eq!(out, "\"a,b\",\"say \"\"hi\"\"\",3,\n");
+}
+
+#[test]
+fn test_write_tsv_row() {
+    let mut out = String::new();
+    write_tsv_row(&mut out, &[json!("a\tb"), json!("two\nlines"), json!(1.5)]);
+    assert_eq!(out, "a b\ttwo lines\t1.5\n");
+}
+
+#[test]
+fn test_markdown_table() {
+    let rows = vec![
+        vec![json!("name"), json!("score")],
+        vec![json!("Ada"), json!(42)],
+        vec![json!("a|b")],
+    ];
+    assert_eq!(
+        markdown_table(&rows),
+        "| name | score |\n| --- | --- |\n| Ada | 42 |\n| a\\|b |  |\n"
+    );
+    assert_eq!(markdown_table(&[]), "");
+}
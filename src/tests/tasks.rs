@@ -0,0 +1,33 @@
+use serde_json::json;
+
+use crate::{
+    servers::tasks::{due_timestamp, task_body, CreateTaskArgs},
+    tests::args,
+};
+
+#[test]
+fn test_due_timestamp() {
+    assert_eq!(
+        due_timestamp("2024-05-01").unwrap(),
+        "2024-05-01T00:00:00.000Z"
+    );
+    assert_eq!(
+        due_timestamp("2024-05-01T09:30:00+02:00").unwrap(),
+        "2024-05-01T07:30:00.000Z"
+    );
+    assert!(due_timestamp("next friday").is_err());
+}
+
+#[test]
+fn test_task_body() {
+    assert_eq!(
+        task_body(&args(json!({"title": "File taxes", "due": "2024-04-15"}))).unwrap(),
+        json!({"title": "File taxes", "due": "2024-04-15T00:00:00.000Z"})
+    );
+    assert_eq!(
+        task_body(&args(json!({"title": "Call Bob", "notes": "re: invoice"}))).unwrap(),
+        json!({"title": "Call Bob", "notes": "re: invoice"})
+    );
+    assert!(task_body(&args(json!({"title": "Pay rent", "due": "soon"}))).is_err());
+    assert!(serde_json::from_value::<CreateTaskArgs>(json!({"notes": "no title"})).is_err());
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::token_store::{
+    decrypt, encrypt, EncryptedFileStore, StoredToken, TokenStore, TokenStoreKind,
+};
+
+fn token(refresh_token: &str) -> StoredToken {
+    StoredToken {
+        refresh_token: refresh_token.to_string(),
+        access_token: Some("ya29.access".to_string()),
+        expires_at: Some(Utc::now() + chrono::Duration::minutes(30)),
+    }
+}
+
+#[test]
+fn test_encrypt_round_trip() {
+    let contents = encrypt("correct horse", b"secret tokens").unwrap();
+    assert!(!contents.contains("secret tokens"));
+    assert_eq!(
+        decrypt("correct horse", &contents).unwrap(),
+        b"secret tokens"
+    );
+    assert!(decrypt("wrong horse", &contents).is_err());
+
+    // Each encryption uses a fresh salt and nonce
+    assert_ne!(
+        encrypt("correct horse", b"secret tokens").unwrap(),
+        contents
+    );
+}
+
+#[test]
+fn test_encrypted_file_store() {
+    let dir = std::env::temp_dir().join(format!("mcp-google-tokens-{}", std::process::id()));
+    let path = dir.join("tokens.enc");
+    let store = EncryptedFileStore::new(path.clone(), "passphrase");
+
+    assert!(store.load("me@example.com").unwrap().is_none());
+    store.save("me@example.com", &token("1//me")).unwrap();
+    store.save("work@example.com", &token("1//work")).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("1//me"));
+    assert_eq!(
+        store.load("me@example.com").unwrap().unwrap().refresh_token,
+        "1//me"
+    );
+
+    store.delete("me@example.com").unwrap();
+    assert!(store.load("me@example.com").unwrap().is_none());
+    assert!(store.load("work@example.com").unwrap().is_some());
+
+    let wrong = EncryptedFileStore::new(path, "not the passphrase");
+    assert!(wrong.load("work@example.com").is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fresh_access_token() {
+    let fresh = token("1//refresh");
+    let (access_token, remaining) = fresh.fresh_access_token(Duration::from_secs(60)).unwrap();
+    assert_eq!(access_token, "ya29.access");
+    assert!(remaining > Duration::from_secs(29 * 60));
+
+    let expiring = StoredToken {
+        expires_at: Some(Utc::now() + chrono::Duration::seconds(30)),
+        ..token("1//refresh")
+    };
+    assert!(expiring
+        .fresh_access_token(Duration::from_secs(60))
+        .is_none());
+    assert!(StoredToken {
+        access_token: None,
+        ..token("1//refresh")
+    }
+    .fresh_access_token(Duration::ZERO)
+    .is_none());
+}
+
+#[test]
+fn test_token_store_kind() {
+    assert_eq!(
+        "Keyring".parse::<TokenStoreKind>(),
+        Ok(TokenStoreKind::Keyring)
+    );
+    assert_eq!("file".parse::<TokenStoreKind>(), Ok(TokenStoreKind::File));
+    assert!("vault".parse::<TokenStoreKind>().is_err());
+}
@@ -0,0 +1,125 @@
+use serde_json::json;
+
+use crate::{
+    servers::drive::upload_source,
+    tests::args,
+    upload::{
+        allowed_path, convertible_content_type, is_public_ip, multipart_body, UploadPolicy,
+        UploadSource,
+    },
+};
+
+#[test]
+fn test_multipart_body() {
+    let body = multipart_body("b", &json!({"name": "a.txt"}), "text/plain", b"hello");
+    assert_eq!(
+        String::from_utf8(body).unwrap(),
+        "--b\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{\"name\":\"a.txt\"}\r\n--b\r\nContent-Type: text/plain\r\n\r\nhello\r\n--b--\r\n"
+    );
+}
+
+#[test]
+fn test_upload_source() {
+    assert_eq!(
+        upload_source(&args(json!({"path": "/tmp/report.pdf", "name": "x"}))).unwrap(),
+        UploadSource::Path("/tmp/report.pdf".to_string())
+    );
+    assert!(upload_source(&args(json!({"name": "x"}))).is_err());
+    assert!(upload_source(&args(json!({"path": "a", "url": "https://example.com/a"}))).is_err());
+}
+
+#[tokio::test]
+async fn test_load_base64_content() {
+    let policy = UploadPolicy::default();
+    let content = UploadSource::Base64("aGVsbG8=".to_string())
+        .load(&policy)
+        .await
+        .unwrap();
+    assert_eq!(content.bytes, b"hello");
+    assert!(content.file_name.is_none());
+
+    assert!(UploadSource::Base64("not base64!".to_string())
+        .load(&policy)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_path_and_url_sources_are_opt_in() {
+    let dir = std::env::temp_dir().join(format!("mcp-google-upload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("notes.txt");
+    std::fs::write(&file, "hello").unwrap();
+    let path = UploadSource::Path(file.display().to_string());
+
+    let error = path.load(&UploadPolicy::default()).await.unwrap_err();
+    assert!(error.to_string().contains("--upload-dir"));
+    let error = UploadSource::Url("https://example.com/a.pdf".to_string())
+        .load(&UploadPolicy::default())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("--allow-upload-urls"));
+
+    let policy = UploadPolicy {
+        dirs: vec![dir.clone()],
+        urls: true,
+    };
+    let content = path.load(&policy).await.unwrap();
+    assert_eq!(content.bytes, b"hello");
+    assert_eq!(content.file_name.as_deref(), Some("notes.txt"));
+
+    // Neither `..` nor other schemes and internal hosts get around the policy
+    let escape = dir.join("..").join("..").join("etc").join("passwd");
+    assert!(allowed_path(&escape, &policy.dirs).is_err());
+    for url in [
+        "file:///etc/passwd",
+        "http://127.0.0.1:8080/admin",
+        "http://169.254.169.254/computeMetadata/v1/",
+        "http://[::1]/",
+        "http://localhost/",
+    ] {
+        assert!(
+            UploadSource::Url(url.to_string())
+                .load(&policy)
+                .await
+                .is_err(),
+            "{}",
+            url
+        );
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_is_public_ip() {
+    for ip in [
+        "127.0.0.1",
+        "10.0.0.8",
+        "172.16.4.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "::1",
+        "fe80::1",
+        "fd12:3456::1",
+        "::ffff:10.0.0.1",
+    ] {
+        assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+    }
+    for ip in ["8.8.8.8", "142.250.74.46", "2a00:1450:4001:80b::200e"] {
+        assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+    }
+}
+
+#[test]
+fn test_convertible_content_type() {
+    assert_eq!(
+        convertible_content_type("scan.PDF"),
+        Some("application/pdf")
+    );
+    assert_eq!(convertible_content_type("receipt.jpeg"), Some("image/jpeg"));
+    assert_eq!(convertible_content_type("archive.zip"), None);
+    assert_eq!(convertible_content_type("README"), None);
+}
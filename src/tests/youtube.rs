@@ -0,0 +1,100 @@
+use serde_json::json;
+
+use crate::servers::youtube::{
+    report_rows, report_window, video_summary, video_update, UpdateVideoMetadataArgs,
+};
+
+fn update_args(value: serde_json::Value) -> UpdateVideoMetadataArgs {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn test_video_summary() {
+    let video = json!({
+        "id": "abc",
+        "snippet": {"title": "Intro", "publishedAt": "2026-01-02T03:04:05Z", "tags": ["x"]},
+        "status": {"privacyStatus": "public"},
+        "statistics": {"viewCount": "10", "likeCount": "2", "commentCount": "1"},
+    });
+    assert_eq!(
+        video_summary(&video),
+        json!({
+            "id": "abc",
+            "title": "Intro",
+            "publishedAt": "2026-01-02T03:04:05Z",
+            "privacyStatus": "public",
+            "views": "10",
+            "likes": "2",
+            "comments": "1",
+        })
+    );
+}
+
+#[test]
+fn test_video_update() {
+    let current = json!({
+        "snippet": {
+            "title": "Old",
+            "description": "Keep me",
+            "categoryId": "27",
+            "channelId": "UC123",
+            "thumbnails": {},
+        },
+        "status": {"privacyStatus": "private", "embeddable": true, "uploadStatus": "processed"},
+    });
+
+    // Only the parts touched are sent, with their other writable fields kept
+    let (parts, body) = video_update(
+        &current,
+        &update_args(json!({"video_id": "abc", "title": "New"})),
+    )
+    .unwrap();
+    assert_eq!(parts, "snippet");
+    assert_eq!(
+        body,
+        json!({
+            "id": "abc",
+            "snippet": {"title": "New", "description": "Keep me", "categoryId": "27"},
+        })
+    );
+
+    let (parts, body) = video_update(
+        &current,
+        &update_args(json!({"video_id": "abc", "tags": ["a"], "privacy_status": "unlisted"})),
+    )
+    .unwrap();
+    assert_eq!(parts, "snippet,status");
+    assert_eq!(body["snippet"]["tags"], json!(["a"]));
+    assert_eq!(
+        body["status"],
+        json!({"privacyStatus": "unlisted", "embeddable": true})
+    );
+
+    assert!(video_update(&current, &update_args(json!({"video_id": "abc"}))).is_err());
+}
+
+#[test]
+fn test_report_window() {
+    assert_eq!(
+        report_window(None, Some("2026-03-01")).unwrap(),
+        ("2026-02-01".to_string(), "2026-03-01".to_string())
+    );
+    assert!(report_window(Some("2026-03-02"), Some("2026-03-01")).is_err());
+    assert!(report_window(Some("March"), None).is_err());
+}
+
+#[test]
+fn test_report_rows() {
+    let report = json!({
+        "columnHeaders": [{"name": "day"}, {"name": "views"}],
+        "rows": [["2026-03-01", 12], ["2026-03-02", 7]],
+    });
+    assert_eq!(
+        report_rows(&report),
+        vec![
+            json!({"day": "2026-03-01", "views": 12}),
+            json!({"day": "2026-03-02", "views": 7}),
+        ]
+    );
+    assert!(report_rows(&json!({"columnHeaders": []})).is_empty());
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305, XNonce};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{config_dir, write_atomically};
+
+/// Service name tokens are filed under in the OS keyring
+pub const KEYRING_SERVICE: &str = "mcp-google-workspace";
+/// Key for tokens whose Google account is unknown
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+const FILE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Where OAuth tokens are kept between runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenStoreKind {
+    /// The `refresh_token` of the config file's credentials
+    #[default]
+    Config,
+    /// The OS keyring: Keychain, Windows Credential Manager or Secret Service
+    Keyring,
+    /// A file encrypted with a passphrase
+    File,
+}
+
+impl FromStr for TokenStoreKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "config" => Ok(Self::Config),
+            "keyring" => Ok(Self::Keyring),
+            "file" => Ok(Self::File),
+            other => Err(format!(
+                "unknown token store `{}` (expected config|keyring|file)",
+                other
+            )),
+        }
+    }
+}
+
+/// The tokens kept for one account. The access token is only worth reusing
+/// until `expires_at`; the refresh token mints new ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub refresh_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl StoredToken {
+    /// The access token and how long it stays valid, when it outlives `margin`.
+    pub fn fresh_access_token(&self, margin: Duration) -> Option<(&str, Duration)> {
+        let remaining = (self.expires_at? - Utc::now()).to_std().ok()?;
+        (remaining > margin).then_some((self.access_token.as_deref()?, remaining))
+    }
+}
+
+/// Persists tokens keyed by Google account.
+pub trait TokenStore: Send + Sync {
+    fn load(&self, account: &str) -> Result<Option<StoredToken>>;
+    fn save(&self, account: &str, token: &StoredToken) -> Result<()>;
+    fn delete(&self, account: &str) -> Result<()>;
+}
+
+/// A token read from a store, with where refreshed tokens are written back.
+#[derive(Clone)]
+pub struct StoredCredentials {
+    pub store: Arc<dyn TokenStore>,
+    pub account: String,
+    pub token: StoredToken,
+}
+
+/// Opens the store of `kind`; `None` for [`TokenStoreKind::Config`], whose
+/// tokens live in the config file itself.
+pub fn open_store(
+    kind: TokenStoreKind,
+    file: Option<&Path>,
+    passphrase: Option<&str>,
+) -> Result<Option<Arc<dyn TokenStore>>> {
+    Ok(match kind {
+        TokenStoreKind::Config => None,
+        TokenStoreKind::Keyring => Some(keyring_store()?),
+        TokenStoreKind::File => {
+            let path = match file {
+                Some(path) => path.to_path_buf(),
+                None => default_token_file().context("Cannot determine the token file location")?,
+            };
+            let passphrase = passphrase.filter(|p| !p.is_empty()).context(
+                "the encrypted token file needs --token-passphrase or MCP_GOOGLE_TOKEN_PASSPHRASE",
+            )?;
+            Some(Arc::new(EncryptedFileStore::new(path, passphrase)))
+        }
+    })
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_store() -> Result<Arc<dyn TokenStore>> {
+    Ok(Arc::new(KeyringStore))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_store() -> Result<Arc<dyn TokenStore>> {
+    anyhow::bail!("this build has no OS keyring support; rebuild with the `keyring` feature or use the file token store")
+}
+
+/// `tokens.enc` next to the default config file.
+pub fn default_token_file() -> Option<PathBuf> {
+    Some(config_dir()?.join("tokens.enc"))
+}
+
+/// Keeps each account's tokens as one JSON entry in the OS keyring.
+#[cfg(feature = "keyring")]
+pub struct KeyringStore;
+
+#[cfg(feature = "keyring")]
+impl KeyringStore {
+    fn entry(account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(KEYRING_SERVICE, account)
+            .with_context(|| format!("Cannot open the keyring entry for {}", account))
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl TokenStore for KeyringStore {
+    fn load(&self, account: &str) -> Result<Option<StoredToken>> {
+        match Self::entry(account)?.get_password() {
+            Ok(secret) => {
+                Ok(Some(serde_json::from_str(&secret).with_context(|| {
+                    format!("Invalid keyring entry for {}", account)
+                })?))
+            }
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to read the keyring entry for {}", account))
+            }
+        }
+    }
+
+    fn save(&self, account: &str, token: &StoredToken) -> Result<()> {
+        Self::entry(account)?
+            .set_password(&serde_json::to_string(token)?)
+            .with_context(|| format!("Failed to write the keyring entry for {}", account))
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        match Self::entry(account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e)
+                .with_context(|| format!("Failed to delete the keyring entry for {}", account)),
+        }
+    }
+}
+
+/// Keeps the tokens of all accounts in one file, encrypted with
+/// XChaCha20-Poly1305 under a key derived from a passphrase with Argon2id.
+/// Every save re-encrypts the file with a fresh salt and nonce.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    passphrase: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: PathBuf, passphrase: &str) -> Self {
+        Self {
+            path,
+            passphrase: passphrase.to_string(),
+        }
+    }
+
+    fn read_all(&self) -> Result<HashMap<String, StoredToken>> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()))
+            }
+        };
+        let plaintext = decrypt(&self.passphrase, &contents)
+            .with_context(|| format!("Cannot decrypt {}", self.path.display()))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn write_all(&self, tokens: &HashMap<String, StoredToken>) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let contents = encrypt(&self.passphrase, &serde_json::to_vec(tokens)?)?;
+        write_atomically(&self.path, &contents)
+    }
+}
+
+impl TokenStore for EncryptedFileStore {
+    fn load(&self, account: &str) -> Result<Option<StoredToken>> {
+        Ok(self.read_all()?.remove(account))
+    }
+
+    fn save(&self, account: &str, token: &StoredToken) -> Result<()> {
+        let mut tokens = self.read_all()?;
+        tokens.insert(account.to_string(), token.clone());
+        self.write_all(&tokens)
+    }
+
+    fn delete(&self, account: &str) -> Result<()> {
+        let mut tokens = self.read_all()?;
+        if tokens.remove(account).is_some() {
+            self.write_all(&tokens)?;
+        }
+        Ok(())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
+
+/// Encrypts `plaintext` into the JSON envelope the token file is stored as.
+pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<String> {
+    let mut rng = rand::thread_rng();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt);
+    rng.fill(&mut nonce);
+
+    let ciphertext = derive_key(passphrase, &salt)?
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+    Ok(serde_json::to_string_pretty(&EncryptedFile {
+        version: FILE_VERSION,
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })?)
+}
+
+/// Reverses [`encrypt`], failing on a wrong passphrase or a tampered file.
+pub fn decrypt(passphrase: &str, contents: &str) -> Result<Vec<u8>> {
+    let file: EncryptedFile = serde_json::from_str(contents)?;
+    anyhow::ensure!(
+        file.version == FILE_VERSION,
+        "unsupported token file version {}",
+        file.version
+    );
+    let salt = STANDARD
+        .decode(&file.salt)
+        .context("salt is not valid base64")?;
+    let nonce = STANDARD
+        .decode(&file.nonce)
+        .context("nonce is not valid base64")?;
+    let ciphertext = STANDARD
+        .decode(&file.ciphertext)
+        .context("ciphertext is not valid base64")?;
+    anyhow::ensure!(nonce.len() == NONCE_LEN, "nonce has the wrong length");
+
+    derive_key(passphrase, &salt)?
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| anyhow::anyhow!("wrong passphrase, or the file was modified"))
+}
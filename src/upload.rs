@@ -0,0 +1,455 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{header, Response, StatusCode};
+use serde_json::Value;
+use url::{Host, Url};
+
+use crate::{
+    circuit::{guarded, Service},
+    client::{drive_root_url, get_http_client, pinned_client},
+    payload, InvokeError,
+};
+
+/// Files larger than this are uploaded with a resumable session by default.
+pub const DEFAULT_RESUMABLE_THRESHOLD: u64 = 5 * 1024 * 1024;
+/// Resumable chunks must be a multiple of 256 KiB
+const RESUMABLE_CHUNK_SIZE: usize = 32 * 256 * 1024;
+const UPLOAD_FIELDS: &str = "id,name,mimeType,size,parents,webViewLink";
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+const MAX_REDIRECTS: usize = 5;
+
+/// Which sources besides base64 content `upload_file` may read. Both are off
+/// by default, since they let callers read the server's files and reach its
+/// network.
+#[derive(Debug, Clone, Default)]
+pub struct UploadPolicy {
+    /// Directories local `path` uploads must lie within; empty disables `path`
+    pub dirs: Vec<PathBuf>,
+    /// Whether `url` uploads may fetch from public http(s) hosts
+    pub urls: bool,
+}
+
+/// Where the bytes of an upload come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadSource {
+    Base64(String),
+    Path(String),
+    Url(String),
+}
+
+/// Bytes to upload, with the content type and file name the source suggests.
+#[derive(Debug)]
+pub struct UploadContent {
+    pub bytes: Vec<u8>,
+    pub content_type: Option<String>,
+    pub file_name: Option<String>,
+}
+
+impl UploadSource {
+    pub async fn load(&self, policy: &UploadPolicy) -> Result<UploadContent> {
+        match self {
+            UploadSource::Base64(data) => Ok(UploadContent {
+                bytes: STANDARD
+                    .decode(data.trim())
+                    .context("content is not valid base64")?,
+                content_type: None,
+                file_name: None,
+            }),
+            UploadSource::Path(path) => {
+                let path = Path::new(path);
+                let bytes = tokio::fs::read(allowed_path(path, &policy.dirs)?)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                Ok(UploadContent {
+                    bytes,
+                    content_type: None,
+                    file_name: path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned()),
+                })
+            }
+            UploadSource::Url(url) => {
+                if !policy.urls {
+                    anyhow::bail!(
+                        "uploading from URLs is disabled; start the server with --allow-upload-urls"
+                    );
+                }
+                let url = Url::parse(url).context("invalid url")?;
+                let (url, response) = fetch_public(url).await?;
+                let content_type = response
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.split(';').next().unwrap_or(v).trim().to_string());
+                let file_name = url
+                    .path_segments()
+                    .and_then(|mut segments| segments.next_back())
+                    .filter(|name| !name.is_empty())
+                    .map(|name| {
+                        urlencoding::decode(name)
+                            .map(|n| n.into_owned())
+                            .unwrap_or_else(|_| name.to_string())
+                    });
+                Ok(UploadContent {
+                    bytes: response.bytes().await?.to_vec(),
+                    content_type,
+                    file_name,
+                })
+            }
+        }
+    }
+}
+
+/// Canonicalizes `path` and checks that it lies within one of `dirs`, so
+/// neither `..` nor symlinks lead outside them.
+pub fn allowed_path(path: &Path, dirs: &[PathBuf]) -> Result<PathBuf> {
+    if dirs.is_empty() {
+        anyhow::bail!("uploading local files is disabled; start the server with --upload-dir");
+    }
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let allowed = dirs
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| canonical.starts_with(dir));
+    if !allowed {
+        anyhow::bail!("{} is outside the upload directories", path.display());
+    }
+    Ok(canonical)
+}
+
+/// Whether `ip` is publicly routable, rather than a loopback, private,
+/// link-local or otherwise internal address that URL uploads must not reach.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || a >= 240
+                // Shared address space (carrier-grade NAT)
+                || (a == 100 && (64..128).contains(&b))
+                // IETF protocol assignments and benchmarking networks
+                || (a == 192 && b == 0 && c == 0)
+                || (a == 198 && (b == 18 || b == 19)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+            let [first, second, ..] = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local and link-local
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                // Documentation
+                || (first == 0x2001 && second == 0x0db8))
+        }
+    }
+}
+
+/// Resolves the host of `url`, refusing it when any of its addresses is not
+/// public, and returns the address to connect to.
+async fn public_address(url: &Url) -> Result<SocketAddr> {
+    let port = url.port_or_known_default().context("url has no port")?;
+    let addresses: Vec<SocketAddr> = match url.host().context("url has no host")? {
+        Host::Ipv4(ip) => vec![SocketAddr::new(ip.into(), port)],
+        Host::Ipv6(ip) => vec![SocketAddr::new(ip.into(), port)],
+        Host::Domain(domain) => tokio::net::lookup_host((domain, port))
+            .await
+            .with_context(|| format!("Failed to resolve {}", domain))?
+            .collect(),
+    };
+    if let Some(address) = addresses.iter().find(|address| !is_public_ip(address.ip())) {
+        anyhow::bail!(
+            "{} resolves to {}, which is not a public address",
+            url.host_str().unwrap_or_default(),
+            address.ip()
+        );
+    }
+    addresses
+        .first()
+        .copied()
+        .with_context(|| format!("Failed to resolve {}", url.host_str().unwrap_or_default()))
+}
+
+/// Fetches `url`, following redirects only to public http(s) hosts. Each hop
+/// connects to the address that was checked. Returns the final URL with its
+/// response.
+async fn fetch_public(mut url: Url) -> Result<(Url, Response)> {
+    for _ in 0..=MAX_REDIRECTS {
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("only http and https URLs can be fetched");
+        }
+        let address = public_address(&url).await?;
+        let domain = match url.host() {
+            Some(Host::Domain(domain)) => Some(domain.to_string()),
+            _ => None,
+        };
+        let response = pinned_client(domain.as_deref(), address)?
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {}", url))?;
+        if !response.status().is_redirection() {
+            let response = response
+                .error_for_status()
+                .with_context(|| format!("Failed to fetch {}", url))?;
+            return Ok((url, response));
+        }
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .with_context(|| format!("{} redirected without a Location", url))?;
+        url = url.join(location).context("invalid redirect location")?;
+    }
+    anyhow::bail!("too many redirects fetching {}", url)
+}
+
+/// Builds a `multipart/related` body of the file's JSON metadata followed by
+/// its content, as Drive's multipart upload expects.
+pub fn multipart_body(
+    boundary: &str,
+    metadata: &Value,
+    content_type: &str,
+    content: &[u8],
+) -> Vec<u8> {
+    let mut body = Vec::with_capacity(content.len() + 512);
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{metadata}\r\n--{boundary}\r\nContent-Type: {content_type}\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    body
+}
+
+/// Upload URL for a new file; `ocr_language` is the language hint for
+/// images and PDFs converted to Google Docs.
+fn upload_url(upload_type: &str, ocr_language: Option<&str>) -> Result<Url> {
+    let mut url = file_upload_url(None, upload_type)?;
+    if let Some(language) = ocr_language {
+        url.query_pairs_mut().append_pair("ocrLanguage", language);
+    }
+    Ok(url)
+}
+
+/// Upload URL for a new file, or for replacing the content of `file_id`.
+fn file_upload_url(file_id: Option<&str>, upload_type: &str) -> Result<Url> {
+    let mut path = format!("{}upload/drive/v3/files", drive_root_url());
+    if let Some(file_id) = file_id {
+        path.push('/');
+        path.push_str(&urlencoding::encode(file_id));
+    }
+    let mut url = Url::parse(&path)?;
+    url.query_pairs_mut()
+        .append_pair("uploadType", upload_type)
+        .append_pair("fields", UPLOAD_FIELDS)
+        .append_pair("supportsAllDrives", "true");
+    Ok(url)
+}
+
+async fn json_body(response: reqwest::Response) -> Result<Value, InvokeError> {
+    if !response.status().is_success() {
+        return Err(InvokeError::from_response(response).await);
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| InvokeError::GoogleApi(e.to_string()))
+}
+
+async fn upload_multipart(
+    access_token: &str,
+    metadata: &Value,
+    content_type: &str,
+    content: &[u8],
+    ocr_language: Option<&str>,
+) -> Result<Value> {
+    let url = upload_url("multipart", ocr_language)?;
+    let boundary = format!("upload_{:016x}", rand::random::<u64>());
+    let body = multipart_body(&boundary, metadata, content_type, content);
+    let client = get_http_client()?;
+
+    guarded(Service::Drive, async {
+        payload::log_request("POST", url.as_str(), Some(metadata));
+        let response = client
+            .post(url.clone())
+            .bearer_auth(access_token)
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/related; boundary={}", boundary),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+        let status = response.status().as_u16();
+        let file = json_body(response).await?;
+        payload::log_response(url.as_str(), status, &file);
+        Ok(file)
+    })
+    .await
+}
+
+/// Uploads through a resumable session: the metadata opens the session and
+/// the content follows in chunks, so a dropped connection costs one chunk
+/// rather than the whole file.
+async fn upload_resumable(
+    access_token: &str,
+    metadata: &Value,
+    content_type: &str,
+    content: &[u8],
+    ocr_language: Option<&str>,
+) -> Result<Value> {
+    let url = upload_url("resumable", ocr_language)?;
+    let client = get_http_client()?;
+    let session = guarded(Service::Drive, async {
+        payload::log_request("POST", url.as_str(), Some(metadata));
+        let response = client
+            .post(url.clone())
+            .bearer_auth(access_token)
+            .header("X-Upload-Content-Type", content_type)
+            .header("X-Upload-Content-Length", content.len())
+            .json(metadata)
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(InvokeError::from_response(response).await);
+        }
+        response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| InvokeError::GoogleApi("no resumable session URL returned".into()))
+    })
+    .await?;
+
+    let total = content.len();
+    let mut offset = 0;
+    loop {
+        let end = (offset + RESUMABLE_CHUNK_SIZE).min(total);
+        let chunk = content[offset..end].to_vec();
+        let content_range = if total == 0 {
+            "bytes */0".to_string()
+        } else {
+            format!("bytes {}-{}/{}", offset, end - 1, total)
+        };
+
+        let response = guarded(Service::Drive, async {
+            client
+                .put(&session)
+                .header(header::CONTENT_RANGE, content_range)
+                .body(chunk)
+                .send()
+                .await
+                .map_err(|e| InvokeError::Transport(e.to_string()))
+        })
+        .await?;
+
+        // 308 Resume Incomplete: the Range header says how much was stored
+        if response.status() == StatusCode::PERMANENT_REDIRECT {
+            offset = response
+                .headers()
+                .get(header::RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|range| range.rsplit('-').next()?.parse::<usize>().ok())
+                .map_or(0, |last| last + 1);
+            continue;
+        }
+
+        let status = response.status().as_u16();
+        let file = json_body(response).await?;
+        payload::log_response(url.as_str(), status, &file);
+        return Ok(file);
+    }
+}
+
+/// Uploads `content` as a new Drive file described by `metadata`, using a
+/// resumable session when it is larger than `resumable_threshold` bytes.
+/// When `metadata` names a Google Docs type, Drive converts the content,
+/// reading images and PDFs with OCR in `ocr_language`.
+pub async fn upload_file(
+    access_token: &str,
+    metadata: &Value,
+    content_type: Option<&str>,
+    content: &[u8],
+    resumable_threshold: u64,
+    ocr_language: Option<&str>,
+) -> Result<Value> {
+    let content_type = content_type.unwrap_or(DEFAULT_CONTENT_TYPE);
+    if content.len() as u64 > resumable_threshold {
+        upload_resumable(access_token, metadata, content_type, content, ocr_language).await
+    } else {
+        upload_multipart(access_token, metadata, content_type, content, ocr_language).await
+    }
+}
+
+/// The content type of files Drive can convert to a Google Doc, by extension.
+pub fn convertible_content_type(file_name: &str) -> Option<&'static str> {
+    let (_, extension) = file_name.rsplit_once('.')?;
+    Some(match extension.to_ascii_lowercase().as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "tif" | "tiff" => "image/tiff",
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "rtf" => "application/rtf",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "odt" => "application/vnd.oasis.opendocument.text",
+        _ => return None,
+    })
+}
+
+/// Replaces the content of an existing file, keeping its metadata. Drive
+/// records the previous content as a revision.
+pub async fn update_content(
+    access_token: &str,
+    file_id: &str,
+    content_type: Option<&str>,
+    content: Vec<u8>,
+) -> Result<Value> {
+    let url = file_upload_url(Some(file_id), "media")?;
+    let content_type = content_type.unwrap_or(DEFAULT_CONTENT_TYPE).to_string();
+    let client = get_http_client()?;
+
+    guarded(Service::Drive, async {
+        payload::log_request("PATCH", url.as_str(), None);
+        let response = client
+            .patch(url.clone())
+            .bearer_auth(access_token)
+            .header(header::CONTENT_TYPE, content_type)
+            .body(content)
+            .send()
+            .await
+            .map_err(|e| InvokeError::Transport(e.to_string()))?;
+        let status = response.status().as_u16();
+        let file = json_body(response).await?;
+        payload::log_response(url.as_str(), status, &file);
+        Ok(file)
+    })
+    .await
+}
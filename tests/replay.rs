@@ -0,0 +1,82 @@
+//! Replays recorded Google API traffic from `tests/fixtures`. Set
+//! `MCP_GOOGLE_RECORD=1` (with `GOOGLE_ACCESS_TOKEN` and `TEST_SPREADSHEET_ID`)
+//! to re-record the cassette against the live APIs.
+#![cfg(feature = "mock-google")]
+
+use async_mcp::{
+    protocol::RequestOptions,
+    transport::{ClientInMemoryTransport, ServerInMemoryTransport, Transport},
+    types::CallToolRequest,
+};
+use mcp_google_workspace::{
+    replay::{start_recorder, start_replay},
+    servers::sheets,
+};
+use serde_json::{json, Value};
+use std::{collections::HashMap, env, path::Path, time::Duration};
+
+const CASSETTE: &str = "tests/fixtures/sheets_read.json";
+const REPLAY_SPREADSHEET_ID: &str = "replay-spreadsheet";
+
+#[tokio::test]
+async fn test_replay_sheets_reads() -> anyhow::Result<()> {
+    dotenv::dotenv().ok();
+    let cassette = Path::new(env!("CARGO_MANIFEST_DIR")).join(CASSETTE);
+
+    let (access_token, spreadsheet_id, _server) = if env::var("MCP_GOOGLE_RECORD").is_ok() {
+        let access_token = env::var("GOOGLE_ACCESS_TOKEN")?;
+        let spreadsheet_id = env::var("TEST_SPREADSHEET_ID")?;
+        start_recorder(
+            &cassette,
+            vec![(spreadsheet_id.clone(), REPLAY_SPREADSHEET_ID.to_string())],
+        )
+        .await?;
+        (access_token, spreadsheet_id, None)
+    } else {
+        let server = start_replay(&cassette).await?;
+        (
+            "replay-token".to_string(),
+            REPLAY_SPREADSHEET_ID.to_string(),
+            Some(server),
+        )
+    };
+
+    let transport = ClientInMemoryTransport::new(|t: ServerInMemoryTransport| {
+        tokio::spawn(async move { sheets::build(t).unwrap().listen().await.unwrap() })
+    });
+    transport.open().await?;
+    let client = async_mcp::client::ClientBuilder::new(transport).build();
+    let client_clone = client.clone();
+    tokio::spawn(async move { client_clone.start().await });
+
+    let call = |name: &str, arguments: Value| CallToolRequest {
+        name: name.to_string(),
+        arguments: Some(serde_json::from_value::<HashMap<String, Value>>(arguments).unwrap()),
+        meta: Some(json!({
+            "access_token": access_token,
+            "spreadsheet_id": spreadsheet_id,
+        })),
+    };
+
+    for params in [
+        call("get_sheet_info", json!({})),
+        call("read_values", json!({"sheet": "Sheet1", "range": "A1:C3"})),
+    ] {
+        let response = client
+            .request(
+                "tools/call",
+                Some(serde_json::to_value(&params)?),
+                RequestOptions::default().timeout(Duration::from_secs(10)),
+            )
+            .await?;
+        assert_ne!(
+            response["isError"],
+            json!(true),
+            "{} did not match the cassette: {}",
+            params.name,
+            response
+        );
+    }
+
+    Ok(())
+}